@@ -0,0 +1,236 @@
+//! The actual token generator for the macro
+use {
+    proc_macro2::{Span, TokenStream},
+    quote::{quote, ToTokens},
+    syn::{
+        parse::Parse, parse_quote, Attribute, Fields, GenericParam, Generics, Ident, Index, Item,
+        ItemStruct, Member, Type,
+    },
+};
+
+pub struct PrefixedBuilder {
+    /// The struct identifier
+    pub ident: Ident,
+    /// The struct's generic arguments (if any)
+    pub generics: Generics,
+    /// The member holding the collection
+    pub member: Member,
+    /// The type of the collection
+    pub container: Type,
+    /// The type of the length prefix
+    pub len: Type,
+}
+
+/// Parses the length prefix type from the `#[prefixed(len = ...)]` attribute
+fn parse_len(attrs: &[Attribute]) -> syn::Result<Type> {
+    let attr = attrs
+        .iter()
+        .find(|a| a.path().is_ident("prefixed"))
+        .ok_or_else(|| {
+            syn::Error::new(
+                Span::call_site(),
+                "`#[prefixed(len = ...)]` attribute not provided",
+            )
+        })?;
+
+    let mut len = None;
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("len") {
+            len = Some(meta.value()?.parse::<Type>()?);
+            Ok(())
+        } else {
+            Err(meta.error("unsupported `prefixed` attribute argument"))
+        }
+    })?;
+
+    len.ok_or_else(|| syn::Error::new_spanned(attr, "missing `len` argument"))
+}
+
+impl TryFrom<ItemStruct> for PrefixedBuilder {
+    type Error = syn::Error;
+
+    fn try_from(item_struct: ItemStruct) -> Result<Self, Self::Error> {
+        let len = parse_len(&item_struct.attrs)?;
+
+        let mut fields = match item_struct.fields {
+            Fields::Named(fields) => fields.named.into_iter(),
+            Fields::Unnamed(fields) => fields.unnamed.into_iter(),
+            Fields::Unit => {
+                return Err(syn::Error::new(
+                    item_struct.ident.span(),
+                    "Unit structs are not supported",
+                ))
+            }
+        };
+
+        let (Some(field), None) = (fields.next(), fields.next()) else {
+            return Err(syn::Error::new(
+                item_struct.ident.span(),
+                "Only structs with a single field are supported",
+            ));
+        };
+
+        let member = match field.ident {
+            Some(ident) => Member::Named(ident),
+            None => Member::Unnamed(Index::from(0)),
+        };
+
+        Ok(Self {
+            ident: item_struct.ident,
+            generics: item_struct.generics,
+            member,
+            container: field.ty,
+            len,
+        })
+    }
+}
+
+impl Parse for PrefixedBuilder {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let item = Item::parse(input)?;
+        match item {
+            Item::Struct(item_struct) => Self::try_from(item_struct),
+            _ => {
+                return Err(syn::Error::new(
+                    Span::call_site(),
+                    "Only structs are supported",
+                ))
+            }
+        }
+        .map_err(|e| syn::Error::new(input.span(), format!("Failed to parse item: {}", e)))
+    }
+}
+
+impl ToTokens for PrefixedBuilder {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        tokens.extend::<TokenStream>(self.into());
+    }
+}
+
+impl From<&PrefixedBuilder> for TokenStream {
+    fn from(builder: &PrefixedBuilder) -> Self {
+        let ident = &builder.ident;
+        let member = &builder.member;
+        let container = &builder.container;
+        let len = &builder.len;
+
+        let construct = match member {
+            Member::Named(name) => quote! { Self { #name: value } },
+            Member::Unnamed(_) => quote! { Self(value) },
+        };
+
+        let item = quote! { <#container as spl_collections::PrefixedContainer>::Item };
+
+        // borsh
+        let mut borsh_ser_generics = builder.generics.clone();
+        borsh_ser_generics
+            .make_where_clause()
+            .predicates
+            .extend::<[syn::WherePredicate; 2]>([
+                parse_quote! { #container: spl_collections::PrefixedContainer },
+                parse_quote! { #item: spl_collections::__private::borsh::BorshSerialize },
+            ]);
+        let (ser_impl, ser_ty, ser_where) = borsh_ser_generics.split_for_impl();
+
+        let mut borsh_de_generics = builder.generics.clone();
+        borsh_de_generics
+            .make_where_clause()
+            .predicates
+            .extend::<[syn::WherePredicate; 2]>([
+                parse_quote! { #container: spl_collections::PrefixedContainer },
+                parse_quote! { #item: spl_collections::__private::borsh::BorshDeserialize },
+            ]);
+        let (de_impl, de_ty, de_where) = borsh_de_generics.split_for_impl();
+
+        // wincode
+        let mut write_generics = builder.generics.clone();
+        write_generics
+            .params
+            .push(parse_quote! { __C: spl_collections::__private::wincode::config::ConfigCore });
+        write_generics
+            .make_where_clause()
+            .predicates
+            .extend::<[syn::WherePredicate; 2]>([
+                parse_quote! { #container: spl_collections::PrefixedContainer },
+                parse_quote! { #item: spl_collections::__private::wincode::SchemaWrite<__C, Src = #item> },
+            ]);
+        let (write_impl, _, write_where) = write_generics.split_for_impl();
+
+        let mut read_generics = builder.generics.clone();
+        read_generics.params.insert(0, parse_quote! { '__de });
+        read_generics
+            .params
+            .push(parse_quote! { __C: spl_collections::__private::wincode::config::ConfigCore });
+        read_generics
+            .make_where_clause()
+            .predicates
+            .extend::<[syn::WherePredicate; 2]>([
+                parse_quote! { #container: spl_collections::PrefixedContainer },
+                parse_quote! { #item: spl_collections::__private::wincode::SchemaRead<'__de, __C, Dst = #item> },
+            ]);
+        // Lifetimes must precede other generic parameters.
+        let params = core::mem::take(&mut read_generics.params);
+        let (lifetimes, others): (Vec<_>, Vec<_>) = params
+            .into_iter()
+            .partition(|param| matches!(param, GenericParam::Lifetime(_)));
+        read_generics.params.extend(lifetimes);
+        read_generics.params.extend(others);
+        let (read_impl, _, read_where) = read_generics.split_for_impl();
+
+        let (_, ty_generics, _) = builder.generics.split_for_impl();
+
+        quote! {
+            spl_collections::__if_borsh! {
+                impl #ser_impl spl_collections::__private::borsh::BorshSerialize for #ident #ser_ty #ser_where {
+                    fn serialize<__W: spl_collections::__private::borsh::io::Write>(
+                        &self,
+                        writer: &mut __W,
+                    ) -> spl_collections::__private::borsh::io::Result<()> {
+                        spl_collections::prefixed::borsh_serialize::<#len, _, _>(&self.#member, writer)
+                    }
+                }
+
+                impl #de_impl spl_collections::__private::borsh::BorshDeserialize for #ident #de_ty #de_where {
+                    fn deserialize_reader<__R: spl_collections::__private::borsh::io::Read>(
+                        reader: &mut __R,
+                    ) -> spl_collections::__private::borsh::io::Result<Self> {
+                        let value = spl_collections::prefixed::borsh_deserialize::<#len, _, _>(reader)?;
+                        Ok(#construct)
+                    }
+                }
+            }
+
+            spl_collections::__if_wincode! {
+                unsafe impl #write_impl spl_collections::__private::wincode::SchemaWrite<__C> for #ident #ty_generics #write_where {
+                    type Src = Self;
+
+                    #[inline(always)]
+                    fn size_of(src: &Self::Src) -> spl_collections::__private::wincode::WriteResult<usize> {
+                        spl_collections::prefixed::wincode_size_of::<#len, _, __C>(&src.#member)
+                    }
+
+                    #[inline(always)]
+                    fn write(
+                        writer: impl spl_collections::__private::wincode::io::Writer,
+                        src: &Self::Src,
+                    ) -> spl_collections::__private::wincode::WriteResult<()> {
+                        spl_collections::prefixed::wincode_write::<#len, _, __C>(writer, &src.#member)
+                    }
+                }
+
+                unsafe impl #read_impl spl_collections::__private::wincode::SchemaRead<'__de, __C> for #ident #ty_generics #read_where {
+                    type Dst = Self;
+
+                    fn read(
+                        reader: impl spl_collections::__private::wincode::io::Reader<'__de>,
+                        dst: &mut core::mem::MaybeUninit<Self::Dst>,
+                    ) -> spl_collections::__private::wincode::ReadResult<()> {
+                        let value = spl_collections::prefixed::wincode_read::<#len, _, __C>(reader)?;
+                        dst.write(#construct);
+                        Ok(())
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,23 @@
+//! Crate defining a derive macro for length-prefixed serialization of
+//! newtypes over collections, using the `spl-collections` library.
+
+#![deny(missing_docs)]
+#![cfg_attr(not(test), forbid(unsafe_code))]
+
+extern crate proc_macro;
+
+mod builder;
+
+use {builder::PrefixedBuilder, proc_macro::TokenStream, quote::ToTokens, syn::parse_macro_input};
+
+/// Derive macro to add length-prefixed `borsh` and `wincode` serialization
+/// to a newtype over a collection
+///
+/// The length prefix type is specified with the `#[prefixed(len = ...)]`
+/// attribute, and must be one of `u8`, `u16`, `u32` or `u64`.
+#[proc_macro_derive(Prefixed, attributes(prefixed))]
+pub fn prefixed(input: TokenStream) -> TokenStream {
+    parse_macro_input!(input as PrefixedBuilder)
+        .to_token_stream()
+        .into()
+}
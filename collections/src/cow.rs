@@ -0,0 +1,349 @@
+//! Types for serializing strings that can borrow from the input.
+//!
+//! This module provides a set of `PrefixedCowStr` types, which are serialized with a
+//! length prefix like the `PrefixedStr` types. When deserialized with `wincode` from a
+//! borrowing reader (e.g., a byte slice), the string borrows directly from the input
+//! without allocating. When deserialized with `borsh`, which cannot borrow from its
+//! reader, the string is owned.
+//!
+//! The types in this module implement the `Deref` trait, allowing them to be used
+//! as `&str` in most contexts regardless of whether the string is borrowed or owned.
+
+#[cfg(feature = "borsh")]
+use {
+    crate::vec::cautious_capacity,
+    alloc::vec::Vec,
+    borsh::{
+        io::{ErrorKind, Read, Write},
+        BorshDeserialize, BorshSerialize,
+    },
+};
+use {
+    crate::CollectionsError,
+    alloc::{borrow::Cow, string::String},
+    core::{
+        fmt::{Debug, Formatter},
+        ops::Deref,
+    },
+};
+#[cfg(feature = "wincode")]
+use {
+    core::{mem::MaybeUninit, str::from_utf8},
+    wincode::{
+        config::ConfigCore,
+        error::write_length_encoding_overflow,
+        io::{Reader, Writer},
+        ReadError, ReadResult, SchemaRead, SchemaWrite, WriteResult,
+    },
+};
+
+/// Macro defining a `PrefixedCowStr` type with a specified length prefix type.
+macro_rules! prefixed_cow_str_type {
+    ( $name:tt, $prefix_type:tt ) => {
+        #[doc = concat!("A `str` that is serialized with an `", stringify!($prefix_type), "` length prefix,")]
+        #[doc = "borrowing from the input when deserialized with `wincode`."]
+        #[derive(Clone, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+        pub struct $name<'a>(Cow<'a, str>);
+
+        impl PartialEq<str> for $name<'_> {
+            fn eq(&self, other: &str) -> bool {
+                self.as_str() == other
+            }
+        }
+
+        impl PartialEq<&str> for $name<'_> {
+            fn eq(&self, other: &&str) -> bool {
+                self.as_str() == *other
+            }
+        }
+
+        impl<'a> $name<'a> {
+            #[doc = concat!("The maximum length in bytes representable by the `", stringify!($prefix_type), "` length prefix.")]
+            pub const MAX_LEN: usize = $prefix_type::MAX as usize;
+
+            /// Creates a new string, checking that its length in bytes fits in the
+            /// length prefix.
+            pub fn try_new<T: Into<Cow<'a, str>>>(value: T) -> Result<Self, CollectionsError> {
+                let value = value.into();
+
+                if value.len() > Self::MAX_LEN {
+                    return Err(CollectionsError::LengthPrefixOverflow);
+                }
+
+                Ok(Self(value))
+            }
+
+            /// Returns the string as a `&str`.
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            /// Returns `true` if the string borrows from its source.
+            pub fn is_borrowed(&self) -> bool {
+                matches!(self.0, Cow::Borrowed(_))
+            }
+
+            /// Converts the string into one that owns its data.
+            pub fn into_owned(self) -> $name<'static> {
+                $name(Cow::Owned(self.0.into_owned()))
+            }
+        }
+
+        impl<'a> From<&'a str> for $name<'a> {
+            fn from(value: &'a str) -> Self {
+                Self(Cow::Borrowed(value))
+            }
+        }
+
+        impl From<String> for $name<'_> {
+            fn from(value: String) -> Self {
+                Self(Cow::Owned(value))
+            }
+        }
+
+        impl<'a> From<Cow<'a, str>> for $name<'a> {
+            fn from(value: Cow<'a, str>) -> Self {
+                Self(value)
+            }
+        }
+
+        impl Deref for $name<'_> {
+            type Target = str;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl Debug for $name<'_> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+                f.write_fmt(format_args!("{:?}", self.as_str()))
+            }
+        }
+
+        #[cfg(feature = "borsh")]
+        impl BorshSerialize for $name<'_> {
+            fn serialize<W: Write>(&self, writer: &mut W) -> borsh::io::Result<()> {
+                BorshSerialize::serialize(
+                    &$prefix_type::try_from(self.0.len()).map_err(|_| ErrorKind::InvalidData)?,
+                    writer,
+                )?;
+                writer.write_all(self.0.as_bytes())
+            }
+        }
+
+        #[cfg(feature = "borsh")]
+        impl BorshDeserialize for $name<'_> {
+            fn deserialize_reader<R: Read>(reader: &mut R) -> borsh::io::Result<Self> {
+                let prefix = $prefix_type::deserialize_reader(reader)? as usize;
+                // Read the bytes in chunks, so that an untrusted prefix cannot
+                // trigger a large allocation before the bytes are actually read.
+                let mut bytes = Vec::with_capacity(cautious_capacity::<u8>(prefix));
+                let mut chunk = [0u8; 256];
+
+                while bytes.len() < prefix {
+                    let chunk = &mut chunk[..prefix.saturating_sub(bytes.len()).min(256)];
+                    reader.read_exact(chunk)?;
+                    bytes.extend_from_slice(chunk);
+                }
+
+                // `borsh` readers cannot be borrowed from, so the string is always
+                // owned. Validate that we got valid UTF-8 bytes.
+                let value = String::from_utf8(bytes).map_err(|_| ErrorKind::InvalidData)?;
+
+                Ok(Self(Cow::Owned(value)))
+            }
+        }
+
+        #[cfg(feature = "wincode")]
+        unsafe impl<C: ConfigCore> SchemaWrite<C> for $name<'_> {
+            type Src = Self;
+
+            #[inline(always)]
+            fn size_of(src: &Self::Src) -> WriteResult<usize> {
+                let prefix = $prefix_type::try_from(src.0.len())
+                    .map_err(|_| write_length_encoding_overflow(stringify!($prefix_type::MAX)))?;
+
+                Ok(<$prefix_type as SchemaWrite<C>>::size_of(&prefix)?.saturating_add(src.0.len()))
+            }
+
+            #[inline(always)]
+            fn write(mut writer: impl Writer, src: &Self::Src) -> WriteResult<()> {
+                <$prefix_type as SchemaWrite<C>>::write(
+                    &mut writer,
+                    &$prefix_type::try_from(src.0.len())
+                        .map_err(|_| write_length_encoding_overflow(stringify!($prefix_type::MAX)))?,
+                )?;
+                writer.write(src.0.as_bytes())?;
+                Ok(())
+            }
+        }
+
+        #[cfg(feature = "wincode")]
+        unsafe impl<'de, C: ConfigCore> SchemaRead<'de, C> for $name<'de> {
+            type Dst = Self;
+
+            fn read(mut reader: impl Reader<'de>, dst: &mut MaybeUninit<Self::Dst>) -> ReadResult<()> {
+                let prefix = <$prefix_type as SchemaRead<'de, C>>::get(&mut reader)? as usize;
+                let bytes = reader.take_borrowed(prefix)?;
+
+                // Validate that we got valid UTF-8 bytes, as the string must
+                // always be valid UTF-8.
+                let Ok(value) = from_utf8(bytes) else {
+                    return Err(ReadError::Custom("invalid UTF-8 bytes"));
+                };
+
+                dst.write(Self(Cow::Borrowed(value)));
+
+                Ok(())
+            }
+        }
+    };
+}
+
+// A `PrefixedCowStr` with a `u8` length prefix.
+prefixed_cow_str_type!(U8PrefixedCowStr, u8);
+
+// A `PrefixedCowStr` with a `u16` length prefix.
+prefixed_cow_str_type!(U16PrefixedCowStr, u16);
+
+// A `PrefixedCowStr` with a `u32` length prefix.
+prefixed_cow_str_type!(U32PrefixedCowStr, u32);
+
+// A `PrefixedCowStr` with a `u64` length prefix.
+prefixed_cow_str_type!(U64PrefixedCowStr, u64);
+
+#[cfg(test)]
+mod tests {
+    use {
+        alloc::{string::ToString, vec::Vec},
+        borsh::{io::ErrorKind, BorshDeserialize},
+        wincode::WriteError,
+    };
+
+    use super::*;
+
+    #[test]
+    fn prefixed_cow_str_borsh_round_trip() {
+        const TEXT: &str = "Prefixed strings have many characters";
+
+        let original = U16PrefixedCowStr::from(TEXT);
+        let bytes = borsh::to_vec(&original).unwrap();
+
+        assert_eq!(u16::from_le_bytes([bytes[0], bytes[1]]), TEXT.len() as u16);
+        assert_eq!(&bytes[2..], TEXT.as_bytes());
+
+        let deserialized = U16PrefixedCowStr::try_from_slice(&bytes).unwrap();
+
+        // `borsh` always produces an owned string.
+        assert!(!deserialized.is_borrowed());
+        assert_eq!(deserialized.as_str(), TEXT);
+        assert_eq!(deserialized, original);
+    }
+
+    #[test]
+    fn prefixed_cow_str_wincode_round_trip() {
+        const TEXT: &str = "Prefixed strings have many characters";
+
+        let original = U32PrefixedCowStr::from(TEXT.to_string());
+        let bytes = wincode::serialize(&original).unwrap();
+
+        assert_eq!(bytes.len(), size_of::<u32>() + TEXT.len());
+        assert_eq!(
+            u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            TEXT.len() as u32
+        );
+
+        let deserialized = wincode::deserialize::<U32PrefixedCowStr>(&bytes).unwrap();
+
+        // `wincode` borrows directly from the input bytes.
+        assert!(deserialized.is_borrowed());
+        assert_eq!(deserialized.as_ptr(), bytes[4..].as_ptr());
+        assert_eq!(deserialized.as_str(), TEXT);
+        assert_eq!(deserialized, original);
+
+        let owned = deserialized.into_owned();
+        assert!(!owned.is_borrowed());
+        assert_eq!(owned.as_str(), TEXT);
+    }
+
+    #[test]
+    fn prefixed_cow_str_matches_prefixed_str_encoding() {
+        const TEXT: &str = "⚙️ same bytes either way";
+
+        let cow_bytes = wincode::serialize(&U8PrefixedCowStr::from(TEXT)).unwrap();
+        let str_bytes = wincode::serialize(&crate::U8PrefixedStr::from(TEXT)).unwrap();
+
+        assert_eq!(cow_bytes, str_bytes);
+        assert_eq!(
+            borsh::to_vec(&U8PrefixedCowStr::from(TEXT)).unwrap(),
+            borsh::to_vec(&crate::U8PrefixedStr::from(TEXT)).unwrap()
+        );
+    }
+
+    #[test]
+    fn prefixed_cow_str_with_remaining_bytes() {
+        let value = "serialized data with extra bytes";
+
+        let mut bytes = Vec::<u8>::new();
+        bytes.push(value.len() as u8);
+        bytes.extend_from_slice(value.as_bytes());
+        // Extra bytes that should be ignored.
+        bytes.extend_from_slice(&[255u8; 16]);
+
+        let deserialized = wincode::deserialize::<U8PrefixedCowStr>(&bytes).unwrap();
+        assert_eq!(deserialized.as_str(), value);
+
+        let mut reader = bytes.as_slice();
+        let deserialized = U8PrefixedCowStr::deserialize(&mut reader).unwrap();
+        assert_eq!(deserialized.as_str(), value);
+        assert_eq!(reader.len(), 16);
+    }
+
+    #[test]
+    fn invalid_prefixed_value() {
+        let large_text = "a".repeat(256);
+
+        let original = U8PrefixedCowStr::from(large_text);
+
+        // borsh
+        let result = borsh::to_vec(&original);
+
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+
+        // wincode
+        let result = wincode::serialize(&original);
+
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            WriteError::LengthEncodingOverflow(_)
+        ));
+    }
+
+    #[test]
+    fn prefixed_cow_str_try_new() {
+        let large_text = "a".repeat(256);
+
+        assert_eq!(
+            U8PrefixedCowStr::try_new(large_text.as_str()).unwrap_err(),
+            CollectionsError::LengthPrefixOverflow
+        );
+
+        let string = U16PrefixedCowStr::try_new(large_text.as_str()).unwrap();
+        assert!(string.is_borrowed());
+        assert_eq!(string.len(), 256);
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        // prefix + 2 invalid UTF-8 bytes
+        let bytes = [2u8, 255, 255];
+
+        assert!(wincode::deserialize::<U8PrefixedCowStr>(&bytes).is_err());
+
+        let mut reader = bytes.as_slice();
+        assert!(U8PrefixedCowStr::deserialize(&mut reader).is_err());
+    }
+}
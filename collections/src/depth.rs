@@ -0,0 +1,92 @@
+//! Nesting depth limit for deserializing prefixed collections.
+//!
+//! A recursive type (e.g., a tree whose nodes hold a prefixed vector of nodes)
+//! is deserialized by recursing once per level of nesting, so untrusted input
+//! could otherwise exhaust the stack with only a few bytes per level.
+//!
+//! The current depth is tracked per thread with the `std` feature. Without it,
+//! the depth is tracked in a global atomic counter, shared by all the threads
+//! deserializing at the same time.
+//!
+//! On-chain programs can't have writable static data, so the depth isn't
+//! tracked there. Exhausting the stack of a program aborts its transaction
+//! instead.
+
+// The depth is only tracked when deserializing with `borsh` or `wincode`.
+#![cfg_attr(not(any(feature = "borsh", feature = "wincode")), allow(dead_code))]
+
+#[cfg(all(not(target_os = "solana"), not(feature = "std")))]
+use core::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(all(not(target_os = "solana"), feature = "std"))]
+use std::cell::Cell;
+
+/// Maximum nesting depth of prefixed collections when deserializing.
+pub const MAX_NESTING_DEPTH: usize = 64;
+
+/// Error message returned when the nesting depth limit is exceeded.
+pub(crate) const NESTING_DEPTH_EXCEEDED: &str = "maximum nesting depth exceeded";
+
+#[cfg(all(not(target_os = "solana"), feature = "std"))]
+std::thread_local! {
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+#[cfg(all(not(target_os = "solana"), not(feature = "std")))]
+static DEPTH: AtomicUsize = AtomicUsize::new(0);
+
+/// Increments the depth, returning `false` if it is already
+/// [`MAX_NESTING_DEPTH`].
+#[cfg(all(not(target_os = "solana"), feature = "std"))]
+fn try_increment_depth() -> bool {
+    DEPTH.with(|depth| {
+        if depth.get() >= MAX_NESTING_DEPTH {
+            return false;
+        }
+        depth.set(depth.get().saturating_add(1));
+        true
+    })
+}
+
+#[cfg(all(not(target_os = "solana"), feature = "std"))]
+fn decrement_depth() {
+    DEPTH.with(|depth| depth.set(depth.get().saturating_sub(1)));
+}
+
+#[cfg(all(not(target_os = "solana"), not(feature = "std")))]
+fn try_increment_depth() -> bool {
+    DEPTH
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |depth| {
+            (depth < MAX_NESTING_DEPTH).then(|| depth.saturating_add(1))
+        })
+        .is_ok()
+}
+
+#[cfg(all(not(target_os = "solana"), not(feature = "std")))]
+fn decrement_depth() {
+    DEPTH.fetch_sub(1, Ordering::Relaxed);
+}
+
+#[cfg(target_os = "solana")]
+fn try_increment_depth() -> bool {
+    true
+}
+
+#[cfg(target_os = "solana")]
+fn decrement_depth() {}
+
+/// Guard for one level of nesting, released when dropped.
+pub(crate) struct DepthGuard(());
+
+impl DepthGuard {
+    /// Enters one level of nesting, returning `None` if this would exceed
+    /// [`MAX_NESTING_DEPTH`].
+    pub(crate) fn enter() -> Option<Self> {
+        try_increment_depth().then_some(Self(()))
+    }
+}
+
+impl Drop for DepthGuard {
+    fn drop(&mut self) {
+        decrement_depth();
+    }
+}
@@ -0,0 +1,12 @@
+//! Error types
+
+/// Errors that may be returned when constructing collection wrappers.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum CollectionsError {
+    /// The string contains characters rejected by the charset validator
+    #[error("The string contains characters rejected by the charset validator")]
+    InvalidCharset,
+    /// The length exceeds the maximum value of the length prefix
+    #[error("The length exceeds the maximum value of the length prefix")]
+    LengthPrefixOverflow,
+}
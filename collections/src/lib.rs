@@ -3,14 +3,104 @@
 //! This crate provides wrappers around collection types to support custom serialization
 //! logic. This is useful for programs that have specific requirements for how data is
 //! stored.
+//!
+//! The following wrappers are provided:
+//!
+//! - `TrailingVec` and the `U*PrefixedVec` types wrap a `Vec<T>`.
+//! - The `U*PrefixedVecDeque` types wrap a `VecDeque<T>`, serialized front-to-back.
+//! - `TrailingStr` and the `U*PrefixedStr` types wrap a UTF-8 string, backed by the
+//!   corresponding byte vector wrapper.
+//! - The `U*PrefixedCowStr` types wrap a `Cow<str>`, borrowing from the input when
+//!   deserialized with `wincode` and owning the string when deserialized with `borsh`.
+//! - `TrailingOptionBox` wraps an `Option<Box<T>>`, present only if bytes remain.
+//! - `ValidatedStr` wraps any of the string types above, restricting its characters to
+//!   those accepted by a `CharsetValidator` (e.g., `AsciiU8PrefixedStr`).
+//!
+//! Newtypes over other collections can be given the same length-prefixed
+//! serialization with the `Prefixed` derive macro, available with the `derive` feature.
+//!
+//! Byte vectors implement `serde` traits as base64 strings or raw bytes with the
+//! `serde-traits` feature.
+//!
+//! `Trailing*` types are serialized without a length prefix and must be the last field
+//! of a struct, while `U*Prefixed*` types are serialized with a length prefix of the
+//! indicated integer type.
+//!
+//! Deserializing prefixed collections nested deeper than `MAX_NESTING_DEPTH` fails,
+//! bounding the recursion of recursive types on untrusted input. Enable the `std`
+//! feature to track the depth per thread when deserializing from multiple threads,
+//! rather than in a counter shared by all threads. The depth isn't tracked in
+//! on-chain programs, which can't have writable static data.
 
 #![no_std]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
 extern crate alloc;
+extern crate self as spl_collections;
+#[cfg(feature = "std")]
+extern crate std;
 
+mod cow;
+mod depth;
+mod error;
+mod option;
+pub mod prefixed;
 mod str;
+mod validated;
 mod vec;
+mod vec_deque;
 
+pub use cow::*;
+pub use depth::MAX_NESTING_DEPTH;
+pub use error::*;
+pub use option::*;
+pub use prefixed::PrefixedContainer;
 pub use str::*;
+pub use validated::*;
 pub use vec::*;
+pub use vec_deque::*;
+
+// Expose derive macro on feature flag
+#[cfg(feature = "derive")]
+pub use spl_collections_derive::Prefixed;
+
+/// Re-exports used by the code generated by the `Prefixed` derive macro.
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(feature = "borsh")]
+    pub use borsh;
+    #[cfg(feature = "wincode")]
+    pub use wincode;
+}
+
+/// Expands the given items only if the `borsh` feature is enabled.
+#[doc(hidden)]
+#[cfg(feature = "borsh")]
+#[macro_export]
+macro_rules! __if_borsh {
+    ($($item:tt)*) => { $($item)* };
+}
+
+/// Expands the given items only if the `borsh` feature is enabled.
+#[doc(hidden)]
+#[cfg(not(feature = "borsh"))]
+#[macro_export]
+macro_rules! __if_borsh {
+    ($($item:tt)*) => {};
+}
+
+/// Expands the given items only if the `wincode` feature is enabled.
+#[doc(hidden)]
+#[cfg(feature = "wincode")]
+#[macro_export]
+macro_rules! __if_wincode {
+    ($($item:tt)*) => { $($item)* };
+}
+
+/// Expands the given items only if the `wincode` feature is enabled.
+#[doc(hidden)]
+#[cfg(not(feature = "wincode"))]
+#[macro_export]
+macro_rules! __if_wincode {
+    ($($item:tt)*) => {};
+}
@@ -0,0 +1,337 @@
+//! Types for serializing optional trailing values.
+//!
+//! This module provides `TrailingOptionBox`, an `Option<Box<T>>` serialized without a
+//! tag. A value is present if there are bytes remaining after the preceding fields
+//! of a struct, and absent otherwise.
+//!
+//! The type in this module also implements the `Deref` trait, allowing it to be used
+//! as a regular `Option<Box<T>>` in most contexts.
+
+#[cfg(feature = "borsh")]
+use borsh::{
+    io::{Read, Result as BorshResult, Write},
+    BorshDeserialize, BorshSerialize,
+};
+use {
+    alloc::boxed::Box,
+    core::{
+        fmt::{Debug, Formatter},
+        ops::Deref,
+    },
+};
+#[cfg(feature = "wincode")]
+use {
+    core::mem::MaybeUninit,
+    wincode::{
+        config::ConfigCore,
+        io::{ReadError as IoReadError, Reader, Writer},
+        ReadResult, SchemaRead, SchemaWrite, WriteResult,
+    },
+};
+
+/// An `Option<Box<T>>` serialized without a tag.
+///
+/// This is useful for data that is optionally extended with an additional
+/// section (e.g., a "v2" section appended to an existing account layout):
+/// the value is deserialized as `Some` if there are bytes remaining, and as
+/// `None` otherwise. Serializing `None` produces no bytes.
+///
+/// The value is boxed to keep the size of the enclosing struct small.
+///
+/// Note that this type is not suitable for serializing values that are not
+/// the last field of a struct, as the presence of the value is inferred from
+/// the remaining bytes.
+///
+/// With `wincode`, a value of a dynamically-sized type that is truncated
+/// exactly between two of its fields cannot be told apart from an absent
+/// value, and is deserialized as `None`. Statically-sized values are read
+/// as a whole and are always reported as an error when truncated.
+///
+/// # Examples
+///
+/// ```
+/// use spl_collections::TrailingOptionBox;
+/// use wincode::{SchemaRead, SchemaWrite};
+///
+/// #[derive(SchemaRead, SchemaWrite)]
+/// pub struct Extension {
+///   pub flags: u32,
+///   pub limit: u64,
+/// }
+///
+/// #[derive(SchemaRead, SchemaWrite)]
+/// pub struct MyStruct {
+///   pub amount: u64,
+///   pub extension: TrailingOptionBox<Extension>,
+/// }
+///
+/// let v1 = MyStruct {
+///   amount: 1_000_000_000,
+///   extension: TrailingOptionBox::from(None),
+/// };
+///
+/// let bytes = wincode::serialize(&v1).unwrap();
+/// // Expected size: amount (8 bytes), no bytes for the absent extension.
+/// assert_eq!(bytes.len(), 8);
+/// # let deserialized = wincode::deserialize::<MyStruct>(&bytes).unwrap();
+/// # assert!(deserialized.extension.is_none());
+///
+/// let v2 = MyStruct {
+///   amount: 1_000_000_000,
+///   extension: TrailingOptionBox::from(Extension { flags: 1, limit: 10 }),
+/// };
+///
+/// let bytes = wincode::serialize(&v2).unwrap();
+/// // Expected size: amount (8 bytes) and extension (4 + 8 bytes).
+/// assert_eq!(bytes.len(), 8 + 4 + 8);
+/// # let deserialized = wincode::deserialize::<MyStruct>(&bytes).unwrap();
+/// # assert_eq!(deserialized.extension.as_ref().unwrap().limit, 10);
+/// ```
+#[derive(Clone, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[repr(transparent)]
+pub struct TrailingOptionBox<T>(Option<Box<T>>);
+
+impl<T> Default for TrailingOptionBox<T> {
+    fn default() -> Self {
+        Self(None)
+    }
+}
+
+impl<T> From<Option<T>> for TrailingOptionBox<T> {
+    fn from(value: Option<T>) -> Self {
+        Self(value.map(Box::new))
+    }
+}
+
+impl<T> From<T> for TrailingOptionBox<T> {
+    fn from(value: T) -> Self {
+        Self(Some(Box::new(value)))
+    }
+}
+
+impl<T> Deref for TrailingOptionBox<T> {
+    type Target = Option<Box<T>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: Debug> Debug for TrailingOptionBox<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_fmt(format_args!("{:?}", self.0))
+    }
+}
+
+/// Reader yielding a byte that was already read before the bytes of the
+/// inner reader.
+#[cfg(feature = "borsh")]
+struct PeekedReader<'a, R> {
+    byte: Option<u8>,
+    inner: &'a mut R,
+}
+
+#[cfg(feature = "borsh")]
+impl<R: Read> Read for PeekedReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> BorshResult<usize> {
+        match (self.byte, buf.first_mut()) {
+            (Some(byte), Some(first)) => {
+                *first = byte;
+                self.byte = None;
+                Ok(1)
+            }
+            _ => self.inner.read(buf),
+        }
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl<T: BorshSerialize> BorshSerialize for TrailingOptionBox<T> {
+    fn serialize<W: Write>(&self, writer: &mut W) -> BorshResult<()> {
+        // Serialize the value without a tag, if present.
+        match &self.0 {
+            Some(value) => value.serialize(writer),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl<T: BorshDeserialize> BorshDeserialize for TrailingOptionBox<T> {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> BorshResult<Self> {
+        // `borsh` readers cannot be peeked, so read a single byte to determine
+        // whether there are bytes remaining.
+        let mut byte = [0u8; 1];
+
+        if reader.read(&mut byte)? == 0 {
+            return Ok(Self(None));
+        }
+
+        let mut reader = PeekedReader {
+            byte: Some(byte[0]),
+            inner: reader,
+        };
+
+        Ok(Self(Some(Box::new(T::deserialize_reader(&mut reader)?))))
+    }
+}
+
+#[cfg(feature = "wincode")]
+unsafe impl<T, C> SchemaWrite<C> for TrailingOptionBox<T>
+where
+    C: ConfigCore,
+    T: SchemaWrite<C, Src = T>,
+{
+    type Src = Self;
+
+    #[inline(always)]
+    fn size_of(src: &Self::Src) -> WriteResult<usize> {
+        match &src.0 {
+            Some(value) => T::size_of(value),
+            None => Ok(0),
+        }
+    }
+
+    #[inline(always)]
+    fn write(writer: impl Writer, src: &Self::Src) -> WriteResult<()> {
+        // Serialize the value without a tag, if present.
+        match &src.0 {
+            Some(value) => T::write(writer, value),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "wincode")]
+unsafe impl<'de, T, C> SchemaRead<'de, C> for TrailingOptionBox<T>
+where
+    C: ConfigCore,
+    T: SchemaRead<'de, C, Dst = T>,
+{
+    type Dst = Self;
+
+    fn read(mut reader: impl Reader<'de>, dst: &mut MaybeUninit<Self::Dst>) -> ReadResult<()> {
+        // The value is present only if there are bytes remaining, so check
+        // for a byte before reading it: running out of bytes partway through
+        // the value is an error.
+        #[allow(deprecated)]
+        let value = match reader.peek_byte() {
+            Ok(_) => Some(Box::new(T::get(reader.by_ref())?)),
+            Err(IoReadError::ReadSizeLimit(_)) => None,
+            Err(error) => return Err(error.into()),
+        };
+
+        dst.write(Self(value));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, alloc::vec::Vec, borsh::BorshDeserialize};
+
+    #[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+    #[cfg_attr(feature = "wincode", derive(SchemaRead, SchemaWrite))]
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    struct Extension {
+        flags: u32,
+        limit: u64,
+    }
+
+    #[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+    #[cfg_attr(feature = "wincode", derive(SchemaRead, SchemaWrite))]
+    #[derive(Debug, Eq, PartialEq)]
+    struct Versioned {
+        amount: u64,
+        extension: TrailingOptionBox<Extension>,
+    }
+
+    #[test]
+    fn trailing_option_box_borsh_round_trip() {
+        // None
+        let original = Versioned {
+            amount: 42,
+            extension: TrailingOptionBox::from(None),
+        };
+        let bytes = borsh::to_vec(&original).unwrap();
+
+        assert_eq!(bytes.len(), 8);
+
+        let deserialized = Versioned::try_from_slice(&bytes).unwrap();
+        assert_eq!(deserialized, original);
+
+        // Some
+        let original = Versioned {
+            amount: 42,
+            extension: TrailingOptionBox::from(Extension {
+                flags: 0x0102_0304,
+                limit: u64::MAX,
+            }),
+        };
+        let bytes = borsh::to_vec(&original).unwrap();
+
+        assert_eq!(bytes.len(), 8 + 4 + 8);
+
+        let deserialized = Versioned::try_from_slice(&bytes).unwrap();
+        assert_eq!(deserialized, original);
+    }
+
+    #[test]
+    fn trailing_option_box_wincode_round_trip() {
+        // None
+        let original = Versioned {
+            amount: 42,
+            extension: TrailingOptionBox::from(None),
+        };
+        let bytes = wincode::serialize(&original).unwrap();
+
+        assert_eq!(bytes.len(), 8);
+
+        let deserialized = wincode::deserialize::<Versioned>(&bytes).unwrap();
+        assert_eq!(deserialized, original);
+
+        // Some
+        let original = Versioned {
+            amount: 42,
+            extension: TrailingOptionBox::from(Extension {
+                flags: 0x0102_0304,
+                limit: u64::MAX,
+            }),
+        };
+        let bytes = wincode::serialize(&original).unwrap();
+
+        assert_eq!(bytes.len(), 8 + 4 + 8);
+
+        let deserialized = wincode::deserialize::<Versioned>(&bytes).unwrap();
+        assert_eq!(deserialized, original);
+        assert_eq!(deserialized.extension.as_deref().unwrap().limit, u64::MAX);
+    }
+
+    #[test]
+    fn trailing_option_box_truncated_value() {
+        // Remaining bytes that are not enough to hold the value are an error,
+        // not a `None`.
+        let mut bytes = Vec::from(42u64.to_le_bytes());
+        bytes.extend_from_slice(&[1, 2, 3]);
+
+        assert!(Versioned::try_from_slice(&bytes).is_err());
+        assert!(wincode::deserialize::<Versioned>(&bytes).is_err());
+
+        // Including when the value is cut off between two of its fields.
+        let mut bytes = Vec::from(42u64.to_le_bytes());
+        bytes.extend_from_slice(&0x0102_0304u32.to_le_bytes());
+
+        assert!(Versioned::try_from_slice(&bytes).is_err());
+        assert!(wincode::deserialize::<Versioned>(&bytes).is_err());
+    }
+
+    #[test]
+    fn trailing_option_box_wincode_invalid_value() {
+        // Invalid remaining bytes are an error even if they are all consumed.
+        let mut bytes = Vec::from(42u64.to_le_bytes());
+        bytes.push(2);
+
+        assert!(wincode::deserialize::<(u64, TrailingOptionBox<bool>)>(&bytes).is_err());
+    }
+}
@@ -0,0 +1,290 @@
+//! Length-prefixed serialization of arbitrary collections.
+//!
+//! This module provides the `PrefixedContainer` trait, describing a collection
+//! that can be serialized as a length prefix followed by its items, together with
+//! the functions implementing that serialization for `borsh` and `wincode`.
+//!
+//! These are used by the `Prefixed` derive macro (available with the `derive`
+//! feature) to implement serialization for newtypes over a collection:
+//!
+//! ```
+//! # #[cfg(feature = "derive")]
+//! # {
+//! use spl_collections::Prefixed;
+//!
+//! #[derive(Prefixed)]
+//! #[prefixed(len = u8)]
+//! pub struct Signers(Vec<[u8; 32]>);
+//!
+//! let signers = Signers(vec![[1; 32], [2; 32]]);
+//! let bytes = wincode::serialize(&signers).unwrap();
+//!
+//! assert_eq!(bytes.len(), 1 + 2 * 32);
+//! # }
+//! ```
+//!
+//! Collections other than the ones supported out of the box can implement
+//! `PrefixedContainer` to be used with the derive macro.
+
+#[cfg(any(feature = "borsh", feature = "wincode"))]
+use crate::depth::{DepthGuard, NESTING_DEPTH_EXCEEDED};
+use alloc::{
+    collections::{BTreeSet, VecDeque},
+    vec::Vec,
+};
+#[cfg(feature = "borsh")]
+use borsh::{
+    io::{ErrorKind, Read, Result as BorshResult, Write},
+    BorshDeserialize, BorshSerialize,
+};
+#[cfg(feature = "wincode")]
+use wincode::{
+    config::ConfigCore,
+    error::write_length_encoding_overflow,
+    io::{Reader, Writer},
+    ReadError, ReadResult, SchemaRead, SchemaWrite, TypeMeta, WriteResult,
+};
+
+/// Trait for collections serialized as a length prefix followed by their items.
+pub trait PrefixedContainer: FromIterator<Self::Item> {
+    /// The type of the items of the collection.
+    type Item;
+
+    /// Returns an iterator over the items of the collection, in serialization order.
+    fn items(&self) -> impl ExactSizeIterator<Item = &Self::Item>;
+}
+
+impl<T> PrefixedContainer for Vec<T> {
+    type Item = T;
+
+    fn items(&self) -> impl ExactSizeIterator<Item = &Self::Item> {
+        self.iter()
+    }
+}
+
+impl<T> PrefixedContainer for VecDeque<T> {
+    type Item = T;
+
+    fn items(&self) -> impl ExactSizeIterator<Item = &Self::Item> {
+        self.iter()
+    }
+}
+
+impl<T: Ord> PrefixedContainer for BTreeSet<T> {
+    type Item = T;
+
+    fn items(&self) -> impl ExactSizeIterator<Item = &Self::Item> {
+        self.iter()
+    }
+}
+
+/// Serializes `container` with `borsh`, using a length prefix of type `P`.
+#[cfg(feature = "borsh")]
+pub fn borsh_serialize<P, C, W>(container: &C, writer: &mut W) -> BorshResult<()>
+where
+    P: BorshSerialize + TryFrom<usize>,
+    C: PrefixedContainer,
+    C::Item: BorshSerialize,
+    W: Write,
+{
+    let mut items = container.items();
+
+    P::try_from(items.len())
+        .map_err(|_| ErrorKind::InvalidData)?
+        .serialize(writer)?;
+    items.try_for_each(|item| item.serialize(writer))
+}
+
+/// Deserializes a collection with `borsh`, using a length prefix of type `P`.
+#[cfg(feature = "borsh")]
+pub fn borsh_deserialize<P, C, R>(reader: &mut R) -> BorshResult<C>
+where
+    P: BorshDeserialize + TryInto<usize>,
+    C: PrefixedContainer,
+    C::Item: BorshDeserialize,
+    R: Read,
+{
+    let Some(_guard) = DepthGuard::enter() else {
+        return Err(borsh::io::Error::new(
+            ErrorKind::InvalidData,
+            NESTING_DEPTH_EXCEEDED,
+        ));
+    };
+
+    let len = P::deserialize_reader(reader)?
+        .try_into()
+        .map_err(|_| ErrorKind::InvalidData)?;
+
+    (0..len)
+        .map(|_| C::Item::deserialize_reader(reader))
+        .collect()
+}
+
+/// Returns the serialized size of `container` with `wincode`, using a length
+/// prefix of type `P`.
+#[cfg(feature = "wincode")]
+pub fn wincode_size_of<P, C, Cfg>(container: &C) -> WriteResult<usize>
+where
+    P: SchemaWrite<Cfg, Src = P> + TryFrom<usize>,
+    C: PrefixedContainer,
+    C::Item: SchemaWrite<Cfg, Src = C::Item>,
+    Cfg: ConfigCore,
+{
+    let mut items = container.items();
+    let prefix =
+        P::try_from(items.len()).map_err(|_| write_length_encoding_overflow("length prefix"))?;
+    let prefix_size = P::size_of(&prefix)?;
+
+    if let TypeMeta::Static { size, .. } = <C::Item as SchemaWrite<Cfg>>::TYPE_META {
+        return Ok(items.len().saturating_mul(size).saturating_add(prefix_size));
+    }
+
+    items.try_fold(prefix_size, |size, item| {
+        Ok(size.saturating_add(C::Item::size_of(item)?))
+    })
+}
+
+/// Serializes `container` with `wincode`, using a length prefix of type `P`.
+#[cfg(feature = "wincode")]
+pub fn wincode_write<P, C, Cfg>(mut writer: impl Writer, container: &C) -> WriteResult<()>
+where
+    P: SchemaWrite<Cfg, Src = P> + TryFrom<usize>,
+    C: PrefixedContainer,
+    C::Item: SchemaWrite<Cfg, Src = C::Item>,
+    Cfg: ConfigCore,
+{
+    let mut items = container.items();
+    let prefix =
+        P::try_from(items.len()).map_err(|_| write_length_encoding_overflow("length prefix"))?;
+
+    P::write(writer.by_ref(), &prefix)?;
+    items.try_for_each(|item| C::Item::write(writer.by_ref(), item))
+}
+
+/// Deserializes a collection with `wincode`, using a length prefix of type `P`.
+#[cfg(feature = "wincode")]
+pub fn wincode_read<'de, P, C, Cfg>(mut reader: impl Reader<'de>) -> ReadResult<C>
+where
+    P: SchemaRead<'de, Cfg, Dst = P> + TryInto<usize>,
+    C: PrefixedContainer,
+    C::Item: SchemaRead<'de, Cfg, Dst = C::Item>,
+    Cfg: ConfigCore,
+{
+    let Some(_guard) = DepthGuard::enter() else {
+        return Err(ReadError::Custom(NESTING_DEPTH_EXCEEDED));
+    };
+
+    let len = P::get(reader.by_ref())?
+        .try_into()
+        .map_err(|_| ReadError::Custom("invalid length prefix"))?;
+
+    (0..len)
+        .map(|_| <C::Item as SchemaRead<'de, Cfg>>::get(reader.by_ref()))
+        .collect()
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use {
+        crate::{Prefixed, U16PrefixedVec},
+        alloc::{
+            collections::{BTreeSet, VecDeque},
+            vec,
+            vec::Vec,
+        },
+        borsh::BorshDeserialize,
+    };
+
+    #[derive(Clone, Debug, Eq, PartialEq, Prefixed)]
+    #[prefixed(len = u16)]
+    struct Signers(Vec<[u8; 32]>);
+
+    #[derive(Debug, Eq, PartialEq, Prefixed)]
+    #[prefixed(len = u8)]
+    struct Queue<T> {
+        items: VecDeque<T>,
+    }
+
+    #[derive(Debug, Eq, PartialEq, Prefixed)]
+    #[prefixed(len = u32)]
+    struct Tags<'a>(BTreeSet<crate::U8PrefixedCowStr<'a>>);
+
+    #[derive(
+        Debug,
+        Eq,
+        PartialEq,
+        borsh::BorshSerialize,
+        borsh::BorshDeserialize,
+        wincode::SchemaRead,
+        wincode::SchemaWrite,
+    )]
+    struct Multisig {
+        signers: Signers,
+        threshold: u8,
+    }
+
+    #[test]
+    fn derived_encoding_matches_prefixed_vec() {
+        let signers = Signers(vec![[1; 32], [2; 32], [3; 32]]);
+        let expected = U16PrefixedVec::from(signers.0.clone());
+
+        assert_eq!(
+            borsh::to_vec(&signers).unwrap(),
+            borsh::to_vec(&expected).unwrap()
+        );
+        assert_eq!(
+            wincode::serialize(&signers).unwrap(),
+            wincode::serialize(&expected).unwrap()
+        );
+    }
+
+    #[test]
+    fn derived_round_trip() {
+        let multisig = Multisig {
+            signers: Signers(vec![[1; 32], [2; 32]]),
+            threshold: 2,
+        };
+
+        let bytes = borsh::to_vec(&multisig).unwrap();
+        assert_eq!(bytes.len(), 2 + 2 * 32 + 1);
+        assert_eq!(Multisig::try_from_slice(&bytes).unwrap(), multisig);
+
+        let bytes = wincode::serialize(&multisig).unwrap();
+        assert_eq!(
+            wincode::serialized_size(&multisig).unwrap() as usize,
+            bytes.len()
+        );
+        assert_eq!(wincode::deserialize::<Multisig>(&bytes).unwrap(), multisig);
+
+        let queue = Queue {
+            items: VecDeque::from(vec![3u64, 2, 1]),
+        };
+
+        let bytes = borsh::to_vec(&queue).unwrap();
+        assert_eq!(Queue::<u64>::try_from_slice(&bytes).unwrap(), queue);
+
+        let bytes = wincode::serialize(&queue).unwrap();
+        assert_eq!(bytes.len(), 1 + 3 * 8);
+        assert_eq!(wincode::deserialize::<Queue<u64>>(&bytes).unwrap(), queue);
+
+        let tags = Tags(BTreeSet::from(["defi".into(), "nft".into()]));
+
+        let bytes = wincode::serialize(&tags).unwrap();
+        assert_eq!(
+            wincode::serialized_size(&tags).unwrap() as usize,
+            4 + (1 + 4) + (1 + 3)
+        );
+        let deserialized = wincode::deserialize::<Tags>(&bytes).unwrap();
+        assert_eq!(deserialized, tags);
+    }
+
+    #[test]
+    fn derived_length_prefix_overflow() {
+        let queue = Queue {
+            items: (0..256u16).collect::<VecDeque<_>>(),
+        };
+
+        assert!(borsh::to_vec(&queue).is_err());
+        assert!(wincode::serialize(&queue).is_err());
+    }
+}
@@ -20,7 +20,10 @@ use borsh::{
 #[cfg(any(feature = "borsh", feature = "wincode"))]
 use core::str::from_utf8;
 use {
-    crate::{TrailingVec, U16PrefixedVec, U32PrefixedVec, U64PrefixedVec, U8PrefixedVec},
+    crate::{
+        CollectionsError, TrailingVec, U16PrefixedVec, U32PrefixedVec, U64PrefixedVec,
+        U8PrefixedVec,
+    },
     core::{
         fmt::{Debug, Formatter},
         ops::Deref,
@@ -85,10 +88,22 @@ use {
 /// ```
 #[cfg_attr(feature = "borsh", derive(BorshSerialize))]
 #[cfg_attr(feature = "wincode", derive(SchemaWrite, UninitBuilder))]
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
 #[repr(transparent)]
 pub struct TrailingStr(TrailingVec<u8>);
 
+impl PartialEq<str> for TrailingStr {
+    fn eq(&self, other: &str) -> bool {
+        self.deref() == other
+    }
+}
+
+impl PartialEq<&str> for TrailingStr {
+    fn eq(&self, other: &&str) -> bool {
+        self.deref() == *other
+    }
+}
+
 impl<T: AsRef<str>> From<T> for TrailingStr {
     fn from(value: T) -> Self {
         Self(TrailingVec::from(value.as_ref().as_bytes()))
@@ -154,10 +169,39 @@ macro_rules! prefixed_str_type {
         #[doc = concat!("A `str` that is serialized with an `", stringify!($prefix_type), "` length prefix.")]
         #[cfg_attr(feature = "borsh", derive(BorshSerialize))]
         #[cfg_attr(feature = "wincode", derive(SchemaWrite))]
-        #[derive(Clone, Eq, PartialEq)]
+        #[derive(Clone, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
         #[repr(transparent)]
         pub struct $name($container_type<u8>);
 
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.deref() == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.deref() == *other
+            }
+        }
+
+        impl $name {
+            #[doc = concat!("The maximum length in bytes representable by the `", stringify!($prefix_type), "` length prefix.")]
+            pub const MAX_LEN: usize = $prefix_type::MAX as usize;
+
+            /// Creates a new string, checking that its length in bytes fits in the
+            /// length prefix.
+            pub fn try_new<T: AsRef<str>>(value: T) -> Result<Self, CollectionsError> {
+                let value = value.as_ref();
+
+                if value.len() > Self::MAX_LEN {
+                    return Err(CollectionsError::LengthPrefixOverflow);
+                }
+
+                Ok(Self($container_type::from(value.as_bytes())))
+            }
+        }
+
         impl<T: AsRef<str>> From<T> for $name {
             fn from(value: T) -> Self {
                 Self($container_type::from(value.as_ref().as_bytes()))
@@ -199,17 +243,24 @@ macro_rules! prefixed_str_type {
         unsafe impl<'de, C: ConfigCore> SchemaRead<'de, C> for $name {
             type Dst = Self;
 
-            fn read(mut reader: impl Reader<'de>, dst: &mut MaybeUninit<Self::Dst>) -> ReadResult<()> {
-                let container = <$container_type::<u8> as SchemaRead<C>>::get(&mut reader)?;
+            fn read(reader: impl Reader<'de>, dst: &mut MaybeUninit<Self::Dst>) -> ReadResult<()> {
+                // SAFETY: `$name` is `repr(transparent)` over its container, so they
+                // have the same layout.
+                let container = unsafe {
+                    &mut *(dst as *mut MaybeUninit<Self>).cast::<MaybeUninit<$container_type<u8>>>()
+                };
+                // Deserialize the container directly into the destination.
+                <$container_type<u8> as SchemaRead<'de, C>>::read(reader, container)?;
 
-                // Validate that we got valid UTF-8 bytes, as `TrailingStr` must
+                // Validate that we got valid UTF-8 bytes, as `PrefixedStr` must
                 // always be valid UTF-8.
-                if from_utf8(&container).is_err() {
+                // SAFETY: The container was initialized by the read above.
+                if from_utf8(unsafe { container.assume_init_ref() }).is_err() {
+                    // SAFETY: The container is initialized and not used afterwards.
+                    unsafe { container.assume_init_drop() };
                     return Err(ReadError::Custom("invalid UTF-8 bytes"));
                 }
 
-                dst.write(Self(container));
-
                 Ok(())
             }
         }
@@ -231,7 +282,10 @@ prefixed_str_type!(U64PrefixedStr, U64PrefixedVec, u64);
 #[cfg(test)]
 mod tests {
     use {
-        alloc::{string::String, vec::Vec},
+        alloc::{
+            string::{String, ToString},
+            vec::Vec,
+        },
         borsh::{io::ErrorKind, BorshDeserialize, BorshSerialize},
         core::mem::size_of,
         wincode::WriteError,
@@ -426,6 +480,21 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn prefixed_str_try_new() {
+        assert_eq!(U8PrefixedStr::MAX_LEN, 255);
+        assert_eq!(U16PrefixedStr::MAX_LEN, 65_535);
+
+        // The limit applies to the length in bytes, not in characters.
+        let string = U8PrefixedStr::try_new("é".repeat(127)).unwrap();
+        assert_eq!(string.len(), 254);
+
+        assert_eq!(
+            U8PrefixedStr::try_new("é".repeat(128)).unwrap_err(),
+            CollectionsError::LengthPrefixOverflow
+        );
+    }
+
     #[test]
     fn prefixed_str_borsh_with_remaining_bytes() {
         let value = "⚙️ serialized data with extra bytes";
@@ -496,4 +565,29 @@ mod tests {
 
         assert!(maybe_deserialized.is_err());
     }
+
+    #[test]
+    fn str_wrapper_comparisons() {
+        assert_eq!(U8PrefixedStr::from("token"), "token");
+        assert_eq!(U8PrefixedStr::from("token"), *"token");
+        assert_eq!(TrailingStr::from("token"), "token");
+        assert_ne!(U16PrefixedStr::from("token"), "other");
+
+        assert_eq!(U32PrefixedStr::default(), "");
+        assert_eq!(TrailingStr::default(), "");
+
+        // Ordering matches the ordering of the underlying strings.
+        let mut strings = [
+            U8PrefixedStr::from("é"),
+            U8PrefixedStr::from("abd"),
+            U8PrefixedStr::from("z"),
+            U8PrefixedStr::from("abc"),
+            U8PrefixedStr::from("ab"),
+        ];
+        strings.sort();
+        assert_eq!(
+            strings.map(|s| s.to_string()),
+            ["ab", "abc", "abd", "z", "é"]
+        );
+    }
 }
@@ -0,0 +1,339 @@
+//! Types for serializing strings restricted to a character set.
+//!
+//! This module provides `ValidatedStr`, a wrapper around any of the string types of
+//! this crate that only accepts strings whose characters are allowed by a
+//! `CharsetValidator`. The validation is performed on construction and on
+//! deserialization, so a `ValidatedStr` always holds a valid string.
+//!
+//! The serialized representation is the same as the wrapped string type.
+
+#[cfg(feature = "borsh")]
+use borsh::{
+    io::{ErrorKind, Read, Write},
+    BorshDeserialize, BorshSerialize,
+};
+use {
+    crate::{
+        CollectionsError, TrailingStr, U16PrefixedStr, U32PrefixedStr, U64PrefixedStr,
+        U8PrefixedStr,
+    },
+    core::{
+        cmp::Ordering,
+        fmt::{Debug, Formatter},
+        hash::{Hash, Hasher},
+        marker::PhantomData,
+        ops::Deref,
+    },
+};
+#[cfg(feature = "wincode")]
+use {
+    core::mem::MaybeUninit,
+    wincode::{
+        config::ConfigCore,
+        io::{Reader, Writer},
+        ReadError, ReadResult, SchemaRead, SchemaWrite, WriteResult,
+    },
+};
+
+/// Trait defining the set of characters allowed in a `ValidatedStr`.
+pub trait CharsetValidator {
+    /// Returns `true` if all characters of `value` are allowed.
+    fn is_valid(value: &str) -> bool;
+}
+
+/// Validator accepting printable ASCII characters.
+///
+/// Non-ASCII and control characters are rejected.
+pub struct Ascii;
+
+impl CharsetValidator for Ascii {
+    fn is_valid(value: &str) -> bool {
+        value
+            .bytes()
+            .all(|byte| byte.is_ascii_graphic() || byte == b' ')
+    }
+}
+
+/// Validator accepting ASCII letters and digits.
+pub struct AsciiAlphanumeric;
+
+impl CharsetValidator for AsciiAlphanumeric {
+    fn is_valid(value: &str) -> bool {
+        value.bytes().all(|byte| byte.is_ascii_alphanumeric())
+    }
+}
+
+/// A string whose characters are validated by a `CharsetValidator`.
+///
+/// # Examples
+///
+/// ```
+/// use spl_collections::{AsciiU8PrefixedStr, U8PrefixedStr};
+///
+/// let symbol = AsciiU8PrefixedStr::try_from("SOL").unwrap();
+/// assert_eq!(&*symbol, "SOL");
+///
+/// // Control and non-ASCII characters are rejected.
+/// assert!(AsciiU8PrefixedStr::try_from("SOL\n").is_err());
+/// assert!(AsciiU8PrefixedStr::try_from("☀️").is_err());
+///
+/// // The serialized representation matches the wrapped string type.
+/// assert_eq!(
+///     wincode::serialize(&symbol).unwrap(),
+///     wincode::serialize(&U8PrefixedStr::from("SOL")).unwrap(),
+/// );
+/// ```
+#[repr(transparent)]
+pub struct ValidatedStr<S, V>(S, PhantomData<V>);
+
+impl<S: Deref<Target = str>, V: CharsetValidator> ValidatedStr<S, V> {
+    /// Creates a new `ValidatedStr`, validating the characters of `value`.
+    pub fn try_new(value: S) -> Result<Self, CollectionsError> {
+        if V::is_valid(&value) {
+            Ok(Self(value, PhantomData))
+        } else {
+            Err(CollectionsError::InvalidCharset)
+        }
+    }
+}
+
+impl<S, V> ValidatedStr<S, V> {
+    /// Returns the wrapped string.
+    pub fn into_inner(self) -> S {
+        self.0
+    }
+}
+
+impl<'a, S, V> TryFrom<&'a str> for ValidatedStr<S, V>
+where
+    S: Deref<Target = str> + From<&'a str>,
+    V: CharsetValidator,
+{
+    type Error = CollectionsError;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        // Validate before converting to avoid copying invalid strings.
+        if V::is_valid(value) {
+            Ok(Self(S::from(value), PhantomData))
+        } else {
+            Err(CollectionsError::InvalidCharset)
+        }
+    }
+}
+
+impl<S: Deref<Target = str>, V> Deref for ValidatedStr<S, V> {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<S: Clone, V> Clone for ValidatedStr<S, V> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone(), PhantomData)
+    }
+}
+
+impl<S: PartialEq, V> PartialEq for ValidatedStr<S, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<S: Eq, V> Eq for ValidatedStr<S, V> {}
+
+impl<S: Deref<Target = str>, V> PartialEq<str> for ValidatedStr<S, V> {
+    fn eq(&self, other: &str) -> bool {
+        self.deref() == other
+    }
+}
+
+impl<S: Deref<Target = str>, V> PartialEq<&str> for ValidatedStr<S, V> {
+    fn eq(&self, other: &&str) -> bool {
+        self.deref() == *other
+    }
+}
+
+impl<S: PartialOrd, V> PartialOrd for ValidatedStr<S, V> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.0.partial_cmp(&other.0)
+    }
+}
+
+impl<S: Ord, V> Ord for ValidatedStr<S, V> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl<S: Hash, V> Hash for ValidatedStr<S, V> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl<S: Debug, V> Debug for ValidatedStr<S, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl<S: BorshSerialize, V> BorshSerialize for ValidatedStr<S, V> {
+    fn serialize<W: Write>(&self, writer: &mut W) -> borsh::io::Result<()> {
+        self.0.serialize(writer)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl<S, V> BorshDeserialize for ValidatedStr<S, V>
+where
+    S: BorshDeserialize + Deref<Target = str>,
+    V: CharsetValidator,
+{
+    fn deserialize_reader<R: Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        let value = S::deserialize_reader(reader)?;
+        Self::try_new(value).map_err(|_| ErrorKind::InvalidData.into())
+    }
+}
+
+#[cfg(feature = "wincode")]
+unsafe impl<S, V, C> SchemaWrite<C> for ValidatedStr<S, V>
+where
+    C: ConfigCore,
+    S: SchemaWrite<C, Src = S>,
+{
+    type Src = Self;
+
+    #[inline(always)]
+    fn size_of(src: &Self::Src) -> WriteResult<usize> {
+        S::size_of(&src.0)
+    }
+
+    #[inline(always)]
+    fn write(writer: impl Writer, src: &Self::Src) -> WriteResult<()> {
+        S::write(writer, &src.0)
+    }
+}
+
+#[cfg(feature = "wincode")]
+unsafe impl<'de, S, V, C> SchemaRead<'de, C> for ValidatedStr<S, V>
+where
+    C: ConfigCore,
+    S: SchemaRead<'de, C, Dst = S> + Deref<Target = str>,
+    V: CharsetValidator,
+{
+    type Dst = Self;
+
+    fn read(mut reader: impl Reader<'de>, dst: &mut MaybeUninit<Self::Dst>) -> ReadResult<()> {
+        let value = S::get(&mut reader)?;
+
+        let Ok(value) = Self::try_new(value) else {
+            return Err(ReadError::Custom("invalid characters in validated string"));
+        };
+
+        dst.write(value);
+
+        Ok(())
+    }
+}
+
+/// A `TrailingStr` restricted to printable ASCII characters.
+pub type AsciiTrailingStr = ValidatedStr<TrailingStr, Ascii>;
+
+/// A `U8PrefixedStr` restricted to printable ASCII characters.
+pub type AsciiU8PrefixedStr = ValidatedStr<U8PrefixedStr, Ascii>;
+
+/// A `U16PrefixedStr` restricted to printable ASCII characters.
+pub type AsciiU16PrefixedStr = ValidatedStr<U16PrefixedStr, Ascii>;
+
+/// A `U32PrefixedStr` restricted to printable ASCII characters.
+pub type AsciiU32PrefixedStr = ValidatedStr<U32PrefixedStr, Ascii>;
+
+/// A `U64PrefixedStr` restricted to printable ASCII characters.
+pub type AsciiU64PrefixedStr = ValidatedStr<U64PrefixedStr, Ascii>;
+
+#[cfg(test)]
+mod tests {
+    use {
+        alloc::vec::Vec,
+        borsh::{io::ErrorKind, BorshDeserialize},
+    };
+
+    use super::*;
+
+    #[test]
+    fn ascii_validator() {
+        assert!(Ascii::is_valid("Hello, World! ~0123"));
+        assert!(Ascii::is_valid(""));
+        assert!(!Ascii::is_valid("tab\there"));
+        assert!(!Ascii::is_valid("null\0"));
+        assert!(!Ascii::is_valid("delete\x7f"));
+        assert!(!Ascii::is_valid("café"));
+
+        assert!(AsciiAlphanumeric::is_valid("USDC2"));
+        assert!(!AsciiAlphanumeric::is_valid("USD C"));
+        assert!(!AsciiAlphanumeric::is_valid("USD-C"));
+    }
+
+    #[test]
+    fn validated_str_construction() {
+        let symbol = AsciiU8PrefixedStr::try_new(U8PrefixedStr::from("SOL")).unwrap();
+        assert_eq!(&*symbol, "SOL");
+        assert_eq!(symbol.into_inner(), U8PrefixedStr::from("SOL"));
+
+        assert_eq!(
+            AsciiU8PrefixedStr::try_new(U8PrefixedStr::from("SOL\r\n")).unwrap_err(),
+            CollectionsError::InvalidCharset
+        );
+        assert_eq!(
+            ValidatedStr::<U16PrefixedStr, AsciiAlphanumeric>::try_from("no spaces").unwrap_err(),
+            CollectionsError::InvalidCharset
+        );
+    }
+
+    #[test]
+    fn validated_str_borsh_round_trip() {
+        let original = AsciiU16PrefixedStr::try_from("example.sol").unwrap();
+        let bytes = borsh::to_vec(&original).unwrap();
+
+        assert_eq!(
+            bytes,
+            borsh::to_vec(&U16PrefixedStr::from("example.sol")).unwrap()
+        );
+
+        let deserialized = AsciiU16PrefixedStr::try_from_slice(&bytes).unwrap();
+        assert_eq!(deserialized, original);
+    }
+
+    #[test]
+    fn validated_str_wincode_round_trip() {
+        let original = AsciiTrailingStr::try_from("example.sol").unwrap();
+        let bytes = wincode::serialize(&original).unwrap();
+
+        assert_eq!(bytes, "example.sol".as_bytes());
+
+        let deserialized = wincode::deserialize::<AsciiTrailingStr>(&bytes).unwrap();
+        assert_eq!(deserialized, original);
+    }
+
+    #[test]
+    fn validated_str_rejects_invalid_characters_on_deserialize() {
+        let value = "bad\u{7}bell";
+
+        let mut bytes = Vec::<u8>::new();
+        bytes.push(value.len() as u8);
+        bytes.extend_from_slice(value.as_bytes());
+
+        // The underlying string type accepts the bytes.
+        assert!(U8PrefixedStr::try_from_slice(&bytes).is_ok());
+        assert!(wincode::deserialize::<U8PrefixedStr>(&bytes).is_ok());
+
+        // The validated string does not.
+        let result = AsciiU8PrefixedStr::try_from_slice(&bytes);
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+
+        assert!(wincode::deserialize::<AsciiU8PrefixedStr>(&bytes).is_err());
+    }
+}
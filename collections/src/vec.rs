@@ -11,30 +11,189 @@
 //!
 //! The types in this module also implement the `Deref` trait, allowing them to be used
 //! as regular `Vec<T>` in most contexts.
+//!
+//! With the `serde-traits` feature, byte vectors (e.g., `TrailingVec<u8>`) implement
+//! `Serialize` and `Deserialize` as a byte blob rather than a sequence of integers:
+//! human-readable formats (e.g., JSON) use a base64 string, while binary formats
+//! (e.g., bincode) use raw bytes.
 
+#[cfg(any(feature = "borsh", feature = "wincode"))]
+use crate::depth::{DepthGuard, NESTING_DEPTH_EXCEEDED};
 #[cfg(feature = "borsh")]
 use borsh::{
     io::{ErrorKind, Read, Write},
     BorshDeserialize, BorshSerialize,
 };
 use {
+    crate::CollectionsError,
     alloc::vec::Vec,
     core::{
         fmt::{Debug, Formatter},
         ops::Deref,
     },
 };
+#[cfg(feature = "serde-traits")]
+use {
+    base64::{prelude::BASE64_STANDARD, Engine},
+    serde::{
+        de::{Error as _, SeqAccess, Unexpected, Visitor},
+        ser::Error as _,
+        Deserialize, Deserializer, Serialize, Serializer,
+    },
+};
 #[cfg(feature = "wincode")]
 use {
     core::mem::MaybeUninit,
     wincode::{
         config::ConfigCore,
-        error::{write_length_encoding_overflow, ReadError},
+        containers::decode_into_slice_t,
+        error::{preallocation_size_limit, write_length_encoding_overflow},
         io::{Reader, Writer},
-        ReadResult, SchemaRead, SchemaWrite, WriteResult,
+        ReadError, ReadResult, SchemaRead, SchemaWrite, TypeMeta, WriteResult,
     },
 };
 
+/// Read `len` items from `reader` into a new `Vec`.
+///
+/// Items are deserialized in place into the spare capacity of the `Vec`, avoiding
+/// a temporary copy of each item. Items with a zero-copy representation are read
+/// with a single copy of the underlying bytes.
+#[cfg(feature = "wincode")]
+#[inline(always)]
+pub(crate) fn read_items<'de, T, C>(reader: impl Reader<'de>, len: usize) -> ReadResult<Vec<T>>
+where
+    C: ConfigCore,
+    T: SchemaRead<'de, C, Dst = T>,
+{
+    let Some(_guard) = DepthGuard::enter() else {
+        return Err(ReadError::Custom(NESTING_DEPTH_EXCEEDED));
+    };
+
+    // The length is read from untrusted input, so check it against the
+    // preallocation limit of the configuration before allocating.
+    if let Some(limit) = C::PREALLOCATION_SIZE_LIMIT {
+        let needed = len.saturating_mul(size_of::<T>());
+
+        if needed > limit {
+            return Err(preallocation_size_limit(needed, limit).into());
+        }
+    }
+
+    let mut items = Vec::with_capacity(len);
+    decode_into_slice_t::<T, C>(reader, &mut items.spare_capacity_mut()[..len])?;
+    // SAFETY: `decode_into_slice_t` initialized the first `len` items, and the
+    // capacity of the `Vec` is at least `len`.
+    unsafe { items.set_len(len) };
+
+    Ok(items)
+}
+
+/// Maximum number of bytes preallocated for items read with `borsh`.
+#[cfg(feature = "borsh")]
+const MAX_BORSH_PREALLOCATION: usize = 4096;
+
+/// Returns the initial capacity for reading `len` items with `borsh`.
+///
+/// `borsh` readers do not expose the number of remaining bytes, so the capacity
+/// is bounded to avoid large allocations from an untrusted length prefix. The
+/// collection grows as the items are actually read.
+#[cfg(feature = "borsh")]
+#[inline(always)]
+pub(crate) fn cautious_capacity<T>(len: usize) -> usize {
+    len.min(MAX_BORSH_PREALLOCATION / size_of::<T>().max(1))
+}
+
+/// Compute the serialized size of a sequence of items, excluding any length prefix.
+///
+/// When `T` has a statically known serialized size, the size is computed directly
+/// from the number of items; otherwise the serialized size of each item is summed,
+/// which is required for elements with a variable encoded size (e.g., prefixed
+/// strings or vectors).
+#[cfg(feature = "wincode")]
+#[inline(always)]
+pub(crate) fn size_of_items<T, C>(items: &[T]) -> WriteResult<usize>
+where
+    C: ConfigCore,
+    T: SchemaWrite<C, Src = T>,
+{
+    if let TypeMeta::Static { size, .. } = <T as SchemaWrite<C>>::TYPE_META {
+        return Ok(items.len().saturating_mul(size));
+    }
+
+    items
+        .iter()
+        .try_fold(0usize, |size, item| -> WriteResult<usize> {
+            Ok(size.saturating_add(<T as SchemaWrite<C>>::size_of(item)?))
+        })
+}
+
+/// Serialize `bytes` as a base64 string for human-readable formats, and as raw
+/// bytes otherwise.
+#[cfg(feature = "serde-traits")]
+pub(crate) fn serialize_bytes<S: Serializer>(
+    bytes: &[u8],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&BASE64_STANDARD.encode(bytes))
+    } else {
+        serializer.serialize_bytes(bytes)
+    }
+}
+
+/// Deserialize bytes serialized by `serialize_bytes`.
+///
+/// Human-readable formats also accept a sequence of integers, which is how byte
+/// vectors are serialized by default.
+#[cfg(feature = "serde-traits")]
+pub(crate) fn deserialize_bytes<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<u8>, D::Error> {
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_any(BytesVisitor)
+    } else {
+        deserializer.deserialize_byte_buf(BytesVisitor)
+    }
+}
+
+/// Visitor for deserializing a byte vector.
+#[cfg(feature = "serde-traits")]
+struct BytesVisitor;
+
+#[cfg(feature = "serde-traits")]
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut Formatter) -> core::fmt::Result {
+        formatter.write_str("a byte array or a base64 string")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        BASE64_STANDARD
+            .decode(v)
+            .map_err(|_| E::invalid_value(Unexpected::Str(v), &self))
+    }
+
+    fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(Vec::from(v))
+    }
+
+    fn visit_byte_buf<E: serde::de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(v)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        // Do not trust the size hint of the input for the initial allocation.
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+
+        Ok(bytes)
+    }
+}
+
 /// A `Vec<T>` serialized without a length prefix.
 ///
 /// This is useful for serializing a `Vec<T>` that is the last field
@@ -75,10 +234,28 @@ use {
 /// # assert_eq!(deserialized.amount, my_struct.amount);
 /// # assert_eq!(deserialized.items, my_struct.items);
 /// ```
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, Hash, Ord, PartialEq, PartialOrd)]
 #[repr(transparent)]
 pub struct TrailingVec<T>(Vec<T>);
 
+impl<T> Default for TrailingVec<T> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<T: PartialEq> PartialEq<[T]> for TrailingVec<T> {
+    fn eq(&self, other: &[T]) -> bool {
+        self.0 == other
+    }
+}
+
+impl<T: PartialEq> PartialEq<Vec<T>> for TrailingVec<T> {
+    fn eq(&self, other: &Vec<T>) -> bool {
+        self.0 == *other
+    }
+}
+
 impl<T> From<Vec<T>> for TrailingVec<T> {
     fn from(value: Vec<T>) -> Self {
         Self(value)
@@ -111,6 +288,20 @@ impl<T: Debug> Debug for TrailingVec<T> {
     }
 }
 
+#[cfg(feature = "serde-traits")]
+impl Serialize for TrailingVec<u8> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_bytes(&self.0, serializer)
+    }
+}
+
+#[cfg(feature = "serde-traits")]
+impl<'de> Deserialize<'de> for TrailingVec<u8> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_bytes(deserializer).map(Self)
+    }
+}
+
 #[cfg(feature = "borsh")]
 impl<T: BorshSerialize> BorshSerialize for TrailingVec<T> {
     fn serialize<W: Write>(&self, writer: &mut W) -> borsh::io::Result<()> {
@@ -142,14 +333,7 @@ where
 
     #[inline(always)]
     fn size_of(src: &Self::Src) -> WriteResult<usize> {
-        // Sum the serialized size of each element, matching the per-element
-        // decoding performed by the read side.
-        let expected_size = src
-            .0
-            .iter()
-            .try_fold(0usize, |size, item| -> WriteResult<usize> {
-                Ok(size.saturating_add(<T as SchemaWrite<C>>::size_of(item)?))
-            })?;
+        let expected_size = size_of_items::<T, C>(&src.0)?;
 
         // `Vec` capacity is limited to `isize::MAX`.
         if expected_size > isize::MAX as usize {
@@ -196,10 +380,43 @@ where
 macro_rules! prefixed_vec_type {
     ( $name:tt, $prefix_type:tt ) => {
         #[doc = concat!("A `Vec<T>` serialized with an `", stringify!($prefix_type), "` length prefix.")]
-        #[derive(Clone, Eq, PartialEq)]
+        #[derive(Clone, Eq, Hash, Ord, PartialEq, PartialOrd)]
         #[repr(transparent)]
         pub struct $name<T>(Vec<T>);
 
+        impl<T> Default for $name<T> {
+            fn default() -> Self {
+                Self(Vec::new())
+            }
+        }
+
+        impl<T: PartialEq> PartialEq<[T]> for $name<T> {
+            fn eq(&self, other: &[T]) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl<T: PartialEq> PartialEq<Vec<T>> for $name<T> {
+            fn eq(&self, other: &Vec<T>) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl<T> $name<T> {
+            #[doc = concat!("The maximum number of items representable by the `", stringify!($prefix_type), "` length prefix.")]
+            pub const MAX_LEN: usize = $prefix_type::MAX as usize;
+
+            /// Creates a new vector, checking that the number of items fits in the
+            /// length prefix.
+            pub fn try_new(value: Vec<T>) -> Result<Self, CollectionsError> {
+                if value.len() > Self::MAX_LEN {
+                    return Err(CollectionsError::LengthPrefixOverflow);
+                }
+
+                Ok(Self(value))
+            }
+        }
+
         impl<T> From<Vec<T>> for $name<T> {
             fn from(value: Vec<T>) -> Self {
                 Self(value)
@@ -232,6 +449,27 @@ macro_rules! prefixed_vec_type {
             }
         }
 
+        #[cfg(feature = "serde-traits")]
+        impl Serialize for $name<u8> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                if self.0.len() > Self::MAX_LEN {
+                    return Err(S::Error::custom(concat!(
+                        "length exceeds ",
+                        stringify!($prefix_type::MAX)
+                    )));
+                }
+
+                serialize_bytes(&self.0, serializer)
+            }
+        }
+
+        #[cfg(feature = "serde-traits")]
+        impl<'de> Deserialize<'de> for $name<u8> {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                Self::try_new(deserialize_bytes(deserializer)?).map_err(D::Error::custom)
+            }
+        }
+
         #[cfg(feature = "borsh")]
         impl<T: BorshSerialize> BorshSerialize for $name<T> {
             fn serialize<W: Write>(&self, writer: &mut W) -> borsh::io::Result<()> {
@@ -246,8 +484,15 @@ macro_rules! prefixed_vec_type {
         #[cfg(feature = "borsh")]
         impl<T: BorshDeserialize> BorshDeserialize for $name<T> {
             fn deserialize_reader<R: Read>(reader: &mut R) -> borsh::io::Result<Self> {
+                let Some(_guard) = DepthGuard::enter() else {
+                    return Err(borsh::io::Error::new(
+                        ErrorKind::InvalidData,
+                        NESTING_DEPTH_EXCEEDED,
+                    ));
+                };
+
                 let prefix = $prefix_type::deserialize_reader(reader)? as usize;
-                let mut items: Vec<T> = Vec::with_capacity(prefix);
+                let mut items: Vec<T> = Vec::with_capacity(cautious_capacity::<T>(prefix));
 
                 while items.len() < prefix {
                     let Ok(item) = T::deserialize_reader(reader) else {
@@ -271,20 +516,17 @@ macro_rules! prefixed_vec_type {
 
             #[inline(always)]
             fn size_of(src: &Self::Src) -> WriteResult<usize> {
-                // Start with the length prefix, then sum the serialized size of
-                // each element, matching the per-element decoding performed by
-                // the read side.
-                let expected_size = src
-                    .0
-                    .iter()
-                    .try_fold(core::mem::size_of::<$prefix_type>(), |size, item| -> WriteResult<usize> {
-                        Ok(size.saturating_add(<T as SchemaWrite<C>>::size_of(item)?))
-                    })?;
+                let prefix = $prefix_type::try_from(src.0.len())
+                    .map_err(|_| write_length_encoding_overflow(stringify!($prefix_type::MAX)))?;
+                // The size of the prefix depends on the integer encoding of the
+                // configuration.
+                let expected_size = size_of_items::<T, C>(&src.0)?
+                    .saturating_add(<$prefix_type as SchemaWrite<C>>::size_of(&prefix)?);
 
                 // `Vec` capacity is limited to `isize::MAX`.
                 if expected_size > isize::MAX as usize {
                     return Err(write_length_encoding_overflow(
-                        "size of items in TrailingVec",
+                        concat!("size of items in ", stringify!($name)),
                     ));
                 }
 
@@ -323,17 +565,7 @@ macro_rules! prefixed_vec_type {
                 // SAFETY: We have just read the prefix from the reader, so it is initialized.
                 let prefix = unsafe { prefix.assume_init() } as usize;
 
-                let mut items = Vec::with_capacity(prefix);
-
-                while items.len() < prefix {
-                    let Ok(item) = T::get(&mut reader) else {
-                        return Err(ReadError::Custom("failed to deserialize"));
-                    };
-
-                    items.push(item);
-                }
-
-                dst.write(Self(items));
+                dst.write(Self(read_items::<T, C>(reader, prefix)?));
 
                 Ok(())
             }
@@ -503,6 +735,20 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn prefixed_vec_try_new() {
+        assert_eq!(U8PrefixedVec::<u8>::MAX_LEN, 255);
+
+        let vec = U8PrefixedVec::try_new(vec![1u8; 255]).unwrap();
+        assert_eq!(vec.len(), 255);
+
+        assert_eq!(
+            U8PrefixedVec::try_new(vec![1u8; 256]).unwrap_err(),
+            CollectionsError::LengthPrefixOverflow
+        );
+        assert!(U16PrefixedVec::try_new(vec![1u8; 256]).is_ok());
+    }
+
     #[test]
     fn prefixed_vec_borsh_with_remaining_bytes() {
         // Bytes representation for a `U8PrefixedVec<u64>` with 8 `u64` values
@@ -629,4 +875,326 @@ mod tests {
         // consumed exactly the bytes it wrote.
         assert_eq!(decoded.trailing_marker, 0x0011_2233_4455_6677);
     }
+
+    #[cfg(feature = "wincode")]
+    #[test]
+    fn prefixed_vec_wincode_size_of_variable_size_elements() {
+        use crate::U8PrefixedStr;
+
+        let original = U16PrefixedVec::from(vec![
+            U8PrefixedStr::from("a"),
+            U8PrefixedStr::from("variable"),
+            U8PrefixedStr::from("sized elements"),
+        ]);
+
+        // 2 (u16 prefix) + each element's u8 prefix and string bytes.
+        let expected_size = 2 + (1 + 1) + (1 + 8) + (1 + 14);
+
+        assert_eq!(
+            wincode::serialized_size(&original).unwrap(),
+            expected_size as u64
+        );
+
+        let mut bytes = vec![0u8; expected_size];
+        wincode::serialize_into(bytes.as_mut_slice(), &original).unwrap();
+
+        let decoded = wincode::deserialize::<U16PrefixedVec<U8PrefixedStr>>(&bytes).unwrap();
+        assert_eq!(decoded, original);
+
+        // Same for a trailing vec, which has no length prefix.
+        let original = TrailingVec::from(vec![
+            U8PrefixedStr::from("trailing"),
+            U8PrefixedStr::from("vec"),
+        ]);
+
+        assert_eq!(
+            wincode::serialized_size(&original).unwrap(),
+            (1 + 8) + (1 + 3)
+        );
+    }
+
+    #[cfg(feature = "wincode")]
+    #[test]
+    fn prefixed_vec_wincode_size_of_static_elements() {
+        const VALUES: [u64; 10] = [255u64; 10];
+
+        let original = U32PrefixedVec::from(&VALUES);
+
+        assert_eq!(
+            wincode::serialized_size(&original).unwrap() as usize,
+            size_of::<u32>() + size_of::<u64>() * VALUES.len()
+        );
+    }
+
+    #[cfg(feature = "wincode")]
+    #[test]
+    fn prefixed_vec_wincode_truncated_items() {
+        use crate::U8PrefixedStr;
+
+        let original = U8PrefixedVec::from(vec![
+            U8PrefixedStr::from("first"),
+            U8PrefixedStr::from("second"),
+        ]);
+        let bytes = wincode::serialize(&original).unwrap();
+
+        // Items read in place before the failing one must not leak or be
+        // observable in the result.
+        for len in 0..bytes.len() {
+            assert!(wincode::deserialize::<U8PrefixedVec<U8PrefixedStr>>(&bytes[..len]).is_err());
+        }
+
+        let decoded = wincode::deserialize::<U8PrefixedVec<U8PrefixedStr>>(&bytes).unwrap();
+        assert_eq!(decoded, original);
+
+        // Zero-copy elements are read with a single copy.
+        let original = U16PrefixedVec::from(&[7u8; 32]);
+        let bytes = wincode::serialize(&original).unwrap();
+
+        assert!(wincode::deserialize::<U16PrefixedVec<u8>>(&bytes[..bytes.len() - 1]).is_err());
+        assert_eq!(
+            wincode::deserialize::<U16PrefixedVec<u8>>(&bytes).unwrap(),
+            original
+        );
+    }
+
+    #[test]
+    fn vec_wrapper_comparisons() {
+        let vec = U8PrefixedVec::from(vec![1u32, 2, 3]);
+
+        assert_eq!(vec, vec![1, 2, 3]);
+        assert_eq!(vec, [1, 2, 3][..]);
+        assert_eq!(TrailingVec::from(vec![1u32, 2, 3]), vec![1, 2, 3]);
+
+        assert!(U8PrefixedVec::<u32>::default().is_empty());
+        assert!(TrailingVec::<u32>::default().is_empty());
+
+        // Usable as ordered keys.
+        let set = alloc::collections::BTreeSet::from([
+            U8PrefixedVec::from(vec![2u32]),
+            U8PrefixedVec::from(vec![1u32, 5]),
+            U8PrefixedVec::from(vec![1u32]),
+        ]);
+        assert_eq!(
+            set.into_iter().collect::<Vec<_>>(),
+            [
+                U8PrefixedVec::from(vec![1u32]),
+                U8PrefixedVec::from(vec![1u32, 5]),
+                U8PrefixedVec::from(vec![2u32]),
+            ]
+        );
+    }
+
+    #[test]
+    fn byte_vec_serde_json() {
+        let original = TrailingVec::from(&[0u8, 1, 2, 253, 254, 255]);
+        let json = serde_json::to_string(&original).unwrap();
+
+        // Bytes are serialized as a base64 string rather than an array of integers.
+        assert_eq!(json, "\"AAEC/f7/\"");
+        assert_eq!(
+            serde_json::from_str::<TrailingVec<u8>>(&json).unwrap(),
+            original
+        );
+
+        // Arrays of integers are still accepted.
+        assert_eq!(
+            serde_json::from_str::<U16PrefixedVec<u8>>("[0,1,2,253,254,255]").unwrap(),
+            original.0
+        );
+
+        assert!(serde_json::from_str::<TrailingVec<u8>>("\"not base64!\"").is_err());
+        assert!(serde_json::from_str::<TrailingVec<u8>>("[256]").is_err());
+    }
+
+    #[test]
+    fn byte_vec_serde_bincode() {
+        let original = U32PrefixedVec::from(&[7u8; 100]);
+        let bytes = bincode::serialize(&original).unwrap();
+
+        // Bytes are serialized as raw bytes, after bincode's own length prefix.
+        assert_eq!(bytes.len(), size_of::<u64>() + 100);
+        assert_eq!(&bytes[size_of::<u64>()..], &[7u8; 100]);
+        assert_eq!(
+            bincode::deserialize::<U32PrefixedVec<u8>>(&bytes).unwrap(),
+            original
+        );
+    }
+
+    #[test]
+    fn byte_vec_serde_length_prefix_overflow() {
+        let original = U8PrefixedVec::from(&[1u8; 256]);
+        assert!(serde_json::to_string(&original).is_err());
+
+        let json = serde_json::to_string(&TrailingVec::from(&[1u8; 256])).unwrap();
+        assert!(serde_json::from_str::<U8PrefixedVec<u8>>(&json).is_err());
+        assert_eq!(
+            serde_json::from_str::<U16PrefixedVec<u8>>(&json)
+                .unwrap()
+                .len(),
+            256
+        );
+    }
+
+    /// Round-trips a nested collection with `borsh` and with `wincode` using both
+    /// the default configuration and a variable-length integer encoding, which
+    /// changes the size of the length prefixes.
+    macro_rules! nested_round_trip {
+        ( $test:ident, $type:ty, $value:expr, $expected_len:expr ) => {
+            #[test]
+            fn $test() {
+                let original: $type = $value;
+
+                // borsh
+                let bytes = borsh::to_vec(&original).unwrap();
+                assert_eq!(bytes.len(), $expected_len);
+                assert_eq!(<$type>::try_from_slice(&bytes).unwrap(), original);
+
+                // wincode (default configuration)
+                let bytes = wincode::serialize(&original).unwrap();
+                assert_eq!(bytes.len(), $expected_len);
+                assert_eq!(
+                    wincode::serialized_size(&original).unwrap() as usize,
+                    bytes.len()
+                );
+                assert_eq!(wincode::deserialize::<$type>(&bytes).unwrap(), original);
+
+                // wincode (variable-length integer encoding)
+                let config = wincode::config::Configuration::default().with_varint_encoding();
+                let bytes = wincode::config::serialize(&original, config).unwrap();
+                assert_eq!(
+                    wincode::config::serialized_size(&original, config).unwrap() as usize,
+                    bytes.len()
+                );
+                assert_eq!(
+                    wincode::config::deserialize::<$type, _>(&bytes, config).unwrap(),
+                    original
+                );
+            }
+        };
+    }
+
+    nested_round_trip!(
+        nested_vec_of_str,
+        U16PrefixedVec<crate::U8PrefixedStr>,
+        U16PrefixedVec::from(vec!["alpha".into(), "".into(), "gamma".into()]),
+        2 + (1 + 5) + 1 + (1 + 5)
+    );
+
+    nested_round_trip!(
+        nested_vec_of_vec,
+        U8PrefixedVec<U16PrefixedVec<u32>>,
+        U8PrefixedVec::from(vec![
+            U16PrefixedVec::from(&[1, 2, 300]),
+            U16PrefixedVec::default(),
+            U16PrefixedVec::from(&[u32::MAX]),
+        ]),
+        1 + (2 + 3 * 4) + 2 + (2 + 4)
+    );
+
+    nested_round_trip!(
+        nested_vec_deque_of_vec,
+        crate::U32PrefixedVecDeque<U8PrefixedVec<u64>>,
+        crate::U32PrefixedVecDeque::from(vec![
+            U8PrefixedVec::from(&[1, 2]),
+            U8PrefixedVec::from(&[u64::MAX]),
+        ]),
+        4 + (1 + 2 * 8) + (1 + 8)
+    );
+
+    nested_round_trip!(
+        nested_trailing_vec_of_str,
+        TrailingVec<crate::U16PrefixedStr>,
+        TrailingVec::from(vec!["one".into(), "two".into()]),
+        (2 + 3) + (2 + 3)
+    );
+
+    nested_round_trip!(
+        nested_vec_of_vec_deque,
+        U64PrefixedVec<crate::U8PrefixedVecDeque<u16>>,
+        U64PrefixedVec::from(vec![crate::U8PrefixedVecDeque::from(&[7, 8, 9])]),
+        8 + (1 + 3 * 2)
+    );
+
+    #[test]
+    fn nested_hostile_length_prefix() {
+        // An outer vector with a single item, whose length prefix claims
+        // `u64::MAX` items but is followed by no bytes.
+        let mut bytes = Vec::from(1u64.to_le_bytes());
+        bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+
+        type Nested = U64PrefixedVec<U64PrefixedVec<u32>>;
+
+        // The prefix is rejected without preallocating the claimed items.
+        assert!(Nested::try_from_slice(&bytes).is_err());
+        assert!(matches!(
+            wincode::deserialize::<Nested>(&bytes),
+            Err(wincode::ReadError::PreallocationSizeLimit { .. })
+        ));
+
+        assert!(crate::U64PrefixedCowStr::try_from_slice(&bytes[8..]).is_err());
+        assert!(wincode::deserialize::<crate::U64PrefixedCowStr>(&bytes[8..]).is_err());
+    }
+
+    #[test]
+    fn nested_preallocation_size_limit() {
+        let original = U8PrefixedVec::from(vec![U16PrefixedVec::from(&[1u64; 16])]);
+        let bytes = wincode::serialize(&original).unwrap();
+
+        // The inner vector needs 128 bytes of preallocation.
+        let config =
+            wincode::config::Configuration::default().with_preallocation_size_limit::<64>();
+        assert!(matches!(
+            wincode::config::deserialize::<U8PrefixedVec<U16PrefixedVec<u64>>, _>(&bytes, config),
+            Err(wincode::ReadError::PreallocationSizeLimit {
+                needed: 128,
+                limit: 64
+            })
+        ));
+
+        let config =
+            wincode::config::Configuration::default().with_preallocation_size_limit::<128>();
+        assert_eq!(
+            wincode::config::deserialize::<U8PrefixedVec<U16PrefixedVec<u64>>, _>(&bytes, config)
+                .unwrap(),
+            original
+        );
+    }
+
+    /// A recursive type, nesting a prefixed vector per level.
+    #[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, SchemaRead, SchemaWrite)]
+    struct Node {
+        children: U8PrefixedVec<Node>,
+    }
+
+    #[test]
+    fn nested_depth_limit() {
+        // Each level is a node with a single child, except for the innermost
+        // node, which has no children.
+        let nested = |depth: usize| {
+            let mut bytes = vec![1u8; depth - 1];
+            bytes.push(0);
+            bytes
+        };
+
+        let bytes = nested(crate::MAX_NESTING_DEPTH);
+        assert!(Node::try_from_slice(&bytes).is_ok());
+        assert!(wincode::deserialize::<Node>(&bytes).is_ok());
+
+        let bytes = nested(crate::MAX_NESTING_DEPTH + 1);
+        assert!(Node::try_from_slice(&bytes).is_err());
+        assert!(matches!(
+            wincode::deserialize::<Node>(&bytes),
+            Err(wincode::ReadError::Custom("maximum nesting depth exceeded"))
+        ));
+
+        // Deeply nested input fails without exhausting the stack, and the depth
+        // is released for subsequent reads.
+        let bytes = nested(1_000_000);
+        assert!(Node::try_from_slice(&bytes).is_err());
+        assert!(wincode::deserialize::<Node>(&bytes).is_err());
+
+        let bytes = nested(crate::MAX_NESTING_DEPTH);
+        assert!(Node::try_from_slice(&bytes).is_ok());
+        assert!(wincode::deserialize::<Node>(&bytes).is_ok());
+    }
 }
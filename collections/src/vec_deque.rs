@@ -0,0 +1,328 @@
+//! Types for serializing `VecDeque<T>` types.
+//!
+//! This module provides a set of `PrefixedVecDeque`s with different length prefix
+//! types. Items are serialized front-to-back, so the serialized representation is
+//! the same as the corresponding `PrefixedVec` holding the items in the same order.
+//!
+//! The types in this module also implement the `Deref` trait, allowing them to be used
+//! as regular `VecDeque<T>` in most contexts.
+
+#[cfg(feature = "wincode")]
+use {
+    crate::vec::{read_items, size_of_items},
+    core::mem::MaybeUninit,
+    wincode::{
+        config::ConfigCore,
+        error::write_length_encoding_overflow,
+        io::{Reader, Writer},
+        ReadResult, SchemaRead, SchemaWrite, WriteResult,
+    },
+};
+use {
+    crate::CollectionsError,
+    alloc::{collections::VecDeque, vec::Vec},
+    core::{
+        fmt::{Debug, Formatter},
+        ops::Deref,
+    },
+};
+#[cfg(feature = "borsh")]
+use {
+    crate::{
+        depth::{DepthGuard, NESTING_DEPTH_EXCEEDED},
+        vec::cautious_capacity,
+    },
+    borsh::{
+        io::{ErrorKind, Read, Write},
+        BorshDeserialize, BorshSerialize,
+    },
+};
+
+/// Macro defining a `PrefixedVecDeque` type with a specified length prefix type.
+macro_rules! prefixed_vec_deque_type {
+    ( $name:tt, $prefix_type:tt ) => {
+        #[doc = concat!("A `VecDeque<T>` serialized front-to-back with an `", stringify!($prefix_type), "` length prefix.")]
+        #[derive(Clone, Eq, Hash, Ord, PartialEq, PartialOrd)]
+        #[repr(transparent)]
+        pub struct $name<T>(VecDeque<T>);
+
+        impl<T> Default for $name<T> {
+            fn default() -> Self {
+                Self(VecDeque::new())
+            }
+        }
+
+        impl<T: PartialEq> PartialEq<[T]> for $name<T> {
+            fn eq(&self, other: &[T]) -> bool {
+                self.0.len() == other.len() && self.0.iter().eq(other)
+            }
+        }
+
+        impl<T: PartialEq> PartialEq<Vec<T>> for $name<T> {
+            fn eq(&self, other: &Vec<T>) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl<T> $name<T> {
+            #[doc = concat!("The maximum number of items representable by the `", stringify!($prefix_type), "` length prefix.")]
+            pub const MAX_LEN: usize = $prefix_type::MAX as usize;
+
+            /// Creates a new deque, checking that the number of items fits in the
+            /// length prefix.
+            pub fn try_new(value: VecDeque<T>) -> Result<Self, CollectionsError> {
+                if value.len() > Self::MAX_LEN {
+                    return Err(CollectionsError::LengthPrefixOverflow);
+                }
+
+                Ok(Self(value))
+            }
+        }
+
+        impl<T> From<VecDeque<T>> for $name<T> {
+            fn from(value: VecDeque<T>) -> Self {
+                Self(value)
+            }
+        }
+
+        impl<T> From<Vec<T>> for $name<T> {
+            fn from(value: Vec<T>) -> Self {
+                Self(VecDeque::from(value))
+            }
+        }
+
+        impl<T: Clone> From<&[T]> for $name<T> {
+            fn from(value: &[T]) -> Self {
+                Self(value.iter().cloned().collect())
+            }
+        }
+
+        impl<const N: usize, T: Clone> From<&[T; N]> for $name<T> {
+            fn from(value: &[T; N]) -> Self {
+                Self(value.iter().cloned().collect())
+            }
+        }
+
+        impl<T> Deref for $name<T> {
+            type Target = VecDeque<T>;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl<T: Debug> Debug for $name<T> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+                f.write_fmt(format_args!("{:?}", self.0))
+            }
+        }
+
+        #[cfg(feature = "borsh")]
+        impl<T: BorshSerialize> BorshSerialize for $name<T> {
+            fn serialize<W: Write>(&self, writer: &mut W) -> borsh::io::Result<()> {
+                BorshSerialize::serialize(
+                    &$prefix_type::try_from(self.0.len()).map_err(|_| ErrorKind::InvalidData)?,
+                    writer,
+                )?;
+                self.0.iter().try_for_each(|item| item.serialize(writer))
+            }
+        }
+
+        #[cfg(feature = "borsh")]
+        impl<T: BorshDeserialize> BorshDeserialize for $name<T> {
+            fn deserialize_reader<R: Read>(reader: &mut R) -> borsh::io::Result<Self> {
+                let Some(_guard) = DepthGuard::enter() else {
+                    return Err(borsh::io::Error::new(
+                        ErrorKind::InvalidData,
+                        NESTING_DEPTH_EXCEEDED,
+                    ));
+                };
+
+                let prefix = $prefix_type::deserialize_reader(reader)? as usize;
+                let mut items: VecDeque<T> =
+                    VecDeque::with_capacity(cautious_capacity::<T>(prefix));
+
+                while items.len() < prefix {
+                    let Ok(item) = T::deserialize_reader(reader) else {
+                        return Err(ErrorKind::InvalidData.into());
+                    };
+
+                    items.push_back(item);
+                }
+
+                Ok(Self(items))
+            }
+        }
+
+        #[cfg(feature = "wincode")]
+        unsafe impl<T, C> SchemaWrite<C> for $name<T>
+        where
+            C: ConfigCore,
+            T: SchemaWrite<C, Src = T>,
+        {
+            type Src = Self;
+
+            #[inline(always)]
+            fn size_of(src: &Self::Src) -> WriteResult<usize> {
+                let prefix = $prefix_type::try_from(src.0.len())
+                    .map_err(|_| write_length_encoding_overflow(stringify!($prefix_type::MAX)))?;
+                let (front, back) = src.0.as_slices();
+                let expected_size = size_of_items::<T, C>(front)?
+                    .saturating_add(size_of_items::<T, C>(back)?)
+                    .saturating_add(<$prefix_type as SchemaWrite<C>>::size_of(&prefix)?);
+
+                // `VecDeque` capacity is limited to `isize::MAX`.
+                if expected_size > isize::MAX as usize {
+                    return Err(write_length_encoding_overflow(
+                        concat!("size of items in ", stringify!($name)),
+                    ));
+                }
+
+                Ok(expected_size)
+            }
+
+            #[inline(always)]
+            fn write(mut writer: impl Writer, src: &Self::Src) -> WriteResult<()> {
+                <$prefix_type as SchemaWrite<C>>::write(
+                    &mut writer,
+                    &$prefix_type::try_from(src.0.len())
+                        .map_err(|_| write_length_encoding_overflow(stringify!($prefix_type::MAX)))?,
+                )?;
+                src.0
+                    .iter()
+                    .try_for_each(|item| T::write(&mut writer, item))
+            }
+        }
+
+        #[cfg(feature = "wincode")]
+        unsafe impl<'de, T, C> SchemaRead<'de, C> for $name<T>
+        where
+            C: ConfigCore,
+            T: SchemaRead<'de, C, Dst = T>,
+        {
+            type Dst = Self;
+
+            fn read(
+                mut reader: impl Reader<'de>,
+                dst: &mut MaybeUninit<Self::Dst>,
+            ) -> ReadResult<()> {
+                let mut prefix = MaybeUninit::<$prefix_type>::uninit();
+                <$prefix_type as SchemaRead<'de, C>>::read(&mut reader, &mut prefix)?;
+                // SAFETY: We have just read the prefix from the reader, so it is initialized.
+                let prefix = unsafe { prefix.assume_init() } as usize;
+
+                // Converting a `Vec` into a `VecDeque` does not reallocate.
+                dst.write(Self(VecDeque::from(read_items::<T, C>(reader, prefix)?)));
+
+                Ok(())
+            }
+        }
+    };
+}
+
+// A `PrefixedVecDeque` with a `u8` length prefix.
+prefixed_vec_deque_type!(U8PrefixedVecDeque, u8);
+
+// A `PrefixedVecDeque` with a `u16` length prefix.
+prefixed_vec_deque_type!(U16PrefixedVecDeque, u16);
+
+// A `PrefixedVecDeque` with a `u32` length prefix.
+prefixed_vec_deque_type!(U32PrefixedVecDeque, u32);
+
+// A `PrefixedVecDeque` with a `u64` length prefix.
+prefixed_vec_deque_type!(U64PrefixedVecDeque, u64);
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::U16PrefixedVec, borsh::BorshDeserialize, core::mem::size_of,
+        wincode::WriteError,
+    };
+
+    /// Builds a deque whose items wrap around the end of its ring buffer, so that
+    /// `as_slices` returns two non-empty slices.
+    fn wrapped_deque() -> VecDeque<u32> {
+        let mut deque = VecDeque::with_capacity(4);
+        deque.extend([0, 0, 1, 2]);
+        deque.pop_front();
+        deque.pop_front();
+        deque.push_back(3);
+        deque.push_back(4);
+
+        let (front, back) = deque.as_slices();
+        assert!(!front.is_empty() && !back.is_empty());
+
+        deque
+    }
+
+    #[test]
+    fn prefixed_vec_deque_borsh_round_trip() {
+        let original = U16PrefixedVecDeque::from(wrapped_deque());
+        let bytes = borsh::to_vec(&original).unwrap();
+
+        // Items are serialized front-to-back, matching a `PrefixedVec`.
+        assert_eq!(
+            bytes,
+            borsh::to_vec(&U16PrefixedVec::from(&[1u32, 2, 3, 4])).unwrap()
+        );
+
+        let deserialized = U16PrefixedVecDeque::<u32>::try_from_slice(&bytes).unwrap();
+
+        assert_eq!(deserialized, original);
+        assert_eq!(deserialized.front(), Some(&1));
+        assert_eq!(deserialized.back(), Some(&4));
+    }
+
+    #[test]
+    fn prefixed_vec_deque_wincode_round_trip() {
+        let original = U32PrefixedVecDeque::from(wrapped_deque());
+        let bytes = wincode::serialize(&original).unwrap();
+
+        assert_eq!(bytes.len(), size_of::<u32>() + 4 * size_of::<u32>());
+        assert_eq!(
+            wincode::serialized_size(&original).unwrap() as usize,
+            bytes.len()
+        );
+
+        let deserialized = wincode::deserialize::<U32PrefixedVecDeque<u32>>(&bytes).unwrap();
+
+        assert_eq!(deserialized, original);
+        assert_eq!(
+            deserialized.iter().copied().collect::<Vec<_>>(),
+            [1, 2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn invalid_prefixed_value() {
+        const VALUES: [u8; 256] = [255u8; 256];
+
+        let original = U8PrefixedVecDeque::from(&VALUES);
+
+        // borsh
+        let result = borsh::to_vec(&original);
+
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+
+        // wincode
+        let result = wincode::serialize(&original);
+
+        assert!(result.is_err());
+        assert!(matches!(
+            result.unwrap_err(),
+            WriteError::LengthEncodingOverflow(_)
+        ));
+    }
+
+    #[test]
+    fn prefixed_vec_deque_try_new() {
+        let deque = VecDeque::from(alloc::vec![1u8; 256]);
+
+        assert_eq!(
+            U8PrefixedVecDeque::try_new(deque.clone()).unwrap_err(),
+            CollectionsError::LengthPrefixOverflow
+        );
+        assert_eq!(U16PrefixedVecDeque::try_new(deque).unwrap().len(), 256);
+    }
+}
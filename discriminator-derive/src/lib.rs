@@ -12,7 +12,20 @@ use {
 
 /// Derive macro library to implement the `SplDiscriminate` trait
 /// on an enum or struct
-#[proc_macro_derive(SplDiscriminate, attributes(discriminator_hash_input))]
+///
+/// With a `#[discriminator_len = N]` attribute other than 8, the
+/// `SplDiscriminateWithLength<N>` trait is implemented instead.
+///
+/// With a `#[discriminator_per_variant("namespace")]` attribute on an enum,
+/// each variant also gets its own discriminator, hashed from its
+/// `#[discriminator_hash_input("...")]` attribute or from
+/// `"namespace:variant_name"`, and the enum gets `discriminator(&self)` and
+/// `try_from_discriminator` functions. The enum's own hash input is then
+/// optional.
+#[proc_macro_derive(
+    SplDiscriminate,
+    attributes(discriminator_hash_input, discriminator_len, discriminator_per_variant)
+)]
 pub fn spl_discriminator(input: TokenStream) -> TokenStream {
     parse_macro_input!(input as SplDiscriminateBuilder)
         .to_token_stream()
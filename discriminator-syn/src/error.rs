@@ -9,4 +9,12 @@ pub enum SplDiscriminateError {
     /// Error parsing discriminator `hash_input` attribute
     #[error("Error parsing discriminator `hash_input` attribute")]
     HashInputAttributeParseError,
+    /// Error parsing discriminator `discriminator_len` attribute
+    #[error(
+        "Error parsing discriminator `discriminator_len` attribute, expected a length from 1 to 32"
+    )]
+    DiscriminatorLenAttributeParseError,
+    /// Error parsing discriminator `discriminator_per_variant` attribute
+    #[error("Error parsing discriminator `discriminator_per_variant` attribute")]
+    VariantNamespaceAttributeParseError,
 }
@@ -7,11 +7,20 @@ mod error;
 pub mod parser;
 
 use {
-    crate::{error::SplDiscriminateError, parser::parse_hash_input},
-    proc_macro2::{Span, TokenStream},
+    crate::{
+        error::SplDiscriminateError,
+        parser::{
+            parse_discriminator_len, parse_hash_input, parse_variant_namespace,
+            DEFAULT_DISCRIMINATOR_LEN,
+        },
+    },
+    heck::ToSnakeCase,
+    proc_macro2::{Literal, Span, TokenStream},
     quote::{quote, ToTokens},
     sha2::{Digest, Sha256},
-    syn::{parse::Parse, Generics, Ident, Item, ItemEnum, ItemStruct, LitByteStr, WhereClause},
+    syn::{
+        parse::Parse, Fields, Generics, Ident, Item, ItemEnum, ItemStruct, LitByteStr, WhereClause,
+    },
 };
 
 /// "Builder" struct to implement the `SplDiscriminate` trait
@@ -23,10 +32,41 @@ pub struct SplDiscriminateBuilder {
     pub generics: Generics,
     /// The item's where clause for generics (if any)
     pub where_clause: Option<WhereClause>,
-    /// The TLV `hash_input`
+    /// The TLV `hash_input`, which enums with per-variant discriminators may
+    /// omit
+    pub hash_input: Option<String>,
+    /// The discriminator length in bytes
+    pub discriminator_len: usize,
+    /// The discriminators of each variant, for enums annotated with
+    /// `#[discriminator_per_variant("...")]`
+    pub variants: Option<Vec<VariantDiscriminator>>,
+}
+
+/// Discriminator of an enum variant
+pub struct VariantDiscriminator {
+    /// The variant identifier
+    pub ident: Ident,
+    /// The variant fields, default-initialized when converting from a
+    /// discriminator
+    pub fields: Fields,
+    /// The variant `hash_input`, from its own `#[discriminator_hash_input]`
+    /// attribute or the enum namespace and the variant name in snake case
     pub hash_input: String,
 }
 
+/// Parses the `hash_input` attribute, which is only optional when `optional`
+/// is set
+fn parse_optional_hash_input(
+    attrs: &[syn::Attribute],
+    optional: bool,
+) -> Result<Option<String>, SplDiscriminateError> {
+    match parse_hash_input(attrs) {
+        Ok(hash_input) => Ok(Some(hash_input)),
+        Err(SplDiscriminateError::HashInputAttributeNotProvided) if optional => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
 impl TryFrom<ItemEnum> for SplDiscriminateBuilder {
     type Error = SplDiscriminateError;
 
@@ -34,12 +74,41 @@ impl TryFrom<ItemEnum> for SplDiscriminateBuilder {
         let ident = item_enum.ident;
         let where_clause = item_enum.generics.where_clause.clone();
         let generics = item_enum.generics;
-        let hash_input = parse_hash_input(&item_enum.attrs)?;
+        let namespace = parse_variant_namespace(&item_enum.attrs)?;
+        let hash_input = parse_optional_hash_input(&item_enum.attrs, namespace.is_some())?;
+        let discriminator_len = parse_discriminator_len(&item_enum.attrs)?;
+        let variants = namespace
+            .map(|namespace| {
+                item_enum
+                    .variants
+                    .into_iter()
+                    .map(|variant| {
+                        let hash_input = parse_optional_hash_input(&variant.attrs, true)?
+                            .unwrap_or_else(|| {
+                                // Anchor-style snake case, keeping acronyms
+                                // together (`HTTPRequest` is `http_request`)
+                                format!(
+                                    "{}:{}",
+                                    namespace,
+                                    variant.ident.to_string().to_snake_case()
+                                )
+                            });
+                        Ok(VariantDiscriminator {
+                            ident: variant.ident,
+                            fields: variant.fields,
+                            hash_input,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, SplDiscriminateError>>()
+            })
+            .transpose()?;
         Ok(Self {
             ident,
             generics,
             where_clause,
             hash_input,
+            discriminator_len,
+            variants,
         })
     }
 }
@@ -52,11 +121,14 @@ impl TryFrom<ItemStruct> for SplDiscriminateBuilder {
         let where_clause = item_struct.generics.where_clause.clone();
         let generics = item_struct.generics;
         let hash_input = parse_hash_input(&item_struct.attrs)?;
+        let discriminator_len = parse_discriminator_len(&item_struct.attrs)?;
         Ok(Self {
             ident,
             generics,
             where_clause,
-            hash_input,
+            hash_input: Some(hash_input),
+            discriminator_len,
+            variants: None,
         })
     }
 }
@@ -89,20 +161,88 @@ impl From<&SplDiscriminateBuilder> for TokenStream {
         let ident = &builder.ident;
         let generics = &builder.generics;
         let where_clause = &builder.where_clause;
-        let bytes = get_discriminator_bytes(&builder.hash_input);
-        quote! {
-            impl #generics spl_discriminator::discriminator::SplDiscriminate for #ident #generics #where_clause {
-                const SPL_DISCRIMINATOR: spl_discriminator::discriminator::ArrayDiscriminator
-                    = spl_discriminator::discriminator::ArrayDiscriminator::new(*#bytes);
+        let len = Literal::usize_unsuffixed(builder.discriminator_len);
+        let mut tokens = match &builder.hash_input {
+            Some(hash_input) => {
+                let bytes = get_discriminator_bytes(hash_input, builder.discriminator_len);
+                if builder.discriminator_len == DEFAULT_DISCRIMINATOR_LEN {
+                    quote! {
+                        impl #generics spl_discriminator::discriminator::SplDiscriminate for #ident #generics #where_clause {
+                            const SPL_DISCRIMINATOR: spl_discriminator::discriminator::ArrayDiscriminator
+                                = spl_discriminator::discriminator::ArrayDiscriminator::new(*#bytes);
+                        }
+                    }
+                } else {
+                    quote! {
+                        impl #generics spl_discriminator::discriminator::SplDiscriminateWithLength<#len> for #ident #generics #where_clause {
+                            const DISCRIMINATOR: spl_discriminator::discriminator::Discriminator<#len>
+                                = spl_discriminator::discriminator::Discriminator::new(*#bytes);
+                        }
+                    }
+                }
             }
+            None => TokenStream::new(),
+        };
+        if let Some(variants) = &builder.variants {
+            let discriminator_type =
+                quote! { spl_discriminator::discriminator::Discriminator<#len> };
+            let discriminator_path =
+                quote! { spl_discriminator::discriminator::Discriminator::<#len> };
+            let variant_idents = variants.iter().map(|variant| &variant.ident);
+            let variant_bytes = variants
+                .iter()
+                .map(|variant| {
+                    get_discriminator_bytes(&variant.hash_input, builder.discriminator_len)
+                })
+                .collect::<Vec<_>>();
+            let variant_defaults = variants.iter().map(|variant| {
+                let ident = &variant.ident;
+                match &variant.fields {
+                    Fields::Named(fields) => {
+                        let names = fields.named.iter().map(|field| &field.ident);
+                        quote! { Self::#ident { #(#names: ::core::default::Default::default()),* } }
+                    }
+                    Fields::Unnamed(fields) => {
+                        let defaults = fields
+                            .unnamed
+                            .iter()
+                            .map(|_| quote! { ::core::default::Default::default() });
+                        quote! { Self::#ident(#(#defaults),*) }
+                    }
+                    Fields::Unit => quote! { Self::#ident },
+                }
+            });
+            tokens.extend(quote! {
+                impl #generics #ident #generics #where_clause {
+                    /// Get the discriminator of this variant
+                    pub const fn discriminator(&self) -> #discriminator_type {
+                        match self {
+                            #(Self::#variant_idents { .. } => #discriminator_path::new(*#variant_bytes),)*
+                        }
+                    }
+
+                    /// Get the variant with the given discriminator, with its
+                    /// fields set to their default values
+                    pub fn try_from_discriminator(discriminator: &#discriminator_type) -> ::core::option::Option<Self> {
+                        #(
+                            if *discriminator == #discriminator_path::new(*#variant_bytes) {
+                                return ::core::option::Option::Some(#variant_defaults);
+                            }
+                        )*
+                        ::core::option::Option::None
+                    }
+                }
+            });
         }
+        tokens
     }
 }
 
-/// Returns the bytes for the TLV `hash_input` discriminator
-fn get_discriminator_bytes(hash_input: &str) -> LitByteStr {
+/// Returns the first `len` bytes of the hash of the TLV `hash_input`
+/// discriminator
+fn get_discriminator_bytes(hash_input: &str, len: usize) -> LitByteStr {
     LitByteStr::new(
-        &Sha256::digest(hash_input.as_bytes())[..8],
+        &Sha256::digest(hash_input.as_bytes())[..len],
         Span::call_site(),
     )
 }
@@ -1,12 +1,13 @@
 //! Parser for the `syn` crate to parse the
-//! `#[discriminator_hash_input("...")]` attribute
+//! `#[discriminator_hash_input("...")]`, `#[discriminator_len = N]` and
+//! `#[discriminator_per_variant("...")]` attributes
 
 use {
     crate::error::SplDiscriminateError,
     syn::{
         parse::{Parse, ParseStream},
         token::Comma,
-        Attribute, LitStr,
+        Attribute, Expr, ExprLit, Lit, LitStr, MetaNameValue,
     },
 };
 
@@ -40,3 +41,51 @@ pub fn parse_hash_input(attrs: &[Attribute]) -> Result<String, SplDiscriminateEr
         None => Err(SplDiscriminateError::HashInputAttributeNotProvided),
     }
 }
+
+/// Length of discriminators when no `#[discriminator_len = N]` attribute is
+/// provided
+pub const DEFAULT_DISCRIMINATOR_LEN: usize = 8;
+
+/// Largest supported discriminator length, the size of a sha256 hash
+pub const MAX_DISCRIMINATOR_LEN: usize = 32;
+
+/// Parses the length from the `#[discriminator_len = N]` attribute, defaulting
+/// to 8 bytes
+pub fn parse_discriminator_len(attrs: &[Attribute]) -> Result<usize, SplDiscriminateError> {
+    match attrs
+        .iter()
+        .find(|a| a.path().is_ident("discriminator_len"))
+    {
+        Some(attr) => match &attr.meta {
+            syn::Meta::NameValue(MetaNameValue {
+                value:
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Int(len), ..
+                    }),
+                ..
+            }) => len
+                .base10_parse::<usize>()
+                .ok()
+                .filter(|len| (1..=MAX_DISCRIMINATOR_LEN).contains(len))
+                .ok_or(SplDiscriminateError::DiscriminatorLenAttributeParseError),
+            _ => Err(SplDiscriminateError::DiscriminatorLenAttributeParseError),
+        },
+        None => Ok(DEFAULT_DISCRIMINATOR_LEN),
+    }
+}
+
+/// Parses the namespace from the `#[discriminator_per_variant("...")]`
+/// attribute, if any, prefixing the hash input of each variant of an enum
+pub fn parse_variant_namespace(
+    attrs: &[Attribute],
+) -> Result<Option<String>, SplDiscriminateError> {
+    attrs
+        .iter()
+        .find(|a| a.path().is_ident("discriminator_per_variant"))
+        .map(|attr| {
+            attr.parse_args::<HashInputValueParser>()
+                .map(|parsed_args| parsed_args.value.value())
+                .map_err(|_| SplDiscriminateError::VariantNamespaceAttributeParseError)
+        })
+        .transpose()
+}
@@ -1,6 +1,7 @@
 //! The traits and types used to create a discriminator for a type
 
 use {
+    crate::sha256,
     bytemuck::{Pod, Zeroable},
     solana_program_error::ProgramError,
     solana_sha256_hasher::hashv,
@@ -14,42 +15,278 @@ pub trait SplDiscriminate {
     const SPL_DISCRIMINATOR_SLICE: &'static [u8] = Self::SPL_DISCRIMINATOR.as_slice();
 }
 
-/// Array Discriminator type
+/// A trait for managing discriminators of any length in a slab of bytes
+///
+/// Every `SplDiscriminate` type has an 8-byte discriminator through this
+/// trait. Derive it with `#[discriminator_len = N]` for other lengths.
+pub trait SplDiscriminateWithLength<const N: usize> {
+    /// The discriminator as a `[u8; N]`
+    const DISCRIMINATOR: Discriminator<N>;
+    /// The discriminator as a slice (`&[u8]`)
+    const DISCRIMINATOR_SLICE: &'static [u8] = Self::DISCRIMINATOR.as_slice();
+}
+
+impl<T: SplDiscriminate> SplDiscriminateWithLength<{ ArrayDiscriminator::LENGTH }> for T {
+    const DISCRIMINATOR: ArrayDiscriminator = T::SPL_DISCRIMINATOR;
+}
+
+/// Discriminator type of `N` bytes
 #[cfg_attr(
     feature = "borsh",
     derive(borsh::BorshSerialize, borsh::BorshDeserialize)
 )]
-#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
 #[repr(transparent)]
-pub struct ArrayDiscriminator([u8; ArrayDiscriminator::LENGTH]);
-impl ArrayDiscriminator {
+pub struct Discriminator<const N: usize>([u8; N]);
+
+/// Array Discriminator type, the 8-byte `Discriminator`
+pub type ArrayDiscriminator = Discriminator<8>;
+
+impl<const N: usize> Discriminator<N> {
     /// Size for discriminator in account data
-    pub const LENGTH: usize = 8;
+    pub const LENGTH: usize = N;
     /// Uninitialized variant of a discriminator
-    pub const UNINITIALIZED: Self = Self::new([0; Self::LENGTH]);
+    pub const UNINITIALIZED: Self = Self::new([0; N]);
     /// Creates a discriminator from an array
-    pub const fn new(value: [u8; Self::LENGTH]) -> Self {
+    pub const fn new(value: [u8; N]) -> Self {
         Self(value)
     }
     /// Get the array as a const slice
     pub const fn as_slice(&self) -> &[u8] {
         self.0.as_slice()
     }
-    /// Creates a new `ArrayDiscriminator` from some hash input string literal
+    /// Creates a new `Discriminator` from some hash input string, using the
+    /// first `N` bytes of its sha256 hash
+    ///
+    /// This is the runtime counterpart of the `SplDiscriminate` derive, which
+    /// gives the same bytes for the same hash input and length, and is
+    /// available without the `derive` feature. For example, tooling can
+    /// compute the discriminator of an instruction name read at runtime:
+    ///
+    /// ```
+    /// use spl_discriminator::{ArrayDiscriminator, Discriminator};
+    /// let name = "execute";
+    /// let hash_input = ["spl-transfer-hook-interface", name].join(":");
+    /// let discriminator = ArrayDiscriminator::new_with_hash_input(&hash_input);
+    /// assert_eq!(discriminator.as_slice(), [105, 37, 101, 197, 75, 251, 102, 26]);
+    /// let short = Discriminator::<4>::new_with_hash_input(&hash_input);
+    /// assert_eq!(short.as_slice(), [105, 37, 101, 197]);
+    /// ```
     pub fn new_with_hash_input(hash_input: &str) -> Self {
+        const { assert!(N <= 32, "discriminators are at most 32 bytes") };
         let hash_bytes = hashv(&[hash_input.as_bytes()]).to_bytes();
-        let mut discriminator_bytes = [0u8; 8];
-        discriminator_bytes.copy_from_slice(&hash_bytes[..8]);
+        let mut discriminator_bytes = [0u8; N];
+        discriminator_bytes.copy_from_slice(&hash_bytes[..N]);
         Self(discriminator_bytes)
     }
+    /// Creates a new `Discriminator` from some hash input string, like
+    /// `new_with_hash_input`, but usable in const contexts
+    ///
+    /// It also accepts strings only known at runtime, with the same truncation
+    /// rules as the derive macro, and doesn't depend on the hashing syscall:
+    ///
+    /// ```
+    /// use spl_discriminator::{ArrayDiscriminator, Discriminator};
+    /// let name = "execute";
+    /// let hash_input = ["spl-transfer-hook-interface", name].join(":");
+    /// assert_eq!(
+    ///     ArrayDiscriminator::from_hash_input(&hash_input),
+    ///     ArrayDiscriminator::new_with_hash_input(&hash_input),
+    /// );
+    /// assert_eq!(
+    ///     Discriminator::<4>::from_hash_input(&hash_input).as_slice(),
+    ///     [105, 37, 101, 197],
+    /// );
+    /// ```
+    ///
+    /// Prefer `new_with_hash_input` at runtime in programs, since it uses the
+    /// runtime's hashing syscall.
+    pub const fn from_hash_input(hash_input: &str) -> Self {
+        const { assert!(N <= 32, "discriminators are at most 32 bytes") };
+        let hash_bytes = sha256::hash(hash_input.as_bytes());
+        let mut discriminator_bytes = [0u8; N];
+        let mut i = 0;
+        while i < N {
+            discriminator_bytes[i] = hash_bytes[i];
+            i += 1;
+        }
+        Self(discriminator_bytes)
+    }
+}
+/// Computes the 8-byte discriminator for some hash input string, usable in
+/// const contexts, see `Discriminator::from_hash_input`
+///
+/// ```
+/// use spl_discriminator::{discriminator, ArrayDiscriminator};
+/// const EXECUTE: ArrayDiscriminator = discriminator("spl-transfer-hook-interface:execute");
+/// assert_eq!(EXECUTE.as_slice(), [105, 37, 101, 197, 75, 251, 102, 26]);
+/// ```
+pub const fn discriminator(hash_input: &str) -> ArrayDiscriminator {
+    ArrayDiscriminator::from_hash_input(hash_input)
+}
+
+/// Check that no two of the given discriminators are equal, usable in const
+/// contexts, see `assert_unique_discriminators!`
+pub const fn are_unique<const N: usize>(discriminators: &[Discriminator<N>]) -> bool {
+    let mut i = 0;
+    while i < discriminators.len() {
+        let mut j = i + 1;
+        while j < discriminators.len() {
+            let (a, b) = (&discriminators[i].0, &discriminators[j].0);
+            let mut k = 0;
+            while k < N && a[k] == b[k] {
+                k += 1;
+            }
+            if k == N {
+                return false;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
 }
-impl AsRef<[u8]> for ArrayDiscriminator {
+
+/// Fail the build if any two of the given `SplDiscriminate` types share a
+/// discriminator
+///
+/// ```
+/// use spl_discriminator::{assert_unique_discriminators, ArrayDiscriminator, SplDiscriminate};
+/// struct A;
+/// impl SplDiscriminate for A {
+///     const SPL_DISCRIMINATOR: ArrayDiscriminator = ArrayDiscriminator::new([1; 8]);
+/// }
+/// struct B;
+/// impl SplDiscriminate for B {
+///     const SPL_DISCRIMINATOR: ArrayDiscriminator = ArrayDiscriminator::new([2; 8]);
+/// }
+/// assert_unique_discriminators!(A, B);
+/// ```
+///
+/// ```compile_fail
+/// use spl_discriminator::{assert_unique_discriminators, ArrayDiscriminator, SplDiscriminate};
+/// struct A;
+/// impl SplDiscriminate for A {
+///     const SPL_DISCRIMINATOR: ArrayDiscriminator = ArrayDiscriminator::new([1; 8]);
+/// }
+/// struct B;
+/// impl SplDiscriminate for B {
+///     const SPL_DISCRIMINATOR: ArrayDiscriminator = ArrayDiscriminator::new([1; 8]);
+/// }
+/// assert_unique_discriminators!(A, B);
+/// ```
+#[macro_export]
+macro_rules! assert_unique_discriminators {
+    ($($ty:ty),+ $(,)?) => {
+        const _: () = ::core::assert!(
+            $crate::discriminator::are_unique(&[
+                $(<$ty as $crate::discriminator::SplDiscriminate>::SPL_DISCRIMINATOR),+
+            ]),
+            "two types share a discriminator",
+        );
+    };
+}
+
+/// Implement `SplDiscriminate` for a type from a hash input string, without
+/// the derive macro
+///
+/// This covers types that can't be annotated with the derive, such as types
+/// generated by other macros, and crates built without the `derive` feature.
+/// The discriminator is hashed at compile time, following the same rules as
+/// the derive macro.
+///
+/// Rust's orphan rules still apply: the type must be defined in the crate
+/// invoking the macro, so foreign types are not supported. Wrap a foreign
+/// type with `discriminated_wrapper!` instead.
+///
+/// ```
+/// use spl_discriminator::{impl_discriminator, ArrayDiscriminator, SplDiscriminate};
+/// pub struct MyType;
+/// impl_discriminator!(MyType, "my_program:my_type");
+/// assert_eq!(
+///     MyType::SPL_DISCRIMINATOR,
+///     ArrayDiscriminator::new_with_hash_input("my_program:my_type")
+/// );
+/// ```
+#[macro_export]
+macro_rules! impl_discriminator {
+    ($ty:ty, $hash_input:expr $(,)?) => {
+        impl $crate::discriminator::SplDiscriminate for $ty {
+            const SPL_DISCRIMINATOR: $crate::discriminator::ArrayDiscriminator =
+                $crate::discriminator::ArrayDiscriminator::from_hash_input($hash_input);
+        }
+    };
+}
+
+/// Define a newtype wrapper implementing `SplDiscriminate` around a type from
+/// another crate
+///
+/// `impl_discriminator!` can't implement `SplDiscriminate` for foreign types,
+/// because of Rust's orphan rules. This macro instead defines a
+/// `#[repr(transparent)]` wrapper with the given discriminator, which
+/// dereferences to the wrapped type and converts from and into it.
+///
+/// ```
+/// use spl_discriminator::{discriminated_wrapper, ArrayDiscriminator, SplDiscriminate};
+/// discriminated_wrapper! {
+///     /// A `u64` counter stored with its own discriminator
+///     pub struct Counter(pub u64), "my_program:counter"
+/// }
+/// let counter = Counter::from(5);
+/// assert_eq!(*counter, 5);
+/// assert_eq!(
+///     Counter::SPL_DISCRIMINATOR,
+///     ArrayDiscriminator::new_with_hash_input("my_program:counter")
+/// );
+/// ```
+#[macro_export]
+macro_rules! discriminated_wrapper {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident($inner_vis:vis $inner:ty), $hash_input:expr $(,)?
+    ) => {
+        $(#[$attr])*
+        #[repr(transparent)]
+        $vis struct $name($inner_vis $inner);
+
+        $crate::impl_discriminator!($name, $hash_input);
+
+        impl ::core::ops::Deref for $name {
+            type Target = $inner;
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+        impl ::core::ops::DerefMut for $name {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.0
+            }
+        }
+        impl ::core::convert::From<$inner> for $name {
+            fn from(from: $inner) -> Self {
+                Self(from)
+            }
+        }
+        impl ::core::convert::From<$name> for $inner {
+            fn from(from: $name) -> Self {
+                from.0
+            }
+        }
+    };
+}
+
+impl<const N: usize> Default for Discriminator<N> {
+    fn default() -> Self {
+        Self::UNINITIALIZED
+    }
+}
+impl<const N: usize> AsRef<[u8]> for Discriminator<N> {
     fn as_ref(&self) -> &[u8] {
         &self.0[..]
     }
 }
-impl AsRef<[u8; ArrayDiscriminator::LENGTH]> for ArrayDiscriminator {
-    fn as_ref(&self) -> &[u8; ArrayDiscriminator::LENGTH] {
+impl<const N: usize> AsRef<[u8; N]> for Discriminator<N> {
+    fn as_ref(&self) -> &[u8; N] {
         &self.0
     }
 }
@@ -58,21 +295,21 @@ impl From<u64> for ArrayDiscriminator {
         Self(from.to_le_bytes())
     }
 }
-impl From<[u8; Self::LENGTH]> for ArrayDiscriminator {
-    fn from(from: [u8; Self::LENGTH]) -> Self {
+impl<const N: usize> From<[u8; N]> for Discriminator<N> {
+    fn from(from: [u8; N]) -> Self {
         Self(from)
     }
 }
-impl TryFrom<&[u8]> for ArrayDiscriminator {
+impl<const N: usize> TryFrom<&[u8]> for Discriminator<N> {
     type Error = ProgramError;
     fn try_from(a: &[u8]) -> Result<Self, Self::Error> {
-        <[u8; Self::LENGTH]>::try_from(a)
+        <[u8; N]>::try_from(a)
             .map(Self::from)
             .map_err(|_| ProgramError::InvalidAccountData)
     }
 }
-impl From<ArrayDiscriminator> for [u8; 8] {
-    fn from(from: ArrayDiscriminator) -> Self {
+impl<const N: usize> From<Discriminator<N>> for [u8; N] {
+    fn from(from: Discriminator<N>) -> Self {
         from.0
     }
 }
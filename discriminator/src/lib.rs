@@ -9,14 +9,16 @@ extern crate self as spl_discriminator;
 
 /// Exports the discriminator module
 pub mod discriminator;
+mod sha256;
 
 // Export for downstream
-pub use {
-    discriminator::{ArrayDiscriminator, SplDiscriminate},
-    spl_discriminator_derive::SplDiscriminate,
+pub use discriminator::{
+    discriminator, ArrayDiscriminator, Discriminator, SplDiscriminate, SplDiscriminateWithLength,
 };
+#[cfg(feature = "derive")]
+pub use spl_discriminator_derive::SplDiscriminate;
 
-#[cfg(test)]
+#[cfg(all(test, feature = "derive"))]
 mod tests {
     use {super::*, crate::discriminator::ArrayDiscriminator};
 
@@ -80,6 +82,43 @@ mod tests {
         data2: &'c [V],
     }
 
+    #[allow(dead_code)]
+    #[derive(SplDiscriminate)]
+    #[discriminator_hash_input("global:my_short_instruction")]
+    #[discriminator_len = 4]
+    pub struct MyInstruction8;
+
+    #[allow(dead_code)]
+    #[derive(SplDiscriminate)]
+    #[discriminator_hash_input("global:my_tiny_instruction")]
+    #[discriminator_len = 1]
+    pub enum MyInstruction9 {
+        One,
+    }
+
+    #[derive(Debug, PartialEq, SplDiscriminate)]
+    #[discriminator_per_variant("my_program")]
+    pub enum MyInstructions {
+        Initialize,
+        #[discriminator_hash_input("custom:transfer")]
+        TransferChecked {
+            amount: u64,
+            decimals: u8,
+        },
+        Close(u8),
+        HTTPRequest,
+        SetV2Authority,
+    }
+
+    #[allow(dead_code)]
+    #[derive(SplDiscriminate)]
+    #[discriminator_hash_input("global:my_short_instructions")]
+    #[discriminator_per_variant("my_short_program")]
+    #[discriminator_len = 4]
+    pub enum MyShortInstructions {
+        Initialize,
+    }
+
     fn assert_discriminator<T: spl_discriminator::discriminator::SplDiscriminate>(
         hash_input: &str,
     ) {
@@ -105,6 +144,136 @@ mod tests {
         ArrayDiscriminator::new(bytes)
     }
 
+    #[test]
+    fn variant_discriminators() {
+        let initialize = build_discriminator("my_program:initialize");
+        let transfer = build_discriminator("custom:transfer");
+        let close = build_discriminator("my_program:close");
+        assert_eq!(MyInstructions::Initialize.discriminator(), initialize);
+        assert_eq!(
+            MyInstructions::TransferChecked {
+                amount: 1,
+                decimals: 2
+            }
+            .discriminator(),
+            transfer
+        );
+        assert_eq!(MyInstructions::Close(1).discriminator(), close);
+
+        // acronyms and digits are kept together, as with Anchor
+        assert_eq!(
+            MyInstructions::HTTPRequest.discriminator(),
+            build_discriminator("my_program:http_request")
+        );
+        assert_eq!(
+            MyInstructions::SetV2Authority.discriminator(),
+            build_discriminator("my_program:set_v2_authority")
+        );
+
+        assert_eq!(
+            MyInstructions::try_from_discriminator(&transfer),
+            Some(MyInstructions::TransferChecked {
+                amount: 0,
+                decimals: 0
+            })
+        );
+        assert_eq!(
+            MyInstructions::try_from_discriminator(&close),
+            Some(MyInstructions::Close(0))
+        );
+        assert_eq!(
+            MyInstructions::try_from_discriminator(&ArrayDiscriminator::UNINITIALIZED),
+            None
+        );
+
+        // the enum discriminator and length are kept
+        assert_discriminator::<MyInstruction2>("global:my_second_instruction");
+        assert_eq!(
+            MyShortInstructions::Initialize.discriminator().as_slice(),
+            &build_discriminator("my_short_program:initialize").as_slice()[..4]
+        );
+        assert_eq!(
+            MyShortInstructions::DISCRIMINATOR.as_slice(),
+            &build_discriminator("global:my_short_instructions").as_slice()[..4]
+        );
+    }
+
+    assert_unique_discriminators!(
+        MyInstruction1<'_>,
+        MyInstruction2,
+        MyInstruction3<'_>,
+        MyInstruction4<u8>,
+        MyInstruction5<'_, u8>,
+        MyInstruction6<'_, u8, u8>,
+        MyInstruction7<'_, u8, u8>,
+    );
+
+    #[allow(dead_code)]
+    pub struct MyForeignType<'a>(&'a [u8]);
+    impl_discriminator!(MyForeignType<'_>, "my_program:my_foreign_type");
+
+    discriminated_wrapper! {
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        pub struct MyWrappedType(pub core::num::NonZeroU64), "my_program:my_wrapped_type"
+    }
+
+    #[test]
+    fn wrapped_foreign_type() {
+        assert_discriminator::<MyWrappedType>("my_program:my_wrapped_type");
+        let value = core::num::NonZeroU64::new(7).unwrap();
+        let mut wrapped = MyWrappedType::from(value);
+        assert_eq!(wrapped.get(), 7);
+        *wrapped = core::num::NonZeroU64::MAX;
+        assert_eq!(
+            core::num::NonZeroU64::from(wrapped),
+            core::num::NonZeroU64::MAX
+        );
+    }
+
+    #[test]
+    fn const_discriminators() {
+        const DISCRIMINATOR: ArrayDiscriminator = discriminator("my_first_instruction");
+        assert_eq!(DISCRIMINATOR, MyInstruction1::SPL_DISCRIMINATOR);
+        const SHORT: Discriminator<4> =
+            Discriminator::from_hash_input("global:my_short_instruction");
+        assert_eq!(SHORT, MyInstruction8::DISCRIMINATOR);
+        assert_discriminator::<MyForeignType<'_>>("my_program:my_foreign_type");
+    }
+
+    #[test]
+    fn runtime_hash_input_constructors() {
+        // both constructors accept runtime strings and agree for all lengths
+        for hash_input in ["", "my_runtime_hash_input", "global:my_short_instruction"] {
+            let hash = build_discriminator(hash_input);
+            assert_eq!(ArrayDiscriminator::from_hash_input(hash_input), hash);
+            assert_eq!(ArrayDiscriminator::new_with_hash_input(hash_input), hash);
+            assert_eq!(
+                Discriminator::<1>::from_hash_input(hash_input),
+                Discriminator::<1>::new_with_hash_input(hash_input),
+            );
+            assert_eq!(
+                Discriminator::<4>::from_hash_input(hash_input).as_slice(),
+                &hash.as_slice()[..4],
+            );
+            assert_eq!(
+                Discriminator::<32>::from_hash_input(hash_input),
+                Discriminator::<32>::new_with_hash_input(hash_input),
+            );
+        }
+    }
+
+    #[test]
+    fn unique_discriminators() {
+        let a = ArrayDiscriminator::new([1; 8]);
+        let mut b = a;
+        assert!(discriminator::are_unique::<8>(&[]));
+        assert!(!discriminator::are_unique(&[a, b]));
+        b = ArrayDiscriminator::new([1, 1, 1, 1, 1, 1, 1, 2]);
+        assert!(discriminator::are_unique(&[a, b]));
+        assert!(!discriminator::are_unique(&[a, b, a]));
+        assert!(discriminator::are_unique(&[Discriminator::new([3])]));
+    }
+
     #[test]
     fn test_discrminators() {
         let runtime_discrim = ArrayDiscriminator::new_with_hash_input("my_runtime_hash_input");
@@ -126,6 +295,23 @@ mod tests {
         assert_discriminator::<MyInstruction7<'_, u8, u8>>(
             "global:my_instruction_with_multiple_generics_and_lifetime_and_where",
         );
+
+        // other lengths use a prefix of the same hash
+        let hash = build_discriminator("global:my_short_instruction");
+        assert_eq!(
+            <MyInstruction8 as SplDiscriminateWithLength<4>>::DISCRIMINATOR,
+            Discriminator::<4>::new_with_hash_input("global:my_short_instruction"),
+        );
+        assert_eq!(MyInstruction8::DISCRIMINATOR_SLICE, &hash.as_slice()[..4]);
+        assert_eq!(
+            MyInstruction9::DISCRIMINATOR.as_slice(),
+            &build_discriminator("global:my_tiny_instruction").as_slice()[..1],
+        );
+        // 8-byte discriminators are available through both traits
+        assert_eq!(
+            <MyInstruction2 as SplDiscriminateWithLength<8>>::DISCRIMINATOR,
+            MyInstruction2::SPL_DISCRIMINATOR,
+        );
     }
 }
 
@@ -0,0 +1,120 @@
+//! SHA-256 implementation usable in const contexts, to compute discriminators
+//! without a derive
+
+/// Round constants
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Initial hash values
+const H: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// Get the byte at index `i` of the padded message
+const fn padded_byte(input: &[u8], padded_len: usize, i: usize) -> u8 {
+    if i < input.len() {
+        input[i]
+    } else if i == input.len() {
+        0x80
+    } else if i >= padded_len - 8 {
+        let bit_len = (input.len() as u64).wrapping_mul(8);
+        (bit_len >> ((padded_len - 1 - i) * 8)) as u8
+    } else {
+        0
+    }
+}
+
+/// Compute the SHA-256 hash of the input
+pub(crate) const fn hash(input: &[u8]) -> [u8; 32] {
+    let padded_len = (input.len() + 9).div_ceil(64) * 64;
+    let mut h = H;
+    let mut block_start = 0;
+    while block_start < padded_len {
+        let mut w = [0u32; 64];
+        let mut t = 0;
+        while t < 16 {
+            let i = block_start + t * 4;
+            w[t] = u32::from_be_bytes([
+                padded_byte(input, padded_len, i),
+                padded_byte(input, padded_len, i + 1),
+                padded_byte(input, padded_len, i + 2),
+                padded_byte(input, padded_len, i + 3),
+            ]);
+            t += 1;
+        }
+        while t < 64 {
+            let s0 = w[t - 15].rotate_right(7) ^ w[t - 15].rotate_right(18) ^ (w[t - 15] >> 3);
+            let s1 = w[t - 2].rotate_right(17) ^ w[t - 2].rotate_right(19) ^ (w[t - 2] >> 10);
+            w[t] = w[t - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[t - 7])
+                .wrapping_add(s1);
+            t += 1;
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = h;
+        t = 0;
+        while t < 64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let temp1 = hh
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(K[t])
+                .wrapping_add(w[t]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let temp2 = s0.wrapping_add(maj);
+            hh = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(temp1);
+            d = c;
+            c = b;
+            b = a;
+            a = temp1.wrapping_add(temp2);
+            t += 1;
+        }
+        let state = [a, b, c, d, e, f, g, hh];
+        let mut i = 0;
+        while i < 8 {
+            h[i] = h[i].wrapping_add(state[i]);
+            i += 1;
+        }
+        block_start += 64;
+    }
+
+    let mut output = [0u8; 32];
+    let mut i = 0;
+    while i < 32 {
+        output[i] = h[i / 4].to_be_bytes()[i % 4];
+        i += 1;
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_sha256() {
+        let input = [b'a'; 130];
+        // cover the padding boundaries of one, two and three blocks
+        for len in [0, 1, 54, 55, 56, 63, 64, 65, 119, 120, 128, 130] {
+            assert_eq!(
+                hash(&input[..len]),
+                solana_sha256_hasher::hashv(&[&input[..len]]).to_bytes(),
+                "length {len}"
+            );
+        }
+    }
+}
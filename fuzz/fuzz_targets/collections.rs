@@ -0,0 +1,82 @@
+//! Deserialize the `spl-collections` wrappers from arbitrary bytes with
+//! `borsh` and `wincode`, checking that they serialize back to values which
+//! deserialize the same
+
+#![no_main]
+
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    libfuzzer_sys::fuzz_target,
+    spl_collections::{
+        AsciiU8PrefixedStr, TrailingOptionBox, TrailingStr, TrailingVec, U16PrefixedCowStr,
+        U16PrefixedVec, U32PrefixedStr, U64PrefixedVecDeque, U8PrefixedVec,
+    },
+    wincode::{config::DefaultConfig, SchemaRead, SchemaWrite},
+};
+
+fn fuzz_borsh<T: BorshDeserialize + BorshSerialize + PartialEq + core::fmt::Debug>(data: &[u8]) {
+    if let Ok(value) = T::try_from_slice(data) {
+        let bytes = borsh::to_vec(&value).unwrap();
+        assert_eq!(T::try_from_slice(&bytes).unwrap(), value);
+    }
+}
+
+fn fuzz_wincode<T>(data: &[u8])
+where
+    T: for<'de> SchemaRead<'de, DefaultConfig, Dst = T> + SchemaWrite<DefaultConfig, Src = T>,
+    T: PartialEq + core::fmt::Debug,
+{
+    if let Ok(value) = wincode::deserialize::<T>(data) {
+        let bytes = wincode::serialize(&value).unwrap();
+        assert_eq!(wincode::deserialize::<T>(&bytes).unwrap(), value);
+    }
+}
+
+macro_rules! fuzz_collections {
+    ($data:expr, $($collection:ty),+ $(,)?) => {
+        let Some((selector, data)) = $data.split_first() else {
+            return;
+        };
+        let mut index = 0u8;
+        $(
+            if *selector / 2 == index {
+                if *selector % 2 == 0 {
+                    fuzz_borsh::<$collection>(data);
+                } else {
+                    fuzz_wincode::<$collection>(data);
+                }
+            }
+            index += 1;
+        )+
+        let _ = index;
+    };
+}
+
+fuzz_target!(|data: &[u8]| {
+    fuzz_collections!(
+        data,
+        U8PrefixedVec<u16>,
+        U16PrefixedVec<U8PrefixedVec<u8>>,
+        TrailingVec<u32>,
+        U32PrefixedStr,
+        TrailingStr,
+        AsciiU8PrefixedStr,
+        U64PrefixedVecDeque<u8>,
+        TrailingOptionBox<u64>,
+    );
+    // borrowing strings are only checked by value, since they can't outlive
+    // the data they were deserialized from
+    if let Ok(value) = U16PrefixedCowStr::try_from_slice(data) {
+        assert_eq!(
+            U16PrefixedCowStr::try_from_slice(&borsh::to_vec(&value).unwrap()).unwrap(),
+            value
+        );
+    }
+    if let Ok(value) = wincode::deserialize::<U16PrefixedCowStr>(data) {
+        assert_eq!(
+            wincode::deserialize::<U16PrefixedCowStr>(&wincode::serialize(&value).unwrap())
+                .unwrap(),
+            value
+        );
+    }
+});
@@ -0,0 +1,81 @@
+//! Resolve arbitrary extra account metas into an instruction, with arbitrary
+//! instruction and account data
+
+#![no_main]
+
+use {
+    arbitrary::Arbitrary,
+    libfuzzer_sys::fuzz_target,
+    solana_instruction::{AccountMeta, Instruction},
+    solana_pubkey::Pubkey,
+    spl_discriminator::{ArrayDiscriminator, SplDiscriminate},
+    spl_tlv_account_resolution::state::{AccountDataResult, ExtraAccountMetaList},
+};
+
+struct FuzzInstruction;
+impl SplDiscriminate for FuzzInstruction {
+    const SPL_DISCRIMINATOR: ArrayDiscriminator = ArrayDiscriminator::new([1; 8]);
+}
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    /// Raw TLV data, or the value of the `FuzzInstruction` entry if `false`
+    raw_validation_data: bool,
+    validation_data: Vec<u8>,
+    instruction_data: Vec<u8>,
+    accounts: Vec<([u8; 32], bool, bool)>,
+    /// Data returned for each fetched account, picked by the first byte of
+    /// its address
+    account_data: Vec<Option<Vec<u8>>>,
+}
+
+fuzz_target!(|input: Input| {
+    let validation_data = if input.raw_validation_data {
+        input.validation_data
+    } else {
+        let Ok(length) = u32::try_from(input.validation_data.len()) else {
+            return;
+        };
+        [
+            FuzzInstruction::SPL_DISCRIMINATOR.as_slice(),
+            &length.to_le_bytes(),
+            &input.validation_data,
+        ]
+        .concat()
+    };
+    let accounts = input
+        .accounts
+        .into_iter()
+        .map(|(address, is_signer, is_writable)| AccountMeta {
+            pubkey: Pubkey::new_from_array(address),
+            is_signer,
+            is_writable,
+        })
+        .collect();
+    let mut instruction = Instruction {
+        program_id: Pubkey::new_from_array([2; 32]),
+        accounts,
+        data: input.instruction_data,
+    };
+    let account_data = &input.account_data;
+    let fetch_account_data_fn = |address: Pubkey| async move {
+        let data = match account_data.len() {
+            0 => None,
+            len => account_data[usize::from(address.as_ref()[0]) % len].clone(),
+        };
+        AccountDataResult::Ok(data)
+    };
+
+    let num_accounts = instruction.accounts.len();
+    let result =
+        futures::executor::block_on(ExtraAccountMetaList::add_to_instruction::<
+            FuzzInstruction,
+            _,
+            _,
+        >(
+            &mut instruction, fetch_account_data_fn, &validation_data
+        ));
+    if result.is_err() {
+        assert!(instruction.accounts.len() >= num_accounts);
+    }
+});
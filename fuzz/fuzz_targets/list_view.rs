@@ -0,0 +1,47 @@
+//! Unpack both `ListView` implementations from arbitrary bytes, and push to
+//! and remove from the lists which unpack
+
+#![no_main]
+
+use {
+    libfuzzer_sys::fuzz_target,
+    solana_zero_copy::unaligned::U16,
+    spl_list_view::{List as _, ListView},
+    spl_pod::{
+        list::{List as _, ListView as PodListView},
+        primitives::PodU16,
+    },
+};
+
+macro_rules! fuzz_list_view {
+    ($list_view:ty, $data:expr) => {{
+        if let Ok(list) = <$list_view>::unpack($data) {
+            assert!(list.len() <= list.capacity());
+            assert!(list.bytes_used().unwrap() <= $data.len());
+            let _ = list
+                .iter()
+                .fold(0u64, |sum, item| sum.wrapping_add(*item as u64));
+        }
+        let mut buf = $data.to_vec();
+        if let Ok(mut list) = <$list_view>::unpack_mut(&mut buf) {
+            let len = list.len();
+            if list.push(Default::default()).is_ok() {
+                assert_eq!(list.len(), len + 1);
+                list.remove(len).unwrap();
+            } else {
+                assert_eq!(len, list.capacity());
+            }
+            if len > 0 {
+                list.remove(0).unwrap();
+                assert_eq!(list.len(), len - 1);
+            }
+        }
+    }};
+}
+
+fuzz_target!(|data: &[u8]| {
+    fuzz_list_view!(ListView<u8>, data);
+    fuzz_list_view!(ListView<u64, U16>, data);
+    fuzz_list_view!(PodListView<u8>, data);
+    fuzz_list_view!(PodListView<u64, PodU16>, data);
+});
@@ -0,0 +1,20 @@
+//! Unpack `PodSlice` and `PodSliceMut` from arbitrary bytes
+
+#![no_main]
+#![allow(deprecated)]
+
+use {
+    libfuzzer_sys::fuzz_target,
+    spl_pod::slice::{PodSlice, PodSliceMut},
+};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(slice) = PodSlice::<[u8; 3]>::unpack(data) {
+        let num_items = slice.data().len();
+        assert!(PodSlice::<[u8; 3]>::size_of(num_items).unwrap() <= data.len());
+    }
+    let mut buf = data.to_vec();
+    if let Ok(mut slice) = PodSliceMut::<u64>::unpack(&mut buf) {
+        let _ = slice.push(u64::MAX);
+    }
+});
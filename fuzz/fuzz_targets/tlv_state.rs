@@ -0,0 +1,25 @@
+//! Unpack TLV state from arbitrary bytes, and read every entry found
+
+#![no_main]
+
+use {
+    libfuzzer_sys::fuzz_target,
+    spl_type_length_value::state::{TlvState, TlvStateBorrowed, TlvStateMut},
+};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(state) = TlvStateBorrowed::unpack(data) else {
+        return;
+    };
+    let discriminators = state.get_discriminators().unwrap();
+    for discriminator in &discriminators {
+        let value = state
+            .get_first_bytes_with_discriminator(*discriminator)
+            .unwrap();
+        assert!(value.len() <= data.len());
+    }
+
+    let mut buf = data.to_vec();
+    let state = TlvStateMut::unpack(&mut buf).unwrap();
+    assert_eq!(state.get_discriminators().unwrap(), discriminators);
+});
@@ -1,9 +1,33 @@
 use {
-    rand::prelude::*,
+    proptest::{collection::vec as prop_vec, prelude::*},
+    rand::Rng,
+    solana_account_info::AccountInfo,
     solana_program_pack::Pack,
-    spl_generic_token::{generic_token, token, token_2022},
+    spl_generic_token::{
+        generic_token::{self, MintView, TokenAccountView, TokenLayout, TokenProgramSpec},
+        token::{self, GenericTokenAccount, GenericTokenMint},
+        token_2022::{
+            self, confidential_transfer, interest_bearing_mint, pointer::Pointer, scaled_ui_amount,
+            transfer_fee,
+        },
+        ui_amount::amount_to_effective_ui_amount,
+    },
     spl_token_2022_interface::{
-        extension::set_account_type,
+        extension::{
+            confidential_transfer::ConfidentialTransferAccount,
+            group_member_pointer::GroupMemberPointer,
+            group_pointer::GroupPointer,
+            immutable_owner::ImmutableOwner,
+            interest_bearing_mint::InterestBearingConfig,
+            memo_transfer::MemoTransfer,
+            metadata_pointer::MetadataPointer,
+            mint_close_authority::MintCloseAuthority,
+            scaled_ui_amount::ScaledUiAmountConfig,
+            set_account_type,
+            transfer_fee::{TransferFee, TransferFeeAmount, TransferFeeConfig},
+            BaseState, BaseStateWithExtensions, BaseStateWithExtensionsMut, ExtensionType,
+            StateWithExtensions, StateWithExtensionsMut,
+        },
         state::{Account as SplAccount2022, Mint as SplMint2022, Multisig as SplMultisig},
     },
     spl_token_interface::state::{
@@ -57,6 +81,41 @@ fn random_token_account() -> SplAccount {
     }
 }
 
+fn assert_account_eq(test_account: &generic_token::Account, expected_account: &SplAccount) {
+    assert_eq!(
+        test_account.mint.to_bytes(),
+        expected_account.mint.to_bytes()
+    );
+    assert_eq!(
+        test_account.owner.to_bytes(),
+        expected_account.owner.to_bytes()
+    );
+    assert_eq!(test_account.amount, expected_account.amount);
+    assert_eq!(
+        test_account.delegate.map(|pubkey| pubkey.to_bytes()),
+        Option::<solana_pubkey::Pubkey>::from(expected_account.delegate)
+            .map(|pubkey| pubkey.to_bytes())
+    );
+    assert_eq!(test_account.state as u8, expected_account.state as u8);
+    assert_eq!(
+        test_account.is_frozen(),
+        expected_account.state == SplAccountState::Frozen
+    );
+    assert_eq!(
+        test_account.is_native,
+        Option::<u64>::from(expected_account.is_native)
+    );
+    assert_eq!(
+        test_account.delegated_amount,
+        expected_account.delegated_amount
+    );
+    assert_eq!(
+        test_account.close_authority.map(|pubkey| pubkey.to_bytes()),
+        Option::<solana_pubkey::Pubkey>::from(expected_account.close_authority)
+            .map(|pubkey| pubkey.to_bytes())
+    );
+}
+
 #[test_case(false; "spl_token")]
 #[test_case(true; "spl_token_2022")]
 fn test_generic_account(is_token_2022_account: bool) {
@@ -66,6 +125,10 @@ fn test_generic_account(is_token_2022_account: bool) {
 
         let mut account_data = vec![0; SplAccount::LEN];
         expected_account.pack_into_slice(&mut account_data);
+        assert_eq!(
+            token::Account::is_frozen(&account_data),
+            is_initialized.then_some(expected_account.state == SplAccountState::Frozen)
+        );
 
         // check the basic rules of the parser:
         // * uninitialized accounts never parse
@@ -84,43 +147,16 @@ fn test_generic_account(is_token_2022_account: bool) {
             // token22
             let test_account =
                 generic_token::Account::unpack(&account_data, &token_2022::id()).unwrap();
-
-            assert_eq!(
-                test_account.mint.to_bytes(),
-                expected_account.mint.to_bytes()
-            );
-            assert_eq!(
-                test_account.owner.to_bytes(),
-                expected_account.owner.to_bytes()
-            );
-            assert_eq!(test_account.amount, expected_account.amount);
+            assert_account_eq(&test_account, &expected_account);
         } else if is_initialized {
             // token
             let test_account = generic_token::Account::unpack(&account_data, &token::id()).unwrap();
-
-            assert_eq!(
-                test_account.mint.to_bytes(),
-                expected_account.mint.to_bytes()
-            );
-            assert_eq!(
-                test_account.owner.to_bytes(),
-                expected_account.owner.to_bytes()
-            );
-            assert_eq!(test_account.amount, expected_account.amount);
+            assert_account_eq(&test_account, &expected_account);
 
             // token22
             let test_account =
                 generic_token::Account::unpack(&account_data, &token_2022::id()).unwrap();
-
-            assert_eq!(
-                test_account.mint.to_bytes(),
-                expected_account.mint.to_bytes()
-            );
-            assert_eq!(
-                test_account.owner.to_bytes(),
-                expected_account.owner.to_bytes()
-            );
-            assert_eq!(test_account.amount, expected_account.amount);
+            assert_account_eq(&test_account, &expected_account);
         } else {
             // token
             assert_eq!(
@@ -186,6 +222,22 @@ fn random_mint() -> SplMint {
     }
 }
 
+fn assert_mint_eq(test_mint: &generic_token::Mint, expected_mint: &SplMint) {
+    assert_eq!(
+        test_mint.mint_authority.map(|pubkey| pubkey.to_bytes()),
+        Option::<solana_pubkey::Pubkey>::from(expected_mint.mint_authority)
+            .map(|pubkey| pubkey.to_bytes())
+    );
+    assert_eq!(test_mint.supply, expected_mint.supply);
+    assert_eq!(test_mint.decimals, expected_mint.decimals);
+    assert_eq!(test_mint.is_initialized, expected_mint.is_initialized);
+    assert_eq!(
+        test_mint.freeze_authority.map(|pubkey| pubkey.to_bytes()),
+        Option::<solana_pubkey::Pubkey>::from(expected_mint.freeze_authority)
+            .map(|pubkey| pubkey.to_bytes())
+    );
+}
+
 #[test_case(false; "spl_token")]
 #[test_case(true; "spl_token_2022")]
 fn test_generic_mint(is_token_2022_mint: bool) {
@@ -212,21 +264,15 @@ fn test_generic_mint(is_token_2022_mint: bool) {
 
             // token22
             let test_mint = generic_token::Mint::unpack(&account_data, &token_2022::id()).unwrap();
-
-            assert_eq!(test_mint.supply, expected_mint.supply);
-            assert_eq!(test_mint.decimals, expected_mint.decimals);
+            assert_mint_eq(&test_mint, &expected_mint);
         } else if is_initialized {
             // token
             let test_mint = generic_token::Mint::unpack(&account_data, &token::id()).unwrap();
-
-            assert_eq!(test_mint.supply, expected_mint.supply);
-            assert_eq!(test_mint.decimals, expected_mint.decimals);
+            assert_mint_eq(&test_mint, &expected_mint);
 
             // token22
             let test_mint = generic_token::Mint::unpack(&account_data, &token_2022::id()).unwrap();
-
-            assert_eq!(test_mint.supply, expected_mint.supply);
-            assert_eq!(test_mint.decimals, expected_mint.decimals);
+            assert_mint_eq(&test_mint, &expected_mint);
         } else {
             // token
             assert_eq!(
@@ -263,3 +309,1165 @@ fn test_generic_mint(is_token_2022_mint: bool) {
         );
     }
 }
+
+#[test]
+fn test_extension_types() {
+    let account_extensions = [
+        ExtensionType::ImmutableOwner,
+        ExtensionType::TransferFeeAmount,
+        ExtensionType::MemoTransfer,
+    ];
+    let account_len =
+        ExtensionType::try_calculate_account_len::<SplAccount2022>(&account_extensions).unwrap();
+    let mut account_data = vec![0; account_len];
+    let mut base = random_token_account();
+    base.state = SplAccountState::Initialized;
+    base.pack_into_slice(&mut account_data[..SplAccount::LEN]);
+    set_account_type::<SplAccount2022>(&mut account_data).unwrap();
+    let mut state = StateWithExtensionsMut::<SplAccount2022>::unpack(&mut account_data).unwrap();
+    state.init_extension::<ImmutableOwner>(true).unwrap();
+    state
+        .init_extension::<TransferFeeAmount>(true)
+        .unwrap()
+        .withheld_amount = 42.into();
+    state.init_extension::<MemoTransfer>(true).unwrap();
+
+    assert_eq!(
+        token_2022::extension_types(&account_data).collect::<Vec<_>>(),
+        account_extensions.map(u16::from)
+    );
+    assert_eq!(
+        token_2022::get_extension(
+            &account_data,
+            token_2022::extension_type::TRANSFER_FEE_AMOUNT
+        ),
+        Some(&42u64.to_le_bytes()[..])
+    );
+    assert_eq!(
+        token_2022::get_extension(&account_data, token_2022::extension_type::TRANSFER_HOOK),
+        None
+    );
+
+    let mint_extensions = [
+        ExtensionType::TransferFeeConfig,
+        ExtensionType::MintCloseAuthority,
+    ];
+    let mint_len =
+        ExtensionType::try_calculate_account_len::<SplMint2022>(&mint_extensions).unwrap();
+    let mut mint_data = vec![0; mint_len];
+    let mut base = random_mint();
+    base.is_initialized = true;
+    base.pack_into_slice(&mut mint_data[..SplMint::LEN]);
+    set_account_type::<SplMint2022>(&mut mint_data).unwrap();
+    let mut state = StateWithExtensionsMut::<SplMint2022>::unpack(&mut mint_data).unwrap();
+    state.init_extension::<TransferFeeConfig>(true).unwrap();
+    state.init_extension::<MintCloseAuthority>(true).unwrap();
+
+    assert_eq!(
+        token_2022::extension_types(&mint_data).collect::<Vec<_>>(),
+        mint_extensions.map(u16::from)
+    );
+
+    // truncated entries and base-only accounts have no further extensions
+    let truncated_len = SplAccount::LEN + 1 + 4 + 2;
+    assert_eq!(
+        token_2022::extension_types(&account_data[..truncated_len]).collect::<Vec<_>>(),
+        [u16::from(ExtensionType::ImmutableOwner)]
+    );
+    assert_eq!(
+        token_2022::extension_types(&account_data[..SplAccount::LEN]).count(),
+        0
+    );
+}
+
+fn random_transfer_fee() -> TransferFee {
+    let mut rng = rand::rng();
+    TransferFee {
+        epoch: rng.random::<u64>().into(),
+        maximum_fee: rng.random::<u64>().into(),
+        transfer_fee_basis_points: rng.random_range(0..=10_000u16).into(),
+    }
+}
+
+fn assert_transfer_fee_eq(test_fee: &transfer_fee::TransferFee, expected_fee: &TransferFee) {
+    assert_eq!(test_fee.epoch, u64::from(expected_fee.epoch));
+    assert_eq!(test_fee.maximum_fee, u64::from(expected_fee.maximum_fee));
+    assert_eq!(
+        test_fee.transfer_fee_basis_points,
+        u16::from(expected_fee.transfer_fee_basis_points)
+    );
+}
+
+#[test]
+fn test_transfer_fee() {
+    let mut rng = rand::rng();
+    for _ in 0..100 {
+        let mint_len = ExtensionType::try_calculate_account_len::<SplMint2022>(&[
+            ExtensionType::MintCloseAuthority,
+            ExtensionType::TransferFeeConfig,
+        ])
+        .unwrap();
+        let mut mint_data = vec![0; mint_len];
+        let mut base = random_mint();
+        base.is_initialized = true;
+        base.pack_into_slice(&mut mint_data[..SplMint::LEN]);
+        set_account_type::<SplMint2022>(&mut mint_data).unwrap();
+        let mut state = StateWithExtensionsMut::<SplMint2022>::unpack(&mut mint_data).unwrap();
+        state.init_extension::<MintCloseAuthority>(true).unwrap();
+        let expected_config = state.init_extension::<TransferFeeConfig>(true).unwrap();
+        expected_config.transfer_fee_config_authority = if rng.random() {
+            Some(solana_pubkey::new_rand())
+        } else {
+            None
+        }
+        .try_into()
+        .unwrap();
+        expected_config.withdraw_withheld_authority =
+            Some(solana_pubkey::new_rand()).try_into().unwrap();
+        expected_config.withheld_amount = rng.random::<u64>().into();
+        expected_config.older_transfer_fee = random_transfer_fee();
+        expected_config.newer_transfer_fee = random_transfer_fee();
+        let expected_config = *expected_config;
+
+        let test_config = transfer_fee::TransferFeeConfig::unpack(&mint_data).unwrap();
+        assert_eq!(
+            test_config
+                .transfer_fee_config_authority
+                .map(|pubkey| pubkey.to_bytes()),
+            Option::<solana_pubkey::Pubkey>::from(expected_config.transfer_fee_config_authority)
+                .map(|pubkey| pubkey.to_bytes())
+        );
+        assert_eq!(
+            test_config
+                .withdraw_withheld_authority
+                .map(|pubkey| pubkey.to_bytes()),
+            Option::<solana_pubkey::Pubkey>::from(expected_config.withdraw_withheld_authority)
+                .map(|pubkey| pubkey.to_bytes())
+        );
+        assert_eq!(
+            test_config.withheld_amount,
+            u64::from(expected_config.withheld_amount)
+        );
+        assert_transfer_fee_eq(
+            &test_config.older_transfer_fee,
+            &expected_config.older_transfer_fee,
+        );
+        assert_transfer_fee_eq(
+            &test_config.newer_transfer_fee,
+            &expected_config.newer_transfer_fee,
+        );
+
+        let epoch = rng.random();
+        let amount = rng.random();
+        assert_transfer_fee_eq(
+            test_config.get_epoch_fee(epoch),
+            expected_config.get_epoch_fee(epoch),
+        );
+        assert_eq!(
+            test_config.get_epoch_fee(epoch).calculate_fee(amount),
+            expected_config.calculate_epoch_fee(epoch, amount)
+        );
+
+        // mints are not accounts
+        assert_eq!(transfer_fee::TransferFeeAmount::unpack(&mint_data), None);
+    }
+
+    let account_len = ExtensionType::try_calculate_account_len::<SplAccount2022>(&[
+        ExtensionType::TransferFeeAmount,
+    ])
+    .unwrap();
+    let mut account_data = vec![0; account_len];
+    let mut base = random_token_account();
+    base.state = SplAccountState::Initialized;
+    base.pack_into_slice(&mut account_data[..SplAccount::LEN]);
+    set_account_type::<SplAccount2022>(&mut account_data).unwrap();
+    let mut state = StateWithExtensionsMut::<SplAccount2022>::unpack(&mut account_data).unwrap();
+    let withheld_amount: u64 = rng.random();
+    state
+        .init_extension::<TransferFeeAmount>(true)
+        .unwrap()
+        .withheld_amount = withheld_amount.into();
+
+    assert_eq!(
+        transfer_fee::TransferFeeAmount::unpack(&account_data),
+        Some(transfer_fee::TransferFeeAmount { withheld_amount })
+    );
+    assert_eq!(transfer_fee::TransferFeeConfig::unpack(&account_data), None);
+    // accounts without the extension
+    assert_eq!(
+        transfer_fee::TransferFeeAmount::unpack(&account_data[..SplAccount::LEN]),
+        None
+    );
+}
+
+#[test]
+fn test_unpack_with_programs() {
+    let fork_id = solana_pubkey::new_rand();
+    let programs = [
+        TokenProgramSpec::new(fork_id, TokenLayout::Token2022),
+        TokenProgramSpec::new(token::id(), TokenLayout::Token),
+    ];
+
+    let mut expected_account = random_token_account();
+    expected_account.state = SplAccountState::Initialized;
+    let mut account_data = vec![0; SplAccount::LEN + 2];
+    expected_account.pack_into_slice(&mut account_data[..SplAccount::LEN]);
+    set_account_type::<SplAccount2022>(&mut account_data).unwrap();
+
+    let test_account =
+        generic_token::Account::unpack_with_programs(&account_data, &fork_id, &programs).unwrap();
+    assert_account_eq(&test_account, &expected_account);
+    assert_eq!(
+        generic_token::Account::unpack(&account_data, &fork_id),
+        None
+    );
+    // the layout is that of the program, so typed 2022 accounts don't parse as token
+    assert_eq!(
+        generic_token::Account::unpack_with_programs(&account_data, &token::id(), &programs),
+        None
+    );
+    assert_eq!(
+        generic_token::Account::unpack_with_programs(&account_data, &token_2022::id(), &programs),
+        None
+    );
+
+    let mut expected_mint = random_mint();
+    expected_mint.is_initialized = true;
+    let mut mint_data = vec![0; SplMint::LEN];
+    expected_mint.pack_into_slice(&mut mint_data);
+
+    let test_mint =
+        generic_token::Mint::unpack_with_programs(&mint_data, &fork_id, &programs).unwrap();
+    assert_mint_eq(&test_mint, &expected_mint);
+    let test_mint =
+        generic_token::Mint::unpack_with_programs(&mint_data, &token::id(), &programs).unwrap();
+    assert_mint_eq(&test_mint, &expected_mint);
+    assert_eq!(generic_token::Mint::unpack(&mint_data, &fork_id), None);
+}
+
+#[test]
+fn test_unpack_many() {
+    let mut rng = rand::rng();
+    let owners = [token::id(), token_2022::id(), solana_pubkey::new_rand()];
+    let mut accounts = vec![];
+    for _ in 0..100 {
+        let mut account = random_token_account();
+        account.state = SplAccountState::Initialized;
+        let mut account_data = vec![0; SplAccount::LEN];
+        account.pack_into_slice(&mut account_data);
+        if rng.random_ratio(1, 4) {
+            account_data.truncate(SplAccount::LEN - 1);
+        }
+        // runs of accounts with the same owner
+        let owner = owners[rng.random_range(0..owners.len())];
+        for _ in 0..rng.random_range(1..4) {
+            accounts.push((owner, account_data.clone()));
+        }
+    }
+
+    let test_accounts: Vec<_> = generic_token::Account::unpack_many(
+        accounts
+            .iter()
+            .map(|(owner, account_data)| (owner, account_data.as_slice())),
+    )
+    .collect();
+    assert_eq!(test_accounts.len(), accounts.len());
+    for ((owner, account_data), test_account) in accounts.iter().zip(test_accounts) {
+        assert_eq!(
+            test_account,
+            generic_token::Account::unpack(account_data, owner)
+        );
+    }
+
+    // forks are parsed with their specs
+    let fork_id = solana_pubkey::new_rand();
+    let programs = [TokenProgramSpec::new(fork_id, TokenLayout::Token)];
+    let expected_account = SplAccount {
+        state: SplAccountState::Initialized,
+        ..random_token_account()
+    };
+    let mut account_data = vec![0; SplAccount::LEN];
+    expected_account.pack_into_slice(&mut account_data);
+    let test_accounts: Vec<_> = generic_token::Account::unpack_many_with_programs(
+        [
+            (&fork_id, account_data.as_slice()),
+            (&token::id(), account_data.as_slice()),
+        ],
+        &programs,
+    )
+    .collect();
+    assert_eq!(test_accounts.len(), 2);
+    assert_account_eq(test_accounts[0].as_ref().unwrap(), &expected_account);
+    assert_eq!(test_accounts[1], None);
+}
+
+#[test]
+fn test_unpack_by_owner() {
+    let mut expected_account = random_token_account();
+    expected_account.state = SplAccountState::Initialized;
+    let mut account_data = vec![0; SplAccount::LEN];
+    expected_account.pack_into_slice(&mut account_data);
+
+    let test_account =
+        generic_token::Account::unpack_keyed_account(&token::id(), &account_data).unwrap();
+    assert_account_eq(&test_account, &expected_account);
+    // the account key is not a token program
+    let key = solana_pubkey::new_rand();
+    assert_eq!(
+        generic_token::Account::unpack_keyed_account(&key, &account_data),
+        None
+    );
+
+    let owner = token_2022::id();
+    let mut lamports = 0;
+    let account_info = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut account_data,
+        &owner,
+        false,
+    );
+    let test_account = generic_token::Account::unpack_account_info(&account_info).unwrap();
+    assert_account_eq(&test_account, &expected_account);
+    assert_eq!(
+        generic_token::Mint::unpack_account_info(&account_info),
+        None
+    );
+
+    let mut expected_mint = random_mint();
+    expected_mint.is_initialized = true;
+    let mut mint_data = vec![0; SplMint::LEN];
+    expected_mint.pack_into_slice(&mut mint_data);
+
+    let test_mint =
+        generic_token::Mint::unpack_keyed_account(&token_2022::id(), &mint_data).unwrap();
+    assert_mint_eq(&test_mint, &expected_mint);
+    let mut lamports = 0;
+    let account_info = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut mint_data,
+        &owner,
+        false,
+    );
+    let test_mint = generic_token::Mint::unpack_account_info(&account_info).unwrap();
+    assert_mint_eq(&test_mint, &expected_mint);
+
+    // fails while the data is borrowed mutably
+    let _data = account_info.try_borrow_mut_data().unwrap();
+    assert_eq!(
+        generic_token::Mint::unpack_account_info(&account_info),
+        None
+    );
+}
+
+#[test]
+fn test_set_account_fields() {
+    let mut expected_account = random_token_account();
+    expected_account.state = SplAccountState::Initialized;
+    let mut account_data = vec![0; SplAccount::LEN + 2];
+    expected_account.pack_into_slice(&mut account_data[..SplAccount::LEN]);
+    set_account_type::<SplAccount2022>(&mut account_data).unwrap();
+
+    let owner = solana_pubkey::new_rand();
+    let amount = rand::rng().random();
+    token_2022::Account::set_account_owner(&mut account_data, &owner).unwrap();
+    token_2022::Account::set_account_amount(&mut account_data, amount).unwrap();
+    token_2022::Account::set_account_state(&mut account_data, token::AccountState::Frozen).unwrap();
+    expected_account.owner = owner;
+    expected_account.amount = amount;
+    expected_account.state = SplAccountState::Frozen;
+    assert_eq!(
+        SplAccount2022::unpack_from_slice(&account_data[..SplAccount::LEN])
+            .unwrap()
+            .owner,
+        owner
+    );
+    let test_account = generic_token::Account::unpack(&account_data, &token_2022::id()).unwrap();
+    assert_account_eq(&test_account, &expected_account);
+
+    // only valid accounts are written through the checked setters
+    assert_eq!(
+        token::Account::set_account_amount(&mut account_data, 0),
+        None
+    );
+    token::Account::pack_account_amount_unchecked(&mut account_data, 0);
+    token::Account::pack_account_state_unchecked(
+        &mut account_data,
+        token::AccountState::Uninitialized,
+    );
+    assert_eq!(
+        token_2022::Account::set_account_amount(&mut account_data, 1),
+        None
+    );
+    assert_eq!(
+        token::Account::unpack_account_amount_unchecked(&account_data),
+        0
+    );
+}
+
+#[test]
+fn test_owner_checked_getters() {
+    let mut expected_account = random_token_account();
+    expected_account.state = SplAccountState::Initialized;
+    let mut account_data = vec![0; SplAccount::LEN];
+    expected_account.pack_into_slice(&mut account_data);
+
+    assert_eq!(
+        token::Account::unpack_account_owner_checked(&account_data, &token::id()),
+        Some(&expected_account.owner)
+    );
+    assert_eq!(
+        token_2022::Account::unpack_account_amount_checked(&account_data, &token_2022::id()),
+        Some(expected_account.amount)
+    );
+    // the account must be owned by the token program of the layout
+    assert_eq!(
+        token::Account::unpack_account_owner_checked(&account_data, &token_2022::id()),
+        None
+    );
+    assert_eq!(
+        token_2022::Account::unpack_account_mint_checked(&account_data, &token::id()),
+        None
+    );
+    assert_eq!(
+        token::Account::is_frozen_checked(&account_data, &solana_pubkey::new_rand()),
+        None
+    );
+    // and still be valid
+    assert_eq!(
+        token::Account::unpack_account_owner_checked(
+            &account_data[..SplAccount::LEN - 1],
+            &token::id()
+        ),
+        None
+    );
+
+    let mut expected_mint = random_mint();
+    expected_mint.is_initialized = true;
+    let mut mint_data = vec![0; SplMint::LEN];
+    expected_mint.pack_into_slice(&mut mint_data);
+
+    assert_eq!(
+        token::Mint::unpack_mint_decimals_checked(&mint_data, &token::id()),
+        Some(expected_mint.decimals)
+    );
+    assert_eq!(
+        token_2022::Mint::unpack_mint_supply_checked(&mint_data, &token_2022::id()),
+        Some(expected_mint.supply)
+    );
+    assert_eq!(
+        token::Mint::unpack_mint_decimals_checked(&mint_data, &token_2022::id()),
+        None
+    );
+    assert_eq!(
+        token_2022::Mint::unpack_mint_authority_checked(&mint_data, &token::id()),
+        None
+    );
+}
+
+fn multisig_data(m: u8, signers: &[solana_pubkey::Pubkey]) -> Vec<u8> {
+    let mut multisig = SplMultisig {
+        m,
+        n: signers.len() as u8,
+        is_initialized: true,
+        ..SplMultisig::default()
+    };
+    multisig.signers[..signers.len()].copy_from_slice(signers);
+    // keys past `n` are never signers
+    for signer in &mut multisig.signers[signers.len()..] {
+        *signer = solana_pubkey::new_rand();
+    }
+    let mut multisig_data = vec![0; SplMultisig::LEN];
+    multisig.pack_into_slice(&mut multisig_data);
+    multisig_data
+}
+
+#[test]
+fn test_multisig() {
+    let keys: Vec<solana_pubkey::Pubkey> = (0..4).map(|_| solana_pubkey::new_rand()).collect();
+    let [a, b, c, d] = [&keys[0], &keys[1], &keys[2], &keys[3]];
+
+    let data = multisig_data(2, &[*a, *b, *c]);
+    let expected_multisig = SplMultisig::unpack(&data).unwrap();
+    for program_id in [token::id(), token_2022::id()] {
+        let multisig = generic_token::Multisig::unpack(&data, &program_id).unwrap();
+        assert_eq!(multisig.m, expected_multisig.m);
+        assert_eq!(multisig.n, expected_multisig.n);
+        assert!(multisig.is_initialized);
+        assert_eq!(multisig.signers, expected_multisig.signers);
+        assert_eq!(multisig.signers().collect::<Vec<_>>(), vec![a, b, c]);
+
+        assert!(!multisig.validate_signers(&[]));
+        assert!(!multisig.validate_signers(&[a]));
+        assert!(multisig.validate_signers(&[a, b]));
+        assert!(multisig.validate_signers(&[d, c, a]));
+        // a key only counts once
+        assert!(!multisig.validate_signers(&[a, a]));
+        // signers past `n` don't count
+        assert!(!multisig.validate_signers(&[a, &multisig.signers[3]]));
+    }
+
+    // like the token programs, a key listed twice fills both of its slots
+    let multisig =
+        generic_token::Multisig::unpack(&multisig_data(2, &[*a, *a, *b]), &token::id()).unwrap();
+    assert!(multisig.validate_signers(&[a]));
+
+    let multisig =
+        generic_token::Multisig::unpack(&multisig_data(11, &[*d; 11]), &token::id()).unwrap();
+    assert_eq!(multisig.signers().count(), 11);
+    assert!(multisig.validate_signers(&[d]));
+
+    // only initialized multisigs of known programs, with at most 11 signers, are parsed
+    assert_eq!(
+        generic_token::Multisig::unpack(&data, &solana_pubkey::new_rand()),
+        None
+    );
+    assert_eq!(
+        generic_token::Multisig::unpack(&data[..SplMultisig::LEN - 1], &token::id()),
+        None
+    );
+    let mut invalid_data = data.clone();
+    invalid_data[2] = 0;
+    assert_eq!(
+        generic_token::Multisig::unpack(&invalid_data, &token::id()),
+        None
+    );
+    let mut invalid_data = data;
+    invalid_data[1] = 12;
+    assert_eq!(
+        generic_token::Multisig::unpack(&invalid_data, &token::id()),
+        None
+    );
+}
+
+fn mint_data_with_extensions(extension_types: &[ExtensionType]) -> (SplMint, Vec<u8>) {
+    let mint_len =
+        ExtensionType::try_calculate_account_len::<SplMint2022>(extension_types).unwrap();
+    let mut mint_data = vec![0; mint_len];
+    let mut mint = random_mint();
+    mint.is_initialized = true;
+    mint.decimals = rand::rng().random_range(0..10);
+    mint.pack_into_slice(&mut mint_data[..SplMint::LEN]);
+    set_account_type::<SplMint2022>(&mut mint_data).unwrap();
+    (mint, mint_data)
+}
+
+#[test]
+fn test_pointers() {
+    let (_, mut mint_data) = mint_data_with_extensions(&[
+        ExtensionType::MetadataPointer,
+        ExtensionType::GroupPointer,
+        ExtensionType::GroupMemberPointer,
+    ]);
+    let mint_address = solana_pubkey::new_rand();
+    let authority = solana_pubkey::new_rand();
+    let mut state = StateWithExtensionsMut::<SplMint2022>::unpack(&mut mint_data).unwrap();
+    let metadata_pointer = state.init_extension::<MetadataPointer>(true).unwrap();
+    metadata_pointer.authority = Some(authority).try_into().unwrap();
+    metadata_pointer.metadata_address = Some(mint_address).try_into().unwrap();
+    let group_pointer = state.init_extension::<GroupPointer>(true).unwrap();
+    group_pointer.group_address = Some(mint_address).try_into().unwrap();
+    let group_member_pointer = state.init_extension::<GroupMemberPointer>(true).unwrap();
+    group_member_pointer.authority = Some(authority).try_into().unwrap();
+
+    assert_eq!(
+        Pointer::unpack_metadata_pointer(&mint_data),
+        Some(Pointer {
+            authority: Some(authority),
+            address: Some(mint_address),
+        })
+    );
+    assert_eq!(
+        Pointer::unpack_group_pointer(&mint_data),
+        Some(Pointer {
+            authority: None,
+            address: Some(mint_address),
+        })
+    );
+    assert_eq!(
+        Pointer::unpack_group_member_pointer(&mint_data),
+        Some(Pointer {
+            authority: Some(authority),
+            address: None,
+        })
+    );
+
+    // mints without the extension have no pointer
+    let (_, mut mint_data) = mint_data_with_extensions(&[ExtensionType::MintCloseAuthority]);
+    let mut state = StateWithExtensionsMut::<SplMint2022>::unpack(&mut mint_data).unwrap();
+    state.init_extension::<MintCloseAuthority>(true).unwrap();
+    assert_eq!(Pointer::unpack_metadata_pointer(&mint_data), None);
+    assert_eq!(Pointer::unpack_group_pointer(&mint_data), None);
+    assert_eq!(Pointer::unpack_group_member_pointer(&mint_data), None);
+}
+
+#[test]
+fn test_ui_amount_extensions() {
+    let mut rng = rand::rng();
+    for _ in 0..100 {
+        let amount = rng.random_range(0..u64::MAX / 4);
+        let unix_timestamp = rng.random_range(0..4_000_000_000);
+
+        let (mint, mut mint_data) =
+            mint_data_with_extensions(&[ExtensionType::InterestBearingConfig]);
+        let mut state = StateWithExtensionsMut::<SplMint2022>::unpack(&mut mint_data).unwrap();
+        let expected_config = state.init_extension::<InterestBearingConfig>(true).unwrap();
+        expected_config.rate_authority = Some(solana_pubkey::new_rand()).try_into().unwrap();
+        expected_config.initialization_timestamp = rng.random_range(0..1_000_000_000).into();
+        expected_config.pre_update_average_rate = rng.random::<i16>().into();
+        expected_config.last_update_timestamp =
+            rng.random_range(1_000_000_000..2_000_000_000).into();
+        expected_config.current_rate = rng.random::<i16>().into();
+        let expected_config = *expected_config;
+
+        let test_config = interest_bearing_mint::InterestBearingConfig::unpack(&mint_data).unwrap();
+        assert_eq!(
+            test_config.rate_authority,
+            Option::<solana_pubkey::Pubkey>::from(expected_config.rate_authority)
+        );
+        assert_eq!(
+            test_config.initialization_timestamp,
+            i64::from(expected_config.initialization_timestamp)
+        );
+        assert_eq!(
+            test_config.pre_update_average_rate,
+            i16::from(expected_config.pre_update_average_rate)
+        );
+        assert_eq!(
+            test_config.last_update_timestamp,
+            i64::from(expected_config.last_update_timestamp)
+        );
+        assert_eq!(
+            test_config.current_rate,
+            i16::from(expected_config.current_rate)
+        );
+        let expected_ui_amount =
+            expected_config.amount_to_ui_amount(amount, mint.decimals, unix_timestamp);
+        assert_eq!(
+            test_config.amount_to_ui_amount(amount, mint.decimals, unix_timestamp),
+            expected_ui_amount
+        );
+        assert_eq!(
+            amount_to_effective_ui_amount(&mint_data, &token_2022::id(), amount, unix_timestamp),
+            expected_ui_amount
+        );
+        assert_eq!(
+            scaled_ui_amount::ScaledUiAmountConfig::unpack(&mint_data),
+            None
+        );
+
+        let (mint, mut mint_data) = mint_data_with_extensions(&[ExtensionType::ScaledUiAmount]);
+        let mut state = StateWithExtensionsMut::<SplMint2022>::unpack(&mut mint_data).unwrap();
+        let expected_config = state.init_extension::<ScaledUiAmountConfig>(true).unwrap();
+        expected_config.multiplier = rng.random_range(0.0..10.0).into();
+        expected_config.new_multiplier_effective_timestamp =
+            rng.random_range(0..4_000_000_000).into();
+        expected_config.new_multiplier = rng.random_range(0.0..10.0).into();
+        let expected_config = *expected_config;
+
+        let test_config = scaled_ui_amount::ScaledUiAmountConfig::unpack(&mint_data).unwrap();
+        assert_eq!(test_config.authority, None);
+        assert_eq!(
+            test_config.multiplier,
+            f64::from(expected_config.multiplier)
+        );
+        assert_eq!(
+            test_config.new_multiplier_effective_timestamp,
+            i64::from(expected_config.new_multiplier_effective_timestamp)
+        );
+        assert_eq!(
+            test_config.new_multiplier,
+            f64::from(expected_config.new_multiplier)
+        );
+        let expected_ui_amount =
+            expected_config.amount_to_ui_amount(amount, mint.decimals, unix_timestamp);
+        assert_eq!(
+            test_config.amount_to_ui_amount(amount, mint.decimals, unix_timestamp),
+            expected_ui_amount
+        );
+        assert_eq!(
+            amount_to_effective_ui_amount(&mint_data, &token_2022::id(), amount, unix_timestamp),
+            expected_ui_amount
+        );
+    }
+
+    // mints without either extension use the plain UI amount
+    let mut mint = random_mint();
+    mint.is_initialized = true;
+    mint.decimals = 3;
+    let mut mint_data = vec![0; SplMint::LEN];
+    mint.pack_into_slice(&mut mint_data);
+    assert_eq!(
+        amount_to_effective_ui_amount(&mint_data, &token::id(), 1_500, 0),
+        Some("1.5".to_string())
+    );
+    assert_eq!(
+        amount_to_effective_ui_amount(&mint_data, &solana_pubkey::new_rand(), 1_500, 0),
+        None
+    );
+}
+
+#[test]
+fn test_native_mint() {
+    assert_eq!(
+        token::native_mint::id(),
+        spl_token_interface::native_mint::id()
+    );
+    assert_eq!(
+        token_2022::native_mint::id(),
+        spl_token_2022_interface::native_mint::id()
+    );
+    assert!(spl_generic_token::is_native_mint(&token::native_mint::id()));
+    assert!(spl_generic_token::is_native_mint(
+        &token_2022::native_mint::id()
+    ));
+    assert!(!spl_generic_token::is_native_mint(&token_2022::id()));
+
+    let expected_mint = SplMint2022 {
+        decimals: spl_token_2022_interface::native_mint::DECIMALS,
+        is_initialized: true,
+        ..SplMint2022::default()
+    };
+    let mut mint_data = vec![0; SplMint2022::LEN];
+    expected_mint.pack_into_slice(&mut mint_data);
+    assert_eq!(mint_data, token_2022::native_mint::ACCOUNT_DATA);
+    let test_mint =
+        generic_token::Mint::unpack(&token_2022::native_mint::ACCOUNT_DATA, &token_2022::id())
+            .unwrap();
+    assert_eq!(test_mint.decimals, 9);
+}
+
+#[test]
+fn test_unpack_strict() {
+    let mut expected_account = random_token_account();
+    expected_account.state = SplAccountState::Initialized;
+    let account_len = ExtensionType::try_calculate_account_len::<SplAccount2022>(&[
+        ExtensionType::ImmutableOwner,
+        ExtensionType::TransferFeeAmount,
+    ])
+    .unwrap();
+    let mut account_data = vec![0; account_len];
+    expected_account.pack_into_slice(&mut account_data[..SplAccount::LEN]);
+    set_account_type::<SplAccount2022>(&mut account_data).unwrap();
+    let mut state = StateWithExtensionsMut::<SplAccount2022>::unpack(&mut account_data).unwrap();
+    state.init_extension::<ImmutableOwner>(true).unwrap();
+    state.init_extension::<TransferFeeAmount>(true).unwrap();
+
+    let program_id = token_2022::id();
+    let test_account =
+        generic_token::Account::unpack_strict(&account_data, &program_id, &program_id).unwrap();
+    assert_account_eq(&test_account, &expected_account);
+    // a trailing byte is allowed, like in Token2022
+    account_data.push(0);
+    assert!(
+        generic_token::Account::unpack_strict(&account_data, &program_id, &program_id).is_some()
+    );
+    account_data.pop();
+
+    // the owner must be the program
+    assert_eq!(
+        generic_token::Account::unpack_strict(&account_data, &program_id, &token::id()),
+        None
+    );
+    // extensions must fit in the data
+    let last_length_offset = account_len - 8 - 2;
+    account_data[last_length_offset] = 9;
+    assert!(generic_token::Account::unpack(&account_data, &program_id).is_some());
+    assert_eq!(
+        generic_token::Account::unpack_strict(&account_data, &program_id, &program_id),
+        None
+    );
+    account_data[last_length_offset] = 8;
+    // extensions must have a known type
+    let first_type_offset = SplAccount::LEN + 1;
+    account_data[first_type_offset..first_type_offset + 2].copy_from_slice(&u16::MAX.to_le_bytes());
+    assert_eq!(
+        generic_token::Account::unpack_strict(&account_data, &program_id, &program_id),
+        None
+    );
+    account_data[first_type_offset..first_type_offset + 2]
+        .copy_from_slice(&(ExtensionType::ImmutableOwner as u16).to_le_bytes());
+    // optional fields must have a valid tag
+    for offset in [72, 109, 129] {
+        let tag = account_data[offset];
+        account_data[offset] = 2;
+        assert!(generic_token::Account::unpack(&account_data, &program_id).is_some());
+        assert_eq!(
+            generic_token::Account::unpack_strict(&account_data, &program_id, &program_id),
+            None
+        );
+        account_data[offset] = tag;
+    }
+    assert!(
+        generic_token::Account::unpack_strict(&account_data, &program_id, &program_id).is_some()
+    );
+    account_data.truncate(account_len - 3);
+    assert_eq!(
+        generic_token::Account::unpack_strict(&account_data, &program_id, &program_id),
+        None
+    );
+
+    let (expected_mint, mut mint_data) =
+        mint_data_with_extensions(&[ExtensionType::MintCloseAuthority]);
+    let mut state = StateWithExtensionsMut::<SplMint2022>::unpack(&mut mint_data).unwrap();
+    state.init_extension::<MintCloseAuthority>(true).unwrap();
+
+    let test_mint =
+        generic_token::Mint::unpack_strict(&mint_data, &program_id, &program_id).unwrap();
+    assert_mint_eq(&test_mint, &expected_mint);
+    // mints are padded with zeroes
+    mint_data[SplMint::LEN] = 1;
+    assert!(generic_token::Mint::unpack(&mint_data, &program_id).is_some());
+    assert_eq!(
+        generic_token::Mint::unpack_strict(&mint_data, &program_id, &program_id),
+        None
+    );
+
+    // base mints only need the owner check
+    let mut mint_data = vec![0; SplMint::LEN];
+    expected_mint.pack_into_slice(&mut mint_data);
+    let program_id = token::id();
+    assert!(generic_token::Mint::unpack_strict(&mint_data, &program_id, &program_id).is_some());
+    assert_eq!(
+        generic_token::Mint::unpack_strict(&mint_data, &program_id, &solana_pubkey::new_rand()),
+        None
+    );
+    // optional fields must have a valid tag, and the initialized flag must be a boolean
+    for (offset, invalid) in [(0, 2), (46, 2), (45, 2), (1, 1)] {
+        let byte = mint_data[offset];
+        mint_data[offset] = invalid;
+        assert!(generic_token::Mint::unpack(&mint_data, &program_id).is_some());
+        assert_eq!(
+            generic_token::Mint::unpack_strict(&mint_data, &program_id, &program_id),
+            None
+        );
+        mint_data[offset] = byte;
+    }
+}
+
+#[test]
+fn test_confidential_transfer_account() {
+    let mut rng = rand::rng();
+    let mut expected_account = random_token_account();
+    expected_account.state = SplAccountState::Initialized;
+    let account_len = ExtensionType::try_calculate_account_len::<SplAccount2022>(&[
+        ExtensionType::ConfidentialTransferAccount,
+    ])
+    .unwrap();
+    let mut account_data = vec![0; account_len];
+    expected_account.pack_into_slice(&mut account_data[..SplAccount::LEN]);
+    set_account_type::<SplAccount2022>(&mut account_data).unwrap();
+    assert!(!confidential_transfer::is_confidential_account(
+        &account_data
+    ));
+
+    let elgamal_pubkey: [u8; 32] = rng.random();
+    let mut decryptable_available_balance = [0; 36];
+    rng.fill(&mut decryptable_available_balance[..]);
+    let mut state = StateWithExtensionsMut::<SplAccount2022>::unpack(&mut account_data).unwrap();
+    let extension = state
+        .init_extension::<ConfidentialTransferAccount>(true)
+        .unwrap();
+    extension.approved = true.into();
+    extension.elgamal_pubkey = elgamal_pubkey.into();
+    extension.available_balance = [1; 64].into();
+    extension.decryptable_available_balance = decryptable_available_balance.into();
+    extension.allow_non_confidential_credits = true.into();
+    extension.actual_pending_balance_credit_counter = u64::MAX.into();
+
+    assert!(confidential_transfer::is_confidential_account(
+        &account_data
+    ));
+    assert_eq!(
+        confidential_transfer::ConfidentialTransferAccount::unpack(&account_data),
+        Some(confidential_transfer::ConfidentialTransferAccount {
+            approved: true,
+            elgamal_pubkey,
+            decryptable_available_balance,
+            allow_confidential_credits: false,
+            allow_non_confidential_credits: true,
+        })
+    );
+    // the account itself still parses
+    let test_account = generic_token::Account::unpack(&account_data, &token_2022::id()).unwrap();
+    assert_account_eq(&test_account, &expected_account);
+}
+
+#[test]
+fn test_views() {
+    for _ in 0..100 {
+        let expected_account = random_token_account();
+        let mut account_data = vec![0; SplAccount::LEN];
+        expected_account.pack_into_slice(&mut account_data);
+
+        let view = TokenAccountView::unpack(&account_data, &token::id());
+        if expected_account.state == SplAccountState::Uninitialized {
+            assert_eq!(view, None);
+            continue;
+        }
+        let view = view.unwrap();
+        assert_eq!(view.account_data(), &account_data[..]);
+        assert_eq!(view.amount(), expected_account.amount);
+        assert_eq!(
+            view.is_frozen(),
+            expected_account.state == SplAccountState::Frozen
+        );
+        assert_account_eq(&view.to_owned(), &expected_account);
+        assert_eq!(
+            view.to_owned(),
+            generic_token::Account::unpack(&account_data, &token::id()).unwrap()
+        );
+        assert_eq!(
+            TokenAccountView::unpack(&account_data, &solana_pubkey::new_rand()),
+            None
+        );
+    }
+
+    let mut expected_mint = random_mint();
+    expected_mint.is_initialized = true;
+    let mut mint_data = vec![0; SplMint::LEN];
+    expected_mint.pack_into_slice(&mut mint_data);
+    let view = MintView::unpack(&mint_data, &token_2022::id()).unwrap();
+    assert_eq!(view.supply(), expected_mint.supply);
+    assert_eq!(view.decimals(), expected_mint.decimals);
+    assert_mint_eq(&view.to_owned(), &expected_mint);
+    assert_eq!(MintView::unpack(&mint_data[1..], &token::id()), None);
+}
+
+#[test]
+fn test_resolve_decimals() {
+    let mint_address = solana_pubkey::new_rand();
+    let mut mint = random_mint();
+    mint.is_initialized = true;
+    mint.decimals = 6;
+    let mut mint_data = vec![0; SplMint::LEN];
+    mint.pack_into_slice(&mut mint_data);
+
+    let mut account = random_token_account();
+    account.mint = mint_address;
+    account.state = SplAccountState::Initialized;
+    account.amount = 1_250_000;
+    let mut account_data = vec![0; SplAccount::LEN];
+    account.pack_into_slice(&mut account_data);
+
+    let program_id = token::id();
+    assert_eq!(
+        generic_token::resolve_decimals(&account_data, &mint_address, &mint_data, &program_id),
+        Some(6)
+    );
+    assert_eq!(
+        generic_token::checked_ui_amount(&account_data, &mint_address, &mint_data, &program_id, 0),
+        Some("1.25".to_string())
+    );
+
+    // another mint, an account as mint, or another program
+    let other_mint = solana_pubkey::new_rand();
+    assert_eq!(
+        generic_token::resolve_decimals(&account_data, &other_mint, &mint_data, &program_id),
+        None
+    );
+    assert_eq!(
+        generic_token::checked_ui_amount(&account_data, &other_mint, &mint_data, &program_id, 0),
+        None
+    );
+    assert_eq!(
+        generic_token::resolve_decimals(&account_data, &mint_address, &account_data, &program_id),
+        None
+    );
+    assert_eq!(
+        generic_token::resolve_decimals(
+            &account_data,
+            &mint_address,
+            &mint_data,
+            &solana_pubkey::new_rand()
+        ),
+        None
+    );
+}
+
+// Strategies for adversarial token data: random bytes, with the fields the parsers check biased
+// towards valid values, so that both valid and invalid data are generated
+
+fn coption_tag() -> impl Strategy<Value = [u8; 4]> {
+    prop_oneof![Just([0; 4]), Just([1, 0, 0, 0]), any::<[u8; 4]>()]
+}
+
+fn base_account_data() -> impl Strategy<Value = Vec<u8>> {
+    (
+        prop_vec(any::<u8>(), SplAccount::LEN),
+        coption_tag(),
+        0u8..4,
+        coption_tag(),
+        coption_tag(),
+    )
+        .prop_map(|(mut data, delegate, state, is_native, close_authority)| {
+            data[72..76].copy_from_slice(&delegate);
+            data[108] = state;
+            data[109..113].copy_from_slice(&is_native);
+            data[129..133].copy_from_slice(&close_authority);
+            data
+        })
+}
+
+fn base_mint_data() -> impl Strategy<Value = Vec<u8>> {
+    (
+        prop_vec(any::<u8>(), SplMint::LEN),
+        coption_tag(),
+        prop_oneof![Just(0u8), Just(1), any::<u8>()],
+        coption_tag(),
+    )
+        .prop_map(
+            |(mut data, mint_authority, is_initialized, freeze_authority)| {
+                data[0..4].copy_from_slice(&mint_authority);
+                data[45] = is_initialized;
+                data[46..50].copy_from_slice(&freeze_authority);
+                data
+            },
+        )
+}
+
+// TLV entries with known or arbitrary types and consistent lengths, optionally truncated or
+// followed by a partial entry, or arbitrary bytes
+fn tlv_data() -> impl Strategy<Value = Vec<u8>> {
+    let entries = prop_vec(
+        (
+            prop_oneof![1u16..=27, any::<u16>()],
+            prop_vec(any::<u8>(), 0..48),
+        ),
+        0..4,
+    );
+    let well_formed = (
+        entries,
+        prop_vec(any::<u8>(), 0..3),
+        any::<prop::sample::Index>(),
+    )
+        .prop_map(|(entries, trailing, truncate)| {
+            let mut data = vec![];
+            for (extension_type, value) in entries {
+                data.extend_from_slice(&extension_type.to_le_bytes());
+                data.extend_from_slice(&(value.len() as u16).to_le_bytes());
+                data.extend_from_slice(&value);
+            }
+            data.extend_from_slice(&trailing);
+            if truncate.index(4) == 0 && !data.is_empty() {
+                data.truncate(truncate.index(data.len()));
+            }
+            data
+        });
+    prop_oneof![3 => well_formed, 1 => prop_vec(any::<u8>(), 0..64)]
+}
+
+fn account_data() -> impl Strategy<Value = Vec<u8>> {
+    let extended = (
+        base_account_data(),
+        prop_oneof![Just(2u8), any::<u8>()],
+        tlv_data(),
+    )
+        .prop_map(|(mut data, account_type, tlv_data)| {
+            data.push(account_type);
+            data.extend_from_slice(&tlv_data);
+            data
+        });
+    prop_oneof![base_account_data(), extended]
+}
+
+fn mint_data() -> impl Strategy<Value = Vec<u8>> {
+    let extended = (
+        base_mint_data(),
+        prop_oneof![
+            3 => Just(vec![0; SplAccount::LEN - SplMint::LEN]),
+            1 => prop_vec(any::<u8>(), 0..=SplAccount::LEN - SplMint::LEN),
+        ],
+        prop_oneof![Just(1u8), any::<u8>()],
+        tlv_data(),
+    )
+        .prop_map(|(mut data, padding, account_type, tlv_data)| {
+            data.extend_from_slice(&padding);
+            data.push(account_type);
+            data.extend_from_slice(&tlv_data);
+            data
+        });
+    prop_oneof![base_mint_data(), extended]
+}
+
+// Token2022 checks the extension data when listing the extension types, not when unpacking
+fn unpack_token_2022<S: BaseState + Pack>(data: &[u8]) -> Option<Vec<u16>> {
+    let state = StateWithExtensions::<S>::unpack(data).ok()?;
+    let extension_types = state.get_extension_types().ok()?;
+    Some(extension_types.into_iter().map(u16::from).collect())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2_000))]
+
+    #[test]
+    fn test_account_matches_spl_token(data in account_data()) {
+        let expected_account = SplAccount::unpack(&data).ok();
+        let program_id = token::id();
+        let test_account = generic_token::Account::unpack_strict(&data, &program_id, &program_id);
+        prop_assert_eq!(test_account.is_some(), expected_account.is_some());
+        if let Some(expected_account) = expected_account {
+            assert_account_eq(&test_account.unwrap(), &expected_account);
+            // the permissive parser accepts a superset with the same fields
+            assert_account_eq(
+                &generic_token::Account::unpack(&data, &program_id).unwrap(),
+                &expected_account,
+            );
+        }
+    }
+
+    #[test]
+    fn test_account_matches_spl_token_2022(data in account_data()) {
+        let expected_extension_types = unpack_token_2022::<SplAccount2022>(&data);
+        let program_id = token_2022::id();
+        let test_account = generic_token::Account::unpack_strict(&data, &program_id, &program_id);
+        prop_assert_eq!(test_account.is_some(), expected_extension_types.is_some());
+        if let Some(test_account) = test_account {
+            // the base layouts are the same
+            let expected_account = SplAccount::unpack(&data[..SplAccount::LEN]).unwrap();
+            assert_account_eq(&test_account, &expected_account);
+            prop_assert_eq!(
+                Some(token_2022::extension_types(&data).collect::<Vec<_>>()),
+                expected_extension_types
+            );
+            prop_assert!(generic_token::Account::unpack(&data, &program_id).is_some());
+        }
+    }
+
+    #[test]
+    fn test_mint_matches_spl_token(data in mint_data()) {
+        let expected_mint = SplMint::unpack(&data).ok();
+        let program_id = token::id();
+        let test_mint = generic_token::Mint::unpack_strict(&data, &program_id, &program_id);
+        prop_assert_eq!(test_mint.is_some(), expected_mint.is_some());
+        if let Some(expected_mint) = expected_mint {
+            assert_mint_eq(&test_mint.unwrap(), &expected_mint);
+            assert_mint_eq(
+                &generic_token::Mint::unpack(&data, &program_id).unwrap(),
+                &expected_mint,
+            );
+        }
+    }
+
+    #[test]
+    fn test_mint_matches_spl_token_2022(data in mint_data()) {
+        let expected_extension_types = unpack_token_2022::<SplMint2022>(&data);
+        let program_id = token_2022::id();
+        let test_mint = generic_token::Mint::unpack_strict(&data, &program_id, &program_id);
+        prop_assert_eq!(test_mint.is_some(), expected_extension_types.is_some());
+        if let Some(test_mint) = test_mint {
+            let expected_mint = SplMint::unpack(&data[..SplMint::LEN]).unwrap();
+            assert_mint_eq(&test_mint, &expected_mint);
+            prop_assert_eq!(
+                Some(token_2022::extension_types(&data).collect::<Vec<_>>()),
+                expected_extension_types
+            );
+            prop_assert!(generic_token::Mint::unpack(&data, &program_id).is_some());
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use spl_generic_token::ui_amount::{
+    amount_to_ui_amount, amount_to_ui_amount_string, amount_to_ui_amount_string_trimmed,
+    try_ui_amount_into_amount, ui_amount_to_amount,
+};
+
+#[test]
+fn test_ui_amount_to_amount() {
+    assert_eq!(ui_amount_to_amount(1.5, 3), 1_500);
+    assert_eq!(ui_amount_to_amount(0.0015, 3), 1);
+    assert_eq!(ui_amount_to_amount(23.0, 0), 23);
+    assert_eq!(ui_amount_to_amount(0.1, 9), 100_000_000);
+
+    assert_eq!(amount_to_ui_amount(1_500, 3), 1.5);
+    assert_eq!(amount_to_ui_amount(1, 3), 0.001);
+    assert_eq!(amount_to_ui_amount(23, 0), 23.0);
+    assert_eq!(amount_to_ui_amount(u64::MAX, 0), u64::MAX as f64);
+}
+
+#[test]
+fn test_ui_amount_string() {
+    assert_eq!(amount_to_ui_amount_string(1_500, 3), "1.500");
+    assert_eq!(amount_to_ui_amount_string(1, 3), "0.001");
+    assert_eq!(amount_to_ui_amount_string(0, 2), "0.00");
+    assert_eq!(amount_to_ui_amount_string(23, 0), "23");
+    assert_eq!(
+        amount_to_ui_amount_string(u64::MAX, 20),
+        "0.18446744073709551615"
+    );
+
+    assert_eq!(amount_to_ui_amount_string_trimmed(1_500, 3), "1.5");
+    assert_eq!(amount_to_ui_amount_string_trimmed(1_000, 3), "1");
+    assert_eq!(amount_to_ui_amount_string_trimmed(0, 2), "0");
+    assert_eq!(amount_to_ui_amount_string_trimmed(230, 0), "230");
+}
+
+#[test]
+fn test_try_ui_amount_into_amount() {
+    assert_eq!(try_ui_amount_into_amount("1.5", 3), Some(1_500));
+    assert_eq!(try_ui_amount_into_amount("1.500000", 3), Some(1_500));
+    assert_eq!(try_ui_amount_into_amount(".001", 3), Some(1));
+    assert_eq!(try_ui_amount_into_amount("23", 0), Some(23));
+    assert_eq!(try_ui_amount_into_amount("23.", 0), Some(23));
+    assert_eq!(
+        try_ui_amount_into_amount("0.18446744073709551615", 20),
+        Some(u64::MAX)
+    );
+
+    // too many decimals, malformed, or overflowing amounts
+    assert_eq!(try_ui_amount_into_amount("0.0001", 3), None);
+    assert_eq!(try_ui_amount_into_amount("", 3), None);
+    assert_eq!(try_ui_amount_into_amount(".", 3), None);
+    assert_eq!(try_ui_amount_into_amount("1.2.3", 3), None);
+    assert_eq!(try_ui_amount_into_amount("1,5", 3), None);
+    assert_eq!(try_ui_amount_into_amount("-1", 3), None);
+    assert_eq!(try_ui_amount_into_amount("18446744073709551616", 0), None);
+    assert_eq!(try_ui_amount_into_amount("18446744073709551615", 1), None);
+
+    for amount in [0, 1, 1_500, u64::MAX] {
+        for decimals in [0, 3, 9, 20] {
+            assert_eq!(
+                try_ui_amount_into_amount(&amount_to_ui_amount_string(amount, decimals), decimals),
+                Some(amount)
+            );
+            assert_eq!(
+                try_ui_amount_into_amount(
+                    &amount_to_ui_amount_string_trimmed(amount, decimals),
+                    decimals
+                ),
+                Some(amount)
+            );
+        }
+    }
+}
@@ -3,80 +3,416 @@
 //! based on the account's program id. `generic_token::Account` and `generic_token::Mint` abstract over
 //! this and require no knowledge of the different token programs on the part of the caller at all.
 //!
-//! We provide the minimum viable interface to determine balances and ownership. For more advanced use-cases,
-//! it is recommended to use to full token program crates instead.
+//! We provide the minimum viable interface to determine balances, ownership, delegation, and mint authorities.
+//! For more advanced use-cases, it is recommended to use to full token program crates instead.
 
+#[cfg(feature = "account-info")]
+use solana_account_info::AccountInfo;
+#[cfg(feature = "std")]
+use {crate::ui_amount::amount_to_effective_ui_amount, std::string::String};
 use {
     crate::{
-        token::{self, GenericTokenAccount, GenericTokenMint},
+        token::{self, AccountState, GenericTokenAccount, GenericTokenMint, MAX_SIGNERS},
         token_2022,
     },
     solana_pubkey::Pubkey,
 };
 
+/// Account layout of a token program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenLayout {
+    /// SPL Token accounts and mints
+    Token,
+    /// SPL Token accounts and mints, optionally followed by Token2022 extensions
+    Token2022,
+}
+
+/// Token program whose accounts are parsed with one of the known layouts. Forks or test deployments
+/// of the token programs can be parsed by passing their specs to the `unpack_with_programs()`
+/// functions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenProgramSpec {
+    pub program_id: Pubkey,
+    pub layout: TokenLayout,
+}
+
+impl TokenProgramSpec {
+    pub const fn new(program_id: Pubkey, layout: TokenLayout) -> Self {
+        Self { program_id, layout }
+    }
+}
+
+/// The SPL Token and Token2022 programs, used by `Account::unpack()` and `Mint::unpack()`
+pub const SPL_TOKEN_PROGRAMS: &[TokenProgramSpec] = &[
+    TokenProgramSpec::new(token::ID, TokenLayout::Token),
+    TokenProgramSpec::new(token_2022::ID, TokenLayout::Token2022),
+];
+
+fn find_layout(program_id: &Pubkey, programs: &[TokenProgramSpec]) -> Option<TokenLayout> {
+    programs
+        .iter()
+        .find(|spec| spec.program_id == *program_id)
+        .map(|spec| spec.layout)
+}
+
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct Account {
     pub mint: Pubkey,
     pub owner: Pubkey,
     pub amount: u64,
+    pub delegate: Option<Pubkey>,
+    pub state: AccountState,
+    pub is_native: Option<u64>,
+    pub delegated_amount: u64,
+    pub close_authority: Option<Pubkey>,
 }
 
 impl Account {
+    pub fn is_frozen(&self) -> bool {
+        self.state == AccountState::Frozen
+    }
+
     pub fn unpack(account_data: &[u8], program_id: &Pubkey) -> Option<Self> {
-        let (mint, owner, amount) = if *program_id == token::id() {
-            token::Account::valid_account_data(account_data).then_some(())?;
+        Self::unpack_with_programs(account_data, program_id, SPL_TOKEN_PROGRAMS)
+    }
 
-            let mint = token::Account::unpack_account_mint_unchecked(account_data);
-            let owner = token::Account::unpack_account_owner_unchecked(account_data);
-            let amount = token::Account::unpack_account_amount_unchecked(account_data);
+    /// Unpack an account for on-chain verification: unlike `unpack()`, the account must be owned by
+    /// `program_id`, its optional fields must be encoded like the token programs do, and Token2022
+    /// extensions must be self-consistent
+    pub fn unpack_strict(account_data: &[u8], program_id: &Pubkey, owner: &Pubkey) -> Option<Self> {
+        (*owner == *program_id && token_2022::valid_extension_data(account_data)).then_some(())?;
+        let account = Self::unpack(account_data, program_id)?;
+        token::valid_account_encoding(account_data).then_some(account)
+    }
 
-            (*mint, *owner, amount)
-        } else if *program_id == token_2022::id() {
-            token_2022::Account::valid_account_data(account_data).then_some(())?;
+    /// Unpack an account owned by `owner`, the token program, such as an account fetched from an RPC
+    pub fn unpack_keyed_account(owner: &Pubkey, account_data: &[u8]) -> Option<Self> {
+        Self::unpack(account_data, owner)
+    }
 
-            let mint = token_2022::Account::unpack_account_mint_unchecked(account_data);
-            let owner = token_2022::Account::unpack_account_owner_unchecked(account_data);
-            let amount = token_2022::Account::unpack_account_amount_unchecked(account_data);
+    /// Unpack an account from its `AccountInfo`, using its owner as the program id
+    #[cfg(feature = "account-info")]
+    pub fn unpack_account_info(account_info: &AccountInfo) -> Option<Self> {
+        let account_data = account_info.try_borrow_data().ok()?;
+        Self::unpack(&account_data, account_info.owner)
+    }
 
-            (*mint, *owner, amount)
-        } else {
-            return None;
-        };
+    /// Unpack an account owned by any of `programs`
+    pub fn unpack_with_programs(
+        account_data: &[u8],
+        program_id: &Pubkey,
+        programs: &[TokenProgramSpec],
+    ) -> Option<Self> {
+        TokenAccountView::unpack_with_programs(account_data, program_id, programs)
+            .map(|view| view.to_owned())
+    }
 
-        Some(Self {
-            mint,
-            owner,
-            amount,
+    /// Unpack a batch of `(owner, account_data)` pairs, such as the accounts of a snapshot, yielding
+    /// `None` for each account that isn't a token account
+    pub fn unpack_many<'a, I>(accounts: I) -> impl Iterator<Item = Option<Self>> + 'a
+    where
+        I: IntoIterator<Item = (&'a Pubkey, &'a [u8])>,
+        I::IntoIter: 'a,
+    {
+        Self::unpack_many_with_programs(accounts, SPL_TOKEN_PROGRAMS)
+    }
+
+    /// Unpack a batch of accounts owned by any of `programs`, see `unpack_many()`
+    ///
+    /// Accounts of the same owner are usually scanned together, so the layout of an owner is only
+    /// looked up again when it differs from the previous account's.
+    pub fn unpack_many_with_programs<'a, I>(
+        accounts: I,
+        programs: &'a [TokenProgramSpec],
+    ) -> impl Iterator<Item = Option<Self>> + 'a
+    where
+        I: IntoIterator<Item = (&'a Pubkey, &'a [u8])>,
+        I::IntoIter: 'a,
+    {
+        let mut last_owner: Option<(Pubkey, Option<TokenLayout>)> = None;
+        accounts.into_iter().map(move |(owner, account_data)| {
+            let layout = match last_owner {
+                Some((last_owner, layout)) if last_owner == *owner => layout,
+                _ => {
+                    let layout = find_layout(owner, programs);
+                    last_owner = Some((*owner, layout));
+                    layout
+                }
+            };
+            TokenAccountView::unpack_with_layout(account_data, layout?).map(|view| view.to_owned())
         })
     }
 }
 
+/// Borrowed view of a token account, reading its fields from the account data on demand instead
+/// of copying them into an `Account`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenAccountView<'a> {
+    account_data: &'a [u8],
+}
+
+// Both token programs share the spl_token layout, so the fields of a validated account are read
+// with the `token::Account` getters
+impl<'a> TokenAccountView<'a> {
+    pub fn unpack(account_data: &'a [u8], program_id: &Pubkey) -> Option<Self> {
+        Self::unpack_with_programs(account_data, program_id, SPL_TOKEN_PROGRAMS)
+    }
+
+    /// View an account owned by any of `programs`
+    pub fn unpack_with_programs(
+        account_data: &'a [u8],
+        program_id: &Pubkey,
+        programs: &[TokenProgramSpec],
+    ) -> Option<Self> {
+        Self::unpack_with_layout(account_data, find_layout(program_id, programs)?)
+    }
+
+    fn unpack_with_layout(account_data: &'a [u8], layout: TokenLayout) -> Option<Self> {
+        let is_valid = match layout {
+            TokenLayout::Token => token::Account::valid_account_data(account_data),
+            TokenLayout::Token2022 => token_2022::Account::valid_account_data(account_data),
+        };
+        is_valid.then_some(Self { account_data })
+    }
+
+    pub fn account_data(&self) -> &'a [u8] {
+        self.account_data
+    }
+
+    pub fn mint(&self) -> &'a Pubkey {
+        token::Account::unpack_account_mint_unchecked(self.account_data)
+    }
+
+    pub fn owner(&self) -> &'a Pubkey {
+        token::Account::unpack_account_owner_unchecked(self.account_data)
+    }
+
+    pub fn amount(&self) -> u64 {
+        token::Account::unpack_account_amount_unchecked(self.account_data)
+    }
+
+    pub fn delegate(&self) -> Option<&'a Pubkey> {
+        token::Account::unpack_account_delegate_unchecked(self.account_data)
+    }
+
+    pub fn state(&self) -> AccountState {
+        token::Account::unpack_account_state_unchecked(self.account_data)
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        token::Account::is_frozen_unchecked(self.account_data)
+    }
+
+    pub fn is_native(&self) -> Option<u64> {
+        token::Account::unpack_account_is_native_unchecked(self.account_data)
+    }
+
+    pub fn delegated_amount(&self) -> u64 {
+        token::Account::unpack_account_delegated_amount_unchecked(self.account_data)
+    }
+
+    pub fn close_authority(&self) -> Option<&'a Pubkey> {
+        token::Account::unpack_account_close_authority_unchecked(self.account_data)
+    }
+
+    /// Copy the fields of the account into an `Account`
+    pub fn to_owned(&self) -> Account {
+        Account {
+            mint: *self.mint(),
+            owner: *self.owner(),
+            amount: self.amount(),
+            delegate: self.delegate().copied(),
+            state: self.state(),
+            is_native: self.is_native(),
+            delegated_amount: self.delegated_amount(),
+            close_authority: self.close_authority().copied(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct Mint {
+    pub mint_authority: Option<Pubkey>,
     pub supply: u64,
     pub decimals: u8,
+    pub is_initialized: bool,
+    pub freeze_authority: Option<Pubkey>,
 }
 
 impl Mint {
     pub fn unpack(account_data: &[u8], program_id: &Pubkey) -> Option<Self> {
-        let (supply, decimals) = if *program_id == token::id() {
-            token::Mint::valid_account_data(account_data).then_some(())?;
+        Self::unpack_with_programs(account_data, program_id, SPL_TOKEN_PROGRAMS)
+    }
+
+    /// Unpack a mint for on-chain verification: unlike `unpack()`, the mint must be owned by
+    /// `program_id`, its optional fields must be encoded like the token programs do, and Token2022
+    /// extensions must be self-consistent
+    pub fn unpack_strict(account_data: &[u8], program_id: &Pubkey, owner: &Pubkey) -> Option<Self> {
+        (*owner == *program_id && token_2022::valid_extension_data(account_data)).then_some(())?;
+        let mint = Self::unpack(account_data, program_id)?;
+        token::valid_mint_encoding(account_data).then_some(mint)
+    }
+
+    /// Unpack a mint owned by `owner`, the token program, such as an account fetched from an RPC
+    pub fn unpack_keyed_account(owner: &Pubkey, account_data: &[u8]) -> Option<Self> {
+        Self::unpack(account_data, owner)
+    }
+
+    /// Unpack a mint from its `AccountInfo`, using its owner as the program id
+    #[cfg(feature = "account-info")]
+    pub fn unpack_account_info(account_info: &AccountInfo) -> Option<Self> {
+        let account_data = account_info.try_borrow_data().ok()?;
+        Self::unpack(&account_data, account_info.owner)
+    }
 
-            let supply = token::Mint::unpack_mint_supply_unchecked(account_data);
-            let decimals = token::Mint::unpack_mint_decimals_unchecked(account_data);
+    /// Unpack a mint owned by any of `programs`
+    pub fn unpack_with_programs(
+        account_data: &[u8],
+        program_id: &Pubkey,
+        programs: &[TokenProgramSpec],
+    ) -> Option<Self> {
+        MintView::unpack_with_programs(account_data, program_id, programs)
+            .map(|view| view.to_owned())
+    }
+}
 
-            (supply, decimals)
-        } else if *program_id == token_2022::id() {
-            token_2022::Mint::valid_account_data(account_data).then_some(())?;
+/// Borrowed view of a mint, reading its fields from the account data on demand instead of copying
+/// them into a `Mint`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MintView<'a> {
+    account_data: &'a [u8],
+}
 
-            let supply = token_2022::Mint::unpack_mint_supply_unchecked(account_data);
-            let decimals = token_2022::Mint::unpack_mint_decimals_unchecked(account_data);
+// Both token programs share the spl_token layout, so the fields of a validated mint are read with
+// the `token::Mint` getters
+impl<'a> MintView<'a> {
+    pub fn unpack(account_data: &'a [u8], program_id: &Pubkey) -> Option<Self> {
+        Self::unpack_with_programs(account_data, program_id, SPL_TOKEN_PROGRAMS)
+    }
 
-            (supply, decimals)
-        } else {
-            return None;
+    /// View a mint owned by any of `programs`
+    pub fn unpack_with_programs(
+        account_data: &'a [u8],
+        program_id: &Pubkey,
+        programs: &[TokenProgramSpec],
+    ) -> Option<Self> {
+        let is_valid = match find_layout(program_id, programs)? {
+            TokenLayout::Token => token::Mint::valid_account_data(account_data),
+            TokenLayout::Token2022 => token_2022::Mint::valid_account_data(account_data),
         };
+        is_valid.then_some(Self { account_data })
+    }
+
+    pub fn account_data(&self) -> &'a [u8] {
+        self.account_data
+    }
+
+    pub fn mint_authority(&self) -> Option<&'a Pubkey> {
+        token::Mint::unpack_mint_authority_unchecked(self.account_data)
+    }
+
+    pub fn supply(&self) -> u64 {
+        token::Mint::unpack_mint_supply_unchecked(self.account_data)
+    }
 
-        Some(Self { supply, decimals })
+    pub fn decimals(&self) -> u8 {
+        token::Mint::unpack_mint_decimals_unchecked(self.account_data)
     }
+
+    pub fn is_initialized(&self) -> bool {
+        token::Mint::unpack_mint_is_initialized_unchecked(self.account_data)
+    }
+
+    pub fn freeze_authority(&self) -> Option<&'a Pubkey> {
+        token::Mint::unpack_mint_freeze_authority_unchecked(self.account_data)
+    }
+
+    /// Copy the fields of the mint into a `Mint`
+    pub fn to_owned(&self) -> Mint {
+        Mint {
+            mint_authority: self.mint_authority().copied(),
+            supply: self.supply(),
+            decimals: self.decimals(),
+            is_initialized: self.is_initialized(),
+            freeze_authority: self.freeze_authority().copied(),
+        }
+    }
+}
+
+/// Multisignature authority of either token program, whose layouts are the same
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Multisig {
+    pub m: u8,
+    pub n: u8,
+    pub is_initialized: bool,
+    pub signers: [Pubkey; MAX_SIGNERS],
+}
+
+impl Multisig {
+    /// Unpack an initialized multisig with at most `MAX_SIGNERS` signers
+    pub fn unpack(account_data: &[u8], program_id: &Pubkey) -> Option<Self> {
+        find_layout(program_id, SPL_TOKEN_PROGRAMS)?;
+        token::valid_multisig_data(account_data).then_some(())?;
+        let (m, n, signers) = token::unpack_multisig_unchecked(account_data);
+        Some(Self {
+            m,
+            n,
+            is_initialized: true,
+            signers,
+        })
+    }
+
+    /// The `n` signers of the multisig
+    pub fn signers(&self) -> impl Iterator<Item = &Pubkey> {
+        self.signers.iter().take(usize::from(self.n))
+    }
+
+    /// Check that `provided` signers satisfy the multisig, the same way the token programs do: each
+    /// of the `n` signer slots is matched by at most one provided key, and at least `m` slots must
+    /// be matched. Duplicate signers in the multisig are slots of their own, matched by the same
+    /// key. `provided` must only contain keys which actually signed.
+    pub fn validate_signers(&self, provided: &[&Pubkey]) -> bool {
+        let mut matched = [false; MAX_SIGNERS];
+        let mut num_signers = 0usize;
+        for signer in provided {
+            for (position, key) in self.signers().enumerate() {
+                if key == *signer && !matched[position] {
+                    matched[position] = true;
+                    num_signers = num_signers.saturating_add(1);
+                }
+            }
+        }
+        num_signers >= usize::from(self.m)
+    }
+}
+
+/// Get the decimals of the mint of a token account, checking that `mint_data` is the data of the
+/// account's mint, at `mint_address`, and that both are owned by `program_id`
+pub fn resolve_decimals(
+    account_data: &[u8],
+    mint_address: &Pubkey,
+    mint_data: &[u8],
+    program_id: &Pubkey,
+) -> Option<u8> {
+    let account = TokenAccountView::unpack(account_data, program_id)?;
+    (account.mint() == mint_address).then_some(())?;
+    Some(MintView::unpack(mint_data, program_id)?.decimals())
+}
+
+/// Get the UI amount of the balance of a token account, with the same checks as
+/// `resolve_decimals()`. Token2022 interest and multipliers are applied at `unix_timestamp`, see
+/// `amount_to_effective_ui_amount()`.
+#[cfg(feature = "std")]
+pub fn checked_ui_amount(
+    account_data: &[u8],
+    mint_address: &Pubkey,
+    mint_data: &[u8],
+    program_id: &Pubkey,
+    unix_timestamp: i64,
+) -> Option<String> {
+    let account = TokenAccountView::unpack(account_data, program_id)?;
+    (account.mint() == mint_address).then_some(())?;
+    amount_to_effective_ui_amount(mint_data, program_id, account.amount(), unix_timestamp)
 }
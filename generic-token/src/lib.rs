@@ -1,11 +1,21 @@
+#![no_std]
+
+#[cfg(feature = "std")]
+extern crate std;
+
 use solana_pubkey::Pubkey;
+#[cfg(feature = "std")]
+use std::{vec, vec::Vec};
 
 pub mod associated_token_account;
 pub mod generic_token;
 pub mod token;
 pub mod token_2022;
+#[cfg(feature = "std")]
+pub mod ui_amount;
 
 /// Returns all known SPL Token program ids
+#[cfg(feature = "std")]
 pub fn spl_token_ids() -> Vec<Pubkey> {
     vec![token::id(), token_2022::id()]
 }
@@ -14,3 +24,8 @@ pub fn spl_token_ids() -> Vec<Pubkey> {
 pub fn is_known_spl_token_id(program_id: &Pubkey) -> bool {
     *program_id == token::id() || *program_id == token_2022::id()
 }
+
+/// Check if the provided pubkey is the native mint of a known SPL Token program
+pub fn is_native_mint(pubkey: &Pubkey) -> bool {
+    *pubkey == token::native_mint::id() || *pubkey == token_2022::native_mint::id()
+}
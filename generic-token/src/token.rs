@@ -1,8 +1,8 @@
 //! Partial SPL Token declarations to avoid a dependency on the spl-token crate.
 
 use {
+    core::mem,
     solana_pubkey::{Pubkey, PUBKEY_BYTES},
-    std::mem,
 };
 
 solana_pubkey::declare_id!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
@@ -22,7 +22,11 @@ solana_pubkey::declare_id!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
 pub const SPL_TOKEN_ACCOUNT_MINT_OFFSET: usize = 0;
 pub const SPL_TOKEN_ACCOUNT_OWNER_OFFSET: usize = 32;
 const SPL_TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+const SPL_TOKEN_ACCOUNT_DELEGATE_OFFSET: usize = 72;
 const SPL_TOKEN_ACCOUNT_STATE_OFFSET: usize = 108;
+const SPL_TOKEN_ACCOUNT_IS_NATIVE_OFFSET: usize = 109;
+const SPL_TOKEN_ACCOUNT_DELEGATED_AMOUNT_OFFSET: usize = 121;
+const SPL_TOKEN_ACCOUNT_CLOSE_AUTHORITY_OFFSET: usize = 129;
 pub(crate) const SPL_TOKEN_ACCOUNT_LENGTH: usize = 165;
 
 /*
@@ -34,13 +38,59 @@ pub(crate) const SPL_TOKEN_ACCOUNT_LENGTH: usize = 165;
         freeze_authority: COption<Pubkey>,
     }
 */
+const SPL_TOKEN_MINT_MINT_AUTHORITY_OFFSET: usize = 0;
 const SPL_TOKEN_MINT_SUPPLY_OFFSET: usize = 36;
 const SPL_TOKEN_MINT_DECIMALS_OFFSET: usize = 44;
 const SPL_TOKEN_MINT_IS_INITIALIZED_OFFSET: usize = 45;
+const SPL_TOKEN_MINT_FREEZE_AUTHORITY_OFFSET: usize = 46;
 pub(crate) const SPL_TOKEN_MINT_LENGTH: usize = 82;
 
+/*
+    spl_token::state::Multisig {
+        m: u8,
+        n: u8,
+        is_initialized: bool,
+        signers: [Pubkey; MAX_SIGNERS],
+    }
+*/
+const SPL_TOKEN_MULTISIG_M_OFFSET: usize = 0;
+const SPL_TOKEN_MULTISIG_N_OFFSET: usize = 1;
+const SPL_TOKEN_MULTISIG_IS_INITIALIZED_OFFSET: usize = 2;
+const SPL_TOKEN_MULTISIG_SIGNERS_OFFSET: usize = 3;
+pub(crate) const SPL_TOKEN_MULTISIG_LENGTH: usize = 355;
+
+/// Maximum number of multisignature signers
+pub const MAX_SIGNERS: usize = 11;
+
+/*
+    spl_token::state::AccountState
+*/
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AccountState {
+    #[default]
+    Uninitialized,
+    Initialized,
+    Frozen,
+}
+
+impl AccountState {
+    // Unknown values are not valid states, so they are treated as uninitialized
+    pub const fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Initialized,
+            2 => Self::Frozen,
+            _ => Self::Uninitialized,
+        }
+    }
+}
+
 pub(crate) fn is_initialized_account(account_data: &[u8]) -> bool {
-    is_initialized_token_data(account_data, SPL_TOKEN_ACCOUNT_STATE_OFFSET)
+    AccountState::from_u8(
+        *account_data
+            .get(SPL_TOKEN_ACCOUNT_STATE_OFFSET)
+            .unwrap_or(&0),
+    ) != AccountState::Uninitialized
 }
 
 pub(crate) fn is_initialized_mint(account_data: &[u8]) -> bool {
@@ -51,37 +101,190 @@ fn is_initialized_token_data(account_data: &[u8], offset: usize) -> bool {
     *account_data.get(offset).unwrap_or(&0) != 0
 }
 
+// spl_token only accepts `COption` tags and booleans of 0 or 1, which the permissive
+// `valid_account_data()` checks don't look at
+pub(crate) fn valid_account_encoding(account_data: &[u8]) -> bool {
+    valid_coption_tag(account_data, SPL_TOKEN_ACCOUNT_DELEGATE_OFFSET)
+        && valid_coption_tag(account_data, SPL_TOKEN_ACCOUNT_IS_NATIVE_OFFSET)
+        && valid_coption_tag(account_data, SPL_TOKEN_ACCOUNT_CLOSE_AUTHORITY_OFFSET)
+}
+
+pub(crate) fn valid_mint_encoding(account_data: &[u8]) -> bool {
+    valid_coption_tag(account_data, SPL_TOKEN_MINT_MINT_AUTHORITY_OFFSET)
+        && valid_coption_tag(account_data, SPL_TOKEN_MINT_FREEZE_AUTHORITY_OFFSET)
+        && matches!(
+            account_data.get(SPL_TOKEN_MINT_IS_INITIALIZED_OFFSET),
+            Some(0 | 1)
+        )
+}
+
+fn valid_coption_tag(account_data: &[u8], offset: usize) -> bool {
+    matches!(
+        account_data.get(offset..offset.wrapping_add(COPTION_TAG_LENGTH)),
+        Some([0 | 1, 0, 0, 0])
+    )
+}
+
+// Borrowed return types name the lifetime `'a` of `account_data`, which can't be elided once the
+// program owner is also borrowed
 macro_rules! define_checked_getter {
-    ($checked_fn:ident, $unchecked_fn:ident, $typ:ty) => {
-        fn $checked_fn(account_data: &[u8]) -> Option<$typ> {
+    ($checked_fn:ident, $owner_checked_fn:ident, $unchecked_fn:ident, $typ:ty) => {
+        #[allow(clippy::needless_lifetimes)]
+        fn $checked_fn<'a>(account_data: &'a [u8]) -> Option<$typ> {
             if Self::valid_account_data(account_data) {
                 Some(Self::$unchecked_fn(account_data))
             } else {
                 None
             }
         }
+
+        #[allow(clippy::needless_lifetimes)]
+        fn $owner_checked_fn<'a>(account_data: &'a [u8], program_owner: &Pubkey) -> Option<$typ> {
+            if Self::valid_account_data_with_owner(account_data, program_owner) {
+                Some(Self::$unchecked_fn(account_data))
+            } else {
+                None
+            }
+        }
+    };
+}
+
+// Multisigs are initialized with `1 <= m <= n <= MAX_SIGNERS`, and `n` bounds the signers read
+pub(crate) fn valid_multisig_data(account_data: &[u8]) -> bool {
+    account_data.len() == SPL_TOKEN_MULTISIG_LENGTH
+        && account_data[SPL_TOKEN_MULTISIG_IS_INITIALIZED_OFFSET] == 1
+        && usize::from(account_data[SPL_TOKEN_MULTISIG_N_OFFSET]) <= MAX_SIGNERS
+}
+
+// Call after the multisig data has already been validated
+pub(crate) fn unpack_multisig_unchecked(account_data: &[u8]) -> (u8, u8, [Pubkey; MAX_SIGNERS]) {
+    let mut signers = [Pubkey::default(); MAX_SIGNERS];
+    for (i, signer) in signers.iter_mut().enumerate() {
+        let offset = SPL_TOKEN_MULTISIG_SIGNERS_OFFSET.wrapping_add(i.wrapping_mul(PUBKEY_BYTES));
+        *signer = *bytemuck::from_bytes(&account_data[offset..offset.wrapping_add(PUBKEY_BYTES)]);
+    }
+    (
+        account_data[SPL_TOKEN_MULTISIG_M_OFFSET],
+        account_data[SPL_TOKEN_MULTISIG_N_OFFSET],
+        signers,
+    )
+}
+
+macro_rules! define_checked_setter {
+    ($checked_fn:ident, $unchecked_fn:ident, $typ:ty) => {
+        fn $checked_fn(account_data: &mut [u8], value: $typ) -> Option<()> {
+            if Self::valid_account_data(account_data) {
+                Self::$unchecked_fn(account_data, value);
+                Some(())
+            } else {
+                None
+            }
+        }
     };
 }
 
 // necessary to forgo bytemuck to treat endianness correctly on BE systems
-fn unpack_u64_unchecked(account_data: &[u8], offset: usize) -> u64 {
+pub(crate) fn unpack_u64_unchecked(account_data: &[u8], offset: usize) -> u64 {
     let mut bytes = [0u8; 8];
     bytes.copy_from_slice(&account_data[offset..offset.wrapping_add(mem::size_of::<u64>())]);
     u64::from_le_bytes(bytes)
 }
 
-// Trait for retrieving mint address, owner, and amount from any token account-like buffer.
-// A token program that copies the spl_token layout need only impl `valid_account_data()`.
+fn pack_u64_unchecked(account_data: &mut [u8], offset: usize, value: u64) {
+    account_data[offset..offset.wrapping_add(mem::size_of::<u64>())]
+        .copy_from_slice(&value.to_le_bytes());
+}
+
+// `COption<Pubkey>` is a 4-byte little-endian tag, 1 for `Some`, followed by the pubkey
+const COPTION_SOME_TAG: [u8; 4] = [1, 0, 0, 0];
+const COPTION_TAG_LENGTH: usize = 4;
+
+fn unpack_coption_pubkey_unchecked(account_data: &[u8], offset: usize) -> Option<&Pubkey> {
+    let pubkey_offset = offset.wrapping_add(COPTION_TAG_LENGTH);
+    (account_data[offset..pubkey_offset] == COPTION_SOME_TAG).then(|| {
+        bytemuck::from_bytes(&account_data[pubkey_offset..pubkey_offset.wrapping_add(PUBKEY_BYTES)])
+    })
+}
+
+fn unpack_coption_u64_unchecked(account_data: &[u8], offset: usize) -> Option<u64> {
+    (account_data[offset..offset.wrapping_add(COPTION_TAG_LENGTH)] == COPTION_SOME_TAG)
+        .then(|| unpack_u64_unchecked(account_data, offset.wrapping_add(COPTION_TAG_LENGTH)))
+}
+
+// Trait for retrieving mint address, owner, amount, and delegation from any token account-like buffer,
+// and for writing back its owner, amount, and state.
+// A token program that copies the spl_token layout need only impl `valid_account_data()`, and
+// `valid_program_owner()` to use the `*_checked()` getters, which also check that the account is
+// owned by the token program.
 pub trait GenericTokenAccount {
     fn valid_account_data(account_data: &[u8]) -> bool;
 
-    define_checked_getter!(unpack_account_mint, unpack_account_mint_unchecked, &Pubkey);
+    // No program is assumed to own the account unless the implementation says so
+    fn valid_program_owner(_program_owner: &Pubkey) -> bool {
+        false
+    }
+
+    fn valid_account_data_with_owner(account_data: &[u8], program_owner: &Pubkey) -> bool {
+        Self::valid_program_owner(program_owner) && Self::valid_account_data(account_data)
+    }
+
+    define_checked_getter!(
+        unpack_account_mint,
+        unpack_account_mint_checked,
+        unpack_account_mint_unchecked,
+        &'a Pubkey
+    );
     define_checked_getter!(
         unpack_account_owner,
+        unpack_account_owner_checked,
         unpack_account_owner_unchecked,
-        &Pubkey
+        &'a Pubkey
+    );
+    define_checked_getter!(
+        unpack_account_amount,
+        unpack_account_amount_checked,
+        unpack_account_amount_unchecked,
+        u64
+    );
+    define_checked_getter!(
+        unpack_account_delegate,
+        unpack_account_delegate_checked,
+        unpack_account_delegate_unchecked,
+        Option<&'a Pubkey>
+    );
+    define_checked_getter!(
+        unpack_account_state,
+        unpack_account_state_checked,
+        unpack_account_state_unchecked,
+        AccountState
+    );
+    define_checked_getter!(is_frozen, is_frozen_checked, is_frozen_unchecked, bool);
+    define_checked_getter!(
+        unpack_account_is_native,
+        unpack_account_is_native_checked,
+        unpack_account_is_native_unchecked,
+        Option<u64>
+    );
+    define_checked_getter!(
+        unpack_account_delegated_amount,
+        unpack_account_delegated_amount_checked,
+        unpack_account_delegated_amount_unchecked,
+        u64
+    );
+    define_checked_getter!(
+        unpack_account_close_authority,
+        unpack_account_close_authority_checked,
+        unpack_account_close_authority_unchecked,
+        Option<&'a Pubkey>
+    );
+
+    define_checked_setter!(set_account_owner, pack_account_owner_unchecked, &Pubkey);
+    define_checked_setter!(set_account_amount, pack_account_amount_unchecked, u64);
+    define_checked_setter!(
+        set_account_state,
+        pack_account_state_unchecked,
+        AccountState
     );
-    define_checked_getter!(unpack_account_amount, unpack_account_amount_unchecked, u64);
 
     // Call after account length has already been verified
     fn unpack_account_mint_unchecked(account_data: &[u8]) -> &Pubkey {
@@ -98,10 +301,57 @@ pub trait GenericTokenAccount {
         unpack_u64_unchecked(account_data, SPL_TOKEN_ACCOUNT_AMOUNT_OFFSET)
     }
 
+    // Call after account length has already been verified
+    fn unpack_account_delegate_unchecked(account_data: &[u8]) -> Option<&Pubkey> {
+        unpack_coption_pubkey_unchecked(account_data, SPL_TOKEN_ACCOUNT_DELEGATE_OFFSET)
+    }
+
+    // Call after account length has already been verified
+    fn unpack_account_state_unchecked(account_data: &[u8]) -> AccountState {
+        AccountState::from_u8(account_data[SPL_TOKEN_ACCOUNT_STATE_OFFSET])
+    }
+
+    // Call after account length has already been verified
+    fn is_frozen_unchecked(account_data: &[u8]) -> bool {
+        Self::unpack_account_state_unchecked(account_data) == AccountState::Frozen
+    }
+
+    // Call after account length has already been verified
+    fn unpack_account_is_native_unchecked(account_data: &[u8]) -> Option<u64> {
+        unpack_coption_u64_unchecked(account_data, SPL_TOKEN_ACCOUNT_IS_NATIVE_OFFSET)
+    }
+
+    // Call after account length has already been verified
+    fn unpack_account_delegated_amount_unchecked(account_data: &[u8]) -> u64 {
+        unpack_u64_unchecked(account_data, SPL_TOKEN_ACCOUNT_DELEGATED_AMOUNT_OFFSET)
+    }
+
+    // Call after account length has already been verified
+    fn unpack_account_close_authority_unchecked(account_data: &[u8]) -> Option<&Pubkey> {
+        unpack_coption_pubkey_unchecked(account_data, SPL_TOKEN_ACCOUNT_CLOSE_AUTHORITY_OFFSET)
+    }
+
     // Call after account length has already been verified
     fn unpack_pubkey_unchecked(account_data: &[u8], offset: usize) -> &Pubkey {
         bytemuck::from_bytes(&account_data[offset..offset.wrapping_add(PUBKEY_BYTES)])
     }
+
+    // Call after account length has already been verified
+    fn pack_account_owner_unchecked(account_data: &mut [u8], owner: &Pubkey) {
+        account_data[SPL_TOKEN_ACCOUNT_OWNER_OFFSET
+            ..SPL_TOKEN_ACCOUNT_OWNER_OFFSET.wrapping_add(PUBKEY_BYTES)]
+            .copy_from_slice(owner.as_ref());
+    }
+
+    // Call after account length has already been verified
+    fn pack_account_amount_unchecked(account_data: &mut [u8], amount: u64) {
+        pack_u64_unchecked(account_data, SPL_TOKEN_ACCOUNT_AMOUNT_OFFSET, amount)
+    }
+
+    // Call after account length has already been verified
+    fn pack_account_state_unchecked(account_data: &mut [u8], state: AccountState) {
+        account_data[SPL_TOKEN_ACCOUNT_STATE_OFFSET] = state as u8;
+    }
 }
 
 pub struct Account;
@@ -115,16 +365,56 @@ impl GenericTokenAccount for Account {
     fn valid_account_data(account_data: &[u8]) -> bool {
         account_data.len() == SPL_TOKEN_ACCOUNT_LENGTH && is_initialized_account(account_data)
     }
+
+    fn valid_program_owner(program_owner: &Pubkey) -> bool {
+        *program_owner == id()
+    }
 }
 
-// Trait for retrieving supply and decimals from any token mint-like buffer.
+// Trait for retrieving authorities, supply, and decimals from any token mint-like buffer.
 // A token program that copies the spl_token layout need only impl `valid_account_data()`.
 // We do not use bytemuck for this because Mint is an unaligned struct.
 pub trait GenericTokenMint {
     fn valid_account_data(account_data: &[u8]) -> bool;
 
-    define_checked_getter!(unpack_mint_supply, unpack_mint_supply_unchecked, u64);
-    define_checked_getter!(unpack_mint_decimals, unpack_mint_decimals_unchecked, u8);
+    // No program is assumed to own the mint unless the implementation says so
+    fn valid_program_owner(_program_owner: &Pubkey) -> bool {
+        false
+    }
+
+    fn valid_account_data_with_owner(account_data: &[u8], program_owner: &Pubkey) -> bool {
+        Self::valid_program_owner(program_owner) && Self::valid_account_data(account_data)
+    }
+
+    define_checked_getter!(
+        unpack_mint_supply,
+        unpack_mint_supply_checked,
+        unpack_mint_supply_unchecked,
+        u64
+    );
+    define_checked_getter!(
+        unpack_mint_decimals,
+        unpack_mint_decimals_checked,
+        unpack_mint_decimals_unchecked,
+        u8
+    );
+    define_checked_getter!(
+        unpack_mint_authority,
+        unpack_mint_authority_checked,
+        unpack_mint_authority_unchecked,
+        Option<&'a Pubkey>
+    );
+    define_checked_getter!(
+        unpack_mint_freeze_authority,
+        unpack_mint_freeze_authority_checked,
+        unpack_mint_freeze_authority_unchecked,
+        Option<&'a Pubkey>
+    );
+
+    // Call after account length has already been verified
+    fn unpack_mint_authority_unchecked(account_data: &[u8]) -> Option<&Pubkey> {
+        unpack_coption_pubkey_unchecked(account_data, SPL_TOKEN_MINT_MINT_AUTHORITY_OFFSET)
+    }
 
     // Call after account length has already been verified
     fn unpack_mint_supply_unchecked(account_data: &[u8]) -> u64 {
@@ -135,6 +425,16 @@ pub trait GenericTokenMint {
     fn unpack_mint_decimals_unchecked(account_data: &[u8]) -> u8 {
         account_data[SPL_TOKEN_MINT_DECIMALS_OFFSET]
     }
+
+    // Call after account length has already been verified
+    fn unpack_mint_is_initialized_unchecked(account_data: &[u8]) -> bool {
+        account_data[SPL_TOKEN_MINT_IS_INITIALIZED_OFFSET] != 0
+    }
+
+    // Call after account length has already been verified
+    fn unpack_mint_freeze_authority_unchecked(account_data: &[u8]) -> Option<&Pubkey> {
+        unpack_coption_pubkey_unchecked(account_data, SPL_TOKEN_MINT_FREEZE_AUTHORITY_OFFSET)
+    }
 }
 
 pub struct Mint;
@@ -148,6 +448,10 @@ impl GenericTokenMint for Mint {
     fn valid_account_data(account_data: &[u8]) -> bool {
         account_data.len() == SPL_TOKEN_MINT_LENGTH && is_initialized_mint(account_data)
     }
+
+    fn valid_program_owner(program_owner: &Pubkey) -> bool {
+        *program_owner == id()
+    }
 }
 
 pub mod native_mint {
@@ -1,28 +1,39 @@
 //! Partial SPL Token declarations to avoid a dependency on the spl-token-2022 crate.
 
-use crate::token::{
-    self, is_initialized_account, is_initialized_mint, GenericTokenAccount, GenericTokenMint,
-    SPL_TOKEN_ACCOUNT_LENGTH,
+use {
+    crate::token::{
+        self, is_initialized_account, is_initialized_mint, GenericTokenAccount, GenericTokenMint,
+        SPL_TOKEN_ACCOUNT_LENGTH, SPL_TOKEN_MINT_LENGTH, SPL_TOKEN_MULTISIG_LENGTH,
+    },
+    solana_pubkey::{Pubkey, PUBKEY_BYTES},
 };
 
+pub mod confidential_transfer;
+pub mod interest_bearing_mint;
+pub mod pointer;
+pub mod scaled_ui_amount;
+pub mod transfer_fee;
+
 solana_pubkey::declare_id!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
 
 // `spl_token_program_2022::extension::AccountType::Account` ordinal value
 pub const ACCOUNTTYPE_ACCOUNT: u8 = 2;
 
-// Token2022 enforces that TLV data cannot make a Mint or Account that is precisely
-// the length of a Multisig, to allow them to be distinguished.
-const SPL_TOKEN_MULTISIG_LENGTH: usize = 355;
-
 pub struct Account;
 impl GenericTokenAccount for Account {
     fn valid_account_data(account_data: &[u8]) -> bool {
         token::Account::valid_account_data(account_data)
             || (account_data.len() > SPL_TOKEN_ACCOUNT_LENGTH
+                // Token2022 enforces that TLV data cannot make a Mint or Account that is precisely
+                // the length of a Multisig, to allow them to be distinguished.
                 && account_data.len() != SPL_TOKEN_MULTISIG_LENGTH
                 && ACCOUNTTYPE_ACCOUNT == account_data[SPL_TOKEN_ACCOUNT_LENGTH]
                 && is_initialized_account(account_data))
     }
+
+    fn valid_program_owner(program_owner: &Pubkey) -> bool {
+        *program_owner == id()
+    }
 }
 
 // `spl_token_program_2022::extension::AccountType::Mint` ordinal value
@@ -40,4 +51,171 @@ impl GenericTokenMint for Mint {
                 && ACCOUNTTYPE_MINT == account_data[SPL_TOKEN_ACCOUNT_LENGTH]
                 && is_initialized_mint(account_data))
     }
+
+    fn valid_program_owner(program_owner: &Pubkey) -> bool {
+        *program_owner == id()
+    }
+}
+
+// Token2022 stores extensions as TLV entries after the account type: a `u16` extension type and a
+// `u16` length, both little-endian, followed by the value
+const SPL_TOKEN_2022_TLV_DATA_OFFSET: usize = SPL_TOKEN_ACCOUNT_LENGTH + 1;
+const EXTENSION_TYPE_LENGTH: usize = 2;
+const EXTENSION_HEADER_LENGTH: usize = 4;
+
+/// `spl_token_2022::extension::ExtensionType` ordinal values
+pub mod extension_type {
+    pub const UNINITIALIZED: u16 = 0;
+    pub const TRANSFER_FEE_CONFIG: u16 = 1;
+    pub const TRANSFER_FEE_AMOUNT: u16 = 2;
+    pub const MINT_CLOSE_AUTHORITY: u16 = 3;
+    pub const CONFIDENTIAL_TRANSFER_MINT: u16 = 4;
+    pub const CONFIDENTIAL_TRANSFER_ACCOUNT: u16 = 5;
+    pub const DEFAULT_ACCOUNT_STATE: u16 = 6;
+    pub const IMMUTABLE_OWNER: u16 = 7;
+    pub const MEMO_TRANSFER: u16 = 8;
+    pub const NON_TRANSFERABLE: u16 = 9;
+    pub const INTEREST_BEARING_CONFIG: u16 = 10;
+    pub const CPI_GUARD: u16 = 11;
+    pub const PERMANENT_DELEGATE: u16 = 12;
+    pub const NON_TRANSFERABLE_ACCOUNT: u16 = 13;
+    pub const TRANSFER_HOOK: u16 = 14;
+    pub const TRANSFER_HOOK_ACCOUNT: u16 = 15;
+    pub const CONFIDENTIAL_TRANSFER_FEE_CONFIG: u16 = 16;
+    pub const CONFIDENTIAL_TRANSFER_FEE_AMOUNT: u16 = 17;
+    pub const METADATA_POINTER: u16 = 18;
+    pub const TOKEN_METADATA: u16 = 19;
+    pub const GROUP_POINTER: u16 = 20;
+    pub const TOKEN_GROUP: u16 = 21;
+    pub const GROUP_MEMBER_POINTER: u16 = 22;
+    pub const TOKEN_GROUP_MEMBER: u16 = 23;
+    pub const CONFIDENTIAL_MINT_BURN: u16 = 24;
+    pub const SCALED_UI_AMOUNT: u16 = 25;
+    pub const PAUSABLE: u16 = 26;
+    pub const PAUSABLE_ACCOUNT: u16 = 27;
+}
+
+/// Iterator over the extensions of a Token2022 account or mint, yielding the type and value of
+/// each extension. Iteration stops at the first uninitialized or malformed entry.
+#[derive(Debug, Clone)]
+pub struct Extensions<'a> {
+    tlv_data: &'a [u8],
+}
+
+impl<'a> Iterator for Extensions<'a> {
+    type Item = (u16, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let header = self.tlv_data.get(..EXTENSION_HEADER_LENGTH)?;
+        let extension_type = u16::from_le_bytes([header[0], header[1]]);
+        let length = usize::from(u16::from_le_bytes([
+            header[EXTENSION_TYPE_LENGTH],
+            header[EXTENSION_TYPE_LENGTH + 1],
+        ]));
+        let value_end = EXTENSION_HEADER_LENGTH.saturating_add(length);
+        let value = self.tlv_data.get(EXTENSION_HEADER_LENGTH..value_end);
+        match value {
+            Some(value) if extension_type != extension_type::UNINITIALIZED => {
+                self.tlv_data = &self.tlv_data[value_end..];
+                Some((extension_type, value))
+            }
+            _ => {
+                self.tlv_data = &[];
+                None
+            }
+        }
+    }
+}
+
+/// Iterate over the extensions of a Token2022 account or mint. The data is not otherwise
+/// validated, so callers should check it with `valid_account_data()` first.
+pub fn extensions(account_data: &[u8]) -> Extensions<'_> {
+    Extensions {
+        tlv_data: account_data
+            .get(SPL_TOKEN_2022_TLV_DATA_OFFSET..)
+            .unwrap_or_default(),
+    }
+}
+
+/// Iterate over the extension types of a Token2022 account or mint, see `extensions()`
+pub fn extension_types(account_data: &[u8]) -> impl Iterator<Item = u16> + '_ {
+    extensions(account_data).map(|(extension_type, _)| extension_type)
+}
+
+/// Get the value of an extension of a Token2022 account or mint, see `extensions()`
+pub fn get_extension(account_data: &[u8], extension_type: u16) -> Option<&[u8]> {
+    extensions(account_data)
+        .find(|(other_type, _)| *other_type == extension_type)
+        .map(|(_, value)| value)
+}
+
+/// Check that the extensions of a Token2022 account or mint are self-consistent, like Token2022
+/// does when unpacking them: mints are padded with zeroes to the account length, and every
+/// extension has a known type and fits in the data. Accounts without extensions are trivially
+/// consistent.
+pub fn valid_extension_data(account_data: &[u8]) -> bool {
+    let Some(mut tlv_data) = account_data.get(SPL_TOKEN_2022_TLV_DATA_OFFSET..) else {
+        return true;
+    };
+    if account_data.len() == SPL_TOKEN_MULTISIG_LENGTH {
+        return false;
+    }
+    if account_data[SPL_TOKEN_ACCOUNT_LENGTH] == ACCOUNTTYPE_MINT
+        && account_data[SPL_TOKEN_MINT_LENGTH..SPL_TOKEN_ACCOUNT_LENGTH]
+            .iter()
+            .any(|byte| *byte != 0)
+    {
+        return false;
+    }
+
+    // like Token2022, a trailing byte or an uninitialized type ends the extensions
+    while tlv_data.len() >= EXTENSION_TYPE_LENGTH {
+        let extension_type = u16::from_le_bytes([tlv_data[0], tlv_data[1]]);
+        if extension_type == extension_type::UNINITIALIZED {
+            break;
+        }
+        if extension_type > extension_type::PAUSABLE_ACCOUNT {
+            return false;
+        }
+        let Some(header) = tlv_data.get(..EXTENSION_HEADER_LENGTH) else {
+            return false;
+        };
+        let length = usize::from(u16::from_le_bytes([
+            header[EXTENSION_TYPE_LENGTH],
+            header[EXTENSION_TYPE_LENGTH + 1],
+        ]));
+        let Some(rest) = tlv_data.get(EXTENSION_HEADER_LENGTH.saturating_add(length)..) else {
+            return false;
+        };
+        tlv_data = rest;
+    }
+    true
+}
+
+pub mod native_mint {
+    solana_pubkey::declare_id!("9pan9bMn5HatX4EJdBwg9VgCa7Uz5HL8N1m5D3NdXejP");
+
+    // Token2022 creates its native mint without extensions, so its data matches the spl_token one
+    pub const ACCOUNT_DATA: [u8; 82] = crate::token::native_mint::ACCOUNT_DATA;
+}
+
+// `OptionalNonZeroPubkey` is a pubkey, with all zeroes meaning `None`
+pub(crate) fn unpack_optional_pubkey(data: &[u8], offset: usize) -> Option<Pubkey> {
+    let pubkey: &Pubkey = bytemuck::from_bytes(&data[offset..offset.wrapping_add(PUBKEY_BYTES)]);
+    (*pubkey != Pubkey::default()).then_some(*pubkey)
+}
+
+// Extension values are unaligned, so integers are read from their little-endian bytes
+pub(crate) fn unpack_i64_unchecked(data: &[u8], offset: usize) -> i64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset.wrapping_add(8)]);
+    i64::from_le_bytes(bytes)
+}
+
+pub(crate) fn unpack_i16_unchecked(data: &[u8], offset: usize) -> i16 {
+    i16::from_le_bytes([data[offset], data[offset.wrapping_add(1)]])
+}
+
+pub(crate) fn unpack_f64_unchecked(data: &[u8], offset: usize) -> f64 {
+    f64::from_bits(crate::token::unpack_u64_unchecked(data, offset))
 }
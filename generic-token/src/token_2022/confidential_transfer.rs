@@ -0,0 +1,73 @@
+//! Partial `spl_token_2022::extension::confidential_transfer` declarations, to detect confidential
+//! Token2022 accounts. The ElGamal pubkey and ciphertexts are returned as opaque bytes, to be
+//! handed to the zk-sdk.
+
+use crate::{
+    token::GenericTokenAccount,
+    token_2022::{self, extension_type},
+};
+
+pub const ELGAMAL_PUBKEY_LENGTH: usize = 32;
+pub const AE_CIPHERTEXT_LENGTH: usize = 36;
+
+// `ConfidentialTransferAccount` layout: approved, ElGamal pubkey, pending balance (low and high),
+// available balance, decryptable available balance, credit flags, then the credit counters
+const CONFIDENTIAL_TRANSFER_ACCOUNT_APPROVED_OFFSET: usize = 0;
+const CONFIDENTIAL_TRANSFER_ACCOUNT_ELGAMAL_PUBKEY_OFFSET: usize = 1;
+const CONFIDENTIAL_TRANSFER_ACCOUNT_DECRYPTABLE_AVAILABLE_BALANCE_OFFSET: usize = 225;
+const CONFIDENTIAL_TRANSFER_ACCOUNT_ALLOW_CONFIDENTIAL_CREDITS_OFFSET: usize = 261;
+const CONFIDENTIAL_TRANSFER_ACCOUNT_ALLOW_NON_CONFIDENTIAL_CREDITS_OFFSET: usize = 262;
+const CONFIDENTIAL_TRANSFER_ACCOUNT_LENGTH: usize = 295;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfidentialTransferAccount {
+    /// Whether the account was approved for confidential transfers
+    pub approved: bool,
+    /// ElGamal pubkey encrypting the balances of the account
+    pub elgamal_pubkey: [u8; ELGAMAL_PUBKEY_LENGTH],
+    /// Available balance, encrypted with the authenticated encryption key of the owner
+    pub decryptable_available_balance: [u8; AE_CIPHERTEXT_LENGTH],
+    pub allow_confidential_credits: bool,
+    pub allow_non_confidential_credits: bool,
+}
+
+impl ConfidentialTransferAccount {
+    /// Read the confidential transfer extension of a Token2022 account, if it has one
+    pub fn unpack(account_data: &[u8]) -> Option<Self> {
+        let data = get_confidential_transfer_account(account_data)?;
+
+        let mut elgamal_pubkey = [0; ELGAMAL_PUBKEY_LENGTH];
+        elgamal_pubkey.copy_from_slice(
+            &data[CONFIDENTIAL_TRANSFER_ACCOUNT_ELGAMAL_PUBKEY_OFFSET..][..ELGAMAL_PUBKEY_LENGTH],
+        );
+        let mut decryptable_available_balance = [0; AE_CIPHERTEXT_LENGTH];
+        decryptable_available_balance.copy_from_slice(
+            &data[CONFIDENTIAL_TRANSFER_ACCOUNT_DECRYPTABLE_AVAILABLE_BALANCE_OFFSET..]
+                [..AE_CIPHERTEXT_LENGTH],
+        );
+        Some(Self {
+            approved: data[CONFIDENTIAL_TRANSFER_ACCOUNT_APPROVED_OFFSET] != 0,
+            elgamal_pubkey,
+            decryptable_available_balance,
+            allow_confidential_credits: data
+                [CONFIDENTIAL_TRANSFER_ACCOUNT_ALLOW_CONFIDENTIAL_CREDITS_OFFSET]
+                != 0,
+            allow_non_confidential_credits: data
+                [CONFIDENTIAL_TRANSFER_ACCOUNT_ALLOW_NON_CONFIDENTIAL_CREDITS_OFFSET]
+                != 0,
+        })
+    }
+}
+
+/// Check if a Token2022 account has the confidential transfer extension, so part of its balance
+/// may be encrypted
+pub fn is_confidential_account(account_data: &[u8]) -> bool {
+    get_confidential_transfer_account(account_data).is_some()
+}
+
+fn get_confidential_transfer_account(account_data: &[u8]) -> Option<&[u8]> {
+    token_2022::Account::valid_account_data(account_data).then_some(())?;
+    let data =
+        token_2022::get_extension(account_data, extension_type::CONFIDENTIAL_TRANSFER_ACCOUNT)?;
+    (data.len() == CONFIDENTIAL_TRANSFER_ACCOUNT_LENGTH).then_some(data)
+}
@@ -0,0 +1,110 @@
+//! Partial `spl_token_2022::extension::interest_bearing_mint` declarations, to read the interest
+//! rate of Token2022 mints.
+
+#[cfg(feature = "std")]
+use {
+    crate::ui_amount::trim_ui_amount_string,
+    std::{format, string::String},
+};
+use {
+    crate::{
+        token::GenericTokenMint,
+        token_2022::{
+            self, extension_type, unpack_i16_unchecked, unpack_i64_unchecked,
+            unpack_optional_pubkey,
+        },
+    },
+    solana_pubkey::Pubkey,
+};
+
+// `InterestBearingConfig` layout: optional rate authority, initialization timestamp, average rate
+// before the last update, last update timestamp, current rate
+const INTEREST_BEARING_CONFIG_RATE_AUTHORITY_OFFSET: usize = 0;
+const INTEREST_BEARING_CONFIG_INITIALIZATION_TIMESTAMP_OFFSET: usize = 32;
+const INTEREST_BEARING_CONFIG_PRE_UPDATE_AVERAGE_RATE_OFFSET: usize = 40;
+const INTEREST_BEARING_CONFIG_LAST_UPDATE_TIMESTAMP_OFFSET: usize = 42;
+const INTEREST_BEARING_CONFIG_CURRENT_RATE_OFFSET: usize = 50;
+const INTEREST_BEARING_CONFIG_LENGTH: usize = 52;
+
+#[cfg(feature = "std")]
+const ONE_IN_BASIS_POINTS: f64 = 10_000.;
+#[cfg(feature = "std")]
+const SECONDS_PER_YEAR: f64 = 60. * 60. * 24. * 365.24;
+
+/// Interest rate of a mint, in basis points, compounded continuously
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InterestBearingConfig {
+    pub rate_authority: Option<Pubkey>,
+    /// Timestamp of initialization, from which interest is accrued
+    pub initialization_timestamp: i64,
+    /// Average rate from initialization until the last update
+    pub pre_update_average_rate: i16,
+    pub last_update_timestamp: i64,
+    /// Rate since the last update
+    pub current_rate: i16,
+}
+
+impl InterestBearingConfig {
+    /// Read the interest-bearing config of a Token2022 mint, if it has one
+    pub fn unpack(mint_data: &[u8]) -> Option<Self> {
+        token_2022::Mint::valid_account_data(mint_data).then_some(())?;
+        let data = token_2022::get_extension(mint_data, extension_type::INTEREST_BEARING_CONFIG)?;
+        (data.len() == INTEREST_BEARING_CONFIG_LENGTH).then_some(())?;
+
+        Some(Self {
+            rate_authority: unpack_optional_pubkey(
+                data,
+                INTEREST_BEARING_CONFIG_RATE_AUTHORITY_OFFSET,
+            ),
+            initialization_timestamp: unpack_i64_unchecked(
+                data,
+                INTEREST_BEARING_CONFIG_INITIALIZATION_TIMESTAMP_OFFSET,
+            ),
+            pre_update_average_rate: unpack_i16_unchecked(
+                data,
+                INTEREST_BEARING_CONFIG_PRE_UPDATE_AVERAGE_RATE_OFFSET,
+            ),
+            last_update_timestamp: unpack_i64_unchecked(
+                data,
+                INTEREST_BEARING_CONFIG_LAST_UPDATE_TIMESTAMP_OFFSET,
+            ),
+            current_rate: unpack_i16_unchecked(data, INTEREST_BEARING_CONFIG_CURRENT_RATE_OFFSET),
+        })
+    }
+
+    #[cfg(feature = "std")]
+    fn exp(rate: i16, timespan: i64) -> Option<f64> {
+        let numerator = i128::from(rate).checked_mul(i128::from(timespan))? as f64;
+        Some((numerator / SECONDS_PER_YEAR / ONE_IN_BASIS_POINTS).exp())
+    }
+
+    /// Scale of a raw amount to its UI amount with the interest accrued at `unix_timestamp`
+    #[cfg(feature = "std")]
+    pub fn total_scale(&self, decimals: u8, unix_timestamp: i64) -> Option<f64> {
+        let pre_update_exp = Self::exp(
+            self.pre_update_average_rate,
+            self.last_update_timestamp
+                .checked_sub(self.initialization_timestamp)?,
+        )?;
+        let post_update_exp = Self::exp(
+            self.current_rate,
+            unix_timestamp.checked_sub(self.last_update_timestamp)?,
+        )?;
+        Some(pre_update_exp * post_update_exp / 10_f64.powi(i32::from(decimals)))
+    }
+
+    /// Convert a raw amount to its UI representation with the interest accrued at
+    /// `unix_timestamp`, like Token2022. Excess zeroes or unneeded decimal point are trimmed.
+    #[cfg(feature = "std")]
+    pub fn amount_to_ui_amount(
+        &self,
+        amount: u64,
+        decimals: u8,
+        unix_timestamp: i64,
+    ) -> Option<String> {
+        let scaled_amount_with_interest =
+            (amount as f64) * self.total_scale(decimals, unix_timestamp)?;
+        let ui_amount = format!("{scaled_amount_with_interest:.*}", usize::from(decimals));
+        Some(trim_ui_amount_string(ui_amount, decimals))
+    }
+}
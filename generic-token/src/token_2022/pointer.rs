@@ -0,0 +1,54 @@
+//! Partial `spl_token_2022::extension::{metadata_pointer, group_pointer, group_member_pointer}`
+//! declarations, to follow the pointers of Token2022 mints to their metadata, group, and group
+//! member.
+
+use {
+    crate::{
+        token::GenericTokenMint,
+        token_2022::{self, extension_type, unpack_optional_pubkey},
+    },
+    solana_pubkey::Pubkey,
+};
+
+// `MetadataPointer`, `GroupPointer`, and `GroupMemberPointer` layout: optional authority, optional
+// target address
+const POINTER_AUTHORITY_OFFSET: usize = 0;
+const POINTER_ADDRESS_OFFSET: usize = 32;
+const POINTER_LENGTH: usize = 64;
+
+/// Address of the account holding the metadata, group, or group member of a mint, often the mint
+/// itself
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pointer {
+    /// Authority which can set the address
+    pub authority: Option<Pubkey>,
+    pub address: Option<Pubkey>,
+}
+
+impl Pointer {
+    /// Read the metadata pointer of a Token2022 mint, if it has one
+    pub fn unpack_metadata_pointer(mint_data: &[u8]) -> Option<Self> {
+        Self::unpack(mint_data, extension_type::METADATA_POINTER)
+    }
+
+    /// Read the group pointer of a Token2022 mint, if it has one
+    pub fn unpack_group_pointer(mint_data: &[u8]) -> Option<Self> {
+        Self::unpack(mint_data, extension_type::GROUP_POINTER)
+    }
+
+    /// Read the group member pointer of a Token2022 mint, if it has one
+    pub fn unpack_group_member_pointer(mint_data: &[u8]) -> Option<Self> {
+        Self::unpack(mint_data, extension_type::GROUP_MEMBER_POINTER)
+    }
+
+    fn unpack(mint_data: &[u8], extension_type: u16) -> Option<Self> {
+        token_2022::Mint::valid_account_data(mint_data).then_some(())?;
+        let data = token_2022::get_extension(mint_data, extension_type)?;
+        (data.len() == POINTER_LENGTH).then_some(())?;
+
+        Some(Self {
+            authority: unpack_optional_pubkey(data, POINTER_AUTHORITY_OFFSET),
+            address: unpack_optional_pubkey(data, POINTER_ADDRESS_OFFSET),
+        })
+    }
+}
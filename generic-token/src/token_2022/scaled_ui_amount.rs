@@ -0,0 +1,83 @@
+//! Partial `spl_token_2022::extension::scaled_ui_amount` declarations, to read the UI amount
+//! multiplier of Token2022 mints.
+
+#[cfg(feature = "std")]
+use {
+    crate::ui_amount::trim_ui_amount_string,
+    std::{format, string::String},
+};
+use {
+    crate::{
+        token::GenericTokenMint,
+        token_2022::{
+            self, extension_type, unpack_f64_unchecked, unpack_i64_unchecked,
+            unpack_optional_pubkey,
+        },
+    },
+    solana_pubkey::Pubkey,
+};
+
+// `ScaledUiAmountConfig` layout: optional authority, multiplier, timestamp of the new multiplier,
+// new multiplier
+const SCALED_UI_AMOUNT_CONFIG_AUTHORITY_OFFSET: usize = 0;
+const SCALED_UI_AMOUNT_CONFIG_MULTIPLIER_OFFSET: usize = 32;
+const SCALED_UI_AMOUNT_CONFIG_NEW_MULTIPLIER_EFFECTIVE_TIMESTAMP_OFFSET: usize = 40;
+const SCALED_UI_AMOUNT_CONFIG_NEW_MULTIPLIER_OFFSET: usize = 48;
+const SCALED_UI_AMOUNT_CONFIG_LENGTH: usize = 56;
+
+/// Multiplier applied to the raw amounts of a mint in their UI representation
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ScaledUiAmountConfig {
+    pub authority: Option<Pubkey>,
+    pub multiplier: f64,
+    /// Timestamp at which `new_multiplier` replaces `multiplier`
+    pub new_multiplier_effective_timestamp: i64,
+    pub new_multiplier: f64,
+}
+
+impl ScaledUiAmountConfig {
+    /// Read the scaled UI amount config of a Token2022 mint, if it has one
+    pub fn unpack(mint_data: &[u8]) -> Option<Self> {
+        token_2022::Mint::valid_account_data(mint_data).then_some(())?;
+        let data = token_2022::get_extension(mint_data, extension_type::SCALED_UI_AMOUNT)?;
+        (data.len() == SCALED_UI_AMOUNT_CONFIG_LENGTH).then_some(())?;
+
+        Some(Self {
+            authority: unpack_optional_pubkey(data, SCALED_UI_AMOUNT_CONFIG_AUTHORITY_OFFSET),
+            multiplier: unpack_f64_unchecked(data, SCALED_UI_AMOUNT_CONFIG_MULTIPLIER_OFFSET),
+            new_multiplier_effective_timestamp: unpack_i64_unchecked(
+                data,
+                SCALED_UI_AMOUNT_CONFIG_NEW_MULTIPLIER_EFFECTIVE_TIMESTAMP_OFFSET,
+            ),
+            new_multiplier: unpack_f64_unchecked(
+                data,
+                SCALED_UI_AMOUNT_CONFIG_NEW_MULTIPLIER_OFFSET,
+            ),
+        })
+    }
+
+    /// Get the multiplier in effect at `unix_timestamp`
+    pub fn current_multiplier(&self, unix_timestamp: i64) -> f64 {
+        if unix_timestamp >= self.new_multiplier_effective_timestamp {
+            self.new_multiplier
+        } else {
+            self.multiplier
+        }
+    }
+
+    /// Convert a raw amount to its UI representation with the multiplier in effect at
+    /// `unix_timestamp`, like Token2022. The scaled amount is truncated towards 0, and excess
+    /// zeroes or unneeded decimal point are trimmed.
+    #[cfg(feature = "std")]
+    pub fn amount_to_ui_amount(
+        &self,
+        amount: u64,
+        decimals: u8,
+        unix_timestamp: i64,
+    ) -> Option<String> {
+        let scaled_amount = (amount as f64) * self.current_multiplier(unix_timestamp);
+        let truncated_amount = scaled_amount.trunc() / 10_f64.powi(i32::from(decimals));
+        let ui_amount = format!("{truncated_amount:.*}", usize::from(decimals));
+        Some(trim_ui_amount_string(ui_amount, decimals))
+    }
+}
@@ -0,0 +1,128 @@
+//! Partial `spl_token_2022::extension::transfer_fee` declarations, to read the transfer fee
+//! extensions of Token2022 mints and accounts.
+
+use {
+    crate::{
+        token::{unpack_u64_unchecked, GenericTokenAccount, GenericTokenMint},
+        token_2022::{self, extension_type, unpack_optional_pubkey},
+    },
+    solana_pubkey::Pubkey,
+};
+
+// `TransferFee` layout: epoch, maximum fee, basis points
+const TRANSFER_FEE_EPOCH_OFFSET: usize = 0;
+const TRANSFER_FEE_MAXIMUM_FEE_OFFSET: usize = 8;
+const TRANSFER_FEE_BASIS_POINTS_OFFSET: usize = 16;
+const TRANSFER_FEE_LENGTH: usize = 18;
+
+// `TransferFeeConfig` layout: two optional authorities, the withheld amount, then the older and
+// newer transfer fees
+const TRANSFER_FEE_CONFIG_AUTHORITY_OFFSET: usize = 0;
+const TRANSFER_FEE_CONFIG_WITHDRAW_WITHHELD_AUTHORITY_OFFSET: usize = 32;
+const TRANSFER_FEE_CONFIG_WITHHELD_AMOUNT_OFFSET: usize = 64;
+const TRANSFER_FEE_CONFIG_OLDER_TRANSFER_FEE_OFFSET: usize = 72;
+const TRANSFER_FEE_CONFIG_NEWER_TRANSFER_FEE_OFFSET: usize = 90;
+const TRANSFER_FEE_CONFIG_LENGTH: usize = 108;
+
+const TRANSFER_FEE_AMOUNT_LENGTH: usize = 8;
+
+const ONE_IN_BASIS_POINTS: u128 = 10_000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TransferFee {
+    /// First epoch where the transfer fee takes effect
+    pub epoch: u64,
+    /// Maximum fee assessed on transfers, in token amount
+    pub maximum_fee: u64,
+    /// Amount of transfer collected as fees, in basis points of the transfer amount
+    pub transfer_fee_basis_points: u16,
+}
+
+impl TransferFee {
+    fn unpack_unchecked(data: &[u8]) -> Self {
+        Self {
+            epoch: unpack_u64_unchecked(data, TRANSFER_FEE_EPOCH_OFFSET),
+            maximum_fee: unpack_u64_unchecked(data, TRANSFER_FEE_MAXIMUM_FEE_OFFSET),
+            transfer_fee_basis_points: u16::from_le_bytes([
+                data[TRANSFER_FEE_BASIS_POINTS_OFFSET],
+                data[TRANSFER_FEE_BASIS_POINTS_OFFSET + 1],
+            ]),
+        }
+    }
+
+    /// Calculate the fee withheld on a transfer of `pre_fee_amount`, rounding up like Token2022
+    pub fn calculate_fee(&self, pre_fee_amount: u64) -> Option<u64> {
+        let transfer_fee_basis_points = u128::from(self.transfer_fee_basis_points);
+        if transfer_fee_basis_points == 0 || pre_fee_amount == 0 {
+            return Some(0);
+        }
+        let numerator = u128::from(pre_fee_amount).checked_mul(transfer_fee_basis_points)?;
+        let fee = numerator.div_ceil(ONE_IN_BASIS_POINTS);
+        Some(u64::try_from(fee).ok()?.min(self.maximum_fee))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TransferFeeConfig {
+    pub transfer_fee_config_authority: Option<Pubkey>,
+    pub withdraw_withheld_authority: Option<Pubkey>,
+    /// Fees withheld on the mint, harvested from token accounts
+    pub withheld_amount: u64,
+    pub older_transfer_fee: TransferFee,
+    pub newer_transfer_fee: TransferFee,
+}
+
+impl TransferFeeConfig {
+    /// Read the transfer fee config of a Token2022 mint, if it has one
+    pub fn unpack(mint_data: &[u8]) -> Option<Self> {
+        token_2022::Mint::valid_account_data(mint_data).then_some(())?;
+        let data = token_2022::get_extension(mint_data, extension_type::TRANSFER_FEE_CONFIG)?;
+        (data.len() == TRANSFER_FEE_CONFIG_LENGTH).then_some(())?;
+
+        Some(Self {
+            transfer_fee_config_authority: unpack_optional_pubkey(
+                data,
+                TRANSFER_FEE_CONFIG_AUTHORITY_OFFSET,
+            ),
+            withdraw_withheld_authority: unpack_optional_pubkey(
+                data,
+                TRANSFER_FEE_CONFIG_WITHDRAW_WITHHELD_AUTHORITY_OFFSET,
+            ),
+            withheld_amount: unpack_u64_unchecked(data, TRANSFER_FEE_CONFIG_WITHHELD_AMOUNT_OFFSET),
+            older_transfer_fee: TransferFee::unpack_unchecked(
+                &data[TRANSFER_FEE_CONFIG_OLDER_TRANSFER_FEE_OFFSET..][..TRANSFER_FEE_LENGTH],
+            ),
+            newer_transfer_fee: TransferFee::unpack_unchecked(
+                &data[TRANSFER_FEE_CONFIG_NEWER_TRANSFER_FEE_OFFSET..][..TRANSFER_FEE_LENGTH],
+            ),
+        })
+    }
+
+    /// Get the transfer fee in effect at `epoch`
+    pub fn get_epoch_fee(&self, epoch: u64) -> &TransferFee {
+        if epoch >= self.newer_transfer_fee.epoch {
+            &self.newer_transfer_fee
+        } else {
+            &self.older_transfer_fee
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TransferFeeAmount {
+    /// Fees withheld on the account, to be harvested to the mint
+    pub withheld_amount: u64,
+}
+
+impl TransferFeeAmount {
+    /// Read the withheld transfer fees of a Token2022 account, if it has the extension
+    pub fn unpack(account_data: &[u8]) -> Option<Self> {
+        token_2022::Account::valid_account_data(account_data).then_some(())?;
+        let data = token_2022::get_extension(account_data, extension_type::TRANSFER_FEE_AMOUNT)?;
+        (data.len() == TRANSFER_FEE_AMOUNT_LENGTH).then_some(())?;
+
+        Some(Self {
+            withheld_amount: unpack_u64_unchecked(data, 0),
+        })
+    }
+}
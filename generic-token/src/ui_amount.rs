@@ -0,0 +1,99 @@
+//! Conversions between raw token amounts and UI amounts, matching those of the spl-token crate.
+
+use {
+    crate::{
+        generic_token::Mint,
+        token_2022::{
+            interest_bearing_mint::InterestBearingConfig, scaled_ui_amount::ScaledUiAmountConfig,
+        },
+    },
+    solana_pubkey::Pubkey,
+    std::{
+        format,
+        string::{String, ToString},
+    },
+};
+
+/// Convert a raw amount to its UI representation, using the given decimals field
+pub fn amount_to_ui_amount(amount: u64, decimals: u8) -> f64 {
+    amount as f64 / 10f64.powi(i32::from(decimals))
+}
+
+/// Convert a UI representation of a token amount to its raw amount, using the given decimals
+/// field. Fractions of the smallest unit are truncated.
+pub fn ui_amount_to_amount(ui_amount: f64, decimals: u8) -> u64 {
+    (ui_amount * 10f64.powi(i32::from(decimals))) as u64
+}
+
+/// Convert a raw amount to its exact UI representation, with all of the decimals, e.g. `"1.500"`
+/// for an amount of 1500 with 3 decimals
+pub fn amount_to_ui_amount_string(amount: u64, decimals: u8) -> String {
+    let decimals = usize::from(decimals);
+    if decimals > 0 {
+        // left-pad zeros to have at least an integer zero
+        let mut ui_amount = format!("{:01$}", amount, decimals + 1);
+        ui_amount.insert(ui_amount.len() - decimals, '.');
+        ui_amount
+    } else {
+        amount.to_string()
+    }
+}
+
+/// Convert a raw amount to its exact UI representation, without trailing zeros, e.g. `"1.5"` for an
+/// amount of 1500 with 3 decimals
+pub fn amount_to_ui_amount_string_trimmed(amount: u64, decimals: u8) -> String {
+    trim_ui_amount_string(amount_to_ui_amount_string(amount, decimals), decimals)
+}
+
+pub(crate) fn trim_ui_amount_string(ui_amount: String, decimals: u8) -> String {
+    if decimals > 0 {
+        ui_amount
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    } else {
+        ui_amount
+    }
+}
+
+/// Parse a UI representation of a token amount to its raw amount, using the given decimals field.
+/// Returns `None` if the string is not a decimal number, has more decimals than the mint, or
+/// overflows.
+pub fn try_ui_amount_into_amount(ui_amount: &str, decimals: u8) -> Option<u64> {
+    let decimals = usize::from(decimals);
+    let mut parts = ui_amount.split('.');
+    // splitting a string always yields at least one part
+    let mut amount = parts.next()?.to_string();
+    let after_decimal = parts.next().unwrap_or("").trim_end_matches('0');
+    if (amount.is_empty() && after_decimal.is_empty())
+        || parts.next().is_some()
+        || after_decimal.len() > decimals
+    {
+        return None;
+    }
+
+    amount.push_str(after_decimal);
+    for _ in 0..decimals.saturating_sub(after_decimal.len()) {
+        amount.push('0');
+    }
+    amount.parse().ok()
+}
+
+/// Convert a raw amount of a mint to its UI representation, without trailing zeros. The amount is
+/// scaled by the multiplier of the Token2022 scaled UI amount extension, or by the interest
+/// accrued at `unix_timestamp` with the interest-bearing extension, if the mint has one of them.
+pub fn amount_to_effective_ui_amount(
+    mint_data: &[u8],
+    program_id: &Pubkey,
+    amount: u64,
+    unix_timestamp: i64,
+) -> Option<String> {
+    let decimals = Mint::unpack(mint_data, program_id)?.decimals;
+    if let Some(config) = ScaledUiAmountConfig::unpack(mint_data) {
+        config.amount_to_ui_amount(amount, decimals, unix_timestamp)
+    } else if let Some(config) = InterestBearingConfig::unpack(mint_data) {
+        config.amount_to_ui_amount(amount, decimals, unix_timestamp)
+    } else {
+        Some(amount_to_ui_amount_string_trimmed(amount, decimals))
+    }
+}
@@ -1,3 +1,8 @@
+#![no_std]
+
+#[cfg(test)]
+extern crate std;
+
 mod error;
 mod list_trait;
 mod list_view;
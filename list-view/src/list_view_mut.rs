@@ -84,6 +84,7 @@ mod tests {
         crate::{List, ListView},
         bytemuck_derive::{Pod, Zeroable},
         solana_zero_copy::unaligned::{U16 as PodU16, U32 as PodU32, U64 as PodU64},
+        std::{vec, vec::Vec},
     };
 
     #[repr(C)]
@@ -107,7 +108,7 @@ mod tests {
     fn init_view_mut<T: Pod, L: PodLength>(
         buffer: &mut Vec<u8>,
         capacity: usize,
-    ) -> ListViewMut<T, L> {
+    ) -> ListViewMut<'_, T, L> {
         let size = ListView::<T, L>::size_of(capacity).unwrap();
         buffer.resize(size, 0);
         ListView::<T, L>::init(buffer).unwrap()
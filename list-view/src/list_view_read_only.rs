@@ -40,6 +40,7 @@ mod tests {
         bytemuck_derive::{Pod as DerivePod, Zeroable},
         core::mem::size_of,
         solana_zero_copy::unaligned::{U32 as PodU32, U64 as PodU64},
+        std::{vec, vec::Vec},
     };
 
     #[repr(C, align(16))]
@@ -114,7 +114,7 @@ mod tests {
     fn init_view_mut<T: Pod, L: PodLength>(
         buffer: &mut Vec<u8>,
         capacity: usize,
-    ) -> ListViewMut<T, L>
+    ) -> ListViewMut<'_, T, L>
     where
         PodSliceError: From<<L as TryFrom<usize>>::Error>,
     {
@@ -25,21 +25,61 @@ use {
 
 /// Derive macro to add `Into<solana_program_error::ProgramError>`
 /// trait
+///
+/// Variants wrapping another error with `#[error(transparent)]` convert into
+/// the `ProgramError` of the wrapped error.
 #[proc_macro_derive(IntoProgramError)]
 pub fn into_program_error(input: TokenStream) -> TokenStream {
-    let ItemEnum { ident, .. } = parse_macro_input!(input as ItemEnum);
-    MacroType::IntoProgramError { ident }
+    let ItemEnum {
+        ident, variants, ..
+    } = parse_macro_input!(input as ItemEnum);
+    MacroType::IntoProgramError { ident, variants }
         .generate_tokens()
         .into()
 }
 
 /// Derive macro to add `solana_program_error::ToStr` trait
-#[proc_macro_derive(ToStr)]
+///
+/// Each variant's string is its `#[error("...")]` message, unformatted, or
+/// the message of a `#[to_str("...")]` attribute, which is useful when the
+/// error message formats the fields of the variant.
+///
+/// Variants must have different strings, so that they can be told apart in
+/// the program logs.
+///
+/// With a `#[to_str(display)]` attribute on the enum, `core::fmt::Display`
+/// is also implemented, writing the `ToStr` string. Leave it out when the
+/// enum derives `thiserror::Error`, which implements `Display` already.
+#[proc_macro_derive(ToStr, attributes(to_str))]
 pub fn to_str(input: TokenStream) -> TokenStream {
+    let ItemEnum {
+        attrs,
+        ident,
+        variants,
+        ..
+    } = parse_macro_input!(input as ItemEnum);
+    let display = macro_impl::has_display_attribute(&attrs);
+    MacroType::ToStr {
+        ident,
+        variants,
+        display,
+    }
+    .generate_tokens()
+    .into()
+}
+
+/// Derive macro to add `TryFrom<u32>` and
+/// `TryFrom<solana_program_error::ProgramError>` traits, mapping error codes
+/// back to the variants without fields
+///
+/// Failed conversions return their input. Don't combine with
+/// `num_enum::TryFromPrimitive`, which also implements `TryFrom<u32>`.
+#[proc_macro_derive(TryFromProgramError)]
+pub fn try_from_program_error(input: TokenStream) -> TokenStream {
     let ItemEnum {
         ident, variants, ..
     } = parse_macro_input!(input as ItemEnum);
-    MacroType::ToStr { ident, variants }
+    MacroType::TryFromProgramError { ident, variants }
         .generate_tokens()
         .into()
 }
@@ -55,6 +95,13 @@ pub fn to_str(input: TokenStream) -> TokenStream {
 /// - `num_derive::FromPrimitive`
 /// - `Into<solana_program_error::ProgramError>`
 /// - `solana_program_error::ToStr`
+/// - `TryFrom<solana_program_error::ProgramError>`
+///
+/// Variants may have fields, in which case `num_derive::FromPrimitive` and
+/// `num_enum::TryFromPrimitive` are not added, since a variant can't be built
+/// from its error code alone. `TryFrom<u32>` and a `from_u32` function are
+/// then generated for the variants without fields instead. The static string of variants with
+/// fields can be set with a `#[to_str("...")]` attribute, see `ToStr`.
 ///
 /// Optionally, you can add `hash_error_code_start: u32` argument to create
 /// a unique `u32` _starting_ error codes from the names of the enum variants.
@@ -66,7 +113,51 @@ pub fn to_str(input: TokenStream) -> TokenStream {
 ///
 /// Syntax: `#[spl_program_error(hash_error_code_start = 1275525928)]`
 /// Hash Input: `spl_program_error:<enum name>:<variant name>`
+///
+/// To salt the hash per program, so that enums with the same name in
+/// different programs get different codes, add a `namespace` argument, which
+/// replaces `spl_program_error` in the hash input.
+///
+/// Syntax: `#[spl_program_error(hash_error_code_start = .., namespace = "my_program")]`
 /// Value: `u32::from_le_bytes(<hash of input>[13..17])`
+///
+/// Alternatively, you can add an `offset: u32` argument to shift all error
+/// codes by a fixed amount, for programs composing several error enums.
+/// Every enum also gets an `ERROR_CODE_RANGE` constant, which
+/// `assert_disjoint_error_codes!` checks for overlaps at compile time.
+///
+/// Syntax: `#[spl_program_error(offset = 6000)]`
+///
+/// To avoid depending on `num_derive` and `num_enum`, add the `minimal`
+/// argument, which skips their derives and generates `TryFrom<u32>` and a
+/// `from_u32` function inline instead, and disable the default
+/// `from-primitive` feature of `spl-program-error`, which enables those
+/// dependencies and is required without `minimal`.
+///
+/// Syntax: `#[spl_program_error(minimal)]`
+///
+/// To add derives to the generated list, such as `serde::Serialize`, add a
+/// `derive(..)` argument.
+///
+/// Syntax: `#[spl_program_error(derive(Hash, serde::Serialize))]`
+///
+/// Variants wrapping another error enum with `#[error(transparent)]`, such as
+/// `Pod(#[from] PodSliceError)`, convert into the `ProgramError` and
+/// `solana_program_error::ToStr` string of the wrapped error, keeping its
+/// error code. The wrapped error must implement both.
+///
+/// For programs built with `pinocchio` rather than depending on
+/// `solana_program_error` directly, add the `pinocchio` argument to use the
+/// `solana_program_error` crate re-exported as `pinocchio::error`. Its
+/// `ProgramError` is the same type, so one enum serves both builds.
+///
+/// Syntax: `#[spl_program_error(pinocchio)]`
+///
+/// Every enum also gets an `ERROR_CATALOG` constant, and an `error_catalog`
+/// function returning it, listing the error code, variant name and
+/// `solana_program_error::ToStr` message of every variant, for clients and
+/// IDL generators. With the `error-map` feature, it also gets an
+/// `error_map_json` function rendering the catalog as JSON.
 #[proc_macro_attribute]
 pub fn spl_program_error(attr: TokenStream, input: TokenStream) -> TokenStream {
     let args = parse_macro_input!(attr as SplProgramErrorArgs);
@@ -6,23 +6,30 @@ use {
     quote::quote,
     sha2::{Digest, Sha256},
     syn::{
-        punctuated::Punctuated, token::Comma, Expr, ExprLit, Ident, ItemEnum, Lit, LitInt, LitStr,
-        Token, Variant,
+        punctuated::Punctuated, token::Comma, Attribute, Expr, ExprLit, Fields, Ident, ItemEnum,
+        Lit, LitInt, LitStr, Token, Variant,
     },
 };
 
 const SPL_ERROR_HASH_NAMESPACE: &str = "spl_program_error";
 const SPL_ERROR_HASH_MIN_VALUE: u32 = 7_000;
+const TO_STR_ATTRIBUTE: &str = "to_str";
 
 /// The type of macro being called, thus directing which tokens to generate
-#[allow(clippy::enum_variant_names)]
+#[allow(clippy::enum_variant_names, clippy::large_enum_variant)]
 pub enum MacroType {
     IntoProgramError {
         ident: Ident,
+        variants: Punctuated<Variant, Comma>,
     },
     ToStr {
         ident: Ident,
         variants: Punctuated<Variant, Comma>,
+        display: bool,
+    },
+    TryFromProgramError {
+        ident: Ident,
+        variants: Punctuated<Variant, Comma>,
     },
     SplProgramError {
         args: SplProgramErrorArgs,
@@ -35,11 +42,23 @@ impl MacroType {
     pub fn generate_tokens(&mut self) -> proc_macro2::TokenStream {
         let default_solana_program_error = SolanaProgramError::default();
         match self {
-            Self::IntoProgramError { ident } => {
-                into_program_error(ident, &default_solana_program_error)
+            Self::IntoProgramError { ident, variants } => {
+                into_program_error(ident, variants, &default_solana_program_error)
             }
-            Self::ToStr { ident, variants } => {
-                to_str(ident, variants, &default_solana_program_error)
+            Self::ToStr {
+                ident,
+                variants,
+                display,
+            } => to_str(ident, variants, *display, &default_solana_program_error),
+            Self::TryFromProgramError { ident, variants } => {
+                let try_from_u32 = try_from_u32(ident, variants, &default_solana_program_error);
+                let try_from_program_error =
+                    try_from_program_error(ident, variants, &default_solana_program_error);
+                quote! {
+                    #try_from_u32
+
+                    #try_from_program_error
+                }
             }
             Self::SplProgramError { args, item_enum } => spl_program_error(args, item_enum),
         }
@@ -49,11 +68,81 @@ impl MacroType {
 /// Builds the implementation of
 /// `Into<solana_program_error::ProgramError>` More specifically,
 /// implements `From<Self> for solana_program_error::ProgramError`
-pub fn into_program_error(ident: &Ident, import: &SolanaProgramError) -> proc_macro2::TokenStream {
+pub fn into_program_error(
+    ident: &Ident,
+    variants: &Punctuated<Variant, Comma>,
+    import: &SolanaProgramError,
+) -> proc_macro2::TokenStream {
+    let program_error = if has_fields(variants) {
+        // enums with fields can't be cast, so match on the variants instead,
+        // keeping the error codes of wrapped errors
+        let match_arms = variants
+            .iter()
+            .zip(error_codes(variants))
+            .map(|(variant, error_code)| {
+                let variant_ident = &variant.ident;
+                match transparent_binding(variant) {
+                    Some(binding) => quote! {
+                        #ident::#variant_ident #binding => #import::ProgramError::from(inner)
+                    },
+                    None => quote! {
+                        #ident::#variant_ident { .. } => #import::ProgramError::Custom(#error_code)
+                    },
+                }
+            });
+        quote! {
+            match e {
+                #(#match_arms),*
+            }
+        }
+    } else {
+        quote! { #import::ProgramError::Custom(e as u32) }
+    };
     let this_impl = quote! {
         impl From<#ident> for #import::ProgramError {
             fn from(e: #ident) -> Self {
-                #import::ProgramError::Custom(e as u32)
+                #program_error
+            }
+        }
+    };
+    import.wrap(this_impl)
+}
+
+/// Builds the conditions returning the variant of an error code `code`, for
+/// the variants without fields
+fn from_error_code(
+    ident: &Ident,
+    variants: &Punctuated<Variant, Comma>,
+) -> Vec<proc_macro2::TokenStream> {
+    variants
+        .iter()
+        .zip(error_codes(variants))
+        .filter(|(variant, _)| matches!(variant.fields, Fields::Unit))
+        .map(|(variant, error_code)| {
+            let variant_ident = &variant.ident;
+            quote! {
+                if code == #error_code {
+                    return Ok(#ident::#variant_ident);
+                }
+            }
+        })
+        .collect()
+}
+
+/// Builds the implementation of `TryFrom<u32>`, returning the error code
+/// back if it doesn't match a variant without fields
+pub fn try_from_u32(
+    ident: &Ident,
+    variants: &Punctuated<Variant, Comma>,
+    import: &SolanaProgramError,
+) -> proc_macro2::TokenStream {
+    let from_error_code = from_error_code(ident, variants);
+    let this_impl = quote! {
+        impl ::core::convert::TryFrom<u32> for #ident {
+            type Error = u32;
+            fn try_from(code: u32) -> Result<Self, u32> {
+                #(#from_error_code)*
+                Err(code)
             }
         }
     };
@@ -61,18 +150,107 @@ pub fn into_program_error(ident: &Ident, import: &SolanaProgramError) -> proc_ma
 }
 
 /// Builds the implementation of
-/// `solana_program_error::ToStr`
+/// `TryFrom<solana_program_error::ProgramError>`, returning the program error
+/// back if it isn't a custom error matching a variant without fields
+pub fn try_from_program_error(
+    ident: &Ident,
+    variants: &Punctuated<Variant, Comma>,
+    import: &SolanaProgramError,
+) -> proc_macro2::TokenStream {
+    let from_error_code = from_error_code(ident, variants);
+    let this_impl = quote! {
+        impl ::core::convert::TryFrom<#import::ProgramError> for #ident {
+            type Error = #import::ProgramError;
+            fn try_from(error: #import::ProgramError) -> Result<Self, #import::ProgramError> {
+                if let #import::ProgramError::Custom(code) = error {
+                    #(#from_error_code)*
+                }
+                Err(error)
+            }
+        }
+    };
+    import.wrap(this_impl)
+}
+
+/// Whether the variant wraps another error with `#[error(transparent)]`
+fn is_transparent(variant: &Variant) -> bool {
+    variant.attrs.iter().any(|attr| {
+        attr.path().is_ident("error")
+            && attr
+                .parse_args::<Ident>()
+                .is_ok_and(|arg| arg == "transparent")
+    })
+}
+
+/// Builds the pattern binding the wrapped error of a transparent variant to
+/// `inner`
+fn transparent_binding(variant: &Variant) -> Option<proc_macro2::TokenStream> {
+    if !is_transparent(variant) {
+        return None;
+    }
+    match &variant.fields {
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => Some(quote! { (inner) }),
+        Fields::Named(fields) if fields.named.len() == 1 => {
+            let field_ident = &fields.named[0].ident;
+            Some(quote! { { #field_ident: inner } })
+        }
+        _ => panic!("Transparent variants must have exactly one field"),
+    }
+}
+
+/// Whether any of the variants has fields
+fn has_fields(variants: &Punctuated<Variant, Comma>) -> bool {
+    variants
+        .iter()
+        .any(|variant| !matches!(variant.fields, Fields::Unit))
+}
+
+/// Builds the error code of every variant, following the discriminant rules
+/// of a `#[repr(u32)]` enum: each variant without an explicit discriminant
+/// takes the previous one plus one, starting from zero
+fn error_codes(variants: &Punctuated<Variant, Comma>) -> Vec<proc_macro2::TokenStream> {
+    let mut base = quote! { 0u32 };
+    let mut offset = 0u32;
+    variants
+        .iter()
+        .map(|variant| {
+            if let Some((_, discriminant)) = &variant.discriminant {
+                base = quote! { (#discriminant) as u32 };
+                offset = 0;
+            }
+            let error_code = if offset == 0 {
+                base.clone()
+            } else {
+                quote! { #base + #offset }
+            };
+            offset += 1;
+            error_code
+        })
+        .collect()
+}
+
+/// Builds the implementation of
+/// `solana_program_error::ToStr`, and optionally of `core::fmt::Display`
+/// delegating to it
 pub fn to_str(
     ident: &Ident,
     variants: &Punctuated<Variant, Comma>,
+    display: bool,
     program_error_import: &SolanaProgramError,
 ) -> proc_macro2::TokenStream {
+    if let Err(error) = check_unique_messages(variants) {
+        return error.to_compile_error();
+    }
     let ppe_match_arms = variants.iter().map(|variant| {
         let variant_ident = &variant.ident;
-        let error_msg = get_error_message(variant)
-            .unwrap_or_else(|| String::from("Unknown custom program error"));
+        if let Some(binding) = transparent_binding(variant) {
+            return quote! {
+                #ident::#variant_ident #binding => #program_error_import::ToStr::to_str(inner)
+            };
+        }
+        let error_msg = get_message(variant);
         quote! {
-            #ident::#variant_ident => #error_msg
+            #ident::#variant_ident { .. } => #error_msg
         }
     });
     let this_impl = quote! {
@@ -84,14 +262,86 @@ pub fn to_str(
             }
         }
     };
-    program_error_import.wrap(this_impl)
+    let display_impl = if display {
+        quote! {
+            impl ::core::fmt::Display for #ident {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    f.write_str(#program_error_import::ToStr::to_str(self))
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+    program_error_import.wrap(quote! {
+        #this_impl
+
+        #display_impl
+    })
+}
+
+/// Whether the enum has a `#[to_str(display)]` attribute, to implement
+/// `Display` with the derived `ToStr`
+pub fn has_display_attribute(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path().is_ident(TO_STR_ATTRIBUTE)
+            && attr.parse_args::<Ident>().is_ok_and(|arg| arg == "display")
+    })
+}
+
+/// Check that no two variants share a message, which would make them
+/// ambiguous in the program logs
+///
+/// Duplicate discriminants don't need a check here, since the compiler
+/// rejects them.
+fn check_unique_messages(variants: &Punctuated<Variant, Comma>) -> syn::Result<()> {
+    let mut messages: Vec<(String, &Ident)> = Vec::new();
+    for variant in variants {
+        if is_transparent(variant) {
+            continue;
+        }
+        let Some(message) = get_to_str_message(variant).or_else(|| get_error_message(variant))
+        else {
+            continue;
+        };
+        if let Some((_, other)) = messages.iter().find(|(other, _)| *other == message) {
+            return Err(syn::Error::new_spanned(
+                &variant.ident,
+                format!(
+                    "Variants `{other}` and `{}` share the message \"{message}\"",
+                    variant.ident
+                ),
+            ));
+        }
+        messages.push((message, &variant.ident));
+    }
+    Ok(())
+}
+
+/// Helper to get the static message of a variant, from its `#[to_str(..)]`
+/// or `#[error(..)]` attribute
+fn get_message(variant: &Variant) -> String {
+    get_to_str_message(variant)
+        .or_else(|| get_error_message(variant))
+        .unwrap_or_else(|| String::from("Unknown custom program error"))
 }
 
 /// Helper to parse out the string literal from the `#[error(..)]` attribute
 fn get_error_message(variant: &Variant) -> Option<String> {
+    get_attribute_message(variant, "error")
+}
+
+/// Helper to parse out the string literal from the `#[to_str(..)]`
+/// attribute, which overrides the `#[error(..)]` message, for example when
+/// the latter formats the fields of the variant
+fn get_to_str_message(variant: &Variant) -> Option<String> {
+    get_attribute_message(variant, TO_STR_ATTRIBUTE)
+}
+
+fn get_attribute_message(variant: &Variant, attribute: &str) -> Option<String> {
     let attrs = &variant.attrs;
     for attr in attrs {
-        if attr.path().is_ident("error") {
+        if attr.path().is_ident(attribute) {
             if let Ok(lit_str) = attr.parse_args::<LitStr>() {
                 return Some(lit_str.value());
             }
@@ -107,40 +357,249 @@ pub fn spl_program_error(
     item_enum: &mut ItemEnum,
 ) -> proc_macro2::TokenStream {
     if let Some(error_code_start) = args.hash_error_code_start {
-        set_first_discriminant(item_enum, error_code_start);
+        let namespace = args
+            .namespace
+            .as_deref()
+            .unwrap_or(SPL_ERROR_HASH_NAMESPACE);
+        set_first_discriminant(item_enum, error_code_start, namespace);
+    }
+    if let Some(offset) = args.offset {
+        offset_discriminants(item_enum, offset);
     }
 
     let ident = &item_enum.ident;
     let variants = &item_enum.variants;
-    let into_program_error = into_program_error(ident, &args.program_error_import);
-    let to_str = to_str(ident, variants, &args.program_error_import);
+    let into_program_error = into_program_error(ident, variants, &args.program_error_import);
+    // `thiserror::Error` already implements `Display`
+    let to_str = to_str(ident, variants, false, &args.program_error_import);
+    let try_from_program_error =
+        try_from_program_error(ident, variants, &args.program_error_import);
+    let error_code_range = error_code_range(ident, variants);
+    let error_catalog = error_catalog(ident, variants);
+
+    // enums with fields can't be built from an error code alone, only their
+    // variants without fields can, so the conversions are generated inline,
+    // as in minimal mode
+    let (from_primitive, try_from_u32) = if args.minimal || has_fields(variants) {
+        let try_from_u32 = try_from_u32(ident, variants, &args.program_error_import);
+        (
+            quote! {
+                #[derive(Clone, Debug, Eq, thiserror::Error, PartialEq)]
+            },
+            quote! {
+                #try_from_u32
+
+                impl #ident {
+                    /// Get the variant of an error code, for variants without
+                    /// fields
+                    pub fn from_u32(code: u32) -> Option<Self> {
+                        <Self as ::core::convert::TryFrom<u32>>::try_from(code).ok()
+                    }
+                }
+            },
+        )
+    } else {
+        from_primitive_derives()
+    };
+
+    // no derive registers the `#[to_str(..)]` attribute here
+    for variant in item_enum.variants.iter_mut() {
+        variant
+            .attrs
+            .retain(|attr| !attr.path().is_ident(TO_STR_ATTRIBUTE));
+    }
+
+    let derives = &args.derives;
+    let derives = if derives.is_empty() {
+        quote! {}
+    } else {
+        quote! { #[derive(#(#derives),*)] }
+    };
 
     quote! {
         #[repr(u32)]
-        #[derive(Clone, Debug, Eq, thiserror::Error, num_derive::FromPrimitive, num_enum::TryFromPrimitive, PartialEq)]
-        #[num_traits = "num_traits"]
+        #from_primitive
+        #derives
         #item_enum
 
         #into_program_error
 
         #to_str
+
+        #try_from_u32
+
+        #try_from_program_error
+
+        #error_code_range
+
+        #error_catalog
+    }
+}
+
+/// Builds the derives of an enum without fields outside of minimal mode,
+/// including `num_derive::FromPrimitive` and `num_enum::TryFromPrimitive`,
+/// which implements `TryFrom<u32>`
+#[cfg(feature = "from-primitive")]
+fn from_primitive_derives() -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    (
+        quote! {
+            #[derive(Clone, Debug, Eq, thiserror::Error, num_derive::FromPrimitive, num_enum::TryFromPrimitive, PartialEq)]
+            #[num_traits = "num_traits"]
+        },
+        quote! {},
+    )
+}
+
+/// Without the `from-primitive` feature, the `num_derive` and `num_enum`
+/// derives are unavailable, so outside of minimal mode, fail with an error
+/// pointing at the feature
+#[cfg(not(feature = "from-primitive"))]
+fn from_primitive_derives() -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    (
+        quote! {
+            #[derive(Clone, Debug, Eq, thiserror::Error, PartialEq)]
+        },
+        quote! {
+            ::core::compile_error!(
+                "`#[spl_program_error]` derives `num_derive::FromPrimitive` and \
+                 `num_enum::TryFromPrimitive`, which require the `from-primitive` \
+                 feature of `spl-program-error`: enable it or use \
+                 `#[spl_program_error(minimal)]`"
+            );
+        },
+    )
+}
+
+/// Builds the `ERROR_CODE_RANGE` constant, covering the error codes of all
+/// variants, to check that the codes of several enums don't overlap
+///
+/// Transparent variants are left out, since they keep the error codes of the
+/// errors they wrap, which must be checked separately
+fn error_code_range(
+    ident: &Ident,
+    variants: &Punctuated<Variant, Comma>,
+) -> proc_macro2::TokenStream {
+    let error_codes = variants
+        .iter()
+        .zip(error_codes(variants))
+        .filter(|(variant, _)| !is_transparent(variant))
+        .map(|(_, error_code)| error_code)
+        .collect::<Vec<_>>();
+    if error_codes.is_empty() {
+        return quote! {
+            impl #ident {
+                /// Range covering the error codes of all variants, empty
+                /// since all variants are transparent
+                #[allow(clippy::reversed_empty_ranges)]
+                pub const ERROR_CODE_RANGE: ::core::ops::RangeInclusive<u32> = 1..=0;
+            }
+        };
+    }
+    quote! {
+        impl #ident {
+            /// Range covering the error codes of all variants, except for
+            /// transparent variants
+            pub const ERROR_CODE_RANGE: ::core::ops::RangeInclusive<u32> = {
+                let error_codes = [#(#error_codes),*];
+                let mut min = error_codes[0];
+                let mut max = error_codes[0];
+                let mut i = 1;
+                while i < error_codes.len() {
+                    if error_codes[i] < min {
+                        min = error_codes[i];
+                    }
+                    if error_codes[i] > max {
+                        max = error_codes[i];
+                    }
+                    i += 1;
+                }
+                min..=max
+            };
+        }
+    }
+}
+
+/// Builds the `ERROR_CATALOG` constant and its `error_catalog` accessor,
+/// listing the error code, name and message of every variant, except for
+/// transparent variants, which keep the error codes of the errors they wrap
+fn error_catalog(ident: &Ident, variants: &Punctuated<Variant, Comma>) -> proc_macro2::TokenStream {
+    let entries = variants
+        .iter()
+        .zip(error_codes(variants))
+        .filter(|(variant, _)| !is_transparent(variant))
+        .map(|(variant, error_code)| {
+            let name = variant.ident.to_string();
+            let message = get_message(variant);
+            quote! { (#error_code, #name, #message) }
+        });
+    let error_map_json = error_map_json(ident);
+    quote! {
+        impl #ident {
+            /// Error code, variant name and message of every variant
+            pub const ERROR_CATALOG: &'static [(u32, &'static str, &'static str)] = &[#(#entries),*];
+
+            /// Get the error code, variant name and message of every variant
+            pub fn error_catalog() -> &'static [(u32, &'static str, &'static str)] {
+                Self::ERROR_CATALOG
+            }
+
+            #error_map_json
+        }
+    }
+}
+
+/// Builds the `error_map_json` function, rendering the error catalog as JSON
+#[cfg(feature = "error-map")]
+fn error_map_json(ident: &Ident) -> proc_macro2::TokenStream {
+    let name = ident.to_string();
+    quote! {
+        /// Render the error catalog as a JSON document, for clients
+        pub fn error_map_json() -> ::std::string::String {
+            ::spl_program_error::error_map_json(#name, Self::ERROR_CATALOG)
+        }
+    }
+}
+
+#[cfg(not(feature = "error-map"))]
+fn error_map_json(_ident: &Ident) -> proc_macro2::TokenStream {
+    quote! {}
+}
+
+/// This function adds `offset` to the discriminant of every enum variant that
+/// has one, and to the first variant, which starts at zero otherwise.
+fn offset_discriminants(item_enum: &mut ItemEnum, offset: u32) {
+    if item_enum.variants.is_empty() {
+        panic!("Enum must have at least one variant");
+    }
+    for (i, variant) in item_enum.variants.iter_mut().enumerate() {
+        let offset = LitInt::new(&offset.to_string(), Span::call_site());
+        match &mut variant.discriminant {
+            Some((_, discriminant)) => {
+                *discriminant = syn::parse_quote! { (#discriminant) + #offset };
+            }
+            None if i == 0 => {
+                let eq = Token![=](Span::call_site());
+                variant.discriminant = Some((eq, syn::parse_quote! { #offset }));
+            }
+            None => {}
+        }
     }
 }
 
 /// This function adds a discriminant to the first enum variant based on the
-/// hash of the `SPL_ERROR_HASH_NAMESPACE` constant, the enum name and variant
+/// hash of the namespace, `SPL_ERROR_HASH_NAMESPACE` by default, and the enum
 /// name.
 /// It will then check to make sure the provided `hash_error_code_start` is
 /// equal to the hash-produced `u32`.
 ///
 /// See the [`syn` docs](https://docs.rs/syn/latest/syn/struct.Variant.html).
-fn set_first_discriminant(item_enum: &mut ItemEnum, error_code_start: u32) {
+fn set_first_discriminant(item_enum: &mut ItemEnum, error_code_start: u32, namespace: &str) {
     let enum_ident = &item_enum.ident;
     if item_enum.variants.is_empty() {
         panic!("Enum must have at least one variant");
     }
     let first_variant = &mut item_enum.variants[0];
-    let discriminant = u32_from_hash(enum_ident);
+    let discriminant = u32_from_hash(namespace, enum_ident);
     if discriminant == error_code_start {
         let eq = Token![=](Span::call_site());
         let expr = Expr::Lit(ExprLit {
@@ -157,10 +616,10 @@ fn set_first_discriminant(item_enum: &mut ItemEnum, error_code_start: u32) {
     }
 }
 
-/// Hashes the `SPL_ERROR_HASH_NAMESPACE` constant, the enum name and variant
-/// name and returns four middle bytes (13 through 16) as a `u32`.
-fn u32_from_hash(enum_ident: &Ident) -> u32 {
-    let hash_input = format!("{}:{}", SPL_ERROR_HASH_NAMESPACE, enum_ident);
+/// Hashes the namespace and the enum name and returns four middle bytes (13
+/// through 16) as a `u32`.
+fn u32_from_hash(namespace: &str, enum_ident: &Ident) -> u32 {
+    let hash_input = format!("{}:{}", namespace, enum_ident);
 
     // We don't want our error code to start at any number below
     // `SPL_ERROR_HASH_MIN_VALUE`!
@@ -179,3 +638,42 @@ fn u32_from_hash(enum_ident: &Ident) -> u32 {
         nonce += 1;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {super::*, syn::parse_quote};
+
+    #[test]
+    fn duplicate_messages() {
+        let item_enum: ItemEnum = parse_quote! {
+            enum ExampleError {
+                #[error("Invalid mint")]
+                InvalidMint,
+                #[error("Invalid owner")]
+                InvalidOwner,
+                #[error("Invalid amount: {0}")]
+                #[to_str("Invalid mint")]
+                InvalidAmount(u64),
+            }
+        };
+        let error = check_unique_messages(&item_enum.variants).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Variants `InvalidMint` and `InvalidAmount` share the message \"Invalid mint\""
+        );
+
+        let item_enum: ItemEnum = parse_quote! {
+            enum ExampleError {
+                #[error("Invalid mint")]
+                InvalidMint,
+                #[error(transparent)]
+                Wrapped(OtherError),
+                #[error(transparent)]
+                OtherWrapped(AnotherError),
+                Unknown,
+                OtherUnknown,
+            }
+        };
+        assert!(check_unique_messages(&item_enum.variants).is_ok());
+    }
+}
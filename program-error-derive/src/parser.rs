@@ -4,9 +4,11 @@ use {
     proc_macro2::{Ident, Span, TokenStream},
     quote::quote,
     syn::{
+        parenthesized,
         parse::{Parse, ParseStream},
+        punctuated::Punctuated,
         token::Comma,
-        LitInt, LitStr, Token,
+        LitInt, LitStr, Path, Token,
     },
 };
 
@@ -15,25 +17,51 @@ pub struct SplProgramErrorArgs {
     /// Whether to hash the error codes using sha-256
     /// or to use the default error code assigned by `num_traits`.
     pub hash_error_code_start: Option<u32>,
+    /// Namespace of the hash of the error codes, instead of
+    /// `spl_program_error`
+    pub namespace: Option<String>,
+    /// Amount added to all error codes
+    pub offset: Option<u32>,
+    /// Whether to generate the conversions from error codes inline, rather
+    /// than with `num_derive` and `num_enum`
+    pub minimal: bool,
+    /// Derives added to the generated list
+    pub derives: Vec<Path>,
     /// Crate to use for `solana_program_error`
     pub program_error_import: SolanaProgramError,
 }
 
 /// Struct representing the path to a `solana_program_error` crate, which may
-/// be renamed or otherwise.
+/// be renamed or otherwise, or re-exported by `pinocchio`.
 pub struct SolanaProgramError {
     import: Ident,
     explicit: bool,
+    pinocchio: bool,
 }
 impl quote::ToTokens for SolanaProgramError {
     fn to_tokens(&self, tokens: &mut TokenStream) {
-        self.import.to_tokens(tokens);
+        if self.pinocchio {
+            tokens.extend(quote! { _pinocchio::error });
+        } else {
+            self.import.to_tokens(tokens);
+        }
     }
 }
 impl SolanaProgramError {
+    /// Use the `solana_program_error` crate re-exported by `pinocchio`
+    pub fn pinocchio() -> Self {
+        Self {
+            import: Ident::new("_pinocchio", Span::call_site()),
+            explicit: false,
+            pinocchio: true,
+        }
+    }
+
     pub fn wrap(&self, output: TokenStream) -> TokenStream {
         if self.explicit {
             output
+        } else if self.pinocchio {
+            pinocchio_anon_const_trick(output)
         } else {
             program_error_anon_const_trick(output)
         }
@@ -44,6 +72,7 @@ impl Default for SolanaProgramError {
         Self {
             import: Ident::new("_solana_program_error", Span::call_site()),
             explicit: false,
+            pinocchio: false,
         }
     }
 }
@@ -51,22 +80,65 @@ impl Default for SolanaProgramError {
 impl Parse for SplProgramErrorArgs {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let mut hash_error_code_start = None;
+        let mut namespace = None;
+        let mut offset = None;
+        let mut minimal = false;
+        let mut derives = Vec::new();
         let mut program_error_import = None;
         while !input.is_empty() {
             match SplProgramErrorArgParser::parse(input)? {
                 SplProgramErrorArgParser::HashErrorCodes { value, .. } => {
                     hash_error_code_start = Some(value.base10_parse::<u32>()?);
                 }
+                SplProgramErrorArgParser::Namespace { value } => {
+                    namespace = Some(value.value());
+                }
+                SplProgramErrorArgParser::Offset { value } => {
+                    offset = Some(value.base10_parse::<u32>()?);
+                }
                 SplProgramErrorArgParser::SolanaProgramErrorCrate { value, .. } => {
+                    if program_error_import.is_some() {
+                        return Err(input.error(
+                            "Arguments 'solana_program_error' and 'pinocchio' can't be used \
+                             together",
+                        ));
+                    }
                     program_error_import = Some(SolanaProgramError {
                         import: value.parse()?,
                         explicit: true,
+                        pinocchio: false,
                     });
                 }
+                SplProgramErrorArgParser::Derive { paths } => {
+                    derives.extend(paths);
+                }
+                SplProgramErrorArgParser::Minimal => {
+                    minimal = true;
+                }
+                SplProgramErrorArgParser::Pinocchio => {
+                    if program_error_import.is_some() {
+                        return Err(input.error(
+                            "Arguments 'solana_program_error' and 'pinocchio' can't be used \
+                             together",
+                        ));
+                    }
+                    program_error_import = Some(SolanaProgramError::pinocchio());
+                }
             }
         }
+        if namespace.is_some() && hash_error_code_start.is_none() {
+            return Err(input.error("Argument 'namespace' requires 'hash_error_code_start'"));
+        }
+        if hash_error_code_start.is_some() && offset.is_some() {
+            return Err(input
+                .error("Arguments 'hash_error_code_start' and 'offset' can't be used together"));
+        }
         Ok(Self {
             hash_error_code_start,
+            namespace,
+            offset,
+            minimal,
+            derives,
             program_error_import: program_error_import.unwrap_or(SolanaProgramError::default()),
         })
     }
@@ -76,7 +148,12 @@ impl Parse for SplProgramErrorArgs {
 /// For example, `#[spl_program_error(hash_error_code_start = 1275525928)]`.
 enum SplProgramErrorArgParser {
     HashErrorCodes { value: LitInt },
+    Namespace { value: LitStr },
+    Offset { value: LitInt },
     SolanaProgramErrorCrate { value: LitStr },
+    Derive { paths: Vec<Path> },
+    Minimal,
+    Pinocchio,
 }
 
 impl Parse for SplProgramErrorArgParser {
@@ -89,16 +166,45 @@ impl Parse for SplProgramErrorArgParser {
                 let _comma: Option<Comma> = input.parse().unwrap_or(None);
                 Ok(Self::HashErrorCodes { value })
             }
+            "namespace" => {
+                let _equals_sign = input.parse::<Token![=]>()?;
+                let value = input.parse::<LitStr>()?;
+                let _comma: Option<Comma> = input.parse().unwrap_or(None);
+                Ok(Self::Namespace { value })
+            }
+            "offset" => {
+                let _equals_sign = input.parse::<Token![=]>()?;
+                let value = input.parse::<LitInt>()?;
+                let _comma: Option<Comma> = input.parse().unwrap_or(None);
+                Ok(Self::Offset { value })
+            }
+            "derive" => {
+                let content;
+                parenthesized!(content in input);
+                let paths = Punctuated::<Path, Comma>::parse_terminated(&content)?;
+                let _comma: Option<Comma> = input.parse().unwrap_or(None);
+                Ok(Self::Derive {
+                    paths: paths.into_iter().collect(),
+                })
+            }
+            "minimal" => {
+                let _comma: Option<Comma> = input.parse().unwrap_or(None);
+                Ok(Self::Minimal)
+            }
+            "pinocchio" => {
+                let _comma: Option<Comma> = input.parse().unwrap_or(None);
+                Ok(Self::Pinocchio)
+            }
             "solana_program_error" => {
                 let _equals_sign = input.parse::<Token![=]>()?;
                 let value = input.parse::<LitStr>()?;
                 let _comma: Option<Comma> = input.parse().unwrap_or(None);
                 Ok(Self::SolanaProgramErrorCrate { value })
             }
-            _ => {
-                Err(input
-                    .error("Expected argument 'hash_error_code_start', 'solana_program_error'"))
-            }
+            _ => Err(input.error(
+                "Expected argument 'hash_error_code_start', 'namespace', 'offset', \
+                 'derive', 'minimal', 'pinocchio', 'solana_program_error'",
+            )),
         }
     }
 }
@@ -131,3 +237,14 @@ fn program_error_anon_const_trick(exp: TokenStream) -> TokenStream {
         };
     }
 }
+
+// Same as `program_error_anon_const_trick`, with `solana_program_error`
+// re-exported by `pinocchio` as `pinocchio::error`
+fn pinocchio_anon_const_trick(exp: TokenStream) -> TokenStream {
+    quote! {
+        const _: () = {
+            extern crate pinocchio as _pinocchio;
+            #exp
+        };
+    }
+}
@@ -8,8 +8,174 @@ extern crate self as spl_program_error;
 
 // Make these available downstream for the macro to work without
 // additional imports
+#[cfg(feature = "from-primitive")]
+pub use {num_derive, num_traits};
 pub use {
-    num_derive, num_traits, solana_program_error,
-    spl_program_error_derive::{spl_program_error, IntoProgramError, ToStr},
+    solana_program_error,
+    spl_program_error_derive::{spl_program_error, IntoProgramError, ToStr, TryFromProgramError},
     thiserror,
 };
+
+/// Render an error catalog, such as the `ERROR_CATALOG` of a
+/// `#[spl_program_error]` enum, as a JSON document
+///
+/// Errors are sorted by code, for a stable output:
+/// `{"name":"MyError","errors":[{"code":0,"name":"MyVariant","message":"My message"}]}`
+#[cfg(feature = "error-map")]
+pub fn error_map_json(name: &str, catalog: &[(u32, &str, &str)]) -> String {
+    use std::fmt::Write;
+
+    fn write_json_str(json: &mut String, value: &str) {
+        json.push('"');
+        for c in value.chars() {
+            match c {
+                '"' => json.push_str("\\\""),
+                '\\' => json.push_str("\\\\"),
+                '\n' => json.push_str("\\n"),
+                '\r' => json.push_str("\\r"),
+                '\t' => json.push_str("\\t"),
+                c if c.is_control() => {
+                    let _ = write!(json, "\\u{:04x}", c as u32);
+                }
+                c => json.push(c),
+            }
+        }
+        json.push('"');
+    }
+
+    let mut errors = catalog.to_vec();
+    errors.sort_by_key(|(code, _, _)| *code);
+    let mut json = String::from("{\"name\":");
+    write_json_str(&mut json, name);
+    json.push_str(",\"errors\":[");
+    for (i, (code, variant, message)) in errors.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        let _ = write!(json, "{{\"code\":{code},\"name\":");
+        write_json_str(&mut json, variant);
+        json.push_str(",\"message\":");
+        write_json_str(&mut json, message);
+        json.push('}');
+    }
+    json.push_str("]}");
+    json
+}
+
+/// Log an error with its `solana_program_error::ToStr` string
+///
+/// Implemented for every error implementing `ToStr`, such as
+/// `#[spl_program_error]` enums.
+#[cfg(feature = "log")]
+pub trait LogError: solana_program_error::ToStr {
+    /// Log the error's string to the program logs
+    fn log(&self) {
+        solana_msg::msg!(self.to_str());
+    }
+}
+
+#[cfg(feature = "log")]
+impl<E: solana_program_error::ToStr> LogError for E {}
+
+/// Extension to log the errors of results, before propagating them with `?`
+///
+/// ```
+/// use spl_program_error::{spl_program_error, ResultExt};
+/// #[spl_program_error]
+/// pub enum ExampleError {
+///     #[error("Mint has no mint authority")]
+///     MintHasNoMintAuthority,
+/// }
+/// fn process() -> Result<(), ExampleError> {
+///     Err(ExampleError::MintHasNoMintAuthority)
+/// }
+/// fn process_logged() -> Result<(), solana_program_error::ProgramError> {
+///     process().log_err()?;
+///     Ok(())
+/// }
+/// assert!(process_logged().is_err());
+/// ```
+#[cfg(feature = "log")]
+pub trait ResultExt {
+    /// Log the error, if any, and return the result unchanged
+    fn log_err(self) -> Self;
+}
+
+#[cfg(feature = "log")]
+impl<T, E: LogError> ResultExt for Result<T, E> {
+    fn log_err(self) -> Self {
+        if let Err(error) = &self {
+            error.log();
+        }
+        self
+    }
+}
+
+/// Check that no two of the given error code ranges overlap, ignoring empty
+/// ranges
+pub const fn are_disjoint(ranges: &[core::ops::RangeInclusive<u32>]) -> bool {
+    let mut i = 0;
+    while i < ranges.len() {
+        let mut j = i + 1;
+        while j < ranges.len() {
+            if *ranges[i].start() <= *ranges[i].end()
+                && *ranges[j].start() <= *ranges[j].end()
+                && *ranges[i].start() <= *ranges[j].end()
+                && *ranges[j].start() <= *ranges[i].end()
+            {
+                return false;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Fail the build if the error codes of any two of the given
+/// `#[spl_program_error]` enums overlap
+///
+/// The `ERROR_CODE_RANGE` of an enum doesn't cover the error codes of the
+/// errors wrapped by its `#[error(transparent)]` variants, so list the wrapped
+/// enums as well.
+///
+/// ```
+/// use spl_program_error::{assert_disjoint_error_codes, spl_program_error};
+/// #[spl_program_error(offset = 6000)]
+/// pub enum InterfaceError {
+///     #[error("Invalid interface")]
+///     InvalidInterface,
+/// }
+/// #[spl_program_error(offset = 7000)]
+/// pub enum MyProgramError {
+///     #[error("Invalid state")]
+///     InvalidState,
+/// }
+/// assert_disjoint_error_codes!(InterfaceError, MyProgramError);
+/// ```
+///
+/// ```compile_fail
+/// use spl_program_error::{assert_disjoint_error_codes, spl_program_error};
+/// #[spl_program_error(offset = 6000)]
+/// pub enum InterfaceError {
+///     #[error("Invalid interface")]
+///     InvalidInterface,
+///     #[error("Invalid data")]
+///     InvalidData,
+/// }
+/// #[spl_program_error(offset = 6001)]
+/// pub enum MyProgramError {
+///     #[error("Invalid state")]
+///     InvalidState,
+/// }
+/// assert_disjoint_error_codes!(InterfaceError, MyProgramError);
+/// ```
+#[macro_export]
+macro_rules! assert_disjoint_error_codes {
+    ($($ty:ty),+ $(,)?) => {
+        const _: () = ::core::assert!(
+            $crate::are_disjoint(&[$(<$ty>::ERROR_CODE_RANGE),+]),
+            "two error enums have overlapping error codes",
+        );
+    };
+}
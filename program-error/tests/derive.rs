@@ -0,0 +1,20 @@
+//! Tests `#[spl_program_error(derive(..))]`
+
+use spl_program_error::*;
+
+/// Example error
+#[spl_program_error(derive(Hash, PartialOrd, Ord), hash_error_code_start = 2_612_633_826)]
+pub enum ExampleError {
+    /// Mint has no mint authority
+    #[error("Mint has no mint authority")]
+    MintHasNoMintAuthority,
+    /// Incorrect mint authority has signed the instruction
+    #[error("Incorrect mint authority has signed the instruction")]
+    IncorrectMintAuthority,
+}
+
+/// Tests that all macros compile
+#[test]
+fn test_macros_compile() {
+    let _ = ExampleError::MintHasNoMintAuthority;
+}
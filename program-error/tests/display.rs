@@ -0,0 +1,21 @@
+//! Tests `#[derive(ToStr)]` with `#[to_str(display)]`
+
+use spl_program_error::*;
+
+/// Example error
+#[derive(Clone, Debug, Eq, IntoProgramError, ToStr, PartialEq)]
+#[to_str(display)]
+pub enum ExampleError {
+    /// Mint has no mint authority
+    #[to_str("Mint has no mint authority")]
+    MintHasNoMintAuthority,
+    /// Incorrect mint authority has signed the instruction
+    #[to_str("Incorrect mint authority has signed the instruction")]
+    IncorrectMintAuthority,
+}
+
+/// Tests that all macros compile
+#[test]
+fn test_macros_compile() {
+    let _ = ExampleError::MintHasNoMintAuthority;
+}
@@ -0,0 +1,52 @@
+//! Tests error enums with fields
+
+use spl_program_error::*;
+
+/// Example error with fields
+#[spl_program_error]
+pub enum ExampleError {
+    /// Mint has no mint authority
+    #[error("Mint has no mint authority")]
+    MintHasNoMintAuthority,
+    /// Invalid amount
+    #[error("Invalid amount: expected {expected}, got {got}")]
+    #[to_str("Invalid amount")]
+    InvalidAmount {
+        /// Expected amount
+        expected: u64,
+        /// Actual amount
+        got: u64,
+    },
+    /// Account not found
+    #[error("Account {0} not found")]
+    AccountNotFound(u8) = 10,
+    /// Incorrect mint authority has signed the instruction
+    #[error("Incorrect mint authority has signed the instruction")]
+    IncorrectMintAuthority,
+}
+
+/// Example error with fields, using the derives
+#[derive(Clone, Debug, Eq, IntoProgramError, ToStr, thiserror::Error, PartialEq)]
+#[repr(u32)]
+pub enum ExampleDeriveError {
+    /// Mint has no mint authority
+    #[error("Mint has no mint authority")]
+    MintHasNoMintAuthority = 5,
+    /// Invalid amount
+    #[error("Invalid amount: expected {expected}, got {got}")]
+    InvalidAmount {
+        /// Expected amount
+        expected: u64,
+        /// Actual amount
+        got: u64,
+    },
+}
+
+/// Tests that all macros compile
+#[test]
+fn test_macros_compile() {
+    let _ = ExampleError::InvalidAmount {
+        expected: 1,
+        got: 2,
+    };
+}
@@ -1,7 +1,15 @@
 pub mod bench;
+pub mod derive;
+pub mod display;
+pub mod fields;
 pub mod into;
+pub mod minimal;
+pub mod offset;
+pub mod pinocchio;
 pub mod spl;
 pub mod to_str;
+pub mod transparent;
+pub mod try_from;
 
 #[cfg(test)]
 mod tests {
@@ -69,4 +77,285 @@ mod tests {
             "Incorrect mint authority has signed the instruction",
         );
     }
+
+    // `error_map_json`
+    #[cfg(feature = "error-map")]
+    #[test]
+    fn test_error_map_json() {
+        assert_eq!(
+            offset::ExampleProgramError::error_map_json(),
+            "{\"name\":\"ExampleProgramError\",\"errors\":[\
+             {\"code\":7000,\"name\":\"MintHasNoMintAuthority\",\"message\":\"Mint has no mint authority\"},\
+             {\"code\":7010,\"name\":\"IncorrectMintAuthority\",\"message\":\"Incorrect mint authority has signed the instruction\"},\
+             {\"code\":7011,\"name\":\"AccountNotFound\",\"message\":\"Account not found\"}]}",
+        );
+        assert_eq!(
+            spl_program_error::error_map_json("Error", &[(2, "B", "\"b\"\n"), (1, "A", "a\\")]),
+            "{\"name\":\"Error\",\"errors\":[\
+             {\"code\":1,\"name\":\"A\",\"message\":\"a\\\\\"},\
+             {\"code\":2,\"name\":\"B\",\"message\":\"\\\"b\\\"\\n\"}]}",
+        );
+    }
+
+    // `LogError` and `ResultExt`
+    #[cfg(feature = "log")]
+    #[test]
+    fn test_log_error() {
+        use spl_program_error::{LogError, ResultExt};
+        spl::ExampleError::MintHasNoMintAuthority.log();
+        let result: Result<(), _> = Err(spl::ExampleError::IncorrectMintAuthority);
+        assert_eq!(
+            result.log_err(),
+            Err(spl::ExampleError::IncorrectMintAuthority)
+        );
+        assert_eq!(Ok::<_, spl::ExampleError>(1).log_err(), Ok(1));
+    }
+
+    // `#[spl_program_error(derive(..))]`
+    #[test]
+    fn test_spl_program_error_derive() {
+        use std::collections::BTreeSet;
+        let errors = BTreeSet::from([
+            derive::ExampleError::IncorrectMintAuthority,
+            derive::ExampleError::MintHasNoMintAuthority,
+        ]);
+        assert_eq!(
+            errors.first(),
+            Some(&derive::ExampleError::MintHasNoMintAuthority)
+        );
+        assert_eq!(
+            Into::<ProgramError>::into(derive::ExampleError::MintHasNoMintAuthority),
+            ProgramError::Custom(2_612_633_826),
+        );
+    }
+
+    // `#[spl_program_error(minimal)]`
+    #[test]
+    fn test_spl_program_error_minimal() {
+        assert_eq!(
+            Into::<ProgramError>::into(bench::ExampleError::IncorrectMintAuthority),
+            Into::<ProgramError>::into(minimal::ExampleError::IncorrectMintAuthority),
+        );
+        assert_eq!(
+            minimal::ExampleError::from_u32(1),
+            Some(minimal::ExampleError::IncorrectMintAuthority),
+        );
+        assert_eq!(minimal::ExampleError::from_u32(2), None);
+        assert_eq!(
+            minimal::ExampleError::try_from(0),
+            Ok(minimal::ExampleError::MintHasNoMintAuthority),
+        );
+    }
+
+    // `#[spl_program_error(offset = ..)]`
+    #[test]
+    fn test_spl_program_error_offset() {
+        assert_eq!(
+            Into::<ProgramError>::into(offset::ExampleInterfaceError::IncorrectMintAuthority),
+            ProgramError::Custom(6001),
+        );
+        assert_eq!(
+            Into::<ProgramError>::into(offset::ExampleProgramError::MintHasNoMintAuthority),
+            ProgramError::Custom(7000),
+        );
+        assert_eq!(
+            Into::<ProgramError>::into(offset::ExampleProgramError::AccountNotFound),
+            ProgramError::Custom(7011),
+        );
+        assert_eq!(offset::ExampleInterfaceError::ERROR_CODE_RANGE, 6000..=6001);
+        assert_eq!(offset::ExampleProgramError::ERROR_CODE_RANGE, 7000..=7011);
+        assert_eq!(spl::ExampleError::ERROR_CODE_RANGE, 0..=1);
+        assert!(!spl_program_error::are_disjoint(&[0..=1, 3..=4, 1..=2]));
+        assert!(spl_program_error::are_disjoint(&[0..=1, 3..=4, 2..=2]));
+    }
+
+    // `#[spl_program_error(pinocchio)]`
+    #[test]
+    fn test_spl_program_error_pinocchio() {
+        assert_eq!(
+            Into::<::pinocchio::error::ProgramError>::into(
+                pinocchio::ExampleError::IncorrectMintAuthority
+            ),
+            Into::<ProgramError>::into(bench::ExampleError::IncorrectMintAuthority),
+        );
+        assert_eq!(
+            ToStr::to_str(&pinocchio::ExampleError::IncorrectMintAuthority),
+            "Incorrect mint authority has signed the instruction",
+        );
+    }
+
+    // `#[spl_program_error]` catalog
+    #[test]
+    fn test_spl_program_error_catalog() {
+        assert_eq!(
+            spl::ExampleError::error_catalog(),
+            [
+                (0, "MintHasNoMintAuthority", "Mint has no mint authority"),
+                (
+                    1,
+                    "IncorrectMintAuthority",
+                    "Incorrect mint authority has signed the instruction"
+                ),
+            ]
+        );
+        assert_eq!(
+            fields::ExampleError::ERROR_CATALOG[1..3],
+            [
+                (1, "InvalidAmount", "Invalid amount"),
+                (10, "AccountNotFound", "Account {0} not found"),
+            ]
+        );
+        assert_eq!(
+            offset::ExampleProgramError::ERROR_CATALOG[2].0,
+            offset::ExampleProgramError::AccountNotFound as u32,
+        );
+    }
+
+    // `#[derive(ToStr)]` with `#[to_str(display)]`
+    #[test]
+    fn test_derive_to_str_display() {
+        assert_eq!(
+            display::ExampleError::IncorrectMintAuthority.to_string(),
+            "Incorrect mint authority has signed the instruction",
+        );
+        assert_eq!(
+            format!("{}", display::ExampleError::MintHasNoMintAuthority),
+            ToStr::to_str(&display::ExampleError::MintHasNoMintAuthority),
+        );
+    }
+
+    // `#[error(transparent)]`
+    #[test]
+    fn test_transparent_error() {
+        let error = transparent::ExampleError::from(
+            transparent::ExampleLibraryError::IncorrectMintAuthority,
+        );
+        assert_eq!(
+            Into::<ProgramError>::into(error.clone()),
+            ProgramError::Custom(101),
+        );
+        assert_eq!(
+            ToStr::to_str(&error),
+            "Incorrect mint authority has signed the instruction",
+        );
+        assert_eq!(
+            error.to_string(),
+            "Incorrect mint authority has signed the instruction",
+        );
+        assert_eq!(
+            Into::<ProgramError>::into(transparent::ExampleError::InvalidData),
+            ProgramError::Custom(2),
+        );
+        assert_eq!(
+            transparent::ExampleError::ERROR_CATALOG,
+            [
+                (0, "InvalidState", "Invalid state"),
+                (2, "InvalidData", "Invalid data")
+            ],
+        );
+    }
+
+    // `#[derive(TryFromProgramError)]`
+    #[test]
+    fn test_derive_try_from_program_error() {
+        // `TryFrom<u32>`
+        assert_eq!(
+            try_from::ExampleError::try_from(1),
+            Ok(try_from::ExampleError::IncorrectMintAuthority),
+        );
+        assert_eq!(try_from::ExampleError::try_from(2), Err(2));
+        // `TryFrom<ProgramError>`
+        assert_eq!(
+            try_from::ExampleError::try_from(ProgramError::from(
+                try_from::ExampleError::MintHasNoMintAuthority
+            )),
+            Ok(try_from::ExampleError::MintHasNoMintAuthority),
+        );
+        assert_eq!(
+            try_from::ExampleError::try_from(ProgramError::InvalidArgument),
+            Err(ProgramError::InvalidArgument),
+        );
+    }
+
+    // `#[spl_program_error]` round trips
+    #[test]
+    fn test_spl_program_error_try_from() {
+        let error = ProgramError::from(spl::ExampleError::IncorrectMintAuthority);
+        assert_eq!(
+            spl::ExampleError::try_from(error),
+            Ok(spl::ExampleError::IncorrectMintAuthority),
+        );
+        assert!(spl::ExampleError::try_from(1u32).is_ok());
+        assert_eq!(
+            spl::ExampleError::try_from(ProgramError::Custom(2)),
+            Err(ProgramError::Custom(2)),
+        );
+        // only variants without fields
+        assert_eq!(
+            fields::ExampleError::try_from(ProgramError::Custom(11)),
+            Ok(fields::ExampleError::IncorrectMintAuthority),
+        );
+        assert_eq!(
+            fields::ExampleError::try_from(ProgramError::Custom(1)),
+            Err(ProgramError::Custom(1)),
+        );
+        assert_eq!(fields::ExampleError::try_from(10u32), Err(10));
+    }
+
+    // Enums with fields
+    #[test]
+    fn test_error_with_fields() {
+        // `Into<ProgramError>`
+        assert_eq!(
+            Into::<ProgramError>::into(fields::ExampleError::MintHasNoMintAuthority),
+            ProgramError::Custom(0),
+        );
+        assert_eq!(
+            Into::<ProgramError>::into(fields::ExampleError::InvalidAmount {
+                expected: 1,
+                got: 2
+            }),
+            ProgramError::Custom(1),
+        );
+        assert_eq!(
+            Into::<ProgramError>::into(fields::ExampleError::AccountNotFound(3)),
+            ProgramError::Custom(10),
+        );
+        assert_eq!(
+            Into::<ProgramError>::into(fields::ExampleError::IncorrectMintAuthority),
+            ProgramError::Custom(11),
+        );
+        assert_eq!(
+            Into::<ProgramError>::into(fields::ExampleDeriveError::InvalidAmount {
+                expected: 1,
+                got: 2
+            }),
+            ProgramError::Custom(6),
+        );
+        // `ToStr`
+        assert_eq!(
+            ToStr::to_str(&fields::ExampleError::InvalidAmount {
+                expected: 1,
+                got: 2
+            }),
+            "Invalid amount",
+        );
+        assert_eq!(
+            ToStr::to_str(&fields::ExampleError::AccountNotFound(3)),
+            "Account {0} not found",
+        );
+        assert_eq!(
+            ToStr::to_str(&fields::ExampleDeriveError::MintHasNoMintAuthority),
+            "Mint has no mint authority",
+        );
+        // `Display` still formats the fields
+        assert_eq!(
+            fields::ExampleError::InvalidAmount {
+                expected: 1,
+                got: 2
+            }
+            .to_string(),
+            "Invalid amount: expected 1, got 2",
+        );
+    }
 }
@@ -0,0 +1,36 @@
+//! Tests `#[spl_program_error(offset = ..)]`
+
+use spl_program_error::*;
+
+/// Example interface error
+#[spl_program_error(offset = 6000)]
+pub enum ExampleInterfaceError {
+    /// Mint has no mint authority
+    #[error("Mint has no mint authority")]
+    MintHasNoMintAuthority,
+    /// Incorrect mint authority has signed the instruction
+    #[error("Incorrect mint authority has signed the instruction")]
+    IncorrectMintAuthority,
+}
+
+/// Example program error
+#[spl_program_error(offset = 7000)]
+pub enum ExampleProgramError {
+    /// Mint has no mint authority
+    #[error("Mint has no mint authority")]
+    MintHasNoMintAuthority,
+    /// Incorrect mint authority has signed the instruction
+    #[error("Incorrect mint authority has signed the instruction")]
+    IncorrectMintAuthority = 10,
+    /// Account not found
+    #[error("Account not found")]
+    AccountNotFound,
+}
+
+assert_disjoint_error_codes!(ExampleInterfaceError, ExampleProgramError);
+
+/// Tests that all macros compile
+#[test]
+fn test_macros_compile() {
+    let _ = ExampleInterfaceError::MintHasNoMintAuthority;
+}
@@ -55,6 +55,11 @@ fn test_library_error_codes() {
 
     let first_error_as_u32 = ExampleLibraryError::VeryInformativeError as u32;
 
+    assert_eq!(
+        namespaced::ExampleLibraryError::VeryInformativeError as u32,
+        get_error_code_check("my_program:ExampleLibraryError"),
+    );
+
     assert_eq!(
         ExampleLibraryError::VeryInformativeError as u32,
         get_error_code_check("spl_program_error:ExampleLibraryError"),
@@ -73,6 +78,19 @@ fn test_library_error_codes() {
     );
 }
 
+/// Example library error with the same name, in another program
+mod namespaced {
+    use super::*;
+
+    /// Example library error with a custom namespace
+    #[spl_program_error(hash_error_code_start = 564_018_254, namespace = "my_program")]
+    pub(super) enum ExampleLibraryError {
+        /// This is a very informative error
+        #[error("This is a very informative error")]
+        VeryInformativeError,
+    }
+}
+
 /// Example error with solana_program_error crate set
 #[spl_program_error(solana_program_error = "solana_program_error")]
 enum ExampleSolanaProgramError {
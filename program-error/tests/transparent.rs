@@ -0,0 +1,73 @@
+//! Tests `#[error(transparent)]` variants
+
+use spl_program_error::*;
+
+/// Example library error
+#[spl_program_error(offset = 100)]
+pub enum ExampleLibraryError {
+    /// Mint has no mint authority
+    #[error("Mint has no mint authority")]
+    MintHasNoMintAuthority,
+    /// Incorrect mint authority has signed the instruction
+    #[error("Incorrect mint authority has signed the instruction")]
+    IncorrectMintAuthority,
+}
+
+/// Example program error wrapping the library error
+#[spl_program_error]
+pub enum ExampleError {
+    /// Invalid state
+    #[error("Invalid state")]
+    InvalidState,
+    /// Library error
+    #[error(transparent)]
+    Library(#[from] ExampleLibraryError),
+    /// Invalid data
+    #[error("Invalid data")]
+    InvalidData,
+}
+
+/// Example program error ending with a transparent variant
+#[spl_program_error]
+pub enum ExampleTrailingError {
+    /// Invalid state
+    #[error("Invalid state")]
+    InvalidState,
+    /// Library error
+    #[error(transparent)]
+    Library(#[from] ExampleLibraryError),
+}
+
+/// Example program error with only transparent variants
+#[spl_program_error]
+pub enum ExampleWrapperError {
+    /// Library error
+    #[error(transparent)]
+    Library(#[from] ExampleLibraryError),
+}
+
+// the wrapped enum is listed, since transparent variants aren't in the ranges
+assert_disjoint_error_codes!(ExampleError, ExampleLibraryError);
+assert_disjoint_error_codes!(ExampleWrapperError, ExampleLibraryError);
+
+/// Tests that all macros compile
+#[test]
+fn test_macros_compile() {
+    let _ = ExampleError::InvalidState;
+}
+
+/// Tests that transparent variants are left out of the error code range
+#[test]
+fn test_error_code_range() {
+    assert_eq!(ExampleError::ERROR_CODE_RANGE, 0..=2);
+    assert_eq!(ExampleTrailingError::ERROR_CODE_RANGE, 0..=0);
+    assert!(ExampleWrapperError::ERROR_CODE_RANGE.is_empty());
+    assert!(!are_disjoint(&[
+        ExampleLibraryError::ERROR_CODE_RANGE,
+        100..=100
+    ]));
+    assert!(are_disjoint(&[
+        ExampleWrapperError::ERROR_CODE_RANGE,
+        ExampleLibraryError::ERROR_CODE_RANGE
+    ]));
+}
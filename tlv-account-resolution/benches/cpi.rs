@@ -0,0 +1,75 @@
+//! Benchmarks of adding extra accounts to a CPI, for hooks with many extra
+//! accounts provided in shuffled order
+
+use {
+    criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion},
+    solana_account_info::AccountInfo,
+    solana_instruction::Instruction,
+    solana_pubkey::Pubkey,
+    spl_discriminator::{ArrayDiscriminator, SplDiscriminate},
+    spl_tlv_account_resolution::{account::ExtraAccountMeta, state::ExtraAccountMetaList},
+    std::hint::black_box,
+};
+
+const SIZES: [usize; 3] = [4, 16, 64];
+
+struct BenchInstruction;
+impl SplDiscriminate for BenchInstruction {
+    const SPL_DISCRIMINATOR: ArrayDiscriminator =
+        ArrayDiscriminator::new([1; ArrayDiscriminator::LENGTH]);
+}
+
+fn bench_add_to_cpi_instruction(c: &mut Criterion) {
+    let mut group = c.benchmark_group("add_to_cpi_instruction");
+    for num_extras in SIZES {
+        let program_id = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let keys = (0..num_extras)
+            .map(|_| Pubkey::new_unique())
+            .collect::<Vec<_>>();
+
+        let metas = keys
+            .iter()
+            .map(|key| ExtraAccountMeta::new_with_pubkey(key, false, false).unwrap())
+            .collect::<Vec<_>>();
+        let mut buffer = vec![0; ExtraAccountMetaList::size_of(metas.len()).unwrap()];
+        ExtraAccountMetaList::init::<BenchInstruction>(&mut buffer, &metas).unwrap();
+
+        let mut lamports = vec![0; num_extras];
+        let mut datas = vec![[0u8; 0]; num_extras];
+        let mut account_infos = keys
+            .iter()
+            .zip(lamports.iter_mut().zip(datas.iter_mut()))
+            .map(|(key, (lamports, data))| {
+                AccountInfo::new(key, false, false, lamports, data, &owner, false)
+            })
+            .collect::<Vec<_>>();
+        // The last extra account is the last one found by a linear search
+        account_infos.reverse();
+
+        group.bench_function(BenchmarkId::from_parameter(num_extras), |b| {
+            b.iter_batched_ref(
+                || {
+                    (
+                        Instruction::new_with_bytes(program_id, &[], vec![]),
+                        Vec::with_capacity(num_extras),
+                    )
+                },
+                |(cpi_instruction, cpi_account_infos)| {
+                    ExtraAccountMetaList::add_to_cpi_instruction::<BenchInstruction, _>(
+                        cpi_instruction,
+                        cpi_account_infos,
+                        black_box(&buffer),
+                        black_box(&account_infos),
+                    )
+                    .unwrap()
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_add_to_cpi_instruction);
+criterion_main!(benches);
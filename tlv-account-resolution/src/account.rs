@@ -5,66 +5,189 @@
 //! addresses derived from a collection of seeds
 
 use {
-    crate::{error::AccountResolutionError, pubkey_data::PubkeyData, seeds::Seed},
+    crate::{
+        error::AccountResolutionError,
+        pubkey_data::{read_pubkey, PubkeyData},
+        seeds::{Seed, ADDRESS_CONFIG_LEN},
+    },
     bytemuck::{Pod, Zeroable},
     solana_account_info::AccountInfo,
     solana_instruction::AccountMeta,
     solana_program_error::ProgramError,
-    solana_pubkey::{Pubkey, PUBKEY_BYTES},
+    solana_pubkey::{Pubkey, MAX_SEED_LEN, PUBKEY_BYTES},
     spl_pod::primitives::PodBool,
+    std::{collections::HashMap, fmt},
 };
+#[cfg(feature = "serde-traits")]
+use {
+    serde::{de::Error as _, ser::Error as _, Deserialize, Deserializer, Serialize, Serializer},
+    std::str::FromStr,
+};
+
+/// Get the bytes of a seed from the instruction data and the accounts that
+/// have already been resolved
+fn resolve_seed<'s, 'a: 's, F>(
+    seed: &'s Seed,
+    instruction_data: &'s [u8],
+    program_id: &'s Pubkey,
+    get_account_key_data_fn: &F,
+) -> Result<Option<&'s [u8]>, ProgramError>
+where
+    F: Fn(usize) -> Option<(&'a Pubkey, Option<&'a [u8]>)>,
+{
+    match seed {
+        Seed::Uninitialized => Ok(None),
+        Seed::Literal { bytes } => Ok(Some(bytes)),
+        Seed::InstructionData { index, length } => {
+            let arg_start = *index as usize;
+            let arg_end = arg_start + *length as usize;
+            if arg_end > instruction_data.len() {
+                return Err(AccountResolutionError::InstructionDataTooSmall.into());
+            }
+            Ok(Some(&instruction_data[arg_start..arg_end]))
+        }
+        Seed::AccountKey { index } => {
+            let account_index = *index as usize;
+            let address = get_account_key_data_fn(account_index)
+                .ok_or::<ProgramError>(AccountResolutionError::AccountNotFound.into())?
+                .0;
+            Ok(Some(address.as_ref()))
+        }
+        Seed::AccountData {
+            account_index,
+            data_index,
+            length,
+        } => {
+            let account_index = *account_index as usize;
+            let account_data = get_account_key_data_fn(account_index)
+                .ok_or::<ProgramError>(AccountResolutionError::AccountNotFound.into())?
+                .1
+                .ok_or::<ProgramError>(AccountResolutionError::AccountDataNotFound.into())?;
+            let arg_start = *data_index as usize;
+            let arg_end = arg_start + *length as usize;
+            if account_data.len() < arg_end {
+                return Err(AccountResolutionError::AccountDataTooSmall.into());
+            }
+            Ok(Some(&account_data[arg_start..arg_end]))
+        }
+        Seed::ProgramId => Ok(Some(program_id.as_ref())),
+        Seed::KnownAccount(id) => Ok(Some(id.address().as_ref())),
+        Seed::InstructionDataLengthPrefixed {
+            offset,
+            prefix_width,
+        } => {
+            let prefix_start = *offset as usize;
+            let prefix_end = prefix_start + *prefix_width as usize;
+            let prefix = instruction_data
+                .get(prefix_start..prefix_end)
+                .ok_or::<ProgramError>(AccountResolutionError::InstructionDataTooSmall.into())?;
+            let mut length = [0; 4];
+            length[..prefix.len()].copy_from_slice(prefix);
+            let length = u32::from_le_bytes(length) as usize;
+            if length > MAX_SEED_LEN {
+                return Err(ProgramError::MaxSeedLengthExceeded);
+            }
+            let arg = instruction_data
+                .get(prefix_end..prefix_end + length)
+                .ok_or::<ProgramError>(AccountResolutionError::InstructionDataTooSmall.into())?;
+            Ok(Some(arg))
+        }
+    }
+}
 
-/// Resolve a program-derived address (PDA) from the instruction data
-/// and the accounts that have already been resolved
+/// Resolve a program-derived address (PDA) for `pda_program_id` from the
+/// instruction data and the accounts that have already been resolved
+///
+/// On failure, also returns the index of the seed that couldn't be resolved,
+/// if any.
 fn resolve_pda<'a, F>(
     seeds: &[Seed],
     instruction_data: &[u8],
     program_id: &Pubkey,
+    pda_program_id: &Pubkey,
+    cache: Option<&mut PdaCache>,
+    bump: Option<u8>,
     get_account_key_data_fn: F,
-) -> Result<Pubkey, ProgramError>
+) -> Result<Pubkey, (Option<usize>, ProgramError)>
 where
     F: Fn(usize) -> Option<(&'a Pubkey, Option<&'a [u8]>)>,
 {
     let mut pda_seeds: Vec<&[u8]> = vec![];
-    for config in seeds {
-        match config {
-            Seed::Uninitialized => (),
-            Seed::Literal { bytes } => pda_seeds.push(bytes),
-            Seed::InstructionData { index, length } => {
-                let arg_start = *index as usize;
-                let arg_end = arg_start + *length as usize;
-                if arg_end > instruction_data.len() {
-                    return Err(AccountResolutionError::InstructionDataTooSmall.into());
-                }
-                pda_seeds.push(&instruction_data[arg_start..arg_end]);
-            }
-            Seed::AccountKey { index } => {
-                let account_index = *index as usize;
-                let address = get_account_key_data_fn(account_index)
-                    .ok_or::<ProgramError>(AccountResolutionError::AccountNotFound.into())?
-                    .0;
-                pda_seeds.push(address.as_ref());
-            }
-            Seed::AccountData {
-                account_index,
-                data_index,
-                length,
-            } => {
-                let account_index = *account_index as usize;
-                let account_data = get_account_key_data_fn(account_index)
-                    .ok_or::<ProgramError>(AccountResolutionError::AccountNotFound.into())?
-                    .1
-                    .ok_or::<ProgramError>(AccountResolutionError::AccountDataNotFound.into())?;
-                let arg_start = *data_index as usize;
-                let arg_end = arg_start + *length as usize;
-                if account_data.len() < arg_end {
-                    return Err(AccountResolutionError::AccountDataTooSmall.into());
-                }
-                pda_seeds.push(&account_data[arg_start..arg_end]);
-            }
+    for (seed_index, seed) in seeds.iter().enumerate() {
+        if let Some(bytes) =
+            resolve_seed(seed, instruction_data, program_id, &get_account_key_data_fn)
+                .map_err(|e| (Some(seed_index), e))?
+        {
+            pda_seeds.push(bytes);
         }
     }
-    Ok(Pubkey::find_program_address(&pda_seeds, program_id).0)
+    match cache {
+        Some(cache) => cache.get_or_derive(&pda_seeds, pda_program_id, bump),
+        None => derive_pda(&pda_seeds, pda_program_id, bump),
+    }
+    .map_err(|e| (None, e))
+}
+
+/// Derive a program-derived address (PDA), searching for the bump seed unless
+/// it is already known
+fn derive_pda(
+    seeds: &[&[u8]],
+    program_id: &Pubkey,
+    bump: Option<u8>,
+) -> Result<Pubkey, ProgramError> {
+    match bump {
+        Some(bump) => {
+            let bump = [bump];
+            let mut seeds = seeds.to_vec();
+            seeds.push(&bump);
+            Pubkey::create_program_address(&seeds, program_id)
+                .map_err(|_| AccountResolutionError::InvalidPdaBump.into())
+        }
+        None => Ok(Pubkey::find_program_address(seeds, program_id).0),
+    }
+}
+
+/// Cache of the program-derived addresses (PDAs) found while resolving a list
+/// of extra account metas
+///
+/// Searching for a PDA's bump seed is expensive on-chain, so metas resolving
+/// to the same seeds and program only search once. Addresses derived from a
+/// caller-provided bump are not cached, since the bump may not be canonical.
+#[derive(Clone, Debug, Default)]
+pub struct PdaCache {
+    addresses: HashMap<(Pubkey, Vec<Vec<u8>>), Pubkey>,
+}
+impl PdaCache {
+    /// Get the cached address for the seeds and program, or derive it
+    fn get_or_derive(
+        &mut self,
+        seeds: &[&[u8]],
+        program_id: &Pubkey,
+        bump: Option<u8>,
+    ) -> Result<Pubkey, ProgramError> {
+        let key = (
+            *program_id,
+            seeds.iter().map(|seed| seed.to_vec()).collect(),
+        );
+        if let Some(address) = self.addresses.get(&key) {
+            return Ok(*address);
+        }
+        let address = derive_pda(seeds, program_id, bump)?;
+        if bump.is_none() {
+            self.addresses.insert(key, address);
+        }
+        Ok(address)
+    }
+
+    /// Number of addresses in the cache
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    /// Whether the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
 }
 
 /// Resolve a pubkey from a pubkey data configuration.
@@ -97,16 +220,264 @@ where
                 .ok_or::<ProgramError>(AccountResolutionError::AccountNotFound.into())?
                 .1
                 .ok_or::<ProgramError>(AccountResolutionError::AccountDataNotFound.into())?;
-            let arg_start = *data_index as usize;
-            let arg_end = arg_start + PUBKEY_BYTES;
-            if account_data.len() < arg_end {
-                return Err(AccountResolutionError::AccountDataTooSmall.into());
+            read_pubkey(account_data, *data_index)
+                .ok_or(AccountResolutionError::AccountDataTooSmall.into())
+        }
+    }
+}
+
+/// Decoded address configuration of an `ExtraAccountMeta`, describing how its
+/// address is resolved.
+///
+/// Its `Display` implementation explains the configuration in a
+/// human-readable form.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde-traits", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-traits", serde(rename_all = "camelCase"))]
+pub enum ResolvedSeedConfig {
+    /// A fixed address
+    Pubkey(
+        #[cfg_attr(
+            feature = "serde-traits",
+            serde(
+                serialize_with = "serialize_pubkey",
+                deserialize_with = "deserialize_pubkey"
+            )
+        )]
+        Pubkey,
+    ),
+    /// A PDA of the executing program, derived from the seeds
+    Pda {
+        /// The seeds of the PDA
+        seeds: Vec<Seed>,
+    },
+    /// A PDA of the program at the given index of the account list, derived
+    /// from the seeds
+    #[cfg_attr(feature = "serde-traits", serde(rename_all = "camelCase"))]
+    ExternalPda {
+        /// The index of the program in the entire accounts list
+        program_index: u8,
+        /// The seeds of the PDA
+        seeds: Vec<Seed>,
+    },
+    /// A pubkey stored in instruction or account data
+    PubkeyData(PubkeyData),
+}
+
+/// Serialize a pubkey as a base58 string
+#[cfg(feature = "serde-traits")]
+fn serialize_pubkey<S: Serializer>(pubkey: &Pubkey, s: S) -> Result<S::Ok, S::Error> {
+    s.collect_str(pubkey)
+}
+
+/// Deserialize a pubkey from a base58 string
+#[cfg(feature = "serde-traits")]
+fn deserialize_pubkey<'de, D: Deserializer<'de>>(d: D) -> Result<Pubkey, D::Error> {
+    let string = String::deserialize(d)?;
+    Pubkey::from_str(&string).map_err(D::Error::custom)
+}
+
+/// Helper to display a list of seeds
+fn fmt_seeds(f: &mut fmt::Formatter<'_>, seeds: &[Seed]) -> fmt::Result {
+    write!(f, "[")?;
+    for (i, seed) in seeds.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{seed}")?;
+    }
+    write!(f, "]")
+}
+
+impl fmt::Display for ResolvedSeedConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pubkey(pubkey) => write!(f, "{pubkey}"),
+            Self::Pda { seeds } => {
+                write!(f, "PDA of the executing program with seeds ")?;
+                fmt_seeds(f, seeds)
             }
-            Ok(Pubkey::new_from_array(
-                account_data[arg_start..arg_end].try_into().unwrap(),
-            ))
+            Self::ExternalPda {
+                program_index,
+                seeds,
+            } => {
+                write!(
+                    f,
+                    "PDA of the program at account {program_index} with seeds "
+                )?;
+                fmt_seeds(f, seeds)
+            }
+            Self::PubkeyData(key_data) => write!(f, "{key_data}"),
+        }
+    }
+}
+
+/// Invalid entry found by `validate_seed_configs` or
+/// `constraints::validate_constraints`
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvalidSeedConfig {
+    /// The index of the invalid entry in the extra account metas
+    pub meta_index: usize,
+    /// The index of the invalid seed in the entry's seeds, if the error
+    /// comes from a seed
+    pub seed_index: Option<usize>,
+    /// The error the entry would produce at resolution time
+    pub error: ProgramError,
+}
+
+impl From<InvalidSeedConfig> for ProgramError {
+    fn from(invalid: InvalidSeedConfig) -> Self {
+        invalid.error
+    }
+}
+
+/// Failure to resolve extra account metas, locating the offending entry
+///
+/// Returned by `ExtraAccountMetaList::resolve_verbose`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResolutionFailure {
+    /// The index of the failing entry in the extra account metas, if the
+    /// error comes from an entry rather than the validation data itself
+    pub meta_index: Option<usize>,
+    /// The index of the failing seed in the entry's seeds, if the error
+    /// comes from a seed
+    pub seed_index: Option<usize>,
+    /// The underlying error
+    pub error: ProgramError,
+}
+
+impl From<ProgramError> for ResolutionFailure {
+    fn from(error: ProgramError) -> Self {
+        Self {
+            meta_index: None,
+            seed_index: None,
+            error,
+        }
+    }
+}
+
+impl From<ResolutionFailure> for ProgramError {
+    fn from(failure: ResolutionFailure) -> Self {
+        failure.error
+    }
+}
+
+impl fmt::Display for ResolutionFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(meta_index) = self.meta_index {
+            write!(f, "extra account meta {}", meta_index)?;
+            if let Some(seed_index) = self.seed_index {
+                write!(f, ", seed {}", seed_index)?;
+            }
+            write!(f, ": ")?;
+        }
+        write!(f, "{}", self.error)
+    }
+}
+
+impl std::error::Error for ResolutionFailure {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Check that an account index refers to an account available at resolution
+/// time
+pub(crate) fn check_account_index(index: u8, num_accounts: usize) -> Result<(), ProgramError> {
+    if usize::from(index) < num_accounts {
+        Ok(())
+    } else {
+        Err(AccountResolutionError::AccountNotFound.into())
+    }
+}
+
+/// Check that a slice of the instruction data is available at resolution
+/// time
+fn check_instruction_data_range(
+    index: u8,
+    length: usize,
+    instruction_data_len: usize,
+) -> Result<(), ProgramError> {
+    if usize::from(index) + length <= instruction_data_len {
+        Ok(())
+    } else {
+        Err(AccountResolutionError::InstructionDataTooSmall.into())
+    }
+}
+
+/// Validate the configurations of extra account metas against the shape of
+/// the instruction they are resolved for
+///
+/// Checks that every entry can be decoded, that instruction data offsets fit
+/// in `instruction_data_len` bytes, and that account indices refer to one of
+/// the `num_instruction_accounts` instruction accounts or to a previous extra
+/// account. Account data can't be checked ahead of resolution.
+///
+/// Returns the first invalid entry, and seed if applicable.
+pub fn validate_seed_configs(
+    extra_account_metas: &[ExtraAccountMeta],
+    instruction_data_len: usize,
+    num_instruction_accounts: usize,
+) -> Result<(), InvalidSeedConfig> {
+    for (meta_index, meta) in extra_account_metas.iter().enumerate() {
+        let invalid = |seed_index, error| InvalidSeedConfig {
+            meta_index,
+            seed_index,
+            error,
+        };
+        // Accounts resolved before this one can be referenced
+        let num_accounts = num_instruction_accounts + meta_index;
+
+        let seeds = match meta.seed_config().map_err(|e| invalid(None, e))? {
+            ResolvedSeedConfig::Pubkey(_) => continue,
+            ResolvedSeedConfig::Pda { seeds } => seeds,
+            ResolvedSeedConfig::ExternalPda {
+                program_index,
+                seeds,
+            } => {
+                check_account_index(program_index, num_accounts).map_err(|e| invalid(None, e))?;
+                seeds
+            }
+            ResolvedSeedConfig::PubkeyData(key_data) => {
+                match key_data {
+                    PubkeyData::Uninitialized => Err(ProgramError::InvalidAccountData),
+                    PubkeyData::InstructionData { index } => {
+                        check_instruction_data_range(index, PUBKEY_BYTES, instruction_data_len)
+                    }
+                    PubkeyData::AccountData { account_index, .. } => {
+                        check_account_index(account_index, num_accounts)
+                    }
+                }
+                .map_err(|e| invalid(None, e))?;
+                continue;
+            }
+        };
+
+        for (seed_index, seed) in seeds.iter().enumerate() {
+            match seed {
+                Seed::Uninitialized => Err(ProgramError::InvalidAccountData),
+                Seed::Literal { .. } | Seed::ProgramId | Seed::KnownAccount(_) => Ok(()),
+                Seed::InstructionData { index, length } => {
+                    check_instruction_data_range(*index, usize::from(*length), instruction_data_len)
+                }
+                // Only the length prefix is known to be present
+                Seed::InstructionDataLengthPrefixed {
+                    offset,
+                    prefix_width,
+                } => check_instruction_data_range(
+                    *offset,
+                    usize::from(*prefix_width),
+                    instruction_data_len,
+                ),
+                Seed::AccountKey { index } => check_account_index(*index, num_accounts),
+                Seed::AccountData { account_index, .. } => {
+                    check_account_index(*account_index, num_accounts)
+                }
+            }
+            .map_err(|e| invalid(Some(seed_index), e))?;
         }
     }
+    Ok(())
 }
 
 /// `Pod` type for defining a required account in a validation account.
@@ -133,6 +504,37 @@ pub struct ExtraAccountMeta {
     /// Whether the account should be writable
     pub is_writable: PodBool,
 }
+/// Human-readable representation of an `ExtraAccountMeta`, used for serde
+#[cfg(feature = "serde-traits")]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExtraAccountMetaConfig {
+    address: ResolvedSeedConfig,
+    is_signer: bool,
+    is_writable: bool,
+}
+
+#[cfg(feature = "serde-traits")]
+impl Serialize for ExtraAccountMeta {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        ExtraAccountMetaConfig {
+            address: self.seed_config().map_err(S::Error::custom)?,
+            is_signer: self.is_signer(),
+            is_writable: self.is_writable(),
+        }
+        .serialize(s)
+    }
+}
+
+#[cfg(feature = "serde-traits")]
+impl<'de> Deserialize<'de> for ExtraAccountMeta {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let config = ExtraAccountMetaConfig::deserialize(d)?;
+        Self::new_with_seed_config(&config.address, config.is_signer, config.is_writable)
+            .map_err(D::Error::custom)
+    }
+}
+
 /// Helper used to know when the top bit is set, to interpret the
 /// discriminator as an index rather than as a type
 const U8_TOP_BIT: u8 = 1 << 7;
@@ -152,6 +554,21 @@ impl ExtraAccountMeta {
         })
     }
 
+    /// Create a `ExtraAccountMeta` from a public key in a const context,
+    /// thus representing a standard `AccountMeta`
+    pub const fn new_with_pubkey_const(
+        pubkey: &Pubkey,
+        is_signer: bool,
+        is_writable: bool,
+    ) -> Self {
+        Self {
+            discriminator: 0,
+            address_config: pubkey.to_bytes(),
+            is_signer: PodBool(is_signer as u8),
+            is_writable: PodBool(is_writable as u8),
+        }
+    }
+
     /// Create a `ExtraAccountMeta` from a list of seed configurations,
     /// thus representing a PDA
     pub fn new_with_seeds(
@@ -203,6 +620,93 @@ impl ExtraAccountMeta {
         })
     }
 
+    /// Create a `ExtraAccountMeta` from a decoded address configuration
+    pub fn new_with_seed_config(
+        config: &ResolvedSeedConfig,
+        is_signer: bool,
+        is_writable: bool,
+    ) -> Result<Self, ProgramError> {
+        match config {
+            ResolvedSeedConfig::Pubkey(pubkey) => {
+                Self::new_with_pubkey(pubkey, is_signer, is_writable)
+            }
+            ResolvedSeedConfig::Pda { seeds } => {
+                Self::new_with_seeds(seeds, is_signer, is_writable)
+            }
+            ResolvedSeedConfig::ExternalPda {
+                program_index,
+                seeds,
+            } => Self::new_external_pda_with_seeds(*program_index, seeds, is_signer, is_writable),
+            ResolvedSeedConfig::PubkeyData(key_data) => {
+                Self::new_with_pubkey_data(key_data, is_signer, is_writable)
+            }
+        }
+    }
+
+    /// Whether the account should sign
+    pub fn is_signer(&self) -> bool {
+        self.is_signer.into()
+    }
+
+    /// Whether the account should be writable
+    pub fn is_writable(&self) -> bool {
+        self.is_writable.into()
+    }
+
+    /// Decode the address configuration, to inspect how the address of the
+    /// account is resolved
+    pub fn seed_config(&self) -> Result<ResolvedSeedConfig, ProgramError> {
+        match self.discriminator {
+            0 => Ok(ResolvedSeedConfig::Pubkey(Pubkey::from(
+                self.address_config,
+            ))),
+            1 => Ok(ResolvedSeedConfig::Pda {
+                seeds: Seed::unpack_address_config(&self.address_config)?,
+            }),
+            2 => Ok(ResolvedSeedConfig::PubkeyData(PubkeyData::unpack(
+                &self.address_config,
+            )?)),
+            x if x >= U8_TOP_BIT => Ok(ResolvedSeedConfig::ExternalPda {
+                program_index: x - U8_TOP_BIT,
+                seeds: Seed::unpack_address_config(&self.address_config)?,
+            }),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+
+    /// Get the number of unused bytes in the address configuration, to know
+    /// whether more seeds can be added
+    ///
+    /// A fixed address uses the whole address configuration.
+    pub fn config_space_remaining(&self) -> Result<usize, ProgramError> {
+        let used = match self.seed_config()? {
+            ResolvedSeedConfig::Pubkey(_) => ADDRESS_CONFIG_LEN,
+            ResolvedSeedConfig::Pda { seeds } | ResolvedSeedConfig::ExternalPda { seeds, .. } => {
+                Seed::packed_len_of(&seeds)
+            }
+            ResolvedSeedConfig::PubkeyData(key_data) => usize::from(key_data.tlv_size()),
+        };
+        Ok(ADDRESS_CONFIG_LEN.saturating_sub(used))
+    }
+
+    /// Returns `true` if resolving this configuration requires the data of
+    /// accounts in the instruction, through `Seed::AccountData` or
+    /// `PubkeyData::AccountData`
+    pub fn requires_account_data(&self) -> Result<bool, ProgramError> {
+        match self.discriminator {
+            x if x == 1 || x >= U8_TOP_BIT => {
+                Ok(Seed::unpack_address_config(&self.address_config)?
+                    .iter()
+                    .any(|seed| matches!(seed, Seed::AccountData { .. })))
+            }
+            2 => Ok(matches!(
+                PubkeyData::unpack(&self.address_config)?,
+                PubkeyData::AccountData { .. }
+            )),
+            _ => Ok(false),
+        }
+    }
+
     /// Resolve an `ExtraAccountMeta` into an `AccountMeta`, potentially
     /// resolving a program-derived address (PDA) if necessary
     pub fn resolve<'a, F>(
@@ -214,38 +718,89 @@ impl ExtraAccountMeta {
     where
         F: Fn(usize) -> Option<(&'a Pubkey, Option<&'a [u8]>)>,
     {
-        match self.discriminator {
-            0 => AccountMeta::try_from(self),
+        self.resolve_inner(
+            instruction_data,
+            program_id,
+            None,
+            None,
+            get_account_key_data_fn,
+        )
+        .map_err(|(_, e)| e)
+    }
+
+    /// Resolve an `ExtraAccountMeta` into an `AccountMeta`, reusing the PDAs
+    /// already found in `cache`
+    ///
+    /// If the meta is a PDA and its bump seed is known, it can be provided
+    /// as `bump` to skip the search for it.
+    pub fn resolve_with_cache<'a, F>(
+        &self,
+        instruction_data: &[u8],
+        program_id: &Pubkey,
+        cache: &mut PdaCache,
+        bump: Option<u8>,
+        get_account_key_data_fn: F,
+    ) -> Result<AccountMeta, ProgramError>
+    where
+        F: Fn(usize) -> Option<(&'a Pubkey, Option<&'a [u8]>)>,
+    {
+        self.resolve_inner(
+            instruction_data,
+            program_id,
+            Some(cache),
+            bump,
+            get_account_key_data_fn,
+        )
+        .map_err(|(_, e)| e)
+    }
+
+    /// Resolve an `ExtraAccountMeta` like `resolve_with_cache`, also
+    /// returning the index of the seed that couldn't be resolved on failure
+    pub(crate) fn resolve_inner<'a, F>(
+        &self,
+        instruction_data: &[u8],
+        program_id: &Pubkey,
+        cache: Option<&mut PdaCache>,
+        bump: Option<u8>,
+        get_account_key_data_fn: F,
+    ) -> Result<AccountMeta, (Option<usize>, ProgramError)>
+    where
+        F: Fn(usize) -> Option<(&'a Pubkey, Option<&'a [u8]>)>,
+    {
+        let pubkey = match self.discriminator {
+            0 => return AccountMeta::try_from(self).map_err(|e| (None, e)),
             x if x == 1 || x >= U8_TOP_BIT => {
-                let program_id = if x == 1 {
+                let pda_program_id = if x == 1 {
                     program_id
                 } else {
                     get_account_key_data_fn(x.saturating_sub(U8_TOP_BIT) as usize)
-                        .ok_or::<ProgramError>(AccountResolutionError::AccountNotFound.into())?
+                        .ok_or((None, AccountResolutionError::AccountNotFound.into()))?
                         .0
                 };
-                let seeds = Seed::unpack_address_config(&self.address_config)?;
-                Ok(AccountMeta {
-                    pubkey: resolve_pda(
-                        &seeds,
-                        instruction_data,
-                        program_id,
-                        get_account_key_data_fn,
-                    )?,
-                    is_signer: self.is_signer.into(),
-                    is_writable: self.is_writable.into(),
-                })
+                let seeds =
+                    Seed::unpack_address_config(&self.address_config).map_err(|e| (None, e))?;
+                resolve_pda(
+                    &seeds,
+                    instruction_data,
+                    program_id,
+                    pda_program_id,
+                    cache,
+                    bump,
+                    get_account_key_data_fn,
+                )?
             }
             2 => {
-                let key_data = PubkeyData::unpack(&self.address_config)?;
-                Ok(AccountMeta {
-                    pubkey: resolve_key_data(&key_data, instruction_data, get_account_key_data_fn)?,
-                    is_signer: self.is_signer.into(),
-                    is_writable: self.is_writable.into(),
-                })
+                let key_data = PubkeyData::unpack(&self.address_config).map_err(|e| (None, e))?;
+                resolve_key_data(&key_data, instruction_data, get_account_key_data_fn)
+                    .map_err(|e| (None, e))?
             }
-            _ => Err(ProgramError::InvalidAccountData),
-        }
+            _ => return Err((None, ProgramError::InvalidAccountData)),
+        };
+        Ok(AccountMeta {
+            pubkey,
+            is_signer: self.is_signer(),
+            is_writable: self.is_writable(),
+        })
     }
 }
 
@@ -287,8 +842,8 @@ impl TryFrom<&ExtraAccountMeta> for AccountMeta {
         if pod.discriminator == 0 {
             Ok(AccountMeta {
                 pubkey: Pubkey::from(pod.address_config),
-                is_signer: pod.is_signer.into(),
-                is_writable: pod.is_writable.into(),
+                is_signer: pod.is_signer(),
+                is_writable: pod.is_writable(),
             })
         } else {
             Err(AccountResolutionError::AccountTypeNotAccountMeta.into())
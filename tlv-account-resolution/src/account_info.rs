@@ -0,0 +1,217 @@
+//! Abstraction over the account types given to a program by the runtime.
+//!
+//! On-chain validation and CPI helpers only need the key, the privileges, the
+//! owner, the executable flag and the data of each provided account, so they
+//! are written against the `AccountInfoView` trait rather than a concrete
+//! account type. The trait is implemented for
+//! `solana_account_info::AccountInfo`, and for pinocchio's `AccountView` when
+//! the `pinocchio` feature is enabled.
+
+use {
+    solana_account_info::AccountInfo, solana_program_error::ProgramError, solana_pubkey::Pubkey,
+    std::ops::Deref,
+};
+
+/// Read-only view of an account provided to a program
+pub trait AccountInfoView {
+    /// Guard type borrowing the account data
+    type Data<'a>: Deref<Target = [u8]>
+    where
+        Self: 'a;
+
+    /// The address of the account
+    fn key(&self) -> Pubkey;
+
+    /// Whether the account signed the transaction
+    fn is_signer(&self) -> bool;
+
+    /// Whether the account is writable in the transaction
+    fn is_writable(&self) -> bool;
+
+    /// The program owning the account
+    fn owner(&self) -> Pubkey;
+
+    /// Whether the account is an executable program
+    fn executable(&self) -> bool;
+
+    /// Borrow the account data, failing if it is already mutably borrowed
+    fn try_borrow_data(&self) -> Result<Self::Data<'_>, ProgramError>;
+}
+
+impl<'info> AccountInfoView for AccountInfo<'info> {
+    type Data<'a>
+        = std::cell::Ref<'a, [u8]>
+    where
+        Self: 'a;
+
+    fn key(&self) -> Pubkey {
+        *self.key
+    }
+
+    fn is_signer(&self) -> bool {
+        self.is_signer
+    }
+
+    fn is_writable(&self) -> bool {
+        self.is_writable
+    }
+
+    fn owner(&self) -> Pubkey {
+        *self.owner
+    }
+
+    fn executable(&self) -> bool {
+        self.executable
+    }
+
+    fn try_borrow_data(&self) -> Result<Self::Data<'_>, ProgramError> {
+        AccountInfo::try_borrow_data(self).map(|data| std::cell::Ref::map(data, |data| &**data))
+    }
+}
+
+#[cfg(feature = "pinocchio")]
+impl AccountInfoView for pinocchio::AccountView {
+    type Data<'a> = pinocchio::account::Ref<'a, [u8]>;
+
+    fn key(&self) -> Pubkey {
+        // `Pubkey` and pinocchio's `Address` may come from different versions
+        // of `solana-address`, so convert through the raw bytes
+        Pubkey::new_from_array(self.address().to_bytes())
+    }
+
+    fn is_signer(&self) -> bool {
+        pinocchio::AccountView::is_signer(self)
+    }
+
+    fn is_writable(&self) -> bool {
+        pinocchio::AccountView::is_writable(self)
+    }
+
+    fn owner(&self) -> Pubkey {
+        Pubkey::new_from_array(pinocchio::AccountView::owner(self).to_bytes())
+    }
+
+    fn executable(&self) -> bool {
+        pinocchio::AccountView::executable(self)
+    }
+
+    fn try_borrow_data(&self) -> Result<Self::Data<'_>, ProgramError> {
+        self.try_borrow()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            account::ExtraAccountMeta, error::AccountResolutionError, state::ExtraAccountMetaList,
+        },
+        solana_instruction::{AccountMeta, Instruction},
+        spl_discriminator::{ArrayDiscriminator, SplDiscriminate},
+    };
+
+    pub struct TestInstruction;
+    impl SplDiscriminate for TestInstruction {
+        const SPL_DISCRIMINATOR: ArrayDiscriminator =
+            ArrayDiscriminator::new([1; ArrayDiscriminator::LENGTH]);
+    }
+
+    /// Minimal account type, standing in for any non-`AccountInfo` runtime
+    /// representation
+    #[derive(Clone)]
+    struct MockAccount {
+        key: Pubkey,
+        is_signer: bool,
+        is_writable: bool,
+        data: Vec<u8>,
+    }
+
+    impl AccountInfoView for MockAccount {
+        type Data<'a> = &'a [u8];
+
+        fn key(&self) -> Pubkey {
+            self.key
+        }
+
+        fn is_signer(&self) -> bool {
+            self.is_signer
+        }
+
+        fn is_writable(&self) -> bool {
+            self.is_writable
+        }
+
+        fn owner(&self) -> Pubkey {
+            Pubkey::default()
+        }
+
+        fn executable(&self) -> bool {
+            false
+        }
+
+        fn try_borrow_data(&self) -> Result<Self::Data<'_>, ProgramError> {
+            Ok(&self.data)
+        }
+    }
+
+    #[test]
+    fn custom_account_type() {
+        let program_id = Pubkey::new_unique();
+        let extra_key = Pubkey::new_unique();
+        let metas = [ExtraAccountMeta::new_with_pubkey(&extra_key, false, true).unwrap()];
+
+        let mut buffer = vec![0; ExtraAccountMetaList::size_of(metas.len()).unwrap()];
+        ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &metas).unwrap();
+
+        let mut accounts = [
+            MockAccount {
+                key: Pubkey::new_unique(),
+                is_signer: true,
+                is_writable: false,
+                data: vec![],
+            },
+            MockAccount {
+                key: extra_key,
+                is_signer: false,
+                is_writable: true,
+                data: vec![],
+            },
+        ];
+        ExtraAccountMetaList::check_account_infos::<TestInstruction>(
+            &accounts,
+            &[],
+            &program_id,
+            &buffer,
+        )
+        .unwrap();
+
+        let mut cpi_instruction = Instruction::new_with_bytes(program_id, &[], vec![]);
+        let mut cpi_accounts = vec![];
+        ExtraAccountMetaList::add_to_cpi_instruction::<TestInstruction, _>(
+            &mut cpi_instruction,
+            &mut cpi_accounts,
+            &buffer,
+            &accounts,
+        )
+        .unwrap();
+        assert_eq!(
+            cpi_instruction.accounts,
+            [AccountMeta::new(extra_key, false)]
+        );
+        assert_eq!(cpi_accounts.len(), 1);
+        assert_eq!(cpi_accounts[0].key, extra_key);
+
+        accounts[1].is_writable = false;
+        assert_eq!(
+            ExtraAccountMetaList::check_account_infos::<TestInstruction>(
+                &accounts,
+                &[],
+                &program_id,
+                &buffer,
+            )
+            .unwrap_err(),
+            AccountResolutionError::IncorrectAccountWritable.into()
+        );
+    }
+}
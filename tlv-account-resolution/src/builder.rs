@@ -0,0 +1,403 @@
+//! Builder for lists of extra account metas
+//!
+//! Every account added through the builder has to be marked as writable or
+//! read-only explicitly, which avoids mixing up the positional signer and
+//! writable flags of the `ExtraAccountMeta` constructors:
+//!
+//! ```rust
+//! use {
+//!     solana_pubkey::Pubkey,
+//!     spl_discriminator::{ArrayDiscriminator, SplDiscriminate},
+//!     spl_tlv_account_resolution::{builder::ExtraAccountMetaListBuilder, seeds::Seed},
+//! };
+//!
+//! struct MyInstruction;
+//! impl SplDiscriminate for MyInstruction {
+//!     const SPL_DISCRIMINATOR: ArrayDiscriminator = ArrayDiscriminator::new([1; ArrayDiscriminator::LENGTH]);
+//! }
+//!
+//! let buffer = ExtraAccountMetaListBuilder::new()
+//!     .account(&Pubkey::new_unique())
+//!     .writable()
+//!     .pda(&[Seed::Literal { bytes: b"config".to_vec() }])
+//!     .readonly()
+//!     .pack::<MyInstruction>()
+//!     .unwrap();
+//! ```
+
+use {
+    crate::{
+        account::{validate_seed_configs, ExtraAccountMeta, ResolvedSeedConfig},
+        constraints::{validate_constraints, ExtraAccountMetaConstraints},
+        pubkey_data::PubkeyData,
+        seeds::Seed,
+        state::ExtraAccountMetaList,
+    },
+    solana_account_info::AccountInfo,
+    solana_program_error::ProgramError,
+    solana_pubkey::Pubkey,
+    spl_discriminator::SplDiscriminate,
+};
+
+/// Shape of the instruction the extra account metas are resolved for
+#[derive(Clone, Copy, Debug)]
+struct InstructionLayout {
+    data_len: usize,
+    num_accounts: usize,
+}
+
+/// Builder for a list of `ExtraAccountMeta`s, and their constraints
+///
+/// Each meta is validated as it is added, and the first error is returned
+/// when the list is built.
+#[derive(Clone, Debug, Default)]
+pub struct ExtraAccountMetaListBuilder {
+    metas: Vec<ExtraAccountMeta>,
+    constraints: Vec<ExtraAccountMetaConstraints>,
+    layout: Option<InstructionLayout>,
+    error: Option<ProgramError>,
+}
+
+impl ExtraAccountMetaListBuilder {
+    /// Create an empty builder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Validate the seeds and constraints of every added meta against the
+    /// shape of the instruction, see `validate_seed_configs` and
+    /// `validate_constraints`
+    pub fn with_instruction_layout(
+        mut self,
+        instruction_data_len: usize,
+        num_instruction_accounts: usize,
+    ) -> Self {
+        self.layout = Some(InstructionLayout {
+            data_len: instruction_data_len,
+            num_accounts: num_instruction_accounts,
+        });
+        self.validate();
+        self
+    }
+
+    /// Add an account with a fixed address
+    pub fn account(self, pubkey: &Pubkey) -> ExtraAccountMetaEntry {
+        self.entry(ResolvedSeedConfig::Pubkey(*pubkey))
+    }
+
+    /// Add a PDA of the program executing the instruction
+    pub fn pda(self, seeds: &[Seed]) -> ExtraAccountMetaEntry {
+        self.entry(ResolvedSeedConfig::Pda {
+            seeds: seeds.to_vec(),
+        })
+    }
+
+    /// Add a PDA of the program at `program_index` in the accounts list
+    pub fn external_pda(self, program_index: u8, seeds: &[Seed]) -> ExtraAccountMetaEntry {
+        self.entry(ResolvedSeedConfig::ExternalPda {
+            program_index,
+            seeds: seeds.to_vec(),
+        })
+    }
+
+    /// Add an account whose address is stored in the instruction data or in
+    /// the data of another account
+    pub fn pubkey_data(self, key_data: &PubkeyData) -> ExtraAccountMetaEntry {
+        self.entry(ResolvedSeedConfig::PubkeyData(key_data.clone()))
+    }
+
+    fn entry(self, config: ResolvedSeedConfig) -> ExtraAccountMetaEntry {
+        ExtraAccountMetaEntry {
+            list: self,
+            config,
+            is_signer: false,
+            constraints: ExtraAccountMetaConstraints::default(),
+        }
+    }
+
+    fn push(
+        mut self,
+        meta: Result<ExtraAccountMeta, ProgramError>,
+        constraints: ExtraAccountMetaConstraints,
+    ) -> Self {
+        match meta {
+            Ok(meta) => {
+                self.metas.push(meta);
+                self.constraints.push(constraints);
+                self.validate();
+            }
+            Err(err) => {
+                self.error.get_or_insert(err);
+            }
+        }
+        self
+    }
+
+    fn validate(&mut self) {
+        if self.error.is_some() {
+            return;
+        }
+        if let Some(layout) = self.layout {
+            if let Err(err) =
+                validate_seed_configs(&self.metas, layout.data_len, layout.num_accounts)
+                    .and_then(|_| validate_constraints(&self.constraints, layout.num_accounts))
+            {
+                self.error = Some(err.into());
+            }
+        }
+    }
+
+    /// Get the list of metas and the constraints on each of them, or the
+    /// first error encountered
+    #[allow(clippy::type_complexity)]
+    pub fn build(
+        self,
+    ) -> Result<(Vec<ExtraAccountMeta>, Vec<ExtraAccountMetaConstraints>), ProgramError> {
+        match self.error {
+            Some(err) => Err(err),
+            None => Ok((self.metas, self.constraints)),
+        }
+    }
+
+    /// Get the byte size required to hold the list, with its constraints
+    /// entry only if any meta has a constraint
+    pub fn size(&self) -> Result<usize, ProgramError> {
+        if self.is_unconstrained() {
+            ExtraAccountMetaList::size_of(self.metas.len())
+        } else {
+            ExtraAccountMetaList::size_of_with_constraints(self.metas.len())
+        }
+    }
+
+    /// Pack the list of metas for the given instruction into a new buffer,
+    /// sized to hold exactly this list
+    pub fn pack<T: SplDiscriminate>(self) -> Result<Vec<u8>, ProgramError> {
+        let mut buffer = vec![0; self.size()?];
+        self.init_data::<T>(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Initialize the list of metas for the given instruction in a
+    /// validation account
+    pub fn init<T: SplDiscriminate>(self, account_info: &AccountInfo) -> Result<(), ProgramError> {
+        self.init_data::<T>(&mut account_info.try_borrow_mut_data()?)
+    }
+
+    fn is_unconstrained(&self) -> bool {
+        self.constraints
+            .iter()
+            .all(ExtraAccountMetaConstraints::is_empty)
+    }
+
+    fn init_data<T: SplDiscriminate>(self, data: &mut [u8]) -> Result<(), ProgramError> {
+        let is_unconstrained = self.is_unconstrained();
+        let (metas, constraints) = self.build()?;
+        if is_unconstrained {
+            ExtraAccountMetaList::init::<T>(data, &metas)
+        } else {
+            ExtraAccountMetaList::init_with_constraints::<T>(data, &metas, &constraints)
+        }
+    }
+}
+
+/// Meta being added to an `ExtraAccountMetaListBuilder`
+///
+/// Finish it with `writable` or `readonly` to get back the list builder.
+#[derive(Clone, Debug)]
+#[must_use = "the meta is only added to the list by `writable` or `readonly`"]
+pub struct ExtraAccountMetaEntry {
+    list: ExtraAccountMetaListBuilder,
+    config: ResolvedSeedConfig,
+    is_signer: bool,
+    constraints: ExtraAccountMetaConstraints,
+}
+
+impl ExtraAccountMetaEntry {
+    /// Require the account to sign the instruction
+    pub fn signer(mut self) -> Self {
+        self.is_signer = true;
+        self
+    }
+
+    /// Allow the account to be omitted if it cannot be resolved, see
+    /// `ExtraAccountMetaConstraints::with_optional`
+    pub fn optional(mut self) -> Self {
+        self.constraints = self.constraints.with_optional(true);
+        self
+    }
+
+    /// Require the account to be executable, see
+    /// `ExtraAccountMetaConstraints::with_executable`
+    pub fn executable(mut self) -> Self {
+        self.constraints = self.constraints.with_executable(true);
+        self
+    }
+
+    /// Require the account to be owned by the program at `owner_index` in
+    /// the accounts list, see `ExtraAccountMetaConstraints::with_owner_index`
+    pub fn owned_by(mut self, owner_index: u8) -> Self {
+        self.constraints = self.constraints.with_owner_index(Some(owner_index));
+        self
+    }
+
+    /// Add the account as writable
+    pub fn writable(self) -> ExtraAccountMetaListBuilder {
+        self.finish(true)
+    }
+
+    /// Add the account as read-only
+    pub fn readonly(self) -> ExtraAccountMetaListBuilder {
+        self.finish(false)
+    }
+
+    fn finish(self, is_writable: bool) -> ExtraAccountMetaListBuilder {
+        let meta =
+            ExtraAccountMeta::new_with_seed_config(&self.config, self.is_signer, is_writable);
+        self.list.push(meta, self.constraints)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::error::AccountResolutionError, solana_instruction::AccountMeta,
+        spl_discriminator::ArrayDiscriminator, spl_type_length_value::state::TlvStateBorrowed,
+    };
+
+    pub struct TestInstruction;
+    impl SplDiscriminate for TestInstruction {
+        const SPL_DISCRIMINATOR: ArrayDiscriminator =
+            ArrayDiscriminator::new([1; ArrayDiscriminator::LENGTH]);
+    }
+
+    #[test]
+    fn build_extra_account_metas() {
+        let pubkey = Pubkey::new_unique();
+        let seeds = [Seed::AccountKey { index: 0 }];
+        let key_data = PubkeyData::InstructionData { index: 0 };
+
+        let (metas, constraints) = ExtraAccountMetaListBuilder::new()
+            .account(&pubkey)
+            .signer()
+            .readonly()
+            .pda(&seeds)
+            .writable()
+            .external_pda(1, &seeds)
+            .optional()
+            .readonly()
+            .pubkey_data(&key_data)
+            .writable()
+            .account(&pubkey)
+            .executable()
+            .owned_by(0)
+            .readonly()
+            .build()
+            .unwrap();
+        assert_eq!(
+            metas,
+            [
+                ExtraAccountMeta::new_with_pubkey(&pubkey, true, false).unwrap(),
+                ExtraAccountMeta::new_with_seeds(&seeds, false, true).unwrap(),
+                ExtraAccountMeta::new_external_pda_with_seeds(1, &seeds, false, false).unwrap(),
+                ExtraAccountMeta::new_with_pubkey_data(&key_data, false, true).unwrap(),
+                ExtraAccountMeta::new_with_pubkey(&pubkey, false, false).unwrap(),
+            ]
+        );
+        let optional = ExtraAccountMetaConstraints::default().with_optional(true);
+        assert_eq!(
+            constraints,
+            [
+                ExtraAccountMetaConstraints::default(),
+                ExtraAccountMetaConstraints::default(),
+                optional,
+                ExtraAccountMetaConstraints::default(),
+                ExtraAccountMetaConstraints::default()
+                    .with_executable(true)
+                    .with_owner_index(Some(0)),
+            ]
+        );
+
+        let buffer = ExtraAccountMetaListBuilder::new()
+            .account(&pubkey)
+            .writable()
+            .pack::<TestInstruction>()
+            .unwrap();
+        assert_eq!(buffer.len(), ExtraAccountMetaList::size_of(1).unwrap());
+        let state = TlvStateBorrowed::unpack(&buffer).unwrap();
+        let unpacked =
+            ExtraAccountMetaList::unpack_with_tlv_state::<TestInstruction>(&state).unwrap();
+        assert_eq!(&*unpacked, &[AccountMeta::new(pubkey, false).into()]);
+
+        // The constraints are only stored if any meta has one
+        let buffer = ExtraAccountMetaListBuilder::new()
+            .account(&pubkey)
+            .optional()
+            .writable()
+            .pack::<TestInstruction>()
+            .unwrap();
+        assert_eq!(
+            buffer.len(),
+            ExtraAccountMetaList::size_of_with_constraints(1).unwrap()
+        );
+        assert_eq!(
+            ExtraAccountMetaList::constraints::<TestInstruction>(&buffer).unwrap(),
+            [optional]
+        );
+    }
+
+    #[test]
+    fn build_reports_first_error() {
+        // Seeds too large to fit in the address config
+        let large_seeds = [Seed::Literal { bytes: vec![1; 32] }];
+        assert_eq!(
+            ExtraAccountMetaListBuilder::new()
+                .pda(&large_seeds)
+                .readonly()
+                .account(&Pubkey::new_unique())
+                .readonly()
+                .build()
+                .unwrap_err(),
+            AccountResolutionError::SeedConfigsTooLarge.into()
+        );
+
+        // Seeds referencing an account that doesn't exist yet
+        let builder = ExtraAccountMetaListBuilder::new()
+            .with_instruction_layout(8, 2)
+            .pda(&[Seed::AccountKey { index: 2 }])
+            .readonly();
+        assert_eq!(
+            builder.clone().build().unwrap_err(),
+            AccountResolutionError::AccountNotFound.into()
+        );
+        assert_eq!(
+            builder
+                .account(&Pubkey::new_unique())
+                .writable()
+                .pack::<TestInstruction>()
+                .unwrap_err(),
+            AccountResolutionError::AccountNotFound.into()
+        );
+
+        // The same seeds are valid for a later account
+        ExtraAccountMetaListBuilder::new()
+            .with_instruction_layout(8, 2)
+            .account(&Pubkey::new_unique())
+            .readonly()
+            .pda(&[Seed::AccountKey { index: 2 }])
+            .readonly()
+            .build()
+            .unwrap();
+
+        // Required owners are validated too
+        assert_eq!(
+            ExtraAccountMetaListBuilder::new()
+                .with_instruction_layout(8, 2)
+                .account(&Pubkey::new_unique())
+                .owned_by(2)
+                .readonly()
+                .build()
+                .unwrap_err(),
+            AccountResolutionError::AccountNotFound.into()
+        );
+    }
+}
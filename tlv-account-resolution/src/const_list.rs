@@ -0,0 +1,300 @@
+//! Fixed-size list of extra account metas
+//!
+//! Programs requiring the same extra accounts for a single instruction can
+//! declare exactly `N` metas at compile time. The list is stored like an
+//! `ExtraAccountMetaList` with a single entry, so any client can read it, but
+//! it is unpacked without searching the TLV entries or allocating.
+//! Resolution is the same as for `ExtraAccountMetaList`.
+
+use {
+    crate::{
+        account::ExtraAccountMeta,
+        account_info::AccountInfoView,
+        state::{AccountFetcher, ExtraAccountMetaList},
+    },
+    bytemuck::{Pod, Zeroable},
+    solana_instruction::Instruction,
+    solana_program_error::ProgramError,
+    solana_pubkey::Pubkey,
+    spl_discriminator::{ArrayDiscriminator, SplDiscriminate},
+    spl_pod::primitives::PodU32,
+    spl_type_length_value::{error::TlvError, length::Length},
+    std::mem::size_of,
+};
+
+/// TLV entry header and list length stored before the metas, as in an
+/// `ExtraAccountMetaList`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+struct Header {
+    discriminator: ArrayDiscriminator,
+    length: Length,
+    num_metas: PodU32,
+}
+
+/// List of exactly `N` extra account metas
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConstExtraAccountMetaList<const N: usize> {
+    metas: [ExtraAccountMeta; N],
+}
+
+impl<const N: usize> ConstExtraAccountMetaList<N> {
+    /// Size of the metas, along with the list length
+    const VALUE_LEN: usize = size_of::<PodU32>() + N * size_of::<ExtraAccountMeta>();
+
+    /// Size of the packed list, in bytes
+    pub const LEN: usize = size_of::<ArrayDiscriminator>() + size_of::<Length>() + Self::VALUE_LEN;
+
+    /// Create a list from its metas
+    pub const fn new(metas: [ExtraAccountMeta; N]) -> Self {
+        Self { metas }
+    }
+
+    /// Get the metas of the list
+    pub fn metas(&self) -> &[ExtraAccountMeta; N] {
+        &self.metas
+    }
+
+    /// Header of the list for the given instruction
+    fn header<T: SplDiscriminate>() -> Result<Header, ProgramError> {
+        Ok(Header {
+            discriminator: T::SPL_DISCRIMINATOR,
+            length: Length::try_from(Self::VALUE_LEN)?,
+            num_metas: PodU32::try_from(N).map_err(|_| ProgramError::InvalidArgument)?,
+        })
+    }
+
+    /// Pack the list for the given instruction at the start of the given data
+    pub fn pack<T: SplDiscriminate>(&self, data: &mut [u8]) -> Result<(), ProgramError> {
+        let dst = data
+            .get_mut(..Self::LEN)
+            .ok_or(ProgramError::AccountDataTooSmall)?;
+        let (header, metas) = dst.split_at_mut(size_of::<Header>());
+        header.copy_from_slice(bytemuck::bytes_of(&Self::header::<T>()?));
+        metas.copy_from_slice(bytemuck::cast_slice(&self.metas));
+        Ok(())
+    }
+
+    /// Unpack the metas for the given instruction stored at the start of the
+    /// given data, checking the header before them
+    pub fn unpack<T: SplDiscriminate>(data: &[u8]) -> Result<&[ExtraAccountMeta; N], ProgramError> {
+        let bytes = data
+            .get(..Self::LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let (header, metas) = bytes.split_at(size_of::<Header>());
+        let header = bytemuck::try_from_bytes::<Header>(header)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        if header.discriminator != T::SPL_DISCRIMINATOR {
+            return Err(TlvError::TypeNotFound.into());
+        }
+        if *header != Self::header::<T>()? {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        bytemuck::try_cast_slice::<u8, ExtraAccountMeta>(metas)
+            .ok()
+            .and_then(|metas| metas.try_into().ok())
+            .ok_or(ProgramError::InvalidAccountData)
+    }
+
+    /// Checks provided account infos against the list stored in the
+    /// validation data, see `ExtraAccountMetaList::check_account_infos`
+    pub fn check_account_infos<T: SplDiscriminate>(
+        account_infos: &[impl AccountInfoView],
+        instruction_data: &[u8],
+        program_id: &Pubkey,
+        data: &[u8],
+    ) -> Result<(), ProgramError> {
+        ExtraAccountMetaList::check_account_infos_with_metas(
+            Self::unpack::<T>(data)?,
+            &[],
+            account_infos,
+            instruction_data,
+            program_id,
+            &[],
+        )
+    }
+
+    /// Add the additional account metas to an existing instruction, see
+    /// `ExtraAccountMetaList::add_to_instruction_with_fetcher`
+    pub async fn add_to_instruction_with_fetcher<T: SplDiscriminate, F: AccountFetcher>(
+        instruction: &mut Instruction,
+        fetcher: &F,
+        data: &[u8],
+    ) -> Result<Vec<usize>, ProgramError> {
+        ExtraAccountMetaList::add_to_instruction_with_metas(
+            instruction,
+            fetcher,
+            Self::unpack::<T>(data)?,
+            &[],
+        )
+        .await
+        .map_err(ProgramError::from)
+    }
+
+    /// Add the additional account metas and account infos for a CPI, see
+    /// `ExtraAccountMetaList::add_to_cpi_instruction`
+    pub fn add_to_cpi_instruction<T: SplDiscriminate, A: AccountInfoView + Clone>(
+        cpi_instruction: &mut Instruction,
+        cpi_account_infos: &mut Vec<A>,
+        data: &[u8],
+        account_infos: &[A],
+    ) -> Result<(), ProgramError> {
+        ExtraAccountMetaList::add_to_cpi_instruction_with_metas(
+            cpi_instruction,
+            cpi_account_infos,
+            Self::unpack::<T>(data)?,
+            &[],
+            account_infos,
+            &[],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::error::AccountResolutionError, crate::seeds::Seed,
+        solana_account_info::AccountInfo, solana_instruction::AccountMeta,
+    };
+
+    pub struct TestInstruction;
+    impl SplDiscriminate for TestInstruction {
+        const SPL_DISCRIMINATOR: ArrayDiscriminator =
+            ArrayDiscriminator::new([1; ArrayDiscriminator::LENGTH]);
+    }
+
+    pub struct TestOtherInstruction;
+    impl SplDiscriminate for TestOtherInstruction {
+        const SPL_DISCRIMINATOR: ArrayDiscriminator =
+            ArrayDiscriminator::new([2; ArrayDiscriminator::LENGTH]);
+    }
+
+    const EXTRA_KEY: Pubkey = Pubkey::new_from_array([7; 32]);
+    const EXTRA_METAS: ConstExtraAccountMetaList<1> =
+        ConstExtraAccountMetaList::new([ExtraAccountMeta::new_with_pubkey_const(
+            &EXTRA_KEY, false, true,
+        )]);
+
+    #[tokio::test]
+    async fn const_extra_account_meta_list() {
+        let program_id = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let seeds = [Seed::AccountKey { index: 1 }];
+        let list = ConstExtraAccountMetaList::new([
+            EXTRA_METAS.metas()[0],
+            ExtraAccountMeta::new_with_seeds(&seeds, false, false).unwrap(),
+        ]);
+        assert_eq!(
+            ConstExtraAccountMetaList::<2>::LEN,
+            ExtraAccountMetaList::size_of(2).unwrap()
+        );
+
+        // Fails if the data is too small
+        let mut data = vec![0; ConstExtraAccountMetaList::<2>::LEN - 1];
+        assert_eq!(
+            list.pack::<TestInstruction>(&mut data).unwrap_err(),
+            ProgramError::AccountDataTooSmall
+        );
+        assert_eq!(
+            ConstExtraAccountMetaList::<2>::unpack::<TestInstruction>(&data).unwrap_err(),
+            ProgramError::InvalidAccountData
+        );
+
+        let mut data = vec![0; ConstExtraAccountMetaList::<2>::LEN];
+        list.pack::<TestInstruction>(&mut data).unwrap();
+        assert_eq!(
+            ConstExtraAccountMetaList::<2>::unpack::<TestInstruction>(&data).unwrap(),
+            list.metas()
+        );
+
+        // The data is an `ExtraAccountMetaList` with a single entry
+        let mut list_data = vec![0; ExtraAccountMetaList::size_of(2).unwrap()];
+        ExtraAccountMetaList::init::<TestInstruction>(&mut list_data, list.metas()).unwrap();
+        assert_eq!(data, list_data);
+
+        // The header must match the instruction and the number of metas
+        assert_eq!(
+            ConstExtraAccountMetaList::<2>::unpack::<TestOtherInstruction>(&data).unwrap_err(),
+            TlvError::TypeNotFound.into()
+        );
+        assert_eq!(
+            ConstExtraAccountMetaList::<1>::unpack::<TestInstruction>(&data).unwrap_err(),
+            ProgramError::InvalidAccountData
+        );
+        let mut corrupted_data = data.clone();
+        corrupted_data[ArrayDiscriminator::LENGTH + size_of::<Length>()] = 3;
+        assert_eq!(
+            ConstExtraAccountMetaList::<2>::unpack::<TestInstruction>(&corrupted_data).unwrap_err(),
+            ProgramError::InvalidAccountData
+        );
+
+        // Resolves like a TLV list
+        let pda = Pubkey::find_program_address(&[EXTRA_KEY.as_ref()], &program_id).0;
+        let mut instruction =
+            Instruction::new_with_bytes(program_id, &[], vec![AccountMeta::new(owner, true)]);
+        ConstExtraAccountMetaList::<2>::add_to_instruction_with_fetcher::<TestInstruction, _>(
+            &mut instruction,
+            &|_| async { Ok(None) },
+            &data,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            instruction.accounts,
+            [
+                AccountMeta::new(owner, true),
+                AccountMeta::new(EXTRA_KEY, false),
+                AccountMeta::new_readonly(pda, false),
+            ]
+        );
+
+        let mut lamports = [0; 3];
+        let mut datas = [[0u8; 0]; 3];
+        let keys = [owner, EXTRA_KEY, pda];
+        let account_infos = keys
+            .iter()
+            .zip(instruction.accounts.iter())
+            .zip(lamports.iter_mut().zip(datas.iter_mut()))
+            .map(|((key, meta), (lamports, data))| {
+                AccountInfo::new(
+                    key,
+                    meta.is_signer,
+                    meta.is_writable,
+                    lamports,
+                    data,
+                    &owner,
+                    false,
+                )
+            })
+            .collect::<Vec<_>>();
+        ConstExtraAccountMetaList::<2>::check_account_infos::<TestInstruction>(
+            &account_infos,
+            &[],
+            &program_id,
+            &data,
+        )
+        .unwrap();
+        assert_eq!(
+            ConstExtraAccountMetaList::<2>::check_account_infos::<TestInstruction>(
+                &account_infos[..2],
+                &[],
+                &program_id,
+                &data
+            )
+            .unwrap_err(),
+            AccountResolutionError::IncorrectAccount.into()
+        );
+
+        let mut cpi_instruction = Instruction::new_with_bytes(program_id, &[], vec![]);
+        let mut cpi_account_infos = vec![account_infos[0].clone()];
+        cpi_instruction.accounts.push(AccountMeta::new(owner, true));
+        ConstExtraAccountMetaList::<2>::add_to_cpi_instruction::<TestInstruction, _>(
+            &mut cpi_instruction,
+            &mut cpi_account_infos,
+            &data,
+            &account_infos,
+        )
+        .unwrap();
+        assert_eq!(cpi_instruction.accounts, instruction.accounts);
+    }
+}
@@ -0,0 +1,374 @@
+//! Constraints on extra accounts, stored next to their metas
+//!
+//! Existing programs and clients read `ExtraAccountMeta` with a fixed layout,
+//! treating any nonzero `is_signer` or `is_writable` byte as `true`, so these
+//! bytes are always 0 or 1. Other constraints on the extra accounts of an
+//! instruction live in a separate TLV entry, holding one
+//! `ExtraAccountMetaConstraints` per extra account meta, in the same order.
+//!
+//! The discriminator of the entry is derived from the one of the extra account
+//! metas, see `Constraints`, and its data starts with a version byte, so that
+//! the format can evolve. Readers unaware of the entry ignore it, and resolve
+//! every account as required.
+
+#[cfg(feature = "serde-traits")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use {
+    crate::account::{check_account_index, InvalidSeedConfig},
+    bytemuck::{Pod, Zeroable},
+    solana_program_error::ProgramError,
+    spl_discriminator::{ArrayDiscriminator, SplDiscriminate},
+    std::{marker::PhantomData, mem::size_of},
+};
+
+/// Version of the constraints entry written by this library
+pub const CONSTRAINTS_VERSION: u8 = 1;
+
+/// Flag marking an account as optional
+const IS_OPTIONAL_FLAG: u8 = 1;
+/// Flag requiring the account to be executable
+const IS_EXECUTABLE_FLAG: u8 = 1 << 1;
+/// Flag requiring the account to be owned by the program at `owner_index`
+const HAS_OWNER_FLAG: u8 = 1 << 2;
+/// Flags understood by this version of the library
+const KNOWN_FLAGS: u8 = IS_OPTIONAL_FLAG | IS_EXECUTABLE_FLAG | HAS_OWNER_FLAG;
+
+/// `Pod` type for the constraints on one extra account
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Pod, Zeroable)]
+pub struct ExtraAccountMetaConstraints {
+    flags: u8,
+    owner_index: u8,
+}
+
+impl ExtraAccountMetaConstraints {
+    /// Whether the account is optional
+    ///
+    /// An optional account that cannot be resolved is omitted: the program id
+    /// of the instruction is provided in its place, as a read-only placeholder
+    /// keeping the indices of the following accounts stable.
+    pub const fn is_optional(&self) -> bool {
+        self.flags & IS_OPTIONAL_FLAG != 0
+    }
+
+    /// Mark the account as optional, or as required
+    pub const fn with_optional(mut self, is_optional: bool) -> Self {
+        if is_optional {
+            self.flags |= IS_OPTIONAL_FLAG;
+        } else {
+            self.flags &= !IS_OPTIONAL_FLAG;
+        }
+        self
+    }
+
+    /// Whether the account must be executable
+    pub const fn is_executable(&self) -> bool {
+        self.flags & IS_EXECUTABLE_FLAG != 0
+    }
+
+    /// Require the account to be executable, or not
+    ///
+    /// Only enforced on-chain, when checking or adding the account infos.
+    pub const fn with_executable(mut self, is_executable: bool) -> Self {
+        if is_executable {
+            self.flags |= IS_EXECUTABLE_FLAG;
+        } else {
+            self.flags &= !IS_EXECUTABLE_FLAG;
+        }
+        self
+    }
+
+    /// Index, in the accounts list, of the program that must own the account
+    pub const fn owner_index(&self) -> Option<u8> {
+        if self.flags & HAS_OWNER_FLAG != 0 {
+            Some(self.owner_index)
+        } else {
+            None
+        }
+    }
+
+    /// Require the account to be owned by the program at the given index in
+    /// the accounts list, or remove the requirement
+    ///
+    /// Only enforced on-chain, when checking or adding the account infos.
+    pub const fn with_owner_index(mut self, owner_index: Option<u8>) -> Self {
+        match owner_index {
+            Some(index) => {
+                self.flags |= HAS_OWNER_FLAG;
+                self.owner_index = index;
+            }
+            None => {
+                self.flags &= !HAS_OWNER_FLAG;
+                self.owner_index = 0;
+            }
+        }
+        self
+    }
+
+    /// Whether the account has no constraint
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Human-readable representation of `ExtraAccountMetaConstraints`, used for
+/// serde
+#[cfg(feature = "serde-traits")]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExtraAccountMetaConstraintsConfig {
+    #[serde(default, skip_serializing_if = "is_false")]
+    is_optional: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    is_executable: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    owner_index: Option<u8>,
+}
+
+#[cfg(feature = "serde-traits")]
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+#[cfg(feature = "serde-traits")]
+impl Serialize for ExtraAccountMetaConstraints {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        ExtraAccountMetaConstraintsConfig {
+            is_optional: self.is_optional(),
+            is_executable: self.is_executable(),
+            owner_index: self.owner_index(),
+        }
+        .serialize(s)
+    }
+}
+
+#[cfg(feature = "serde-traits")]
+impl<'de> Deserialize<'de> for ExtraAccountMetaConstraints {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let config = ExtraAccountMetaConstraintsConfig::deserialize(d)?;
+        Ok(Self::default()
+            .with_optional(config.is_optional)
+            .with_executable(config.is_executable)
+            .with_owner_index(config.owner_index))
+    }
+}
+
+/// Validate the constraints on extra account metas against the shape of the
+/// instruction they are resolved for, like `validate_seed_configs`
+///
+/// Checks that every required owner refers to one of the
+/// `num_instruction_accounts` instruction accounts or to a previous extra
+/// account.
+pub fn validate_constraints(
+    extra_account_meta_constraints: &[ExtraAccountMetaConstraints],
+    num_instruction_accounts: usize,
+) -> Result<(), InvalidSeedConfig> {
+    for (meta_index, constraints) in extra_account_meta_constraints.iter().enumerate() {
+        if let Some(owner_index) = constraints.owner_index() {
+            check_account_index(owner_index, num_instruction_accounts + meta_index).map_err(
+                |error| InvalidSeedConfig {
+                    meta_index,
+                    seed_index: None,
+                    error,
+                },
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Discriminator type for the constraints on the extra account metas stored
+/// with the discriminator of `T`
+///
+/// `T` may be an instruction or a `Versioned` entry.
+pub struct Constraints<T>(PhantomData<T>);
+
+impl<T: SplDiscriminate> SplDiscriminate for Constraints<T> {
+    const SPL_DISCRIMINATOR: ArrayDiscriminator = constraints_discriminator(T::SPL_DISCRIMINATOR);
+}
+
+/// Get the discriminator of the constraints on the extra account metas stored
+/// with the given discriminator
+pub const fn constraints_discriminator(discriminator: ArrayDiscriminator) -> ArrayDiscriminator {
+    derive_discriminator("constraints", discriminator.as_slice())
+}
+
+/// Longest hash input of a derived discriminator
+const MAX_HASH_INPUT_LEN: usize = 128;
+
+/// Derive a discriminator managed by the library, hashing the given label and
+/// the hex encoding of the given bytes
+///
+/// The hash input is `spl-tlv-account-resolution:<label>:<hex bytes>`, so the
+/// derived discriminators can't collide with each other, nor with any
+/// discriminator derived by hashing an instruction name.
+pub(crate) const fn derive_discriminator(label: &str, bytes: &[u8]) -> ArrayDiscriminator {
+    const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+    const fn append(input: &mut [u8; MAX_HASH_INPUT_LEN], len: usize, bytes: &[u8]) -> usize {
+        let mut i = 0;
+        while i < bytes.len() {
+            input[len + i] = bytes[i];
+            i += 1;
+        }
+        len + bytes.len()
+    }
+
+    let mut input = [0; MAX_HASH_INPUT_LEN];
+    let mut len = append(&mut input, 0, b"spl-tlv-account-resolution:");
+    len = append(&mut input, len, label.as_bytes());
+    len = append(&mut input, len, b":");
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = [
+            HEX_DIGITS[(bytes[i] >> 4) as usize],
+            HEX_DIGITS[(bytes[i] & 0xf) as usize],
+        ];
+        len = append(&mut input, len, &hex);
+        i += 1;
+    }
+    match core::str::from_utf8(input.split_at(len).0) {
+        Ok(hash_input) => ArrayDiscriminator::from_hash_input(hash_input),
+        Err(_) => panic!("hash input is not valid UTF-8"),
+    }
+}
+
+/// Get the length of the data of a constraints entry for `num_items` metas
+pub(crate) fn packed_len(num_items: usize) -> usize {
+    1 + num_items * size_of::<ExtraAccountMetaConstraints>()
+}
+
+/// Pack the constraints into the data of a constraints entry, of length
+/// `packed_len(constraints.len())`
+pub(crate) fn pack(data: &mut [u8], constraints: &[ExtraAccountMetaConstraints]) {
+    let (version, dst) = data.split_first_mut().unwrap();
+    *version = CONSTRAINTS_VERSION;
+    dst.copy_from_slice(bytemuck::cast_slice(constraints));
+}
+
+/// Unpack the data of a constraints entry for a list of `num_items` metas
+///
+/// Fails if the entry has another version, doesn't hold exactly one item per
+/// meta, uses flags unknown to this version of the library, or stores an owner
+/// index without requiring an owner.
+pub(crate) fn unpack(
+    data: &[u8],
+    num_items: usize,
+) -> Result<&[ExtraAccountMetaConstraints], ProgramError> {
+    let (version, bytes) = data.split_first().ok_or(ProgramError::InvalidAccountData)?;
+    if *version != CONSTRAINTS_VERSION {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let constraints = bytemuck::try_cast_slice::<u8, ExtraAccountMetaConstraints>(bytes)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    if constraints.len() != num_items
+        || constraints.iter().any(|constraints| {
+            constraints.flags & !KNOWN_FLAGS != 0
+                || (constraints.owner_index().is_none() && constraints.owner_index != 0)
+        })
+    {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(constraints)
+}
+
+/// Get the constraints on the `index`-th meta of a list, which has none if
+/// no constraints are stored for the list
+pub(crate) fn get(
+    constraints: &[ExtraAccountMetaConstraints],
+    index: usize,
+) -> ExtraAccountMetaConstraints {
+    constraints.get(index).copied().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::error::AccountResolutionError};
+
+    #[test]
+    fn derived_discriminators() {
+        let discriminator = ArrayDiscriminator::new([1; ArrayDiscriminator::LENGTH]);
+        assert_eq!(
+            constraints_discriminator(discriminator),
+            ArrayDiscriminator::new_with_hash_input(
+                "spl-tlv-account-resolution:constraints:0101010101010101"
+            )
+        );
+        // Discriminators differing in their last byte don't collide
+        let other = ArrayDiscriminator::new([1, 1, 1, 1, 1, 1, 1, 2]);
+        assert_ne!(
+            constraints_discriminator(discriminator),
+            constraints_discriminator(other)
+        );
+        assert_ne!(constraints_discriminator(discriminator), discriminator);
+    }
+
+    #[test]
+    fn pack_and_unpack() {
+        let constraints = [
+            ExtraAccountMetaConstraints::default().with_optional(true),
+            ExtraAccountMetaConstraints::default()
+                .with_executable(true)
+                .with_owner_index(Some(u8::MAX)),
+            ExtraAccountMetaConstraints::default(),
+        ];
+        assert!(constraints[0].is_optional() && !constraints[0].is_executable());
+        assert_eq!(constraints[0].owner_index(), None);
+        assert!(!constraints[1].is_optional() && constraints[1].is_executable());
+        assert_eq!(constraints[1].owner_index(), Some(u8::MAX));
+        assert!(!constraints[0].is_empty() && constraints[2].is_empty());
+        assert!(constraints[0].with_optional(false).is_empty());
+        assert!(constraints[1]
+            .with_executable(false)
+            .with_owner_index(None)
+            .is_empty());
+
+        let mut data = vec![0; packed_len(constraints.len())];
+        pack(&mut data, &constraints);
+        assert_eq!(
+            data,
+            [
+                CONSTRAINTS_VERSION,
+                IS_OPTIONAL_FLAG,
+                0,
+                IS_EXECUTABLE_FLAG | HAS_OWNER_FLAG,
+                u8::MAX,
+                0,
+                0
+            ]
+        );
+        assert_eq!(unpack(&data, 3).unwrap(), constraints);
+
+        // One item per meta
+        assert_eq!(unpack(&data, 2), Err(ProgramError::InvalidAccountData));
+        // Unknown version
+        data[0] = CONSTRAINTS_VERSION + 1;
+        assert_eq!(unpack(&data, 3), Err(ProgramError::InvalidAccountData));
+        data[0] = CONSTRAINTS_VERSION;
+        // Owner index without an owner requirement
+        data[6] = 1;
+        assert_eq!(unpack(&data, 3), Err(ProgramError::InvalidAccountData));
+        // Unknown flags
+        data[6] = 0;
+        data[5] = 1 << 7;
+        assert_eq!(unpack(&data, 3), Err(ProgramError::InvalidAccountData));
+        assert_eq!(unpack(&[], 0), Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn validate_owner_indices() {
+        let owned_by = |index| ExtraAccountMetaConstraints::default().with_owner_index(Some(index));
+        let constraints = [ExtraAccountMetaConstraints::default(), owned_by(2)];
+        // The owner may be any instruction account, or a previous extra account
+        validate_constraints(&constraints, 2).unwrap();
+        validate_constraints(&[owned_by(1), owned_by(2)], 2).unwrap();
+        assert_eq!(
+            validate_constraints(&[owned_by(2)], 2).unwrap_err(),
+            InvalidSeedConfig {
+                meta_index: 0,
+                seed_index: None,
+                error: AccountResolutionError::AccountNotFound.into(),
+            }
+        );
+    }
+}
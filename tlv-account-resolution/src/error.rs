@@ -78,6 +78,28 @@ pub enum AccountResolutionError {
     /// Tried to pack an invalid pubkey data configuration
     #[error("Tried to pack an invalid pubkey data configuration")]
     InvalidPubkeyDataConfig,
+    /// Provided account has an incorrect signer privilege
+    #[error("Provided account has an incorrect signer privilege")]
+    IncorrectAccountSigner,
+    /// Provided account has an incorrect writable privilege
+    #[error("Provided account has an incorrect writable privilege")]
+    IncorrectAccountWritable,
+    /// Provided bump seed does not derive a valid program address
+    #[error("Provided bump seed does not derive a valid program address")]
+    InvalidPdaBump,
+    /// Provided account is not owned by the expected program
+    #[error("Provided account is not owned by the expected program")]
+    IncorrectAccountOwner,
+    /// Provided account is not executable
+    #[error("Provided account is not executable")]
+    AccountNotExecutable,
+    /// Constraints are stored for the instruction and must be updated along
+    /// with the extra account metas
+    #[error(
+        "Constraints are stored for the instruction and must be updated along with the extra \
+         account metas"
+    )]
+    ConstraintsNotUpdated,
 }
 
 impl From<AccountResolutionError> for ProgramError {
@@ -149,6 +171,24 @@ impl ToStr for AccountResolutionError {
             AccountResolutionError::InvalidPubkeyDataConfig => {
                 "Tried to pack an invalid pubkey data configuration"
             }
+            AccountResolutionError::IncorrectAccountSigner => {
+                "Provided account has an incorrect signer privilege"
+            }
+            AccountResolutionError::IncorrectAccountWritable => {
+                "Provided account has an incorrect writable privilege"
+            }
+            AccountResolutionError::InvalidPdaBump => {
+                "Provided bump seed does not derive a valid program address"
+            }
+            AccountResolutionError::IncorrectAccountOwner => {
+                "Provided account is not owned by the expected program"
+            }
+            AccountResolutionError::AccountNotExecutable => {
+                "Provided account is not executable"
+            }
+            AccountResolutionError::ConstraintsNotUpdated => {
+                "Constraints are stored for the instruction and must be updated along with the extra account metas"
+            }
         }
     }
 }
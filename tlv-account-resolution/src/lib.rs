@@ -8,10 +8,16 @@
 #![cfg_attr(not(test), forbid(unsafe_code))]
 
 pub mod account;
+pub mod account_info;
+pub mod builder;
+pub mod const_list;
+pub mod constraints;
 pub mod error;
 pub mod pubkey_data;
 pub mod seeds;
 pub mod state;
+pub mod transfer_hook;
+pub mod versioned;
 
 // Export current sdk types for downstream users building with a different sdk
 // version
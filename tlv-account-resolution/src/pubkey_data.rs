@@ -3,10 +3,22 @@
 //!
 //! This can be either account data from some account in the list of accounts
 //! or from the instruction data itself.
+//!
+//! A pubkey stored in account data is read as the 32 bytes starting at the
+//! configured data index, with no length prefix or other encoding. The
+//! configuration itself is packed as a discriminator byte followed by the
+//! indices, as documented on each `PubkeyData` variant.
+//! `resolve_pubkey_from_account_data` can be used to follow this indirection
+//! outside of an extra account meta list.
 
 #[cfg(feature = "serde-traits")]
 use serde::{Deserialize, Serialize};
-use {crate::error::AccountResolutionError, solana_program_error::ProgramError};
+use {
+    crate::{account_info::AccountInfoView, error::AccountResolutionError},
+    core::fmt,
+    solana_program_error::ProgramError,
+    solana_pubkey::{Pubkey, PUBKEY_BYTES},
+};
 
 /// Enum to describe a required key stored in some data.
 #[derive(Clone, Debug, PartialEq)]
@@ -117,9 +129,66 @@ impl PubkeyData {
     }
 }
 
+/// Read the pubkey stored in `data` starting at `index`, if the data is long
+/// enough.
+pub(crate) fn read_pubkey(data: &[u8], index: u8) -> Option<Pubkey> {
+    let start = usize::from(index);
+    data.get(start..start + PUBKEY_BYTES)
+        .map(|bytes| Pubkey::new_from_array(bytes.try_into().unwrap()))
+}
+
+/// Resolve a pubkey stored in the data of one of the `accounts`, as
+/// configured by a `PubkeyData::AccountData`.
+///
+/// The pubkey is read from the 32 bytes of the account data starting at
+/// `data_index`. Any other configuration fails, since it doesn't point to
+/// account data.
+pub fn resolve_pubkey_from_account_data(
+    accounts: &[impl AccountInfoView],
+    config: &PubkeyData,
+) -> Result<Pubkey, ProgramError> {
+    let PubkeyData::AccountData {
+        account_index,
+        data_index,
+    } = config
+    else {
+        return Err(AccountResolutionError::InvalidPubkeyDataConfig.into());
+    };
+    let account = accounts
+        .get(usize::from(*account_index))
+        .ok_or::<ProgramError>(AccountResolutionError::AccountNotFound.into())?;
+    let account_data = account.try_borrow_data()?;
+    read_pubkey(&account_data, *data_index)
+        .ok_or(AccountResolutionError::AccountDataTooSmall.into())
+}
+
+impl fmt::Display for PubkeyData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Uninitialized => write!(f, "uninitialized"),
+            Self::InstructionData { index } => write!(
+                f,
+                "pubkey in instruction data [{}..{}]",
+                index,
+                usize::from(*index) + PUBKEY_BYTES
+            ),
+            Self::AccountData {
+                account_index,
+                data_index,
+            } => write!(
+                f,
+                "pubkey in data of account {} [{}..{}]",
+                account_index,
+                data_index,
+                usize::from(*data_index) + PUBKEY_BYTES
+            ),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use {super::*, solana_account_info::AccountInfo};
 
     #[test]
     fn test_pack() {
@@ -182,4 +251,58 @@ mod tests {
             data_index: 0,
         });
     }
+
+    #[test]
+    fn test_resolve_pubkey_from_account_data() {
+        let owner = Pubkey::new_unique();
+        let stored_key = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = [[7u8; 4].as_slice(), stored_key.as_ref()].concat();
+        let accounts = [AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+        )];
+
+        let config = PubkeyData::AccountData {
+            account_index: 0,
+            data_index: 4,
+        };
+        assert_eq!(
+            resolve_pubkey_from_account_data(&accounts, &config).unwrap(),
+            stored_key
+        );
+
+        // Should fail if the pubkey runs past the end of the data.
+        let config = PubkeyData::AccountData {
+            account_index: 0,
+            data_index: 5,
+        };
+        assert_eq!(
+            resolve_pubkey_from_account_data(&accounts, &config).unwrap_err(),
+            AccountResolutionError::AccountDataTooSmall.into(),
+        );
+
+        // Should fail if the account isn't provided.
+        let config = PubkeyData::AccountData {
+            account_index: 1,
+            data_index: 0,
+        };
+        assert_eq!(
+            resolve_pubkey_from_account_data(&accounts, &config).unwrap_err(),
+            AccountResolutionError::AccountNotFound.into(),
+        );
+
+        // Should fail for configurations that don't read account data.
+        let config = PubkeyData::InstructionData { index: 0 };
+        assert_eq!(
+            resolve_pubkey_from_account_data(&accounts, &config).unwrap_err(),
+            AccountResolutionError::InvalidPubkeyDataConfig.into(),
+        );
+    }
 }
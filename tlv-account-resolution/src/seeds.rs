@@ -23,13 +23,131 @@
 //!         * 1 - Index of account in accounts list
 //!         * 1 - Start index of account data
 //!         * 1 - Length of account data starting at index
+//!     * `Seed::ProgramId`: `1`
+//!         * 1 - Discriminator
+//!     * `Seed::KnownAccount`: `1 + 1 = 2`
+//!         * 1 - Discriminator
+//!         * 1 - Identifier of the well-known account
+//!     * `Seed::InstructionDataLengthPrefixed`: `1 + 1 + 1 = 3`
+//!         * 1 - Discriminator
+//!         * 1 - Start index of the length prefix in instruction data
+//!         * 1 - Width of the length prefix, in bytes
 //!
 //! No matter which types of seeds you choose, the total size of all seed
 //! configurations must be less than or equal to 32 bytes.
 
 #[cfg(feature = "serde-traits")]
 use serde::{Deserialize, Serialize};
-use {crate::error::AccountResolutionError, solana_program_error::ProgramError};
+use {
+    crate::error::AccountResolutionError, core::fmt, solana_program_error::ProgramError,
+    solana_pubkey::Pubkey,
+};
+
+static CLOCK_SYSVAR_ID: Pubkey =
+    Pubkey::from_str_const("SysvarC1ock11111111111111111111111111111111");
+static RENT_SYSVAR_ID: Pubkey =
+    Pubkey::from_str_const("SysvarRent111111111111111111111111111111111");
+static TOKEN_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+static TOKEN_2022_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+static ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+/// Well-known account whose address can be used as a seed without storing it
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-traits", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-traits", serde(rename_all = "camelCase"))]
+pub enum KnownAccountId {
+    /// The clock sysvar
+    ClockSysvar = 1,
+    /// The rent sysvar
+    RentSysvar = 2,
+    /// The SPL Token program
+    TokenProgram = 3,
+    /// The SPL Token-2022 program
+    Token2022Program = 4,
+    /// The SPL Associated Token Account program
+    AssociatedTokenProgram = 5,
+}
+impl KnownAccountId {
+    /// Get the address of the account
+    pub fn address(&self) -> &'static Pubkey {
+        match self {
+            Self::ClockSysvar => &CLOCK_SYSVAR_ID,
+            Self::RentSysvar => &RENT_SYSVAR_ID,
+            Self::TokenProgram => &TOKEN_PROGRAM_ID,
+            Self::Token2022Program => &TOKEN_2022_PROGRAM_ID,
+            Self::AssociatedTokenProgram => &ASSOCIATED_TOKEN_PROGRAM_ID,
+        }
+    }
+}
+
+impl TryFrom<u8> for KnownAccountId {
+    type Error = ProgramError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::ClockSysvar),
+            2 => Ok(Self::RentSysvar),
+            3 => Ok(Self::TokenProgram),
+            4 => Ok(Self::Token2022Program),
+            5 => Ok(Self::AssociatedTokenProgram),
+            _ => Err(AccountResolutionError::InvalidBytesForSeed.into()),
+        }
+    }
+}
+
+impl fmt::Display for KnownAccountId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ClockSysvar => write!(f, "clock sysvar"),
+            Self::RentSysvar => write!(f, "rent sysvar"),
+            Self::TokenProgram => write!(f, "token program"),
+            Self::Token2022Program => write!(f, "token-2022 program"),
+            Self::AssociatedTokenProgram => write!(f, "associated token program"),
+        }
+    }
+}
+
+/// Number of bytes available for seed configurations in the address
+/// configuration of an `ExtraAccountMeta`
+pub const ADDRESS_CONFIG_LEN: usize = 32;
+
+/// Seed configurations too large to fit in an address configuration
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SeedConfigsOverflow {
+    /// The size of the packed seed configurations, in bytes
+    pub packed_len: usize,
+}
+
+impl SeedConfigsOverflow {
+    /// Number of bytes over the size of an address configuration
+    pub fn excess(&self) -> usize {
+        self.packed_len.saturating_sub(ADDRESS_CONFIG_LEN)
+    }
+}
+
+impl fmt::Display for SeedConfigsOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "seed configurations take {} bytes, {} over the {} bytes available",
+            self.packed_len,
+            self.excess(),
+            ADDRESS_CONFIG_LEN
+        )
+    }
+}
+
+impl std::error::Error for SeedConfigsOverflow {}
+
+impl From<SeedConfigsOverflow> for ProgramError {
+    fn from(_: SeedConfigsOverflow) -> Self {
+        AccountResolutionError::SeedConfigsTooLarge.into()
+    }
+}
 
 /// Enum to describe a required seed for a Program-Derived Address
 #[derive(Clone, Debug, PartialEq)]
@@ -95,6 +213,39 @@ pub enum Seed {
         /// Note: Max seed length is 32 bytes, so `u8` is appropriate here
         length: u8,
     },
+    /// The program id of the instruction being resolved, ie. the executing
+    /// program on-chain.
+    ///
+    /// Note: This is the program id of the instruction even for PDAs derived
+    /// for an external program.
+    ///
+    /// Packed as:
+    ///     * 1 - Discriminator
+    ProgramId,
+    /// The address of a well-known account, such as a sysvar or a token
+    /// program, without storing it as a literal
+    ///
+    /// Packed as:
+    ///     * 1 - Discriminator
+    ///     * 1 - Identifier of the well-known account
+    KnownAccount(KnownAccountId),
+    /// An instruction-provided argument of variable length, such as a borsh
+    /// string, to be resolved from the instruction data
+    ///
+    /// The argument is stored as a little-endian length prefix of
+    /// `prefix_width` bytes, followed by that many bytes used as the seed.
+    ///
+    /// Packed as:
+    ///     * 1 - Discriminator
+    ///     * 1 - Start index of the length prefix in instruction data
+    ///     * 1 - Width of the length prefix, in bytes: 1, 2 or 4
+    #[cfg_attr(feature = "serde-traits", serde(rename_all = "camelCase"))]
+    InstructionDataLengthPrefixed {
+        /// The index where the length prefix of the argument begins
+        offset: u8,
+        /// The width of the length prefix (number of bytes)
+        prefix_width: u8,
+    },
 }
 impl Seed {
     /// Get the size of a seed configuration
@@ -111,9 +262,41 @@ impl Seed {
             // 1 byte for the discriminator, 1 byte for the account index,
             // 1 byte for the data index 1 byte for the length
             Self::AccountData { .. } => 1 + 1 + 1 + 1,
+            // 1 byte for the discriminator
+            Self::ProgramId => 1,
+            // 1 byte for the discriminator, 1 byte for the account identifier
+            Self::KnownAccount(_) => 1 + 1,
+            // 1 byte for the discriminator, 1 byte for the offset, 1 byte for
+            // the prefix width
+            Self::InstructionDataLengthPrefixed { .. } => 1 + 1 + 1,
+        }
+    }
+
+    /// Get the size of the packed seed configuration, in bytes
+    ///
+    /// Unlike `tlv_size`, this doesn't wrap around for literals too large to
+    /// be packed.
+    pub fn packed_len(&self) -> usize {
+        match self {
+            Self::Literal { bytes } => 1 + 1 + bytes.len(),
+            _ => usize::from(self.tlv_size()),
         }
     }
 
+    /// Get the total size of packed seed configurations, in bytes
+    pub fn packed_len_of(seeds: &[Self]) -> usize {
+        seeds.iter().map(Self::packed_len).sum()
+    }
+
+    /// Check that seed configurations fit in an address configuration,
+    /// returning the number of bytes left
+    pub fn check_address_config_len(seeds: &[Self]) -> Result<usize, SeedConfigsOverflow> {
+        let packed_len = Self::packed_len_of(seeds);
+        ADDRESS_CONFIG_LEN
+            .checked_sub(packed_len)
+            .ok_or(SeedConfigsOverflow { packed_len })
+    }
+
     /// Packs a seed configuration into a slice
     pub fn pack(&self, dst: &mut [u8]) -> Result<(), ProgramError> {
         if dst.len() != self.tlv_size() as usize {
@@ -148,6 +331,23 @@ impl Seed {
                 dst[2] = *data_index;
                 dst[3] = *length;
             }
+            Self::ProgramId => {
+                dst[0] = 5;
+            }
+            Self::KnownAccount(id) => {
+                dst[0] = 6;
+                dst[1] = *id as u8;
+            }
+            Self::InstructionDataLengthPrefixed {
+                offset,
+                prefix_width,
+            } => {
+                check_prefix_width(*prefix_width)
+                    .map_err(|_| AccountResolutionError::InvalidSeedConfig)?;
+                dst[0] = 7;
+                dst[1] = *offset;
+                dst[2] = *prefix_width;
+            }
         }
         Ok(())
     }
@@ -155,6 +355,7 @@ impl Seed {
     /// Packs a vector of seed configurations into a 32-byte array,
     /// filling the rest with zeroes. Errors if it overflows.
     pub fn pack_into_address_config(seeds: &[Self]) -> Result<[u8; 32], ProgramError> {
+        Self::check_address_config_len(seeds)?;
         let mut packed = [0u8; 32];
         let mut i: usize = 0;
         for seed in seeds {
@@ -180,6 +381,9 @@ impl Seed {
             2 => unpack_seed_instruction_arg(rest),
             3 => unpack_seed_account_key(rest),
             4 => unpack_seed_account_data(rest),
+            5 => Ok(Self::ProgramId),
+            6 => unpack_seed_known_account(rest),
+            7 => unpack_seed_instruction_arg_length_prefixed(rest),
             _ => Err(ProgramError::InvalidAccountData),
         }
     }
@@ -248,6 +452,78 @@ fn unpack_seed_account_data(bytes: &[u8]) -> Result<Seed, ProgramError> {
     })
 }
 
+fn unpack_seed_known_account(bytes: &[u8]) -> Result<Seed, ProgramError> {
+    let id = bytes
+        .first()
+        // Should be at least 1 byte
+        .ok_or::<ProgramError>(AccountResolutionError::InvalidBytesForSeed.into())?;
+    Ok(Seed::KnownAccount(KnownAccountId::try_from(*id)?))
+}
+
+fn unpack_seed_instruction_arg_length_prefixed(bytes: &[u8]) -> Result<Seed, ProgramError> {
+    if bytes.len() < 2 {
+        // Should be at least 2 bytes
+        return Err(AccountResolutionError::InvalidBytesForSeed.into());
+    }
+    check_prefix_width(bytes[1])?;
+    Ok(Seed::InstructionDataLengthPrefixed {
+        offset: bytes[0],
+        prefix_width: bytes[1],
+    })
+}
+
+/// Check that a length prefix width is supported
+fn check_prefix_width(prefix_width: u8) -> Result<(), ProgramError> {
+    match prefix_width {
+        1 | 2 | 4 => Ok(()),
+        _ => Err(AccountResolutionError::InvalidBytesForSeed.into()),
+    }
+}
+
+impl fmt::Display for Seed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Uninitialized => write!(f, "uninitialized"),
+            Self::Literal { bytes } => match core::str::from_utf8(bytes) {
+                Ok(string) if string.chars().all(|c| c.is_ascii_graphic() || c == ' ') => {
+                    write!(f, "literal {string:?}")
+                }
+                _ => {
+                    write!(f, "literal 0x")?;
+                    bytes.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+                }
+            },
+            Self::InstructionData { index, length } => write!(
+                f,
+                "instruction data [{}..{}]",
+                index,
+                usize::from(*index) + usize::from(*length)
+            ),
+            Self::AccountKey { index } => write!(f, "key of account {index}"),
+            Self::AccountData {
+                account_index,
+                data_index,
+                length,
+            } => write!(
+                f,
+                "data of account {} [{}..{}]",
+                account_index,
+                data_index,
+                usize::from(*data_index) + usize::from(*length)
+            ),
+            Self::ProgramId => write!(f, "program id"),
+            Self::KnownAccount(id) => write!(f, "key of the {id}"),
+            Self::InstructionDataLengthPrefixed {
+                offset,
+                prefix_width,
+            } => write!(
+                f,
+                "instruction data [{offset}..] with a {prefix_width}-byte length prefix"
+            ),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -298,10 +574,36 @@ mod tests {
             index: 0,
             length: 4,
         }; // 3 bytes
+        let seeds = [seed1, seed2];
         assert_eq!(
-            Seed::pack_into_address_config(&[seed1, seed2]).unwrap_err(),
+            Seed::pack_into_address_config(&seeds).unwrap_err(),
             AccountResolutionError::SeedConfigsTooLarge.into()
         );
+        let overflow = Seed::check_address_config_len(&seeds).unwrap_err();
+        assert_eq!(overflow, SeedConfigsOverflow { packed_len: 35 });
+        assert_eq!(overflow.excess(), 3);
+        assert_eq!(
+            overflow.to_string(),
+            "seed configurations take 35 bytes, 3 over the 32 bytes available"
+        );
+
+        // Literals too large for `tlv_size` are still measured
+        let seed = Seed::Literal {
+            bytes: vec![1; 300],
+        };
+        assert_eq!(seed.packed_len(), 302);
+        assert_eq!(
+            Seed::check_address_config_len(&[seed])
+                .unwrap_err()
+                .excess(),
+            270
+        );
+
+        // Space left for more seeds
+        let seeds = [Seed::Literal { bytes: vec![1; 20] }, Seed::ProgramId];
+        assert_eq!(Seed::packed_len_of(&seeds), 23);
+        assert_eq!(Seed::check_address_config_len(&seeds).unwrap(), 9);
+        assert_eq!(Seed::check_address_config_len(&[]).unwrap(), 32);
     }
 
     #[test]
@@ -356,7 +658,7 @@ mod tests {
             1, // Discrim (Literal)
             4, // Length
             1, 1, 1, 1, // 4
-            6, // Discrim (Invalid)
+            8, // Discrim (Invalid)
             2, // Index
             1, // Length
             0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
@@ -370,7 +672,7 @@ mod tests {
         // start of a config
         //
         // Namely, if a seed config is unpacked and leaves 1 byte remaining,
-        // it has to be 0, since no valid seed config can be 1 byte long
+        // it has to be 0 or a single-byte seed config (`Seed::ProgramId`)
         let bytes = [
             1,  // Discrim (Literal)
             16, // Length
@@ -506,6 +808,42 @@ mod tests {
         test_pack_unpack_seed(seed.clone());
         mixed.push(seed);
 
+        // Program id
+
+        let seed = Seed::ProgramId;
+        test_pack_unpack_seed(seed.clone());
+        mixed.push(seed);
+
+        // Known account
+
+        let seed = Seed::KnownAccount(KnownAccountId::Token2022Program);
+        test_pack_unpack_seed(seed.clone());
+        mixed.push(seed);
+
+        // Length-prefixed instruction data
+
+        let seed = Seed::InstructionDataLengthPrefixed {
+            offset: 8,
+            prefix_width: 4,
+        };
+        test_pack_unpack_seed(seed.clone());
+        mixed.push(seed);
+
+        // Only 1, 2 and 4-byte prefixes are supported
+        let seed = Seed::InstructionDataLengthPrefixed {
+            offset: 8,
+            prefix_width: 3,
+        };
+        let mut packed = vec![0; seed.tlv_size() as usize];
+        assert_eq!(
+            seed.pack(&mut packed).unwrap_err(),
+            AccountResolutionError::InvalidSeedConfig.into()
+        );
+        assert_eq!(
+            Seed::unpack(&[7, 8, 3]).unwrap_err(),
+            AccountResolutionError::InvalidBytesForSeed.into()
+        );
+
         // Arrays
 
         let packed_array = Seed::pack_into_address_config(&mixed).unwrap();
@@ -521,4 +859,87 @@ mod tests {
         let unpacked_array = Seed::unpack_address_config(&packed_array).unwrap();
         assert_eq!(shuffled_mixed, unpacked_array);
     }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            Seed::Literal {
+                bytes: b"seed prefix".to_vec()
+            }
+            .to_string(),
+            "literal \"seed prefix\""
+        );
+        assert_eq!(
+            Seed::Literal {
+                bytes: vec![0, 1, 255]
+            }
+            .to_string(),
+            "literal 0x0001ff"
+        );
+        assert_eq!(
+            Seed::InstructionData {
+                index: 8,
+                length: 32
+            }
+            .to_string(),
+            "instruction data [8..40]"
+        );
+        assert_eq!(
+            Seed::AccountKey { index: 3 }.to_string(),
+            "key of account 3"
+        );
+        assert_eq!(
+            Seed::AccountData {
+                account_index: 2,
+                data_index: 255,
+                length: 32
+            }
+            .to_string(),
+            "data of account 2 [255..287]"
+        );
+        assert_eq!(Seed::ProgramId.to_string(), "program id");
+        assert_eq!(
+            Seed::KnownAccount(KnownAccountId::RentSysvar).to_string(),
+            "key of the rent sysvar"
+        );
+        assert_eq!(
+            Seed::InstructionDataLengthPrefixed {
+                offset: 8,
+                prefix_width: 4
+            }
+            .to_string(),
+            "instruction data [8..] with a 4-byte length prefix"
+        );
+    }
+
+    #[test]
+    fn test_known_accounts() {
+        for id in [
+            KnownAccountId::ClockSysvar,
+            KnownAccountId::RentSysvar,
+            KnownAccountId::TokenProgram,
+            KnownAccountId::Token2022Program,
+            KnownAccountId::AssociatedTokenProgram,
+        ] {
+            assert_eq!(KnownAccountId::try_from(id as u8).unwrap(), id);
+        }
+        assert_eq!(
+            KnownAccountId::ClockSysvar.address().to_string(),
+            "SysvarC1ock11111111111111111111111111111111"
+        );
+
+        // Unknown identifiers and missing bytes are rejected
+        assert_eq!(
+            Seed::unpack(&[6, 0]).unwrap_err(),
+            AccountResolutionError::InvalidBytesForSeed.into()
+        );
+        assert_eq!(
+            Seed::unpack(&[6, 6]).unwrap_err(),
+            AccountResolutionError::InvalidBytesForSeed.into()
+        );
+        assert_eq!(
+            Seed::unpack(&[6]).unwrap_err(),
+            AccountResolutionError::InvalidBytesForSeed.into()
+        );
+    }
 }
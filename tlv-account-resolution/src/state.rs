@@ -1,16 +1,30 @@
 //! State transition types
 
 use {
-    crate::{account::ExtraAccountMeta, error::AccountResolutionError},
+    crate::{
+        account::{
+            validate_seed_configs, ExtraAccountMeta, PdaCache, ResolutionFailure,
+            ResolvedSeedConfig,
+        },
+        account_info::AccountInfoView,
+        constraints::{self, constraints_discriminator, Constraints, ExtraAccountMetaConstraints},
+        error::AccountResolutionError,
+        versioned::{versioned_discriminator, MAX_VERSION},
+    },
     solana_account_info::AccountInfo,
     solana_instruction::{AccountMeta, Instruction},
+    solana_message::AddressLookupTableAccount,
     solana_program_error::ProgramError,
     solana_pubkey::Pubkey,
-    spl_discriminator::SplDiscriminate,
+    solana_rent::Rent,
+    spl_discriminator::{ArrayDiscriminator, SplDiscriminate},
     spl_list_view::{ListView, ListViewReadOnly},
     spl_pod::primitives::PodU32,
-    spl_type_length_value::state::{TlvState, TlvStateBorrowed, TlvStateMut},
-    std::future::Future,
+    spl_type_length_value::{
+        error::TlvError,
+        state::{TlvState, TlvStateBorrowed, TlvStateMut},
+    },
+    std::{fmt, future::Future},
 };
 
 /// Type representing the output of an account fetching function, for easy
@@ -20,36 +34,275 @@ pub type AccountDataResult = Result<Option<Vec<u8>>, AccountFetchError>;
 /// data
 pub type AccountFetchError = Box<dyn std::error::Error + Send + Sync>;
 
-/// Helper to convert an `AccountInfo` to an `AccountMeta`
-fn account_info_to_meta(account_info: &AccountInfo) -> AccountMeta {
-    AccountMeta {
-        pubkey: *account_info.key,
-        is_signer: account_info.is_signer,
-        is_writable: account_info.is_writable,
+/// Client backend able to fetch account data off-chain, such as an RPC
+/// client, a bank simulator or a local cache
+pub trait AccountFetcher {
+    /// Fetch the data of multiple accounts, returning `None` for accounts that
+    /// do not exist
+    ///
+    /// The returned data must be in the same order as `pubkeys`.
+    fn get_multiple_account_data(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> impl Future<Output = Result<Vec<Option<Vec<u8>>>, AccountFetchError>>;
+}
+
+/// Any function fetching the data of a single account is an `AccountFetcher`,
+/// fetching accounts one by one
+impl<F, Fut> AccountFetcher for F
+where
+    F: Fn(Pubkey) -> Fut,
+    Fut: Future<Output = AccountDataResult>,
+{
+    async fn get_multiple_account_data(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> Result<Vec<Option<Vec<u8>>>, AccountFetchError> {
+        let mut account_datas = Vec::with_capacity(pubkeys.len());
+        for pubkey in pubkeys {
+            account_datas.push(self(*pubkey).await?);
+        }
+        Ok(account_datas)
     }
 }
 
-/// De-escalate an account meta if necessary
-fn de_escalate_account_meta(account_meta: &mut AccountMeta, account_metas: &[AccountMeta]) {
-    // This is a little tricky to read, but checks if this account is marked as
-    // writable in the instruction. If it's read-only, de-escalate it to read-only
-    // in the CPI.
-    let maybe_highest_privileges = account_metas
-        .iter()
-        .filter(|&x| x.pubkey == account_meta.pubkey)
-        .map(|x| x.is_writable)
-        .reduce(|acc, x| acc || x);
-    // If `Some`, then the account was found somewhere in the instruction
-    if let Some(is_writable) = maybe_highest_privileges {
-        if !is_writable && is_writable != account_meta.is_writable {
-            // Existing account is *NOT* writable already, but the CPI
-            // wants it to be, so de-escalate to not be writable
-            account_meta.is_writable = false;
+/// Location of an account in an address lookup table
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LookupTableEntry {
+    /// Index of the table in the provided address lookup tables
+    pub table_index: usize,
+    /// Index of the account in the table's addresses
+    pub address_index: u8,
+}
+
+/// Outcome of resolving one extra account meta, see
+/// `ExtraAccountMetaList::diagnose`
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExtraAccountMetaDiagnosis {
+    /// The meta resolved to one of the available accounts
+    Resolved(AccountMeta),
+    /// The meta resolved to an account that is not available
+    Missing(AccountMeta),
+    /// The meta is optional and couldn't be resolved, so the program id
+    /// placeholder is expected in its place
+    Omitted {
+        /// Index of the seed that couldn't be resolved, if any
+        seed_index: Option<usize>,
+        /// Error that prevented the resolution
+        error: ProgramError,
+    },
+    /// The meta couldn't be resolved
+    Failed {
+        /// Index of the seed that couldn't be resolved, if any
+        seed_index: Option<usize>,
+        /// Error that prevented the resolution
+        error: ProgramError,
+    },
+}
+
+impl fmt::Display for ExtraAccountMetaDiagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let write_error = |f: &mut fmt::Formatter<'_>, seed_index, error| match seed_index {
+            Some(seed_index) => write!(f, "seed {} failed: {}", seed_index, error),
+            None => write!(f, "{}", error),
+        };
+        match self {
+            Self::Resolved(meta) => write!(f, "resolved {}", meta.pubkey),
+            Self::Missing(meta) => write!(f, "missing {}", meta.pubkey),
+            Self::Omitted { seed_index, error } => {
+                write!(f, "omitted, ")?;
+                write_error(f, *seed_index, error)
+            }
+            Self::Failed { seed_index, error } => {
+                write!(f, "failed, ")?;
+                write_error(f, *seed_index, error)
+            }
+        }
+    }
+}
+
+/// Report of a dry-run resolution of extra account metas, see
+/// `ExtraAccountMetaList::diagnose`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResolutionReport {
+    /// Outcome of each extra account meta, in order
+    pub metas: Vec<ExtraAccountMetaDiagnosis>,
+}
+
+impl ResolutionReport {
+    /// Whether every extra account meta resolved to an available account, or
+    /// was omitted
+    pub fn is_ok(&self) -> bool {
+        self.metas.iter().all(|diagnosis| {
+            matches!(
+                diagnosis,
+                ExtraAccountMetaDiagnosis::Resolved(_) | ExtraAccountMetaDiagnosis::Omitted { .. }
+            )
+        })
+    }
+
+    /// Accounts that resolved but are not available, with the index of
+    /// their extra account meta
+    pub fn missing_accounts(&self) -> impl Iterator<Item = (usize, &AccountMeta)> {
+        self.metas
+            .iter()
+            .enumerate()
+            .filter_map(|(i, diagnosis)| match diagnosis {
+                ExtraAccountMetaDiagnosis::Missing(meta) => Some((i, meta)),
+                _ => None,
+            })
+    }
+
+    /// Extra account metas that failed to resolve, with the index of the
+    /// failing seed and the error
+    pub fn failures(&self) -> impl Iterator<Item = (usize, Option<usize>, &ProgramError)> {
+        self.metas
+            .iter()
+            .enumerate()
+            .filter_map(|(i, diagnosis)| match diagnosis {
+                ExtraAccountMetaDiagnosis::Failed { seed_index, error } => {
+                    Some((i, *seed_index, error))
+                }
+                _ => None,
+            })
+    }
+}
+
+impl fmt::Display for ResolutionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, diagnosis) in self.metas.iter().enumerate() {
+            writeln!(f, "extra account {}: {}", i, diagnosis)?;
         }
+        Ok(())
     }
+}
 
-    // Always mark an account as a non-signer
+/// Merge the privileges of an account meta with those of the entries for the
+/// same account already in the instruction
+///
+/// The meta keeps its own writable privilege, and also gets the privileges
+/// of the existing entries, which the runtime gives to every entry of a
+/// duplicate account. Resolution can't provide signatures, so the meta is
+/// only a signer if the account already signs the instruction.
+///
+/// The meta is still appended rather than merged into an existing entry,
+/// since extra account metas are resolved by their position in the account
+/// list, see `ExtraAccountMetaList::add_to_instruction_dedup`.
+fn merge_account_meta(account_meta: &mut AccountMeta, account_metas: &[AccountMeta]) {
     account_meta.is_signer = false;
+    for existing in account_metas
+        .iter()
+        .filter(|existing| existing.pubkey == account_meta.pubkey)
+    {
+        account_meta.is_signer |= existing.is_signer;
+        account_meta.is_writable |= existing.is_writable;
+    }
+}
+
+/// Most account infos indexed on the stack, which is the most accounts a
+/// transaction can reference
+const MAX_INDEXED_ACCOUNT_INFOS: usize = 256;
+
+/// Account infos sorted by key without allocating, to look up each account
+/// in O(log n)
+///
+/// Ties are sorted by position, so the first matching account info is found,
+/// as with a linear search. Longer lists are searched linearly.
+struct AccountInfoIndex<'b, A> {
+    account_infos: &'b [A],
+    sorted_indices: [u8; MAX_INDEXED_ACCOUNT_INFOS],
+}
+impl<'b, A: AccountInfoView> AccountInfoIndex<'b, A> {
+    fn new(account_infos: &'b [A]) -> Self {
+        let mut sorted_indices = [0; MAX_INDEXED_ACCOUNT_INFOS];
+        if let Some(sorted_indices) = sorted_indices.get_mut(..account_infos.len()) {
+            for (i, index) in sorted_indices.iter_mut().enumerate() {
+                *index = i as u8;
+            }
+            sorted_indices.sort_unstable_by(|a, b| {
+                account_infos[*a as usize]
+                    .key()
+                    .cmp(&account_infos[*b as usize].key())
+                    .then(a.cmp(b))
+            });
+        }
+        Self {
+            account_infos,
+            sorted_indices,
+        }
+    }
+
+    fn find(&self, key: &Pubkey) -> Option<&'b A> {
+        let Some(sorted_indices) = self.sorted_indices.get(..self.account_infos.len()) else {
+            return self.account_infos.iter().find(|info| info.key() == *key);
+        };
+        let position =
+            sorted_indices.partition_point(|i| self.account_infos[*i as usize].key() < *key);
+        sorted_indices
+            .get(position)
+            .map(|i| &self.account_infos[*i as usize])
+            .filter(|info| info.key() == *key)
+    }
+}
+
+/// Get the constraints stored next to the extra account metas with the given
+/// discriminator, or an empty list if none are stored
+/// Check whether constraints are stored for the given instruction
+fn has_constraints<T: SplDiscriminate>(state: &impl TlvState) -> Result<bool, ProgramError> {
+    Ok(state
+        .get_discriminators()?
+        .contains(&Constraints::<T>::SPL_DISCRIMINATOR))
+}
+
+fn unpack_constraints<'a>(
+    state: &'a TlvStateBorrowed,
+    discriminator: ArrayDiscriminator,
+    num_items: usize,
+) -> Result<&'a [ExtraAccountMetaConstraints], ProgramError> {
+    let discriminator = constraints_discriminator(discriminator);
+    if !state.get_discriminators()?.contains(&discriminator) {
+        return Ok(&[]);
+    }
+    constraints::unpack(
+        state.get_first_bytes_with_discriminator(discriminator)?,
+        num_items,
+    )
+}
+
+/// Check that a provided account is executable and owned by the expected
+/// program, if required by the constraints on its extra account meta
+///
+/// `account_key_fn` returns the key of the account at the given index in the
+/// accounts list, if it was provided.
+fn check_account_requirements<F>(
+    constraints: ExtraAccountMetaConstraints,
+    provided: &impl AccountInfoView,
+    account_key_fn: F,
+) -> Result<(), ProgramError>
+where
+    F: Fn(usize) -> Option<Pubkey>,
+{
+    if constraints.is_executable() && !provided.executable() {
+        return Err(AccountResolutionError::AccountNotExecutable.into());
+    }
+    if let Some(owner_index) = constraints.owner_index() {
+        let owner = account_key_fn(usize::from(owner_index))
+            .ok_or(AccountResolutionError::AccountNotFound)?;
+        if provided.owner() != owner {
+            return Err(AccountResolutionError::IncorrectAccountOwner.into());
+        }
+    }
+    Ok(())
+}
+
+/// Entry of the JSON export of an extra account meta list
+#[cfg(feature = "serde-traits")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ExtraAccountMetaJson {
+    #[serde(flatten)]
+    meta: ExtraAccountMeta,
+    #[serde(flatten)]
+    constraints: ExtraAccountMetaConstraints,
 }
 
 /// Stateless helper for storing additional accounts required for an
@@ -141,7 +394,7 @@ fn de_escalate_account_meta(account_meta: &mut AccountMeta, account_metas: &[Acc
 /// let mut cpi_instruction = Instruction::new_with_bytes(program_id, &[0, 1, 2], vec![]);
 /// let mut cpi_account_infos = vec![]; // assume the other required account infos are already included
 /// let remaining_account_infos: &[AccountInfo<'_>] = &[]; // these are the account infos provided to the instruction that are *not* part of any other known interface
-/// ExtraAccountMetaList::add_to_cpi_instruction::<MyInstruction>(
+/// ExtraAccountMetaList::add_to_cpi_instruction::<MyInstruction, _>(
 ///     &mut cpi_instruction,
 ///     &mut cpi_account_infos,
 ///     &buffer,
@@ -166,18 +419,234 @@ impl ExtraAccountMetaList {
         Ok(())
     }
 
+    /// Initialize pod slice data for the given instruction and its required
+    /// list of `ExtraAccountMeta`s, along with the constraints on each of
+    /// them, see `ExtraAccountMetaConstraints`
+    ///
+    /// The constraints are stored in their own entry, so the data must be
+    /// sized with `ExtraAccountMetaList::size_of_with_constraints`.
+    pub fn init_with_constraints<T: SplDiscriminate>(
+        data: &mut [u8],
+        extra_account_metas: &[ExtraAccountMeta],
+        extra_account_meta_constraints: &[ExtraAccountMetaConstraints],
+    ) -> Result<(), ProgramError> {
+        if extra_account_meta_constraints.len() != extra_account_metas.len() {
+            return Err(ProgramError::InvalidArgument);
+        }
+        Self::init::<T>(data, extra_account_metas)?;
+        let mut state = TlvStateMut::unpack(data)?;
+        let length = constraints::packed_len(extra_account_meta_constraints.len());
+        let (bytes, _) = state.alloc::<Constraints<T>>(length, false)?;
+        constraints::pack(bytes, extra_account_meta_constraints);
+        Ok(())
+    }
+
+    /// Initialize pod slice data for the given instruction and its required
+    /// list of `ExtraAccountMeta`s, after validating their configurations
+    ///
+    /// The configurations are validated against the expected instruction
+    /// data length and number of instruction accounts, see
+    /// `validate_seed_configs`.
+    pub fn init_with_validation<T: SplDiscriminate>(
+        data: &mut [u8],
+        extra_account_metas: &[ExtraAccountMeta],
+        instruction_data_len: usize,
+        num_instruction_accounts: usize,
+    ) -> Result<(), ProgramError> {
+        validate_seed_configs(
+            extra_account_metas,
+            instruction_data_len,
+            num_instruction_accounts,
+        )?;
+        Self::init::<T>(data, extra_account_metas)
+    }
+
     /// Update pod slice data for the given instruction and its required
     /// list of `ExtraAccountMeta`s
+    ///
+    /// The existing entry is resized in place, moving any following entries,
+    /// so the data must have enough space for the new list of metas.
+    ///
+    /// Fails if constraints are stored for the instruction, since they must be
+    /// updated along with the metas, see
+    /// `ExtraAccountMetaList::update_with_constraints`.
     pub fn update<T: SplDiscriminate>(
         data: &mut [u8],
         extra_account_metas: &[ExtraAccountMeta],
     ) -> Result<(), ProgramError> {
-        let mut state = TlvStateMut::unpack(data).unwrap();
+        Self::update_with_iter::<T>(data, extra_account_metas.iter().copied())
+    }
+
+    /// Update pod slice data for the given instruction and its required
+    /// list of `AccountMeta`s
+    ///
+    /// Fails if constraints are stored for the instruction, see
+    /// `ExtraAccountMetaList::update_with_constraints`.
+    pub fn update_with_account_metas<T: SplDiscriminate>(
+        data: &mut [u8],
+        account_metas: &[AccountMeta],
+    ) -> Result<(), ProgramError> {
+        Self::update_with_iter::<T>(data, account_metas.iter().map(ExtraAccountMeta::from))
+    }
+
+    /// Update pod slice data for the given instruction and its required
+    /// list of `AccountInfo`s
+    ///
+    /// Fails if constraints are stored for the instruction, see
+    /// `ExtraAccountMetaList::update_with_constraints`.
+    pub fn update_with_account_infos<T: SplDiscriminate>(
+        data: &mut [u8],
+        account_infos: &[AccountInfo],
+    ) -> Result<(), ProgramError> {
+        Self::update_with_iter::<T>(data, account_infos.iter().map(ExtraAccountMeta::from))
+    }
+
+    /// Update pod slice data for the given instruction and its required
+    /// list of `ExtraAccountMeta`s, along with the constraints on each of
+    /// them
+    ///
+    /// The constraints entry is created if it doesn't exist yet. The data must
+    /// have enough space for both entries.
+    pub fn update_with_constraints<T: SplDiscriminate>(
+        data: &mut [u8],
+        extra_account_metas: &[ExtraAccountMeta],
+        extra_account_meta_constraints: &[ExtraAccountMetaConstraints],
+    ) -> Result<(), ProgramError> {
+        if extra_account_meta_constraints.len() != extra_account_metas.len() {
+            return Err(ProgramError::InvalidArgument);
+        }
+        Self::write_metas::<T>(data, extra_account_metas.iter().copied())?;
+        let mut state = TlvStateMut::unpack(data)?;
+        let length = constraints::packed_len(extra_account_meta_constraints.len());
+        let bytes = if has_constraints::<T>(&state)? {
+            state.realloc_first::<Constraints<T>>(length)?
+        } else {
+            state.alloc::<Constraints<T>>(length, false)?.0
+        };
+        constraints::pack(bytes, extra_account_meta_constraints);
+        Ok(())
+    }
+
+    /// Helper to update the pod slice data from any list of metas, refusing
+    /// to leave stored constraints out of sync with them
+    fn update_with_iter<T: SplDiscriminate>(
+        data: &mut [u8],
+        extra_account_metas: impl ExactSizeIterator<Item = ExtraAccountMeta>,
+    ) -> Result<(), ProgramError> {
+        if has_constraints::<T>(&TlvStateBorrowed::unpack(data)?)? {
+            return Err(AccountResolutionError::ConstraintsNotUpdated.into());
+        }
+        Self::write_metas::<T>(data, extra_account_metas)
+    }
+
+    /// Helper to resize the pod slice data and write the metas into it
+    fn write_metas<T: SplDiscriminate>(
+        data: &mut [u8],
+        extra_account_metas: impl ExactSizeIterator<Item = ExtraAccountMeta>,
+    ) -> Result<(), ProgramError> {
+        let mut state = TlvStateMut::unpack(data)?;
         let tlv_size = ListView::<ExtraAccountMeta>::size_of(extra_account_metas.len())?;
         let bytes = state.realloc_first::<T>(tlv_size)?;
         let mut validation_data = ListView::<ExtraAccountMeta>::init(bytes)?;
         for meta in extra_account_metas {
-            validation_data.push(*meta)?;
+            validation_data.push(meta)?;
+        }
+        Ok(())
+    }
+
+    /// Get the change in data size needed to update the extra account metas
+    /// for the given instruction to a list of `num_items` metas
+    ///
+    /// A positive value means the data must grow, a negative value means it
+    /// may shrink.
+    pub fn size_diff_for_update<T: SplDiscriminate>(
+        data: &[u8],
+        num_items: usize,
+    ) -> Result<isize, ProgramError> {
+        let state = TlvStateBorrowed::unpack(data)?;
+        let current_len = state.get_first_bytes::<T>()?.len();
+        let new_len = ListView::<ExtraAccountMeta>::size_of(num_items)?;
+        let current_len =
+            isize::try_from(current_len).map_err(|_| AccountResolutionError::CalculationFailure)?;
+        let new_len =
+            isize::try_from(new_len).map_err(|_| AccountResolutionError::CalculationFailure)?;
+        Ok(new_len - current_len)
+    }
+
+    /// Get the account data length after updating the extra account metas for
+    /// the given instruction to a list of `num_items` metas
+    fn account_len_for_update<T: SplDiscriminate>(
+        account_info: &AccountInfo,
+        num_items: usize,
+    ) -> Result<usize, ProgramError> {
+        let size_diff = {
+            let data = account_info.try_borrow_data()?;
+            Self::size_diff_for_update::<T>(&data, num_items)?
+        };
+        account_info
+            .try_data_len()?
+            .checked_add_signed(size_diff)
+            .ok_or_else(|| AccountResolutionError::CalculationFailure.into())
+    }
+
+    /// Get the lamports that must be added to the validation account so that
+    /// it stays rent-exempt after updating it to a list of `num_items` metas
+    pub fn lamports_needed_for_update<T: SplDiscriminate>(
+        account_info: &AccountInfo,
+        rent: &Rent,
+        num_items: usize,
+    ) -> Result<u64, ProgramError> {
+        let new_len = Self::account_len_for_update::<T>(account_info, num_items)?;
+        Ok(rent
+            .minimum_balance(new_len)
+            .saturating_sub(account_info.lamports()))
+    }
+
+    /// Update the extra account metas for the given instruction in a
+    /// validation account, resizing the account as needed
+    ///
+    /// The account must already hold enough lamports to be rent-exempt at its
+    /// new size, see `lamports_needed_for_update`. Fails if constraints are
+    /// stored for the instruction, see
+    /// `ExtraAccountMetaList::update_with_constraints`.
+    pub fn update_account_info<T: SplDiscriminate>(
+        account_info: &AccountInfo,
+        rent: &Rent,
+        extra_account_metas: &[ExtraAccountMeta],
+    ) -> Result<(), ProgramError> {
+        if has_constraints::<T>(&TlvStateBorrowed::unpack(&account_info.try_borrow_data()?)?)? {
+            return Err(AccountResolutionError::ConstraintsNotUpdated.into());
+        }
+        let previous_len = account_info.try_data_len()?;
+        let new_len = Self::account_len_for_update::<T>(account_info, extra_account_metas.len())?;
+        if !rent.is_exempt(account_info.lamports(), new_len) {
+            return Err(ProgramError::AccountNotRentExempt);
+        }
+        // Grow the account before moving entries, or shrink it afterwards,
+        // so that the TLV data always fits in the account
+        if new_len > previous_len {
+            account_info.resize(new_len)?;
+        }
+        Self::update::<T>(
+            &mut account_info.try_borrow_mut_data()?,
+            extra_account_metas,
+        )?;
+        if new_len < previous_len {
+            account_info.resize(new_len)?;
+        }
+        Ok(())
+    }
+
+    /// Remove the extra account metas for the given instruction, and their
+    /// constraints if any
+    ///
+    /// The entries are deleted from the TLV data, moving any following entries
+    /// back and zeroing out the freed bytes at the end of the data.
+    pub fn remove<T: SplDiscriminate>(data: &mut [u8]) -> Result<(), ProgramError> {
+        let mut state = TlvStateMut::unpack(data)?;
+        state.dealloc::<T>()?;
+        if has_constraints::<T>(&state)? {
+            state.dealloc::<Constraints<T>>()?;
         }
         Ok(())
     }
@@ -194,62 +663,249 @@ impl ExtraAccountMetaList {
         ListView::<ExtraAccountMeta, PodU32>::unpack(bytes)
     }
 
+    /// Iterate over the extra account metas stored for the instruction,
+    /// together with their decoded address configuration
+    ///
+    /// This is meant for introspection, e.g. to display the configuration
+    /// of a validation account.
+    pub fn iter<T: SplDiscriminate>(
+        data: &[u8],
+    ) -> Result<impl Iterator<Item = (ExtraAccountMeta, ResolvedSeedConfig)>, ProgramError> {
+        let state = TlvStateBorrowed::unpack(data)?;
+        let extra_meta_list = ExtraAccountMetaList::unpack_with_tlv_state::<T>(&state)?;
+        let entries = extra_meta_list
+            .iter()
+            .map(|meta| Ok((*meta, meta.seed_config()?)))
+            .collect::<Result<Vec<_>, ProgramError>>()?;
+        Ok(entries.into_iter())
+    }
+
+    /// Get the constraints on each of the extra account metas stored for the
+    /// instruction, which have none if no constraints are stored
+    pub fn constraints<T: SplDiscriminate>(
+        data: &[u8],
+    ) -> Result<Vec<ExtraAccountMetaConstraints>, ProgramError> {
+        let state = TlvStateBorrowed::unpack(data)?;
+        let extra_meta_list = ExtraAccountMetaList::unpack_with_tlv_state::<T>(&state)?;
+        let extra_meta_constraints =
+            unpack_constraints(&state, T::SPL_DISCRIMINATOR, extra_meta_list.len())?;
+        Ok((0..extra_meta_list.len())
+            .map(|i| constraints::get(extra_meta_constraints, i))
+            .collect())
+    }
+
+    /// Export the extra account metas stored for the instruction as JSON,
+    /// along with their constraints
+    #[cfg(feature = "serde-traits")]
+    pub fn to_json<T: SplDiscriminate>(data: &[u8]) -> Result<String, ProgramError> {
+        let entries = Self::iter::<T>(data)?
+            .zip(Self::constraints::<T>(data)?)
+            .map(|((meta, _), constraints)| ExtraAccountMetaJson { meta, constraints })
+            .collect::<Vec<_>>();
+        serde_json::to_string_pretty(&entries).map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    /// Parse extra account metas and their constraints from JSON, as exported
+    /// by `ExtraAccountMetaList::to_json`
+    #[cfg(feature = "serde-traits")]
+    #[allow(clippy::type_complexity)]
+    pub fn from_json(
+        json: &str,
+    ) -> Result<(Vec<ExtraAccountMeta>, Vec<ExtraAccountMetaConstraints>), ProgramError> {
+        let entries = serde_json::from_str::<Vec<ExtraAccountMetaJson>>(json)
+            .map_err(|_| ProgramError::InvalidArgument)?;
+        Ok(entries
+            .into_iter()
+            .map(|entry| (entry.meta, entry.constraints))
+            .unzip())
+    }
+
+    /// Initialize pod slice data for the given instruction and its required
+    /// list of `ExtraAccountMeta`s, parsed from JSON
+    ///
+    /// The constraints entry is only written if any meta has a constraint.
+    /// The data must be large enough to hold the parsed entries, see
+    /// `ExtraAccountMetaList::size_of` and
+    /// `ExtraAccountMetaList::size_of_with_constraints`.
+    #[cfg(feature = "serde-traits")]
+    pub fn init_from_json<T: SplDiscriminate>(
+        data: &mut [u8],
+        json: &str,
+    ) -> Result<(), ProgramError> {
+        let (metas, extra_meta_constraints) = Self::from_json(json)?;
+        if extra_meta_constraints
+            .iter()
+            .all(ExtraAccountMetaConstraints::is_empty)
+        {
+            Self::init::<T>(data, &metas)
+        } else {
+            Self::init_with_constraints::<T>(data, &metas, &extra_meta_constraints)
+        }
+    }
+
     /// Get the byte size required to hold `num_items` items
     pub fn size_of(num_items: usize) -> Result<usize, ProgramError> {
         Ok(TlvStateBorrowed::get_base_len()
             .saturating_add(ListView::<ExtraAccountMeta>::size_of(num_items)?))
     }
 
+    /// Get the byte size required to hold `num_items` items along with their
+    /// constraints
+    pub fn size_of_with_constraints(num_items: usize) -> Result<usize, ProgramError> {
+        Ok(Self::size_of(num_items)?
+            .saturating_add(TlvStateBorrowed::get_base_len())
+            .saturating_add(constraints::packed_len(num_items)))
+    }
+
     /// Checks provided account infos against validation data, using
     /// instruction data and program ID to resolve any dynamic PDAs
     /// if necessary.
     ///
     /// Note: this function will also verify all extra required accounts
-    /// have been provided in the correct order
+    /// have been provided in the correct order, and that optional accounts
+    /// which cannot be resolved are replaced with the program id
+    ///
+    /// Accepts any account type implementing `AccountInfoView`, such as
+    /// `AccountInfo` or, with the `pinocchio` feature, pinocchio's
+    /// `AccountView`.
     pub fn check_account_infos<T: SplDiscriminate>(
-        account_infos: &[AccountInfo],
+        account_infos: &[impl AccountInfoView],
         instruction_data: &[u8],
         program_id: &Pubkey,
         data: &[u8],
     ) -> Result<(), ProgramError> {
-        let state = TlvStateBorrowed::unpack(data).unwrap();
-        let extra_meta_list = ExtraAccountMetaList::unpack_with_tlv_state::<T>(&state)?;
+        Self::check_account_infos_with_bumps::<T>(
+            account_infos,
+            instruction_data,
+            program_id,
+            data,
+            &[],
+        )
+    }
 
-        let initial_accounts_len = account_infos.len() - extra_meta_list.len();
+    /// Checks provided account infos against validation data, like
+    /// `check_account_infos`, using known PDA bump seeds
+    ///
+    /// `known_bumps[i]` is the bump seed of the `i`-th extra account meta, if
+    /// it is a PDA and its bump is known. Providing it skips the search for
+    /// the bump, which is the most expensive part of the check.
+    pub fn check_account_infos_with_bumps<T: SplDiscriminate>(
+        account_infos: &[impl AccountInfoView],
+        instruction_data: &[u8],
+        program_id: &Pubkey,
+        data: &[u8],
+        known_bumps: &[Option<u8>],
+    ) -> Result<(), ProgramError> {
+        let state = TlvStateBorrowed::unpack(data)?;
+        let extra_meta_list = ExtraAccountMetaList::unpack_with_tlv_state::<T>(&state)?;
+        let extra_meta_constraints =
+            unpack_constraints(&state, T::SPL_DISCRIMINATOR, extra_meta_list.len())?;
+        Self::check_account_infos_with_metas(
+            &extra_meta_list,
+            extra_meta_constraints,
+            account_infos,
+            instruction_data,
+            program_id,
+            known_bumps,
+        )
+    }
 
-        // Convert to `AccountMeta` to check resolved metas
-        let provided_metas = account_infos
+    /// Helper to check provided account infos against a list of metas and
+    /// their constraints, which may be empty if there are none
+    pub(crate) fn check_account_infos_with_metas(
+        extra_meta_list: &[ExtraAccountMeta],
+        extra_meta_constraints: &[ExtraAccountMetaConstraints],
+        account_infos: &[impl AccountInfoView],
+        instruction_data: &[u8],
+        program_id: &Pubkey,
+        known_bumps: &[Option<u8>],
+    ) -> Result<(), ProgramError> {
+        // The extra accounts are expected at the end of the account list
+        let initial_accounts_len = account_infos
+            .len()
+            .checked_sub(extra_meta_list.len())
+            .ok_or(AccountResolutionError::NotEnoughAccounts)?;
+
+        // Create a list of `Ref`s so we can reference account data in the
+        // resolution step. Seeds refer to accounts by their index in the
+        // entire account list, including the extra accounts.
+        let account_key_data_refs = account_infos
             .iter()
-            .map(account_info_to_meta)
-            .collect::<Vec<_>>();
+            .map(|info| {
+                let key = info.key();
+                let data = info.try_borrow_data()?;
+                Ok((key, data))
+            })
+            .collect::<Result<Vec<_>, ProgramError>>()?;
 
-        for (i, config) in extra_meta_list.iter().enumerate() {
-            let meta = {
-                // Create a list of `Ref`s so we can reference account data in the
-                // resolution step
-                let account_key_data_refs = account_infos
-                    .iter()
-                    .map(|info| {
-                        let key = *info.key;
-                        let data = info.try_borrow_data()?;
-                        Ok((key, data))
-                    })
-                    .collect::<Result<Vec<_>, ProgramError>>()?;
+        let mut omitted_indices = vec![];
+        let mut pda_cache = PdaCache::default();
 
-                config.resolve(instruction_data, program_id, |usize| {
+        for (i, config) in extra_meta_list.iter().enumerate() {
+            let resolved = config.resolve_with_cache(
+                instruction_data,
+                program_id,
+                &mut pda_cache,
+                known_bumps.get(i).copied().flatten(),
+                |usize| {
+                    if omitted_indices.contains(&usize) {
+                        return None;
+                    }
                     account_key_data_refs
                         .get(usize)
                         .map(|(pubkey, opt_data)| (pubkey, Some(opt_data.as_ref())))
-                })?
-            };
+                },
+            );
 
-            // Ensure the account is in the correct position
+            // Ensure the account is in the correct position, with the
+            // expected privileges
             let expected_index = i
                 .checked_add(initial_accounts_len)
                 .ok_or::<ProgramError>(AccountResolutionError::CalculationFailure.into())?;
-            if provided_metas.get(expected_index) != Some(&meta) {
+            let provided = account_infos
+                .get(expected_index)
+                .ok_or(AccountResolutionError::NotEnoughAccounts)?;
+
+            let meta = match resolved {
+                Ok(meta) => meta,
+                // Omitted optional accounts are replaced with the program id
+                Err(_) if constraints::get(extra_meta_constraints, i).is_optional() => {
+                    if provided.key() != *program_id {
+                        return Err(AccountResolutionError::IncorrectAccount.into());
+                    }
+                    omitted_indices.push(expected_index);
+                    continue;
+                }
+                Err(err) => return Err(err),
+            };
+            let provided_key = provided.key();
+            if provided_key != meta.pubkey {
                 return Err(AccountResolutionError::IncorrectAccount.into());
             }
+            // The runtime merges the privileges of duplicate accounts, so an
+            // account also provided earlier may have more privileges than
+            // expected, but never fewer
+            let is_duplicate = account_infos[..expected_index]
+                .iter()
+                .any(|info| info.key() == provided_key);
+            if provided.is_signer() != meta.is_signer && !(is_duplicate && provided.is_signer()) {
+                return Err(AccountResolutionError::IncorrectAccountSigner.into());
+            }
+            if provided.is_writable() != meta.is_writable
+                && !(is_duplicate && provided.is_writable())
+            {
+                return Err(AccountResolutionError::IncorrectAccountWritable.into());
+            }
+            check_account_requirements(
+                constraints::get(extra_meta_constraints, i),
+                provided,
+                |index| {
+                    account_infos
+                        .get(index)
+                        .filter(|_| !omitted_indices.contains(&index))
+                        .map(|info| info.key())
+                },
+            )?;
         }
 
         Ok(())
@@ -265,90 +921,459 @@ impl ExtraAccountMetaList {
         F: Fn(Pubkey) -> Fut,
         Fut: Future<Output = AccountDataResult>,
     {
-        let state = TlvStateBorrowed::unpack(data)?;
-        let bytes = state.get_first_bytes::<T>()?;
-        let extra_account_metas = ListView::<ExtraAccountMeta>::unpack(bytes)?;
-
-        // Fetch account data for each of the instruction accounts
-        let mut account_key_datas = vec![];
-        for meta in instruction.accounts.iter() {
-            let account_data = fetch_account_data_fn(meta.pubkey)
-                .await
-                .map_err::<ProgramError, _>(|_| {
-                    AccountResolutionError::AccountFetchFailed.into()
-                })?;
-            account_key_datas.push((meta.pubkey, account_data));
-        }
+        Self::add_to_instruction_with_fetcher::<T, _>(instruction, &fetch_account_data_fn, data)
+            .await
+            .map(|_| ())
+    }
 
-        for extra_meta in extra_account_metas.iter() {
-            let mut meta =
-                extra_meta.resolve(&instruction.data, &instruction.program_id, |usize| {
-                    account_key_datas
-                        .get(usize)
-                        .map(|(pubkey, opt_data)| (pubkey, opt_data.as_ref().map(|x| x.as_slice())))
-                })?;
-            de_escalate_account_meta(&mut meta, &instruction.accounts);
-
-            // Fetch account data for the new account
-            account_key_datas.push((
-                meta.pubkey,
-                fetch_account_data_fn(meta.pubkey)
-                    .await
-                    .map_err::<ProgramError, _>(|_| {
-                        AccountResolutionError::AccountFetchFailed.into()
-                    })?,
-            ));
-            instruction.accounts.push(meta);
+    /// Add the additional account metas to an existing instruction, like
+    /// `add_to_instruction`, listing each account only once
+    ///
+    /// An extra account already in the instruction is not appended again.
+    /// Its privileges are merged into the first entry for the account instead.
+    /// Seeds still refer to accounts by their position in the instruction with
+    /// every extra account appended, so they resolve as with
+    /// `add_to_instruction`. However, the extra accounts are then no longer at
+    /// the positions expected by `check_account_infos`, so only use it for
+    /// programs looking up their extra accounts by address.
+    pub async fn add_to_instruction_dedup<T: SplDiscriminate, F, Fut>(
+        instruction: &mut Instruction,
+        fetch_account_data_fn: F,
+        data: &[u8],
+    ) -> Result<(), ProgramError>
+    where
+        F: Fn(Pubkey) -> Fut,
+        Fut: Future<Output = AccountDataResult>,
+    {
+        let extra_start = instruction.accounts.len();
+        Self::add_to_instruction::<T, F, Fut>(instruction, fetch_account_data_fn, data).await?;
+
+        for meta in instruction.accounts.split_off(extra_start) {
+            match instruction
+                .accounts
+                .iter_mut()
+                .find(|existing| existing.pubkey == meta.pubkey)
+            {
+                Some(existing) => {
+                    existing.is_signer |= meta.is_signer;
+                    existing.is_writable |= meta.is_writable;
+                }
+                None => instruction.accounts.push(meta),
+            }
         }
         Ok(())
     }
 
-    /// Add the additional account metas and account infos for a CPI
-    pub fn add_to_cpi_instruction<'a, T: SplDiscriminate>(
-        cpi_instruction: &mut Instruction,
-        cpi_account_infos: &mut Vec<AccountInfo<'a>>,
+    /// Add the additional account metas to an existing instruction, fetching
+    /// account data with the given `AccountFetcher`
+    ///
+    /// Account data is only fetched when an extra account meta requires it,
+    /// fetching all the accounts that have not been fetched yet at once.
+    ///
+    /// Returns the indices, in the instruction's accounts, of the optional
+    /// accounts that could not be resolved and were replaced with the
+    /// program id placeholder.
+    pub async fn add_to_instruction_with_fetcher<T: SplDiscriminate, F: AccountFetcher>(
+        instruction: &mut Instruction,
+        fetcher: &F,
         data: &[u8],
-        account_infos: &[AccountInfo<'a>],
-    ) -> Result<(), ProgramError> {
+    ) -> Result<Vec<usize>, ProgramError> {
+        Self::add_to_instruction_verbose::<T, F>(instruction, fetcher, data)
+            .await
+            .map_err(ProgramError::from)
+    }
+
+    /// Get the highest version of the extra account metas for the given
+    /// instruction present in the validation data, up to `MAX_VERSION`, see
+    /// `Versioned`
+    pub fn latest_version<T: SplDiscriminate>(data: &[u8]) -> Result<Option<u8>, ProgramError> {
+        let state = TlvStateBorrowed::unpack(data)?;
+        let discriminators = state.get_discriminators()?;
+        Ok((1..=MAX_VERSION).rev().find(|version| {
+            discriminators.contains(&versioned_discriminator(T::SPL_DISCRIMINATOR, *version))
+        }))
+    }
+
+    /// Add the additional account metas of the highest version present in
+    /// the validation data to an existing instruction, see `Versioned`
+    ///
+    /// Returns the version used.
+    pub async fn add_to_instruction_versioned<T: SplDiscriminate, F: AccountFetcher>(
+        instruction: &mut Instruction,
+        fetcher: &F,
+        data: &[u8],
+    ) -> Result<u8, ProgramError> {
+        let version = Self::latest_version::<T>(data)?.ok_or(TlvError::TypeNotFound)?;
+        let state = TlvStateBorrowed::unpack(data)?;
+        let bytes = state.get_first_bytes_with_discriminator(versioned_discriminator(
+            T::SPL_DISCRIMINATOR,
+            version,
+        ))?;
+        let extra_account_metas = ListView::<ExtraAccountMeta>::unpack(bytes)?;
+        let extra_meta_constraints = unpack_constraints(
+            &state,
+            versioned_discriminator(T::SPL_DISCRIMINATOR, version),
+            extra_account_metas.len(),
+        )?;
+        Self::add_to_instruction_with_metas(
+            instruction,
+            fetcher,
+            &extra_account_metas,
+            extra_meta_constraints,
+        )
+        .await?;
+        Ok(version)
+    }
+
+    /// Resolve the extra account metas for an instruction, without modifying
+    /// it, like `add_to_instruction_with_fetcher`
+    ///
+    /// On failure, the error locates the failing extra account meta and seed,
+    /// which is useful to debug a validation account off-chain.
+    pub async fn resolve_verbose<T: SplDiscriminate, F: AccountFetcher>(
+        instruction: &Instruction,
+        fetcher: &F,
+        data: &[u8],
+    ) -> Result<Vec<AccountMeta>, ResolutionFailure> {
+        let mut resolved_instruction = instruction.clone();
+        Self::add_to_instruction_verbose::<T, F>(&mut resolved_instruction, fetcher, data).await?;
+        Ok(resolved_instruction
+            .accounts
+            .split_off(instruction.accounts.len()))
+    }
+
+    /// Helper to add the extra account metas to an instruction, locating any
+    /// failure
+    async fn add_to_instruction_verbose<T: SplDiscriminate, F: AccountFetcher>(
+        instruction: &mut Instruction,
+        fetcher: &F,
+        data: &[u8],
+    ) -> Result<Vec<usize>, ResolutionFailure> {
         let state = TlvStateBorrowed::unpack(data)?;
         let bytes = state.get_first_bytes::<T>()?;
         let extra_account_metas = ListView::<ExtraAccountMeta>::unpack(bytes)?;
+        let extra_meta_constraints =
+            unpack_constraints(&state, T::SPL_DISCRIMINATOR, extra_account_metas.len())?;
+        Self::add_to_instruction_with_metas(
+            instruction,
+            fetcher,
+            &extra_account_metas,
+            extra_meta_constraints,
+        )
+        .await
+    }
 
-        for extra_meta in extra_account_metas.iter() {
-            let mut meta = {
-                // Create a list of `Ref`s so we can reference account data in the
-                // resolution step
-                let account_key_data_refs = cpi_account_infos
+    /// Helper to add a list of metas to an instruction, locating any failure
+    ///
+    /// The constraints on the metas may be empty if there are none.
+    pub(crate) async fn add_to_instruction_with_metas<F: AccountFetcher>(
+        instruction: &mut Instruction,
+        fetcher: &F,
+        extra_account_metas: &[ExtraAccountMeta],
+        extra_meta_constraints: &[ExtraAccountMetaConstraints],
+    ) -> Result<Vec<usize>, ResolutionFailure> {
+        let mut account_key_datas: Vec<(Pubkey, Option<Vec<u8>>)> = instruction
+            .accounts
+            .iter()
+            .map(|meta| (meta.pubkey, None))
+            .collect();
+        let mut fetched_len = 0;
+        let mut omitted_indices = vec![];
+        let mut pda_cache = PdaCache::default();
+
+        for (meta_index, extra_meta) in extra_account_metas.iter().enumerate() {
+            let failure = |seed_index, error| ResolutionFailure {
+                meta_index: Some(meta_index),
+                seed_index,
+                error,
+            };
+            // Invalid configurations fail to resolve below
+            let requires_account_data = extra_meta.requires_account_data().unwrap_or(false);
+            if requires_account_data && fetched_len < account_key_datas.len() {
+                let unfetched = &mut account_key_datas[fetched_len..];
+                let pubkeys = unfetched
                     .iter()
-                    .map(|info| {
-                        let key = *info.key;
-                        let data = info.try_borrow_data()?;
-                        Ok((key, data))
-                    })
-                    .collect::<Result<Vec<_>, ProgramError>>()?;
+                    .map(|(pubkey, _)| *pubkey)
+                    .collect::<Vec<_>>();
+                let account_datas = fetcher
+                    .get_multiple_account_data(&pubkeys)
+                    .await
+                    .ok()
+                    .filter(|account_datas| account_datas.len() == pubkeys.len())
+                    .ok_or_else(|| {
+                        failure(None, AccountResolutionError::AccountFetchFailed.into())
+                    })?;
+                for ((_, data), account_data) in unfetched.iter_mut().zip(account_datas) {
+                    *data = account_data;
+                }
+                fetched_len = account_key_datas.len();
+            }
 
-                extra_meta.resolve(
-                    &cpi_instruction.data,
-                    &cpi_instruction.program_id,
-                    |usize| {
-                        account_key_data_refs
-                            .get(usize)
-                            .map(|(pubkey, opt_data)| (pubkey, Some(opt_data.as_ref())))
-                    },
-                )?
+            let resolved = extra_meta.resolve_inner(
+                &instruction.data,
+                &instruction.program_id,
+                Some(&mut pda_cache),
+                None,
+                |usize| {
+                    if omitted_indices.contains(&usize) {
+                        return None;
+                    }
+                    account_key_datas
+                        .get(usize)
+                        .map(|(pubkey, opt_data)| (pubkey, opt_data.as_deref()))
+                },
+            );
+            let mut meta = match resolved {
+                Ok(meta) => meta,
+                Err(_) if constraints::get(extra_meta_constraints, meta_index).is_optional() => {
+                    omitted_indices.push(instruction.accounts.len());
+                    AccountMeta::new_readonly(instruction.program_id, false)
+                }
+                Err((seed_index, error)) => return Err(failure(seed_index, error)),
             };
-            de_escalate_account_meta(&mut meta, &cpi_instruction.accounts);
-
-            let account_info = account_infos
-                .iter()
-                .find(|&x| *x.key == meta.pubkey)
-                .ok_or(AccountResolutionError::IncorrectAccount)?
-                .clone();
+            merge_account_meta(&mut meta, &instruction.accounts);
 
-            cpi_instruction.accounts.push(meta);
-            cpi_account_infos.push(account_info);
+            account_key_datas.push((meta.pubkey, None));
+            instruction.accounts.push(meta);
         }
-        Ok(())
+        Ok(omitted_indices)
+    }
+
+    /// Add the additional account metas to an existing instruction meant for
+    /// a `v0` message, fetching account data with the given `AccountFetcher`
+    ///
+    /// Returns, for each added account meta, its location in the given address
+    /// lookup tables, if the account can be loaded through one of them.
+    /// Signers and the invoked program must be static account keys of the
+    /// message, so they are never reported in a table.
+    ///
+    /// The same tables can be given to `v0::Message::try_compile` to load the
+    /// reported accounts through them, keeping them out of the transaction's
+    /// static account keys.
+    pub async fn add_to_instruction_with_lookup_tables<T: SplDiscriminate, F: AccountFetcher>(
+        instruction: &mut Instruction,
+        fetcher: &F,
+        data: &[u8],
+        address_lookup_table_accounts: &[AddressLookupTableAccount],
+    ) -> Result<Vec<Option<LookupTableEntry>>, ProgramError> {
+        let extra_start = instruction.accounts.len();
+        Self::add_to_instruction_with_fetcher::<T, _>(instruction, fetcher, data).await?;
+
+        let lookup_table_entries = instruction.accounts[extra_start..]
+            .iter()
+            .map(|meta| {
+                let is_static = meta.pubkey == instruction.program_id
+                    || instruction
+                        .accounts
+                        .iter()
+                        .any(|other| other.pubkey == meta.pubkey && other.is_signer);
+                if is_static {
+                    return None;
+                }
+                address_lookup_table_accounts
+                    .iter()
+                    .enumerate()
+                    .find_map(|(table_index, table)| {
+                        let address_index = table
+                            .addresses
+                            .iter()
+                            .position(|address| *address == meta.pubkey)?;
+                        Some(LookupTableEntry {
+                            table_index,
+                            address_index: u8::try_from(address_index).ok()?,
+                        })
+                    })
+            })
+            .collect();
+        Ok(lookup_table_entries)
+    }
+
+    /// Resolve the extra account metas for an instruction without modifying
+    /// it, reporting the outcome of each meta
+    ///
+    /// Every resolved account is checked against `available_keys`, such as
+    /// the accounts an integrator is about to provide. Account data is not
+    /// fetched, so metas depending on it fail with `AccountDataNotFound`.
+    /// Resolution continues past failures, treating the failed accounts as
+    /// unknown.
+    pub fn diagnose<T: SplDiscriminate>(
+        instruction: &Instruction,
+        data: &[u8],
+        available_keys: &[Pubkey],
+    ) -> Result<ResolutionReport, ProgramError> {
+        let state = TlvStateBorrowed::unpack(data)?;
+        let extra_account_metas = ExtraAccountMetaList::unpack_with_tlv_state::<T>(&state)?;
+        let extra_meta_constraints =
+            unpack_constraints(&state, T::SPL_DISCRIMINATOR, extra_account_metas.len())?;
+
+        let mut account_keys: Vec<Option<Pubkey>> = instruction
+            .accounts
+            .iter()
+            .map(|meta| Some(meta.pubkey))
+            .collect();
+        let mut pda_cache = PdaCache::default();
+        let mut metas = Vec::with_capacity(extra_account_metas.len());
+
+        for (i, extra_meta) in extra_account_metas.iter().enumerate() {
+            let resolved = extra_meta.resolve_inner(
+                &instruction.data,
+                &instruction.program_id,
+                Some(&mut pda_cache),
+                None,
+                |usize| {
+                    account_keys
+                        .get(usize)
+                        .and_then(|key| key.as_ref())
+                        .map(|key| (key, None))
+                },
+            );
+            let (key, diagnosis) = match resolved {
+                Ok(meta) if available_keys.contains(&meta.pubkey) => {
+                    (Some(meta.pubkey), ExtraAccountMetaDiagnosis::Resolved(meta))
+                }
+                Ok(meta) => (Some(meta.pubkey), ExtraAccountMetaDiagnosis::Missing(meta)),
+                Err((seed_index, error))
+                    if constraints::get(extra_meta_constraints, i).is_optional() =>
+                {
+                    (
+                        None,
+                        ExtraAccountMetaDiagnosis::Omitted { seed_index, error },
+                    )
+                }
+                Err((seed_index, error)) => (
+                    None,
+                    ExtraAccountMetaDiagnosis::Failed { seed_index, error },
+                ),
+            };
+            account_keys.push(key);
+            metas.push(diagnosis);
+        }
+        Ok(ResolutionReport { metas })
+    }
+
+    /// Add the additional account metas and account infos for a CPI
+    ///
+    /// Optional accounts that cannot be resolved are replaced with the CPI
+    /// program id, whose account info must be provided in `account_infos`.
+    ///
+    /// The account infos may be of any type implementing `AccountInfoView`,
+    /// such as pinocchio's `AccountView`.
+    pub fn add_to_cpi_instruction<T: SplDiscriminate, A: AccountInfoView + Clone>(
+        cpi_instruction: &mut Instruction,
+        cpi_account_infos: &mut Vec<A>,
+        data: &[u8],
+        account_infos: &[A],
+    ) -> Result<(), ProgramError> {
+        Self::add_to_cpi_instruction_with_bumps::<T, A>(
+            cpi_instruction,
+            cpi_account_infos,
+            data,
+            account_infos,
+            &[],
+        )
+    }
+
+    /// Add the additional account metas and account infos for a CPI, like
+    /// `add_to_cpi_instruction`, using known PDA bump seeds
+    ///
+    /// `known_bumps[i]` is the bump seed of the `i`-th extra account meta, if
+    /// it is a PDA and its bump is known.
+    pub fn add_to_cpi_instruction_with_bumps<T: SplDiscriminate, A: AccountInfoView + Clone>(
+        cpi_instruction: &mut Instruction,
+        cpi_account_infos: &mut Vec<A>,
+        data: &[u8],
+        account_infos: &[A],
+        known_bumps: &[Option<u8>],
+    ) -> Result<(), ProgramError> {
+        let state = TlvStateBorrowed::unpack(data)?;
+        let bytes = state.get_first_bytes::<T>()?;
+        let extra_account_metas = ListView::<ExtraAccountMeta>::unpack(bytes)?;
+        let extra_meta_constraints =
+            unpack_constraints(&state, T::SPL_DISCRIMINATOR, extra_account_metas.len())?;
+        Self::add_to_cpi_instruction_with_metas(
+            cpi_instruction,
+            cpi_account_infos,
+            &extra_account_metas,
+            extra_meta_constraints,
+            account_infos,
+            known_bumps,
+        )
+    }
+
+    /// Helper to add a list of metas and their account infos for a CPI
+    ///
+    /// The constraints on the metas may be empty if there are none.
+    pub(crate) fn add_to_cpi_instruction_with_metas<A: AccountInfoView + Clone>(
+        cpi_instruction: &mut Instruction,
+        cpi_account_infos: &mut Vec<A>,
+        extra_account_metas: &[ExtraAccountMeta],
+        extra_meta_constraints: &[ExtraAccountMetaConstraints],
+        account_infos: &[A],
+        known_bumps: &[Option<u8>],
+    ) -> Result<(), ProgramError> {
+        let account_info_index = AccountInfoIndex::new(account_infos);
+
+        // Borrow the data of each account once, so it can be referenced in the
+        // resolution step. The resolved account infos are only added to the
+        // CPI account infos at the end, since they are borrowed until then.
+        let mut account_key_data_refs = cpi_account_infos
+            .iter()
+            .map(|info| Ok((info.key(), info.try_borrow_data()?)))
+            .collect::<Result<Vec<_>, ProgramError>>()?;
+        let mut resolved_account_infos = Vec::with_capacity(extra_account_metas.len());
+
+        let mut omitted_indices = vec![];
+        let mut pda_cache = PdaCache::default();
+
+        for (i, extra_meta) in extra_account_metas.iter().enumerate() {
+            let resolved = extra_meta.resolve_with_cache(
+                &cpi_instruction.data,
+                &cpi_instruction.program_id,
+                &mut pda_cache,
+                known_bumps.get(i).copied().flatten(),
+                |usize| {
+                    if omitted_indices.contains(&usize) {
+                        return None;
+                    }
+                    account_key_data_refs
+                        .get(usize)
+                        .map(|(pubkey, data)| (pubkey, Some(data.as_ref())))
+                },
+            );
+            let mut meta = match resolved {
+                Ok(meta) => meta,
+                Err(_) if constraints::get(extra_meta_constraints, i).is_optional() => {
+                    omitted_indices.push(account_key_data_refs.len());
+                    AccountMeta::new_readonly(cpi_instruction.program_id, false)
+                }
+                Err(err) => return Err(err),
+            };
+            merge_account_meta(&mut meta, &cpi_instruction.accounts);
+
+            let account_info = account_info_index
+                .find(&meta.pubkey)
+                .ok_or(AccountResolutionError::IncorrectAccount)?;
+            if !omitted_indices.contains(&account_key_data_refs.len()) {
+                check_account_requirements(
+                    constraints::get(extra_meta_constraints, i),
+                    account_info,
+                    |index| {
+                        account_key_data_refs
+                            .get(index)
+                            .filter(|_| !omitted_indices.contains(&index))
+                            .map(|(key, _)| *key)
+                    },
+                )?;
+            }
+
+            account_key_data_refs.push((account_info.key(), account_info.try_borrow_data()?));
+            cpi_instruction.accounts.push(meta);
+            resolved_account_infos.push(account_info);
+        }
+
+        drop(account_key_data_refs);
+        cpi_account_infos.extend(resolved_account_infos.into_iter().cloned());
+        Ok(())
     }
 }
 
@@ -356,11 +1381,20 @@ impl ExtraAccountMetaList {
 mod tests {
     use {
         super::*,
-        crate::{pubkey_data::PubkeyData, seeds::Seed},
+        crate::{
+            account::InvalidSeedConfig,
+            constraints::validate_constraints,
+            pubkey_data::PubkeyData,
+            seeds::{KnownAccountId, Seed},
+            versioned::Versioned,
+        },
         solana_instruction::AccountMeta,
         solana_pubkey::Pubkey,
         spl_discriminator::{ArrayDiscriminator, SplDiscriminate},
-        std::collections::HashMap,
+        std::{
+            cell::RefCell,
+            collections::{HashMap, HashSet},
+        },
     };
 
     pub struct TestInstruction;
@@ -395,6 +1429,25 @@ mod tests {
         }
     }
 
+    /// Fetcher recording the batches of requested accounts
+    #[derive(Default)]
+    struct BatchFetcher {
+        accounts: HashMap<Pubkey, Vec<u8>>,
+        requests: RefCell<Vec<Vec<Pubkey>>>,
+    }
+    impl AccountFetcher for BatchFetcher {
+        async fn get_multiple_account_data(
+            &self,
+            pubkeys: &[Pubkey],
+        ) -> Result<Vec<Option<Vec<u8>>>, AccountFetchError> {
+            self.requests.borrow_mut().push(pubkeys.to_vec());
+            Ok(pubkeys
+                .iter()
+                .map(|pubkey| self.accounts.get(pubkey).cloned())
+                .collect())
+        }
+    }
+
     /// Helper to convert an `AccountInfo` to an `AccountMeta`
     fn account_info_to_meta_non_signer(account_info: &AccountInfo) -> AccountMeta {
         AccountMeta {
@@ -643,95 +1696,162 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn init_multiple() {
-        let extra_meta5_literal_str = "seed_prefix";
-        let extra_meta5_literal_u32 = 4u32;
-        let other_meta2_literal_str = "other_seed_prefix";
+    async fn length_prefixed_instruction_data_seed() {
+        let program_id = Pubkey::new_unique();
 
-        let extra_meta1 = AccountMeta::new(Pubkey::new_unique(), false);
-        let extra_meta2 = AccountMeta::new(Pubkey::new_unique(), true);
-        let extra_meta3 = AccountMeta::new_readonly(Pubkey::new_unique(), true);
-        let extra_meta4 = AccountMeta::new_readonly(Pubkey::new_unique(), false);
-        let extra_meta5 = ExtraAccountMeta::new_with_seeds(
+        // A borsh string following an 8-byte discriminator
+        let metas = [ExtraAccountMeta::new_with_seeds(
             &[
                 Seed::Literal {
-                    bytes: extra_meta5_literal_str.as_bytes().to_vec(),
-                },
-                Seed::Literal {
-                    bytes: extra_meta5_literal_u32.to_le_bytes().to_vec(),
+                    bytes: b"name".to_vec(),
                 },
-                Seed::InstructionData {
-                    index: 5,
-                    length: 1, // u8
+                Seed::InstructionDataLengthPrefixed {
+                    offset: 8,
+                    prefix_width: 4,
                 },
-                Seed::AccountKey { index: 2 },
             ],
             false,
-            true,
-        )
-        .unwrap();
-        let extra_meta6 = ExtraAccountMeta::new_with_pubkey_data(
-            &PubkeyData::InstructionData { index: 8 },
             false,
-            true,
         )
-        .unwrap();
+        .unwrap()];
+        let mut buffer = vec![0; ExtraAccountMetaList::size_of(metas.len()).unwrap()];
+        ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &metas).unwrap();
 
-        let other_meta1 = AccountMeta::new(Pubkey::new_unique(), false);
-        let other_meta2 = ExtraAccountMeta::new_with_seeds(
+        let instruction_data = |name: &str| {
+            let mut data = vec![1; 8];
+            data.extend_from_slice(&(name.len() as u32).to_le_bytes());
+            data.extend_from_slice(name.as_bytes());
+            data
+        };
+        let buffer = &buffer;
+        let resolve = |data: Vec<u8>| async move {
+            let mut instruction = Instruction::new_with_bytes(program_id, &data, vec![]);
+            ExtraAccountMetaList::add_to_instruction::<TestInstruction, _, _>(
+                &mut instruction,
+                |_| async { Ok(None) },
+                buffer,
+            )
+            .await
+            .map(|()| instruction.accounts)
+        };
+
+        for name in ["", "alice", "a much longer name"] {
+            let pda = Pubkey::find_program_address(&[b"name", name.as_bytes()], &program_id).0;
+            assert_eq!(
+                resolve(instruction_data(name)).await.unwrap(),
+                [AccountMeta::new_readonly(pda, false)]
+            );
+        }
+
+        // The string is cut short
+        let mut data = instruction_data("alice");
+        data.pop();
+        assert_eq!(
+            resolve(data).await.unwrap_err(),
+            AccountResolutionError::InstructionDataTooSmall.into()
+        );
+        // The length prefix is cut short
+        assert_eq!(
+            resolve(vec![1; 10]).await.unwrap_err(),
+            AccountResolutionError::InstructionDataTooSmall.into()
+        );
+        // The string is too long to be a seed
+        assert_eq!(
+            resolve(instruction_data(&"a".repeat(33)))
+                .await
+                .unwrap_err(),
+            ProgramError::MaxSeedLengthExceeded
+        );
+    }
+
+    #[tokio::test]
+    async fn known_account_seed() {
+        let program_id = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+
+        // Associated token account-style seeds, without storing the token
+        // program id as a literal
+        let metas = [ExtraAccountMeta::new_with_seeds(
             &[
-                Seed::Literal {
-                    bytes: other_meta2_literal_str.as_bytes().to_vec(),
-                },
-                Seed::InstructionData {
-                    index: 1,
-                    length: 4, // u32
-                },
                 Seed::AccountKey { index: 0 },
+                Seed::KnownAccount(KnownAccountId::TokenProgram),
+                Seed::AccountKey { index: 1 },
             ],
             false,
             true,
         )
-        .unwrap();
-        let other_meta3 = ExtraAccountMeta::new_with_pubkey_data(
-            &PubkeyData::InstructionData { index: 7 },
-            false,
-            true,
+        .unwrap()];
+        let mut buffer = vec![0; ExtraAccountMetaList::size_of(metas.len()).unwrap()];
+        ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &metas).unwrap();
+
+        let mut instruction = Instruction::new_with_bytes(
+            program_id,
+            &[],
+            vec![
+                AccountMeta::new_readonly(owner, false),
+                AccountMeta::new_readonly(mint, false),
+            ],
+        );
+        ExtraAccountMetaList::add_to_instruction::<TestInstruction, _, _>(
+            &mut instruction,
+            |_| async { Ok(None) },
+            &buffer,
         )
+        .await
         .unwrap();
 
+        let token_program_id = KnownAccountId::TokenProgram.address();
+        assert_eq!(
+            token_program_id.to_string(),
+            "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+        );
+        let pda = Pubkey::find_program_address(
+            &[owner.as_ref(), token_program_id.as_ref(), mint.as_ref()],
+            &program_id,
+        )
+        .0;
+        assert_eq!(instruction.accounts[2], AccountMeta::new(pda, false));
+    }
+
+    #[tokio::test]
+    async fn program_id_seed() {
+        let program_id = Pubkey::new_unique();
+        let external_program_id = Pubkey::new_unique();
+
+        let ix_account = AccountMeta::new_readonly(external_program_id, false);
+
         let metas = [
-            ExtraAccountMeta::from(&extra_meta1),
-            ExtraAccountMeta::from(&extra_meta2),
-            ExtraAccountMeta::from(&extra_meta3),
-            ExtraAccountMeta::from(&extra_meta4),
-            extra_meta5,
-            extra_meta6,
-        ];
-        let other_metas = [
-            ExtraAccountMeta::from(&other_meta1),
-            other_meta2,
-            other_meta3,
+            ExtraAccountMeta::new_with_seeds(
+                &[
+                    Seed::Literal {
+                        bytes: b"config".to_vec(),
+                    },
+                    Seed::ProgramId,
+                ],
+                false,
+                false,
+            )
+            .unwrap(),
+            ExtraAccountMeta::new_external_pda_with_seeds(
+                0,
+                &[Seed::ProgramId, Seed::AccountKey { index: 1 }],
+                false,
+                true,
+            )
+            .unwrap(),
         ];
 
-        let account_size = ExtraAccountMetaList::size_of(metas.len()).unwrap()
-            + ExtraAccountMetaList::size_of(other_metas.len()).unwrap();
+        let mut instruction =
+            Instruction::new_with_bytes(program_id, &[], vec![ix_account.clone()]);
+
+        let account_size = ExtraAccountMetaList::size_of(metas.len()).unwrap();
         let mut buffer = vec![0; account_size];
 
         ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &metas).unwrap();
-        ExtraAccountMetaList::init::<TestOtherInstruction>(&mut buffer, &other_metas).unwrap();
 
         let mock_rpc = MockRpc::setup(&[]);
 
-        let program_id = Pubkey::new_unique();
-
-        let mut ix_data = vec![0, 0, 0, 0, 0, 7, 0, 0];
-        let check_extra_meta6_pubkey = Pubkey::new_unique();
-        ix_data.extend_from_slice(check_extra_meta6_pubkey.as_ref());
-
-        let ix_accounts = vec![];
-
-        let mut instruction = Instruction::new_with_bytes(program_id, &ix_data, ix_accounts);
         ExtraAccountMetaList::add_to_instruction::<TestInstruction, _, _>(
             &mut instruction,
             |pubkey| mock_rpc.get_account_data(pubkey),
@@ -740,48 +1860,67 @@ mod tests {
         .await
         .unwrap();
 
-        let check_extra_meta5_u8_arg = ix_data[5];
-        let check_extra_meta5_pubkey = Pubkey::find_program_address(
-            &[
-                extra_meta5_literal_str.as_bytes(),
-                extra_meta5_literal_u32.to_le_bytes().as_ref(),
-                &[check_extra_meta5_u8_arg],
-                extra_meta3.pubkey.as_ref(),
-            ],
-            &program_id,
+        let check_config_pubkey =
+            Pubkey::find_program_address(&[b"config", program_id.as_ref()], &program_id).0;
+        // The program id seed resolves to the instruction's program id, even
+        // when deriving for an external program
+        let check_external_pubkey = Pubkey::find_program_address(
+            &[program_id.as_ref(), check_config_pubkey.as_ref()],
+            &external_program_id,
         )
         .0;
-        let check_metas = [
-            de_escalate_signer(extra_meta1),
-            de_escalate_signer(extra_meta2),
-            de_escalate_signer(extra_meta3),
-            de_escalate_signer(extra_meta4),
-            AccountMeta::new(check_extra_meta5_pubkey, false),
-            AccountMeta::new(check_extra_meta6_pubkey, false),
-        ];
 
         assert_eq!(
-            instruction.accounts.get(4).unwrap().pubkey,
-            check_extra_meta5_pubkey,
-        );
-        assert_eq!(
-            instruction.accounts.get(5).unwrap().pubkey,
-            check_extra_meta6_pubkey,
+            instruction.accounts,
+            [
+                ix_account,
+                AccountMeta::new_readonly(check_config_pubkey, false),
+                AccountMeta::new(check_external_pubkey, false),
+            ]
         );
-        assert_eq!(instruction.accounts, check_metas,);
+    }
 
+    #[tokio::test]
+    async fn external_pda() {
         let program_id = Pubkey::new_unique();
+        let associated_token_program_id = Pubkey::new_unique();
+        let token_program_id = Pubkey::new_unique();
 
-        let ix_account1 = AccountMeta::new(Pubkey::new_unique(), false);
-        let ix_account2 = AccountMeta::new(Pubkey::new_unique(), true);
-        let ix_accounts = vec![ix_account1.clone(), ix_account2.clone()];
+        let mint = AccountMeta::new_readonly(Pubkey::new_unique(), false);
+        let owner = AccountMeta::new_readonly(Pubkey::new_unique(), true);
 
-        let mut ix_data = vec![0, 26, 0, 0, 0, 0, 0];
-        let check_other_meta3_pubkey = Pubkey::new_unique();
-        ix_data.extend_from_slice(check_other_meta3_pubkey.as_ref());
+        let metas = [
+            ExtraAccountMeta::from(AccountMeta::new_readonly(token_program_id, false)),
+            ExtraAccountMeta::from(AccountMeta::new_readonly(
+                associated_token_program_id,
+                false,
+            )),
+            // Associated token account of the owner, derived against the
+            // associated token program at index 3
+            ExtraAccountMeta::new_external_pda_with_seeds(
+                3,
+                &[
+                    Seed::AccountKey { index: 1 },
+                    Seed::AccountKey { index: 2 },
+                    Seed::AccountKey { index: 0 },
+                ],
+                false,
+                true,
+            )
+            .unwrap(),
+        ];
 
-        let mut instruction = Instruction::new_with_bytes(program_id, &ix_data, ix_accounts);
-        ExtraAccountMetaList::add_to_instruction::<TestOtherInstruction, _, _>(
+        let mut instruction =
+            Instruction::new_with_bytes(program_id, &[], vec![mint.clone(), owner.clone()]);
+
+        let account_size = ExtraAccountMetaList::size_of(metas.len()).unwrap();
+        let mut buffer = vec![0; account_size];
+
+        ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &metas).unwrap();
+
+        let mock_rpc = MockRpc::setup(&[]);
+
+        ExtraAccountMetaList::add_to_instruction::<TestInstruction, _, _>(
             &mut instruction,
             |pubkey| mock_rpc.get_account_data(pubkey),
             &buffer,
@@ -789,80 +1928,233 @@ mod tests {
         .await
         .unwrap();
 
-        let check_other_meta2_u32_arg = u32::from_le_bytes(ix_data[1..5].try_into().unwrap());
-        let check_other_meta2_pubkey = Pubkey::find_program_address(
+        let check_associated_token_account = Pubkey::find_program_address(
             &[
-                other_meta2_literal_str.as_bytes(),
-                check_other_meta2_u32_arg.to_le_bytes().as_ref(),
-                ix_account1.pubkey.as_ref(),
+                owner.pubkey.as_ref(),
+                token_program_id.as_ref(),
+                mint.pubkey.as_ref(),
             ],
-            &program_id,
+            &associated_token_program_id,
         )
         .0;
-        let check_other_metas = [
-            ix_account1,
-            ix_account2,
-            other_meta1,
-            AccountMeta::new(check_other_meta2_pubkey, false),
-            AccountMeta::new(check_other_meta3_pubkey, false),
-        ];
 
         assert_eq!(
-            instruction.accounts.get(3).unwrap().pubkey,
-            check_other_meta2_pubkey,
-        );
-        assert_eq!(
-            instruction.accounts.get(4).unwrap().pubkey,
-            check_other_meta3_pubkey,
+            instruction.accounts,
+            [
+                mint,
+                owner,
+                AccountMeta::new_readonly(token_program_id, false),
+                AccountMeta::new_readonly(associated_token_program_id, false),
+                AccountMeta::new(check_associated_token_account, false),
+            ]
+        );
+
+        // Fails if the program index is not in the account list yet
+        let metas = [ExtraAccountMeta::new_external_pda_with_seeds(
+            2,
+            &[Seed::AccountKey { index: 0 }],
+            false,
+            true,
+        )
+        .unwrap()];
+        let mut buffer = vec![0; ExtraAccountMetaList::size_of(metas.len()).unwrap()];
+        ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &metas).unwrap();
+
+        let mut instruction = Instruction::new_with_bytes(program_id, &[], vec![]);
+        assert_eq!(
+            ExtraAccountMetaList::add_to_instruction::<TestInstruction, _, _>(
+                &mut instruction,
+                |pubkey| mock_rpc.get_account_data(pubkey),
+                &buffer,
+            )
+            .await
+            .unwrap_err(),
+            AccountResolutionError::AccountNotFound.into(),
         );
-        assert_eq!(instruction.accounts, check_other_metas,);
     }
 
     #[tokio::test]
-    async fn init_mixed() {
-        let extra_meta5_literal_str = "seed_prefix";
-        let extra_meta6_literal_u64 = 28u64;
+    async fn add_to_instruction_with_fetcher() {
+        let program_id = Pubkey::new_unique();
 
-        let pubkey1 = Pubkey::new_unique();
-        let mut lamports1 = 0;
-        let mut data1 = [];
-        let pubkey2 = Pubkey::new_unique();
-        let mut lamports2 = 0;
-        let mut data2 = [];
-        let pubkey3 = Pubkey::new_unique();
-        let mut lamports3 = 0;
-        let mut data3 = [];
-        let owner = Pubkey::new_unique();
-        let account_infos = [
-            AccountInfo::new(
-                &pubkey1,
-                false,
-                true,
-                &mut lamports1,
-                &mut data1,
-                &owner,
-                false,
-            ),
-            AccountInfo::new(
-                &pubkey2,
-                true,
-                false,
-                &mut lamports2,
-                &mut data2,
-                &owner,
+        let ix_account = AccountMeta::new(Pubkey::new_unique(), false);
+        let extra_meta1 = AccountMeta::new_readonly(Pubkey::new_unique(), false);
+        let extra_meta2 = AccountMeta::new_readonly(Pubkey::new_unique(), false);
+        let stored_pubkey = Pubkey::new_unique();
+
+        let metas = [
+            ExtraAccountMeta::from(&extra_meta1),
+            ExtraAccountMeta::new_with_seeds(&[Seed::AccountKey { index: 1 }], false, true)
+                .unwrap(),
+            ExtraAccountMeta::new_with_pubkey_data(
+                &PubkeyData::AccountData {
+                    account_index: 1,
+                    data_index: 8,
+                },
                 false,
-            ),
-            AccountInfo::new(
-                &pubkey3,
                 false,
+            )
+            .unwrap(),
+            ExtraAccountMeta::from(&extra_meta2),
+            ExtraAccountMeta::new_with_seeds(
+                &[Seed::AccountData {
+                    account_index: 4,
+                    data_index: 0,
+                    length: 8,
+                }],
                 false,
-                &mut lamports3,
-                &mut data3,
-                &owner,
                 false,
-            ),
+            )
+            .unwrap(),
+        ];
+
+        let account_size = ExtraAccountMetaList::size_of(metas.len()).unwrap();
+        let mut buffer = vec![0; account_size];
+        ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &metas).unwrap();
+
+        let mut extra_meta1_data = vec![7; 8];
+        extra_meta1_data.extend_from_slice(stored_pubkey.as_ref());
+        let extra_meta2_data = vec![9; 8];
+
+        let fetcher = BatchFetcher {
+            accounts: HashMap::from([
+                (extra_meta1.pubkey, extra_meta1_data),
+                (extra_meta2.pubkey, extra_meta2_data.clone()),
+            ]),
+            ..BatchFetcher::default()
+        };
+
+        let mut instruction =
+            Instruction::new_with_bytes(program_id, &[], vec![ix_account.clone()]);
+        ExtraAccountMetaList::add_to_instruction_with_fetcher::<TestInstruction, _>(
+            &mut instruction,
+            &fetcher,
+            &buffer,
+        )
+        .await
+        .unwrap();
+
+        let check_pda = Pubkey::find_program_address(&[extra_meta1.pubkey.as_ref()], &program_id).0;
+        let check_data_pda = Pubkey::find_program_address(&[&extra_meta2_data], &program_id).0;
+
+        assert_eq!(
+            instruction.accounts,
+            [
+                ix_account.clone(),
+                extra_meta1.clone(),
+                AccountMeta::new(check_pda, false),
+                AccountMeta::new_readonly(stored_pubkey, false),
+                extra_meta2.clone(),
+                AccountMeta::new_readonly(check_data_pda, false),
+            ]
+        );
+
+        // Accounts are only fetched when needed, in batches
+        assert_eq!(
+            *fetcher.requests.borrow(),
+            [
+                vec![ix_account.pubkey, extra_meta1.pubkey, check_pda],
+                vec![stored_pubkey, extra_meta2.pubkey],
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn add_to_instruction_with_lookup_tables() {
+        let program_id = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+
+        let ix_account = AccountMeta::new(payer, true);
+        let extra_meta1 = AccountMeta::new_readonly(Pubkey::new_unique(), false);
+        let extra_meta2 = AccountMeta::new(Pubkey::new_unique(), false);
+        let extra_meta3 = AccountMeta::new_readonly(Pubkey::new_unique(), false);
+
+        let metas = [
+            ExtraAccountMeta::from(&extra_meta1),
+            ExtraAccountMeta::from(&extra_meta2),
+            ExtraAccountMeta::from(&extra_meta3),
+            // Signers must be static keys, even if found in a table
+            ExtraAccountMeta::from(AccountMeta::new(payer, false)),
+            ExtraAccountMeta::new_with_seeds(&[Seed::AccountKey { index: 1 }], false, true)
+                .unwrap(),
+        ];
+
+        let account_size = ExtraAccountMetaList::size_of(metas.len()).unwrap();
+        let mut buffer = vec![0; account_size];
+        ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &metas).unwrap();
+
+        let check_pda = Pubkey::find_program_address(&[extra_meta1.pubkey.as_ref()], &program_id).0;
+
+        let address_lookup_table_accounts = [
+            AddressLookupTableAccount {
+                key: Pubkey::new_unique(),
+                addresses: vec![Pubkey::new_unique(), extra_meta2.pubkey, payer],
+            },
+            AddressLookupTableAccount {
+                key: Pubkey::new_unique(),
+                addresses: vec![check_pda, extra_meta1.pubkey],
+            },
         ];
 
+        let mock_rpc = MockRpc::setup(&[]);
+        let mut instruction =
+            Instruction::new_with_bytes(program_id, &[], vec![ix_account.clone()]);
+        let lookup_table_entries =
+            ExtraAccountMetaList::add_to_instruction_with_lookup_tables::<TestInstruction, _>(
+                &mut instruction,
+                &|pubkey| mock_rpc.get_account_data(pubkey),
+                &buffer,
+                &address_lookup_table_accounts,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            lookup_table_entries,
+            [
+                Some(LookupTableEntry {
+                    table_index: 1,
+                    address_index: 1,
+                }),
+                Some(LookupTableEntry {
+                    table_index: 0,
+                    address_index: 1,
+                }),
+                None,
+                None,
+                Some(LookupTableEntry {
+                    table_index: 1,
+                    address_index: 0,
+                }),
+            ]
+        );
+
+        // The reported accounts are loaded through the tables
+        let message = solana_message::v0::Message::try_compile(
+            &payer,
+            &[instruction],
+            &address_lookup_table_accounts,
+            solana_message::Hash::default(),
+        )
+        .unwrap();
+
+        assert_eq!(message.account_keys.len(), 3);
+        assert_eq!(message.account_keys[0], payer);
+        assert!(message.account_keys.contains(&extra_meta3.pubkey));
+        assert!(message.account_keys.contains(&program_id));
+        assert_eq!(message.address_table_lookups.len(), 2);
+        assert_eq!(message.address_table_lookups[0].writable_indexes, [1]);
+        assert!(message.address_table_lookups[0].readonly_indexes.is_empty());
+        assert_eq!(message.address_table_lookups[1].writable_indexes, [0]);
+        assert_eq!(message.address_table_lookups[1].readonly_indexes, [1]);
+    }
+
+    #[tokio::test]
+    async fn init_multiple() {
+        let extra_meta5_literal_str = "seed_prefix";
+        let extra_meta5_literal_u32 = 4u32;
+        let other_meta2_literal_str = "other_seed_prefix";
+
         let extra_meta1 = AccountMeta::new(Pubkey::new_unique(), false);
         let extra_meta2 = AccountMeta::new(Pubkey::new_unique(), true);
         let extra_meta3 = AccountMeta::new_readonly(Pubkey::new_unique(), true);
@@ -872,13 +2164,12 @@ mod tests {
                 Seed::Literal {
                     bytes: extra_meta5_literal_str.as_bytes().to_vec(),
                 },
-                Seed::InstructionData {
-                    index: 1,
-                    length: 8, // [u8; 8]
+                Seed::Literal {
+                    bytes: extra_meta5_literal_u32.to_le_bytes().to_vec(),
                 },
                 Seed::InstructionData {
-                    index: 9,
-                    length: 32, // Pubkey
+                    index: 5,
+                    length: 1, // u8
                 },
                 Seed::AccountKey { index: 2 },
             ],
@@ -886,86 +2177,69 @@ mod tests {
             true,
         )
         .unwrap();
-        let extra_meta6 = ExtraAccountMeta::new_with_seeds(
+        let extra_meta6 = ExtraAccountMeta::new_with_pubkey_data(
+            &PubkeyData::InstructionData { index: 8 },
+            false,
+            true,
+        )
+        .unwrap();
+
+        let other_meta1 = AccountMeta::new(Pubkey::new_unique(), false);
+        let other_meta2 = ExtraAccountMeta::new_with_seeds(
             &[
                 Seed::Literal {
-                    bytes: extra_meta6_literal_u64.to_le_bytes().to_vec(),
+                    bytes: other_meta2_literal_str.as_bytes().to_vec(),
                 },
-                Seed::AccountKey { index: 1 },
-                Seed::AccountKey { index: 4 },
+                Seed::InstructionData {
+                    index: 1,
+                    length: 4, // u32
+                },
+                Seed::AccountKey { index: 0 },
             ],
             false,
             true,
         )
         .unwrap();
-        let extra_meta7 = ExtraAccountMeta::new_with_pubkey_data(
-            &PubkeyData::InstructionData { index: 41 }, // After the other pubkey arg.
+        let other_meta3 = ExtraAccountMeta::new_with_pubkey_data(
+            &PubkeyData::InstructionData { index: 7 },
             false,
             true,
         )
         .unwrap();
 
-        let test_ix_required_extra_accounts = account_infos
-            .iter()
-            .map(ExtraAccountMeta::from)
-            .collect::<Vec<_>>();
-        let test_other_ix_required_extra_accounts = [
+        let metas = [
             ExtraAccountMeta::from(&extra_meta1),
             ExtraAccountMeta::from(&extra_meta2),
             ExtraAccountMeta::from(&extra_meta3),
             ExtraAccountMeta::from(&extra_meta4),
             extra_meta5,
             extra_meta6,
-            extra_meta7,
+        ];
+        let other_metas = [
+            ExtraAccountMeta::from(&other_meta1),
+            other_meta2,
+            other_meta3,
         ];
 
-        let account_size = ExtraAccountMetaList::size_of(test_ix_required_extra_accounts.len())
-            .unwrap()
-            + ExtraAccountMetaList::size_of(test_other_ix_required_extra_accounts.len()).unwrap();
+        let account_size = ExtraAccountMetaList::size_of(metas.len()).unwrap()
+            + ExtraAccountMetaList::size_of(other_metas.len()).unwrap();
         let mut buffer = vec![0; account_size];
 
-        ExtraAccountMetaList::init::<TestInstruction>(
-            &mut buffer,
-            &test_ix_required_extra_accounts,
-        )
-        .unwrap();
-        ExtraAccountMetaList::init::<TestOtherInstruction>(
-            &mut buffer,
-            &test_other_ix_required_extra_accounts,
-        )
-        .unwrap();
-
-        let mock_rpc = MockRpc::setup(&account_infos);
-
-        let program_id = Pubkey::new_unique();
-        let mut instruction = Instruction::new_with_bytes(program_id, &[], vec![]);
-        ExtraAccountMetaList::add_to_instruction::<TestInstruction, _, _>(
-            &mut instruction,
-            |pubkey| mock_rpc.get_account_data(pubkey),
-            &buffer,
-        )
-        .await
-        .unwrap();
+        ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &metas).unwrap();
+        ExtraAccountMetaList::init::<TestOtherInstruction>(&mut buffer, &other_metas).unwrap();
 
-        let test_ix_check_metas = account_infos
-            .iter()
-            .map(account_info_to_meta_non_signer)
-            .collect::<Vec<_>>();
-        assert_eq!(instruction.accounts, test_ix_check_metas,);
+        let mock_rpc = MockRpc::setup(&[]);
 
         let program_id = Pubkey::new_unique();
 
-        let instruction_u8array_arg = [1, 2, 3, 4, 5, 6, 7, 8];
-        let instruction_pubkey_arg = Pubkey::new_unique();
-        let instruction_key_data_pubkey_arg = Pubkey::new_unique();
+        let mut ix_data = vec![0, 0, 0, 0, 0, 7, 0, 0];
+        let check_extra_meta6_pubkey = Pubkey::new_unique();
+        ix_data.extend_from_slice(check_extra_meta6_pubkey.as_ref());
 
-        let mut instruction_data = vec![0];
-        instruction_data.extend_from_slice(&instruction_u8array_arg);
-        instruction_data.extend_from_slice(instruction_pubkey_arg.as_ref());
-        instruction_data.extend_from_slice(instruction_key_data_pubkey_arg.as_ref());
+        let ix_accounts = vec![];
 
-        let mut instruction = Instruction::new_with_bytes(program_id, &instruction_data, vec![]);
-        ExtraAccountMetaList::add_to_instruction::<TestOtherInstruction, _, _>(
+        let mut instruction = Instruction::new_with_bytes(program_id, &ix_data, ix_accounts);
+        ExtraAccountMetaList::add_to_instruction::<TestInstruction, _, _>(
             &mut instruction,
             |pubkey| mock_rpc.get_account_data(pubkey),
             &buffer,
@@ -973,35 +2247,24 @@ mod tests {
         .await
         .unwrap();
 
+        let check_extra_meta5_u8_arg = ix_data[5];
         let check_extra_meta5_pubkey = Pubkey::find_program_address(
             &[
                 extra_meta5_literal_str.as_bytes(),
-                &instruction_u8array_arg,
-                instruction_pubkey_arg.as_ref(),
+                extra_meta5_literal_u32.to_le_bytes().as_ref(),
+                &[check_extra_meta5_u8_arg],
                 extra_meta3.pubkey.as_ref(),
             ],
             &program_id,
         )
         .0;
-
-        let check_extra_meta6_pubkey = Pubkey::find_program_address(
-            &[
-                extra_meta6_literal_u64.to_le_bytes().as_ref(),
-                extra_meta2.pubkey.as_ref(),
-                check_extra_meta5_pubkey.as_ref(), // The first PDA should be at index 4
-            ],
-            &program_id,
-        )
-        .0;
-
-        let test_other_ix_check_metas = vec![
+        let check_metas = [
             de_escalate_signer(extra_meta1),
             de_escalate_signer(extra_meta2),
             de_escalate_signer(extra_meta3),
             de_escalate_signer(extra_meta4),
             AccountMeta::new(check_extra_meta5_pubkey, false),
             AccountMeta::new(check_extra_meta6_pubkey, false),
-            AccountMeta::new(instruction_key_data_pubkey_arg, false),
         ];
 
         assert_eq!(
@@ -1012,361 +2275,177 @@ mod tests {
             instruction.accounts.get(5).unwrap().pubkey,
             check_extra_meta6_pubkey,
         );
-        assert_eq!(
-            instruction.accounts.get(6).unwrap().pubkey,
-            instruction_key_data_pubkey_arg,
-        );
-        assert_eq!(instruction.accounts, test_other_ix_check_metas,);
-    }
-
-    #[tokio::test]
-    async fn cpi_instruction() {
-        // Say we have a program that CPIs to another program.
-        //
-        // Say that _other_ program will need extra account infos.
+        assert_eq!(instruction.accounts, check_metas,);
 
-        // This will be our program
         let program_id = Pubkey::new_unique();
-        let owner = Pubkey::new_unique();
-
-        // Some seeds used by the program for PDAs
-        let required_pda1_literal_string = "required_pda1";
-        let required_pda2_literal_u32 = 4u32;
-        let required_key_data_instruction_data = Pubkey::new_unique();
 
-        // Define instruction data
-        //  - 0: u8
-        //  - 1-8: [u8; 8]
-        //  - 9-16: u64
-        let instruction_u8array_arg = [1, 2, 3, 4, 5, 6, 7, 8];
-        let instruction_u64_arg = 208u64;
-        let mut instruction_data = vec![0];
-        instruction_data.extend_from_slice(&instruction_u8array_arg);
-        instruction_data.extend_from_slice(instruction_u64_arg.to_le_bytes().as_ref());
-        instruction_data.extend_from_slice(required_key_data_instruction_data.as_ref());
-
-        // Define known instruction accounts
-        let ix_accounts = vec![
-            AccountMeta::new(Pubkey::new_unique(), false),
-            AccountMeta::new(Pubkey::new_unique(), false),
-        ];
-
-        // Define extra account metas required by the program we will CPI to
-        let extra_meta1 = AccountMeta::new(Pubkey::new_unique(), false);
-        let extra_meta2 = AccountMeta::new(Pubkey::new_unique(), true);
-        let extra_meta3 = AccountMeta::new_readonly(Pubkey::new_unique(), false);
-        let required_accounts = [
-            ExtraAccountMeta::from(&extra_meta1),
-            ExtraAccountMeta::from(&extra_meta2),
-            ExtraAccountMeta::from(&extra_meta3),
-            ExtraAccountMeta::new_with_seeds(
-                &[
-                    Seed::Literal {
-                        bytes: required_pda1_literal_string.as_bytes().to_vec(),
-                    },
-                    Seed::InstructionData {
-                        index: 1,
-                        length: 8, // [u8; 8]
-                    },
-                    Seed::AccountKey { index: 1 },
-                ],
-                false,
-                true,
-            )
-            .unwrap(),
-            ExtraAccountMeta::new_with_seeds(
-                &[
-                    Seed::Literal {
-                        bytes: required_pda2_literal_u32.to_le_bytes().to_vec(),
-                    },
-                    Seed::InstructionData {
-                        index: 9,
-                        length: 8, // u64
-                    },
-                    Seed::AccountKey { index: 5 },
-                ],
-                false,
-                true,
-            )
-            .unwrap(),
-            ExtraAccountMeta::new_with_seeds(
-                &[
-                    Seed::InstructionData {
-                        index: 0,
-                        length: 1, // u8
-                    },
-                    Seed::AccountData {
-                        account_index: 2,
-                        data_index: 0,
-                        length: 8,
-                    },
-                ],
-                false,
-                true,
-            )
-            .unwrap(),
-            ExtraAccountMeta::new_with_seeds(
-                &[
-                    Seed::AccountData {
-                        account_index: 5,
-                        data_index: 4,
-                        length: 4,
-                    }, // This one is a PDA!
-                ],
-                false,
-                true,
-            )
-            .unwrap(),
-            ExtraAccountMeta::new_with_pubkey_data(
-                &PubkeyData::InstructionData { index: 17 },
-                false,
-                true,
-            )
-            .unwrap(),
-            ExtraAccountMeta::new_with_pubkey_data(
-                &PubkeyData::AccountData {
-                    account_index: 6,
-                    data_index: 0,
-                },
-                false,
-                true,
-            )
-            .unwrap(),
-            ExtraAccountMeta::new_with_pubkey_data(
-                &PubkeyData::AccountData {
-                    account_index: 7,
-                    data_index: 8,
-                },
-                false,
-                true,
-            )
-            .unwrap(),
-        ];
+        let ix_account1 = AccountMeta::new(Pubkey::new_unique(), false);
+        let ix_account2 = AccountMeta::new(Pubkey::new_unique(), true);
+        let ix_accounts = vec![ix_account1.clone(), ix_account2.clone()];
 
-        // Now here we're going to build the list of account infos
-        // We'll need to include:
-        //  - The instruction account infos for the program to CPI to
-        //  - The extra account infos for the program to CPI to
-        //  - Some other arbitrary account infos our program may use
+        let mut ix_data = vec![0, 26, 0, 0, 0, 0, 0];
+        let check_other_meta3_pubkey = Pubkey::new_unique();
+        ix_data.extend_from_slice(check_other_meta3_pubkey.as_ref());
 
-        // First we need to manually derive each PDA
-        let check_required_pda1_pubkey = Pubkey::find_program_address(
-            &[
-                required_pda1_literal_string.as_bytes(),
-                &instruction_u8array_arg,
-                ix_accounts.get(1).unwrap().pubkey.as_ref(), // The second account
-            ],
-            &program_id,
-        )
-        .0;
-        let check_required_pda2_pubkey = Pubkey::find_program_address(
-            &[
-                required_pda2_literal_u32.to_le_bytes().as_ref(),
-                instruction_u64_arg.to_le_bytes().as_ref(),
-                check_required_pda1_pubkey.as_ref(), // The first PDA should be at index 5
-            ],
-            &program_id,
-        )
-        .0;
-        let check_required_pda3_pubkey = Pubkey::find_program_address(
-            &[
-                &[0],    // Instruction "discriminator" (u8)
-                &[8; 8], // The first 8 bytes of the data for account at index 2 (extra account 1)
-            ],
-            &program_id,
+        let mut instruction = Instruction::new_with_bytes(program_id, &ix_data, ix_accounts);
+        ExtraAccountMetaList::add_to_instruction::<TestOtherInstruction, _, _>(
+            &mut instruction,
+            |pubkey| mock_rpc.get_account_data(pubkey),
+            &buffer,
         )
-        .0;
-        let check_required_pda4_pubkey = Pubkey::find_program_address(
+        .await
+        .unwrap();
+
+        let check_other_meta2_u32_arg = u32::from_le_bytes(ix_data[1..5].try_into().unwrap());
+        let check_other_meta2_pubkey = Pubkey::find_program_address(
             &[
-                &[7; 4], /* 4 bytes starting at index 4 of the data for account at index 5 (extra
-                         * pda 1) */
+                other_meta2_literal_str.as_bytes(),
+                check_other_meta2_u32_arg.to_le_bytes().as_ref(),
+                ix_account1.pubkey.as_ref(),
             ],
             &program_id,
         )
         .0;
-        let check_key_data1_pubkey = required_key_data_instruction_data;
-        let check_key_data2_pubkey = Pubkey::new_from_array([8; 32]);
-        let check_key_data3_pubkey = Pubkey::new_from_array([9; 32]);
+        let check_other_metas = [
+            ix_account1,
+            ix_account2,
+            other_meta1,
+            AccountMeta::new(check_other_meta2_pubkey, false),
+            AccountMeta::new(check_other_meta3_pubkey, false),
+        ];
 
-        // The instruction account infos for the program to CPI to
-        let pubkey_ix_1 = ix_accounts.first().unwrap().pubkey;
-        let mut lamports_ix_1 = 0;
-        let mut data_ix_1 = [];
-        let pubkey_ix_2 = ix_accounts.get(1).unwrap().pubkey;
-        let mut lamports_ix_2 = 0;
-        let mut data_ix_2 = [];
+        assert_eq!(
+            instruction.accounts.get(3).unwrap().pubkey,
+            check_other_meta2_pubkey,
+        );
+        assert_eq!(
+            instruction.accounts.get(4).unwrap().pubkey,
+            check_other_meta3_pubkey,
+        );
+        assert_eq!(instruction.accounts, check_other_metas,);
+    }
 
-        // The extra account infos for the program to CPI to
+    #[tokio::test]
+    async fn init_mixed() {
+        let extra_meta5_literal_str = "seed_prefix";
+        let extra_meta6_literal_u64 = 28u64;
+
+        let pubkey1 = Pubkey::new_unique();
         let mut lamports1 = 0;
-        let mut data1 = [8; 12];
+        let mut data1 = [];
+        let pubkey2 = Pubkey::new_unique();
         let mut lamports2 = 0;
         let mut data2 = [];
+        let pubkey3 = Pubkey::new_unique();
         let mut lamports3 = 0;
         let mut data3 = [];
-        let mut lamports_pda1 = 0;
-        let mut data_pda1 = [7; 12];
-        let mut lamports_pda2 = 0;
-        let mut data_pda2 = [8; 32];
-        let mut lamports_pda3 = 0;
-        let mut data_pda3 = [0; 40];
-        data_pda3[8..].copy_from_slice(&[9; 32]); // Add pubkey data for pubkey data pubkey 3.
-        let mut lamports_pda4 = 0;
-        let mut data_pda4 = [];
-        let mut data_key_data1 = [];
-        let mut lamports_key_data1 = 0;
-        let mut data_key_data2 = [];
-        let mut lamports_key_data2 = 0;
-        let mut data_key_data3 = [];
-        let mut lamports_key_data3 = 0;
-
-        // Some other arbitrary account infos our program may use
-        let pubkey_arb_1 = Pubkey::new_unique();
-        let mut lamports_arb_1 = 0;
-        let mut data_arb_1 = [];
-        let pubkey_arb_2 = Pubkey::new_unique();
-        let mut lamports_arb_2 = 0;
-        let mut data_arb_2 = [];
-
-        let all_account_infos = [
-            AccountInfo::new(
-                &pubkey_ix_1,
-                ix_accounts.first().unwrap().is_signer,
-                ix_accounts.first().unwrap().is_writable,
-                &mut lamports_ix_1,
-                &mut data_ix_1,
-                &owner,
-                false,
-            ),
+        let owner = Pubkey::new_unique();
+        let account_infos = [
             AccountInfo::new(
-                &pubkey_ix_2,
-                ix_accounts.get(1).unwrap().is_signer,
-                ix_accounts.get(1).unwrap().is_writable,
-                &mut lamports_ix_2,
-                &mut data_ix_2,
-                &owner,
+                &pubkey1,
                 false,
-            ),
-            AccountInfo::new(
-                &extra_meta1.pubkey,
-                required_accounts.first().unwrap().is_signer.into(),
-                required_accounts.first().unwrap().is_writable.into(),
+                true,
                 &mut lamports1,
                 &mut data1,
                 &owner,
                 false,
             ),
             AccountInfo::new(
-                &extra_meta2.pubkey,
-                required_accounts.get(1).unwrap().is_signer.into(),
-                required_accounts.get(1).unwrap().is_writable.into(),
+                &pubkey2,
+                true,
+                false,
                 &mut lamports2,
                 &mut data2,
                 &owner,
                 false,
             ),
             AccountInfo::new(
-                &extra_meta3.pubkey,
-                required_accounts.get(2).unwrap().is_signer.into(),
-                required_accounts.get(2).unwrap().is_writable.into(),
-                &mut lamports3,
-                &mut data3,
-                &owner,
-                false,
-            ),
-            AccountInfo::new(
-                &check_required_pda1_pubkey,
-                required_accounts.get(3).unwrap().is_signer.into(),
-                required_accounts.get(3).unwrap().is_writable.into(),
-                &mut lamports_pda1,
-                &mut data_pda1,
-                &owner,
-                false,
-            ),
-            AccountInfo::new(
-                &check_required_pda2_pubkey,
-                required_accounts.get(4).unwrap().is_signer.into(),
-                required_accounts.get(4).unwrap().is_writable.into(),
-                &mut lamports_pda2,
-                &mut data_pda2,
-                &owner,
-                false,
-            ),
-            AccountInfo::new(
-                &check_required_pda3_pubkey,
-                required_accounts.get(5).unwrap().is_signer.into(),
-                required_accounts.get(5).unwrap().is_writable.into(),
-                &mut lamports_pda3,
-                &mut data_pda3,
-                &owner,
-                false,
-            ),
-            AccountInfo::new(
-                &check_required_pda4_pubkey,
-                required_accounts.get(6).unwrap().is_signer.into(),
-                required_accounts.get(6).unwrap().is_writable.into(),
-                &mut lamports_pda4,
-                &mut data_pda4,
-                &owner,
-                false,
-            ),
-            AccountInfo::new(
-                &check_key_data1_pubkey,
-                required_accounts.get(7).unwrap().is_signer.into(),
-                required_accounts.get(7).unwrap().is_writable.into(),
-                &mut lamports_key_data1,
-                &mut data_key_data1,
-                &owner,
-                false,
-            ),
-            AccountInfo::new(
-                &check_key_data2_pubkey,
-                required_accounts.get(8).unwrap().is_signer.into(),
-                required_accounts.get(8).unwrap().is_writable.into(),
-                &mut lamports_key_data2,
-                &mut data_key_data2,
-                &owner,
-                false,
-            ),
-            AccountInfo::new(
-                &check_key_data3_pubkey,
-                required_accounts.get(9).unwrap().is_signer.into(),
-                required_accounts.get(9).unwrap().is_writable.into(),
-                &mut lamports_key_data3,
-                &mut data_key_data3,
-                &owner,
-                false,
-            ),
-            AccountInfo::new(
-                &pubkey_arb_1,
-                false,
-                true,
-                &mut lamports_arb_1,
-                &mut data_arb_1,
-                &owner,
+                &pubkey3,
                 false,
-            ),
-            AccountInfo::new(
-                &pubkey_arb_2,
                 false,
-                true,
-                &mut lamports_arb_2,
-                &mut data_arb_2,
+                &mut lamports3,
+                &mut data3,
                 &owner,
                 false,
             ),
         ];
 
-        // Let's use a mock RPC and set up a test instruction to check the CPI
-        // instruction against later
-        let rpc_account_infos = all_account_infos.clone();
-        let mock_rpc = MockRpc::setup(&rpc_account_infos);
+        let extra_meta1 = AccountMeta::new(Pubkey::new_unique(), false);
+        let extra_meta2 = AccountMeta::new(Pubkey::new_unique(), true);
+        let extra_meta3 = AccountMeta::new_readonly(Pubkey::new_unique(), true);
+        let extra_meta4 = AccountMeta::new_readonly(Pubkey::new_unique(), false);
+        let extra_meta5 = ExtraAccountMeta::new_with_seeds(
+            &[
+                Seed::Literal {
+                    bytes: extra_meta5_literal_str.as_bytes().to_vec(),
+                },
+                Seed::InstructionData {
+                    index: 1,
+                    length: 8, // [u8; 8]
+                },
+                Seed::InstructionData {
+                    index: 9,
+                    length: 32, // Pubkey
+                },
+                Seed::AccountKey { index: 2 },
+            ],
+            false,
+            true,
+        )
+        .unwrap();
+        let extra_meta6 = ExtraAccountMeta::new_with_seeds(
+            &[
+                Seed::Literal {
+                    bytes: extra_meta6_literal_u64.to_le_bytes().to_vec(),
+                },
+                Seed::AccountKey { index: 1 },
+                Seed::AccountKey { index: 4 },
+            ],
+            false,
+            true,
+        )
+        .unwrap();
+        let extra_meta7 = ExtraAccountMeta::new_with_pubkey_data(
+            &PubkeyData::InstructionData { index: 41 }, // After the other pubkey arg.
+            false,
+            true,
+        )
+        .unwrap();
 
-        let account_size = ExtraAccountMetaList::size_of(required_accounts.len()).unwrap();
+        let test_ix_required_extra_accounts = account_infos
+            .iter()
+            .map(ExtraAccountMeta::from)
+            .collect::<Vec<_>>();
+        let test_other_ix_required_extra_accounts = [
+            ExtraAccountMeta::from(&extra_meta1),
+            ExtraAccountMeta::from(&extra_meta2),
+            ExtraAccountMeta::from(&extra_meta3),
+            ExtraAccountMeta::from(&extra_meta4),
+            extra_meta5,
+            extra_meta6,
+            extra_meta7,
+        ];
+
+        let account_size = ExtraAccountMetaList::size_of(test_ix_required_extra_accounts.len())
+            .unwrap()
+            + ExtraAccountMetaList::size_of(test_other_ix_required_extra_accounts.len()).unwrap();
         let mut buffer = vec![0; account_size];
-        ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &required_accounts).unwrap();
 
-        let mut instruction =
-            Instruction::new_with_bytes(program_id, &instruction_data, ix_accounts.clone());
+        ExtraAccountMetaList::init::<TestInstruction>(
+            &mut buffer,
+            &test_ix_required_extra_accounts,
+        )
+        .unwrap();
+        ExtraAccountMetaList::init::<TestOtherInstruction>(
+            &mut buffer,
+            &test_other_ix_required_extra_accounts,
+        )
+        .unwrap();
+
+        let mock_rpc = MockRpc::setup(&account_infos);
+
+        let program_id = Pubkey::new_unique();
+        let mut instruction = Instruction::new_with_bytes(program_id, &[], vec![]);
         ExtraAccountMetaList::add_to_instruction::<TestInstruction, _, _>(
             &mut instruction,
             |pubkey| mock_rpc.get_account_data(pubkey),
@@ -1375,166 +2454,2242 @@ mod tests {
         .await
         .unwrap();
 
-        // Perform the account resolution for the CPI instruction
+        let test_ix_check_metas = account_infos
+            .iter()
+            .map(account_info_to_meta_non_signer)
+            .collect::<Vec<_>>();
+        assert_eq!(instruction.accounts, test_ix_check_metas,);
 
-        // Create the instruction itself
-        let mut cpi_instruction =
-            Instruction::new_with_bytes(program_id, &instruction_data, ix_accounts);
+        let program_id = Pubkey::new_unique();
 
-        // Start with the known account infos
-        let mut cpi_account_infos =
-            vec![all_account_infos[0].clone(), all_account_infos[1].clone()];
+        let instruction_u8array_arg = [1, 2, 3, 4, 5, 6, 7, 8];
+        let instruction_pubkey_arg = Pubkey::new_unique();
+        let instruction_key_data_pubkey_arg = Pubkey::new_unique();
 
-        // Mess up the ordering of the account infos to make it harder!
-        let mut messed_account_infos = all_account_infos.clone();
-        messed_account_infos.swap(0, 4);
-        messed_account_infos.swap(1, 2);
-        messed_account_infos.swap(3, 4);
-        messed_account_infos.swap(5, 6);
-        messed_account_infos.swap(8, 7);
+        let mut instruction_data = vec![0];
+        instruction_data.extend_from_slice(&instruction_u8array_arg);
+        instruction_data.extend_from_slice(instruction_pubkey_arg.as_ref());
+        instruction_data.extend_from_slice(instruction_key_data_pubkey_arg.as_ref());
 
-        // Resolve the rest!
-        ExtraAccountMetaList::add_to_cpi_instruction::<TestInstruction>(
-            &mut cpi_instruction,
-            &mut cpi_account_infos,
+        let mut instruction = Instruction::new_with_bytes(program_id, &instruction_data, vec![]);
+        ExtraAccountMetaList::add_to_instruction::<TestOtherInstruction, _, _>(
+            &mut instruction,
+            |pubkey| mock_rpc.get_account_data(pubkey),
+            &buffer,
+        )
+        .await
+        .unwrap();
+
+        let check_extra_meta5_pubkey = Pubkey::find_program_address(
+            &[
+                extra_meta5_literal_str.as_bytes(),
+                &instruction_u8array_arg,
+                instruction_pubkey_arg.as_ref(),
+                extra_meta3.pubkey.as_ref(),
+            ],
+            &program_id,
+        )
+        .0;
+
+        let check_extra_meta6_pubkey = Pubkey::find_program_address(
+            &[
+                extra_meta6_literal_u64.to_le_bytes().as_ref(),
+                extra_meta2.pubkey.as_ref(),
+                check_extra_meta5_pubkey.as_ref(), // The first PDA should be at index 4
+            ],
+            &program_id,
+        )
+        .0;
+
+        let test_other_ix_check_metas = vec![
+            de_escalate_signer(extra_meta1),
+            de_escalate_signer(extra_meta2),
+            de_escalate_signer(extra_meta3),
+            de_escalate_signer(extra_meta4),
+            AccountMeta::new(check_extra_meta5_pubkey, false),
+            AccountMeta::new(check_extra_meta6_pubkey, false),
+            AccountMeta::new(instruction_key_data_pubkey_arg, false),
+        ];
+
+        assert_eq!(
+            instruction.accounts.get(4).unwrap().pubkey,
+            check_extra_meta5_pubkey,
+        );
+        assert_eq!(
+            instruction.accounts.get(5).unwrap().pubkey,
+            check_extra_meta6_pubkey,
+        );
+        assert_eq!(
+            instruction.accounts.get(6).unwrap().pubkey,
+            instruction_key_data_pubkey_arg,
+        );
+        assert_eq!(instruction.accounts, test_other_ix_check_metas,);
+    }
+
+    #[tokio::test]
+    async fn cpi_instruction() {
+        // Say we have a program that CPIs to another program.
+        //
+        // Say that _other_ program will need extra account infos.
+
+        // This will be our program
+        let program_id = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+
+        // Some seeds used by the program for PDAs
+        let required_pda1_literal_string = "required_pda1";
+        let required_pda2_literal_u32 = 4u32;
+        let required_key_data_instruction_data = Pubkey::new_unique();
+
+        // Define instruction data
+        //  - 0: u8
+        //  - 1-8: [u8; 8]
+        //  - 9-16: u64
+        let instruction_u8array_arg = [1, 2, 3, 4, 5, 6, 7, 8];
+        let instruction_u64_arg = 208u64;
+        let mut instruction_data = vec![0];
+        instruction_data.extend_from_slice(&instruction_u8array_arg);
+        instruction_data.extend_from_slice(instruction_u64_arg.to_le_bytes().as_ref());
+        instruction_data.extend_from_slice(required_key_data_instruction_data.as_ref());
+
+        // Define known instruction accounts
+        let ix_accounts = vec![
+            AccountMeta::new(Pubkey::new_unique(), false),
+            AccountMeta::new(Pubkey::new_unique(), false),
+        ];
+
+        // Define extra account metas required by the program we will CPI to
+        let extra_meta1 = AccountMeta::new(Pubkey::new_unique(), false);
+        let extra_meta2 = AccountMeta::new(Pubkey::new_unique(), true);
+        let extra_meta3 = AccountMeta::new_readonly(Pubkey::new_unique(), false);
+        let required_accounts = [
+            ExtraAccountMeta::from(&extra_meta1),
+            ExtraAccountMeta::from(&extra_meta2),
+            ExtraAccountMeta::from(&extra_meta3),
+            ExtraAccountMeta::new_with_seeds(
+                &[
+                    Seed::Literal {
+                        bytes: required_pda1_literal_string.as_bytes().to_vec(),
+                    },
+                    Seed::InstructionData {
+                        index: 1,
+                        length: 8, // [u8; 8]
+                    },
+                    Seed::AccountKey { index: 1 },
+                ],
+                false,
+                true,
+            )
+            .unwrap(),
+            ExtraAccountMeta::new_with_seeds(
+                &[
+                    Seed::Literal {
+                        bytes: required_pda2_literal_u32.to_le_bytes().to_vec(),
+                    },
+                    Seed::InstructionData {
+                        index: 9,
+                        length: 8, // u64
+                    },
+                    Seed::AccountKey { index: 5 },
+                ],
+                false,
+                true,
+            )
+            .unwrap(),
+            ExtraAccountMeta::new_with_seeds(
+                &[
+                    Seed::InstructionData {
+                        index: 0,
+                        length: 1, // u8
+                    },
+                    Seed::AccountData {
+                        account_index: 2,
+                        data_index: 0,
+                        length: 8,
+                    },
+                ],
+                false,
+                true,
+            )
+            .unwrap(),
+            ExtraAccountMeta::new_with_seeds(
+                &[
+                    Seed::AccountData {
+                        account_index: 5,
+                        data_index: 4,
+                        length: 4,
+                    }, // This one is a PDA!
+                ],
+                false,
+                true,
+            )
+            .unwrap(),
+            ExtraAccountMeta::new_with_pubkey_data(
+                &PubkeyData::InstructionData { index: 17 },
+                false,
+                true,
+            )
+            .unwrap(),
+            ExtraAccountMeta::new_with_pubkey_data(
+                &PubkeyData::AccountData {
+                    account_index: 6,
+                    data_index: 0,
+                },
+                false,
+                true,
+            )
+            .unwrap(),
+            ExtraAccountMeta::new_with_pubkey_data(
+                &PubkeyData::AccountData {
+                    account_index: 7,
+                    data_index: 8,
+                },
+                false,
+                true,
+            )
+            .unwrap(),
+        ];
+
+        // Now here we're going to build the list of account infos
+        // We'll need to include:
+        //  - The instruction account infos for the program to CPI to
+        //  - The extra account infos for the program to CPI to
+        //  - Some other arbitrary account infos our program may use
+
+        // First we need to manually derive each PDA
+        let check_required_pda1_pubkey = Pubkey::find_program_address(
+            &[
+                required_pda1_literal_string.as_bytes(),
+                &instruction_u8array_arg,
+                ix_accounts.get(1).unwrap().pubkey.as_ref(), // The second account
+            ],
+            &program_id,
+        )
+        .0;
+        let check_required_pda2_pubkey = Pubkey::find_program_address(
+            &[
+                required_pda2_literal_u32.to_le_bytes().as_ref(),
+                instruction_u64_arg.to_le_bytes().as_ref(),
+                check_required_pda1_pubkey.as_ref(), // The first PDA should be at index 5
+            ],
+            &program_id,
+        )
+        .0;
+        let check_required_pda3_pubkey = Pubkey::find_program_address(
+            &[
+                &[0],    // Instruction "discriminator" (u8)
+                &[8; 8], // The first 8 bytes of the data for account at index 2 (extra account 1)
+            ],
+            &program_id,
+        )
+        .0;
+        let check_required_pda4_pubkey = Pubkey::find_program_address(
+            &[
+                &[7; 4], /* 4 bytes starting at index 4 of the data for account at index 5 (extra
+                         * pda 1) */
+            ],
+            &program_id,
+        )
+        .0;
+        let check_key_data1_pubkey = required_key_data_instruction_data;
+        let check_key_data2_pubkey = Pubkey::new_from_array([8; 32]);
+        let check_key_data3_pubkey = Pubkey::new_from_array([9; 32]);
+
+        // The instruction account infos for the program to CPI to
+        let pubkey_ix_1 = ix_accounts.first().unwrap().pubkey;
+        let mut lamports_ix_1 = 0;
+        let mut data_ix_1 = [];
+        let pubkey_ix_2 = ix_accounts.get(1).unwrap().pubkey;
+        let mut lamports_ix_2 = 0;
+        let mut data_ix_2 = [];
+
+        // The extra account infos for the program to CPI to
+        let mut lamports1 = 0;
+        let mut data1 = [8; 12];
+        let mut lamports2 = 0;
+        let mut data2 = [];
+        let mut lamports3 = 0;
+        let mut data3 = [];
+        let mut lamports_pda1 = 0;
+        let mut data_pda1 = [7; 12];
+        let mut lamports_pda2 = 0;
+        let mut data_pda2 = [8; 32];
+        let mut lamports_pda3 = 0;
+        let mut data_pda3 = [0; 40];
+        data_pda3[8..].copy_from_slice(&[9; 32]); // Add pubkey data for pubkey data pubkey 3.
+        let mut lamports_pda4 = 0;
+        let mut data_pda4 = [];
+        let mut data_key_data1 = [];
+        let mut lamports_key_data1 = 0;
+        let mut data_key_data2 = [];
+        let mut lamports_key_data2 = 0;
+        let mut data_key_data3 = [];
+        let mut lamports_key_data3 = 0;
+
+        // Some other arbitrary account infos our program may use
+        let pubkey_arb_1 = Pubkey::new_unique();
+        let mut lamports_arb_1 = 0;
+        let mut data_arb_1 = [];
+        let pubkey_arb_2 = Pubkey::new_unique();
+        let mut lamports_arb_2 = 0;
+        let mut data_arb_2 = [];
+
+        let all_account_infos = [
+            AccountInfo::new(
+                &pubkey_ix_1,
+                ix_accounts.first().unwrap().is_signer,
+                ix_accounts.first().unwrap().is_writable,
+                &mut lamports_ix_1,
+                &mut data_ix_1,
+                &owner,
+                false,
+            ),
+            AccountInfo::new(
+                &pubkey_ix_2,
+                ix_accounts.get(1).unwrap().is_signer,
+                ix_accounts.get(1).unwrap().is_writable,
+                &mut lamports_ix_2,
+                &mut data_ix_2,
+                &owner,
+                false,
+            ),
+            AccountInfo::new(
+                &extra_meta1.pubkey,
+                required_accounts.first().unwrap().is_signer.into(),
+                required_accounts.first().unwrap().is_writable.into(),
+                &mut lamports1,
+                &mut data1,
+                &owner,
+                false,
+            ),
+            AccountInfo::new(
+                &extra_meta2.pubkey,
+                required_accounts.get(1).unwrap().is_signer.into(),
+                required_accounts.get(1).unwrap().is_writable.into(),
+                &mut lamports2,
+                &mut data2,
+                &owner,
+                false,
+            ),
+            AccountInfo::new(
+                &extra_meta3.pubkey,
+                required_accounts.get(2).unwrap().is_signer.into(),
+                required_accounts.get(2).unwrap().is_writable.into(),
+                &mut lamports3,
+                &mut data3,
+                &owner,
+                false,
+            ),
+            AccountInfo::new(
+                &check_required_pda1_pubkey,
+                required_accounts.get(3).unwrap().is_signer.into(),
+                required_accounts.get(3).unwrap().is_writable.into(),
+                &mut lamports_pda1,
+                &mut data_pda1,
+                &owner,
+                false,
+            ),
+            AccountInfo::new(
+                &check_required_pda2_pubkey,
+                required_accounts.get(4).unwrap().is_signer.into(),
+                required_accounts.get(4).unwrap().is_writable.into(),
+                &mut lamports_pda2,
+                &mut data_pda2,
+                &owner,
+                false,
+            ),
+            AccountInfo::new(
+                &check_required_pda3_pubkey,
+                required_accounts.get(5).unwrap().is_signer.into(),
+                required_accounts.get(5).unwrap().is_writable.into(),
+                &mut lamports_pda3,
+                &mut data_pda3,
+                &owner,
+                false,
+            ),
+            AccountInfo::new(
+                &check_required_pda4_pubkey,
+                required_accounts.get(6).unwrap().is_signer.into(),
+                required_accounts.get(6).unwrap().is_writable.into(),
+                &mut lamports_pda4,
+                &mut data_pda4,
+                &owner,
+                false,
+            ),
+            AccountInfo::new(
+                &check_key_data1_pubkey,
+                required_accounts.get(7).unwrap().is_signer.into(),
+                required_accounts.get(7).unwrap().is_writable.into(),
+                &mut lamports_key_data1,
+                &mut data_key_data1,
+                &owner,
+                false,
+            ),
+            AccountInfo::new(
+                &check_key_data2_pubkey,
+                required_accounts.get(8).unwrap().is_signer.into(),
+                required_accounts.get(8).unwrap().is_writable.into(),
+                &mut lamports_key_data2,
+                &mut data_key_data2,
+                &owner,
+                false,
+            ),
+            AccountInfo::new(
+                &check_key_data3_pubkey,
+                required_accounts.get(9).unwrap().is_signer.into(),
+                required_accounts.get(9).unwrap().is_writable.into(),
+                &mut lamports_key_data3,
+                &mut data_key_data3,
+                &owner,
+                false,
+            ),
+            AccountInfo::new(
+                &pubkey_arb_1,
+                false,
+                true,
+                &mut lamports_arb_1,
+                &mut data_arb_1,
+                &owner,
+                false,
+            ),
+            AccountInfo::new(
+                &pubkey_arb_2,
+                false,
+                true,
+                &mut lamports_arb_2,
+                &mut data_arb_2,
+                &owner,
+                false,
+            ),
+        ];
+
+        // Let's use a mock RPC and set up a test instruction to check the CPI
+        // instruction against later
+        let rpc_account_infos = all_account_infos.clone();
+        let mock_rpc = MockRpc::setup(&rpc_account_infos);
+
+        let account_size = ExtraAccountMetaList::size_of(required_accounts.len()).unwrap();
+        let mut buffer = vec![0; account_size];
+        ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &required_accounts).unwrap();
+
+        let mut instruction =
+            Instruction::new_with_bytes(program_id, &instruction_data, ix_accounts.clone());
+        ExtraAccountMetaList::add_to_instruction::<TestInstruction, _, _>(
+            &mut instruction,
+            |pubkey| mock_rpc.get_account_data(pubkey),
+            &buffer,
+        )
+        .await
+        .unwrap();
+
+        // Perform the account resolution for the CPI instruction
+
+        // Create the instruction itself
+        let mut cpi_instruction =
+            Instruction::new_with_bytes(program_id, &instruction_data, ix_accounts);
+
+        // Start with the known account infos
+        let mut cpi_account_infos =
+            vec![all_account_infos[0].clone(), all_account_infos[1].clone()];
+
+        // Mess up the ordering of the account infos to make it harder!
+        let mut messed_account_infos = all_account_infos.clone();
+        messed_account_infos.swap(0, 4);
+        messed_account_infos.swap(1, 2);
+        messed_account_infos.swap(3, 4);
+        messed_account_infos.swap(5, 6);
+        messed_account_infos.swap(8, 7);
+
+        // Resolve the rest!
+        ExtraAccountMetaList::add_to_cpi_instruction::<TestInstruction, _>(
+            &mut cpi_instruction,
+            &mut cpi_account_infos,
+            &buffer,
+            &messed_account_infos,
+        )
+        .unwrap();
+
+        // Our CPI instruction should match the check instruction.
+        assert_eq!(cpi_instruction, instruction);
+
+        // CPI account infos should have the instruction account infos
+        // and the extra required account infos from the validation account,
+        // and they should be in the correct order.
+        // Note: The two additional arbitrary account infos for the currently
+        // executing program won't be present in the CPI instruction's account
+        // infos, so we will omit them (hence the `..9`).
+        let check_account_infos = &all_account_infos[..12];
+        assert_eq!(cpi_account_infos.len(), check_account_infos.len());
+        for (a, b) in std::iter::zip(cpi_account_infos, check_account_infos) {
+            assert_eq!(a.key, b.key);
+            assert_eq!(a.is_signer, b.is_signer);
+            assert_eq!(a.is_writable, b.is_writable);
+        }
+    }
+
+    async fn update_and_assert_metas(
+        program_id: Pubkey,
+        buffer: &mut Vec<u8>,
+        updated_metas: &[ExtraAccountMeta],
+        check_metas: &[AccountMeta],
+    ) {
+        // resize buffer if necessary
+        let account_size = ExtraAccountMetaList::size_of(updated_metas.len()).unwrap();
+        if account_size > buffer.len() {
+            buffer.resize(account_size, 0);
+        }
+
+        // update
+        ExtraAccountMetaList::update::<TestInstruction>(buffer, updated_metas).unwrap();
+
+        // retrieve metas and assert
+        let state = TlvStateBorrowed::unpack(buffer).unwrap();
+        let unpacked_metas_pod =
+            ExtraAccountMetaList::unpack_with_tlv_state::<TestInstruction>(&state).unwrap();
+        assert_eq!(
+            &*unpacked_metas_pod, updated_metas,
+            "The ExtraAccountMetas in the buffer should match the expected ones."
+        );
+
+        let mock_rpc = MockRpc::setup(&[]);
+
+        let mut instruction = Instruction::new_with_bytes(program_id, &[], vec![]);
+        ExtraAccountMetaList::add_to_instruction::<TestInstruction, _, _>(
+            &mut instruction,
+            |pubkey| mock_rpc.get_account_data(pubkey),
+            buffer,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(instruction.accounts, check_metas,);
+    }
+
+    #[tokio::test]
+    async fn update_extra_account_meta_list() {
+        let program_id = Pubkey::new_unique();
+
+        // Create list of initial metas
+        let initial_metas = [
+            ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), false, true).unwrap(),
+            ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), true, false).unwrap(),
+        ];
+
+        // initialize
+        let initial_account_size = ExtraAccountMetaList::size_of(initial_metas.len()).unwrap();
+        let mut buffer = vec![0; initial_account_size];
+        ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &initial_metas).unwrap();
+
+        // Create updated metas list of the same size
+        let updated_metas_1 = [
+            ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), true, true).unwrap(),
+            ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), false, false).unwrap(),
+        ];
+        let check_metas_1 = updated_metas_1
+            .iter()
+            .map(|e| de_escalate_signer(AccountMeta::try_from(e).unwrap()))
+            .collect::<Vec<_>>();
+        update_and_assert_metas(program_id, &mut buffer, &updated_metas_1, &check_metas_1).await;
+
+        // Create updated and larger list of metas
+        let updated_metas_2 = [
+            ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), true, true).unwrap(),
+            ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), false, false).unwrap(),
+            ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), false, true).unwrap(),
+        ];
+        let check_metas_2 = updated_metas_2
+            .iter()
+            .map(|e| de_escalate_signer(AccountMeta::try_from(e).unwrap()))
+            .collect::<Vec<_>>();
+        update_and_assert_metas(program_id, &mut buffer, &updated_metas_2, &check_metas_2).await;
+
+        // Create updated and smaller list of metas
+        let updated_metas_3 =
+            [ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), true, true).unwrap()];
+        let check_metas_3 = updated_metas_3
+            .iter()
+            .map(|e| de_escalate_signer(AccountMeta::try_from(e).unwrap()))
+            .collect::<Vec<_>>();
+        update_and_assert_metas(program_id, &mut buffer, &updated_metas_3, &check_metas_3).await;
+
+        // Create updated list of metas with a simple PDA
+        let seed_pubkey = Pubkey::new_unique();
+        let updated_metas_4 = [
+            ExtraAccountMeta::new_with_pubkey(&seed_pubkey, true, true).unwrap(),
+            ExtraAccountMeta::new_with_seeds(
+                &[
+                    Seed::Literal {
+                        bytes: b"seed-prefix".to_vec(),
+                    },
+                    Seed::AccountKey { index: 0 },
+                ],
+                false,
+                true,
+            )
+            .unwrap(),
+        ];
+        let simple_pda = Pubkey::find_program_address(
+            &[
+                b"seed-prefix",       // Literal prefix
+                seed_pubkey.as_ref(), // Account at index 0
+            ],
+            &program_id,
+        )
+        .0;
+        let check_metas_4 = [
+            AccountMeta::new(seed_pubkey, false),
+            AccountMeta::new(simple_pda, false),
+        ];
+
+        update_and_assert_metas(program_id, &mut buffer, &updated_metas_4, &check_metas_4).await;
+    }
+
+    #[test]
+    fn update_with_account_metas_and_infos() {
+        let initial_metas = [
+            AccountMeta::new(Pubkey::new_unique(), false).into(),
+            AccountMeta::new_readonly(Pubkey::new_unique(), false).into(),
+        ];
+        let other_metas = [AccountMeta::new(Pubkey::new_unique(), true).into()];
+
+        // Leave space for one more meta after both entries
+        let account_size = ExtraAccountMetaList::size_of(initial_metas.len()).unwrap()
+            + ExtraAccountMetaList::size_of(other_metas.len()).unwrap()
+            + std::mem::size_of::<ExtraAccountMeta>();
+        let mut buffer = vec![0; account_size];
+        ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &initial_metas).unwrap();
+        ExtraAccountMetaList::init::<TestOtherInstruction>(&mut buffer, &other_metas).unwrap();
+
+        let assert_metas = |buffer: &[u8], expected: &[AccountMeta]| {
+            let state = TlvStateBorrowed::unpack(buffer).unwrap();
+            let unpacked =
+                ExtraAccountMetaList::unpack_with_tlv_state::<TestInstruction>(&state).unwrap();
+            let expected = expected
+                .iter()
+                .map(ExtraAccountMeta::from)
+                .collect::<Vec<_>>();
+            assert_eq!(&*unpacked, expected.as_slice());
+
+            // The following entry is preserved
+            let unpacked =
+                ExtraAccountMetaList::unpack_with_tlv_state::<TestOtherInstruction>(&state)
+                    .unwrap();
+            assert_eq!(&*unpacked, &other_metas);
+        };
+
+        // Grow using account metas
+        let grown_metas = [
+            AccountMeta::new(Pubkey::new_unique(), false),
+            AccountMeta::new(Pubkey::new_unique(), true),
+            AccountMeta::new_readonly(Pubkey::new_unique(), false),
+        ];
+        ExtraAccountMetaList::update_with_account_metas::<TestInstruction>(
+            &mut buffer,
+            &grown_metas,
+        )
+        .unwrap();
+        assert_metas(&buffer, &grown_metas);
+
+        // Growing past the available space fails, leaving the data untouched
+        let too_many_metas = vec![AccountMeta::new(Pubkey::new_unique(), false); 4];
+        assert_eq!(
+            ExtraAccountMetaList::update_with_account_metas::<TestInstruction>(
+                &mut buffer,
+                &too_many_metas,
+            )
+            .unwrap_err(),
+            ProgramError::InvalidAccountData,
+        );
+        assert_metas(&buffer, &grown_metas);
+
+        // Shrink using account infos
+        let pubkey = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = [];
+        let account_infos = [AccountInfo::new(
+            &pubkey,
+            true,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+        )];
+        ExtraAccountMetaList::update_with_account_infos::<TestInstruction>(
+            &mut buffer,
+            &account_infos,
+        )
+        .unwrap();
+        assert_metas(&buffer, &[AccountMeta::new_readonly(pubkey, true)]);
+
+        // The freed space at the end of the buffer is zeroed
+        let used_size = ExtraAccountMetaList::size_of(1).unwrap()
+            + ExtraAccountMetaList::size_of(other_metas.len()).unwrap();
+        assert!(buffer[used_size..].iter().all(|&byte| byte == 0));
+
+        // Updating a missing entry fails
+        let mut buffer = vec![0; ExtraAccountMetaList::size_of(1).unwrap()];
+        assert_eq!(
+            ExtraAccountMetaList::update_with_account_metas::<TestInstruction>(
+                &mut buffer,
+                &grown_metas[..1],
+            )
+            .unwrap_err(),
+            TlvError::TypeNotFound.into(),
+        );
+    }
+
+    #[test]
+    fn remove_extra_account_meta_list() {
+        let metas = [
+            AccountMeta::new(Pubkey::new_unique(), false).into(),
+            AccountMeta::new_readonly(Pubkey::new_unique(), false).into(),
+        ];
+        let other_metas = [AccountMeta::new(Pubkey::new_unique(), true).into()];
+
+        let account_size = ExtraAccountMetaList::size_of(metas.len()).unwrap()
+            + ExtraAccountMetaList::size_of(other_metas.len()).unwrap();
+        let mut buffer = vec![0; account_size];
+        ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &metas).unwrap();
+        ExtraAccountMetaList::init::<TestOtherInstruction>(&mut buffer, &other_metas).unwrap();
+
+        ExtraAccountMetaList::remove::<TestInstruction>(&mut buffer).unwrap();
+
+        let state = TlvStateBorrowed::unpack(&buffer).unwrap();
+        assert_eq!(
+            ExtraAccountMetaList::unpack_with_tlv_state::<TestInstruction>(&state).unwrap_err(),
+            TlvError::TypeNotFound.into(),
+        );
+        let unpacked =
+            ExtraAccountMetaList::unpack_with_tlv_state::<TestOtherInstruction>(&state).unwrap();
+        assert_eq!(&*unpacked, &other_metas);
+
+        // Removing a missing entry fails
+        assert_eq!(
+            ExtraAccountMetaList::remove::<TestInstruction>(&mut buffer).unwrap_err(),
+            TlvError::TypeNotFound.into(),
+        );
+
+        // The entry can be initialized again in the freed space
+        ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &metas).unwrap();
+
+        ExtraAccountMetaList::remove::<TestOtherInstruction>(&mut buffer).unwrap();
+        ExtraAccountMetaList::remove::<TestInstruction>(&mut buffer).unwrap();
+        assert!(buffer.iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn update_and_remove_constraints() {
+        let metas = [
+            AccountMeta::new(Pubkey::new_unique(), false).into(),
+            AccountMeta::new_readonly(Pubkey::new_unique(), false).into(),
+        ];
+        let optional = ExtraAccountMetaConstraints::default().with_optional(true);
+
+        let mut buffer = vec![0; ExtraAccountMetaList::size_of_with_constraints(2).unwrap()];
+        ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &metas[..1]).unwrap();
+        assert_eq!(
+            ExtraAccountMetaList::constraints::<TestInstruction>(&buffer).unwrap(),
+            [ExtraAccountMetaConstraints::default()]
+        );
+
+        // The constraints entry is created, then resized along with the metas
+        ExtraAccountMetaList::update_with_constraints::<TestInstruction>(
+            &mut buffer,
+            &metas[..1],
+            &[optional],
+        )
+        .unwrap();
+        assert_eq!(
+            ExtraAccountMetaList::constraints::<TestInstruction>(&buffer).unwrap(),
+            [optional]
+        );
+        ExtraAccountMetaList::update_with_constraints::<TestInstruction>(
+            &mut buffer,
+            &metas,
+            &[ExtraAccountMetaConstraints::default(), optional],
+        )
+        .unwrap();
+        assert_eq!(
+            ExtraAccountMetaList::constraints::<TestInstruction>(&buffer).unwrap(),
+            [ExtraAccountMetaConstraints::default(), optional]
+        );
+
+        // The metas can't be updated without their constraints
+        assert_eq!(
+            ExtraAccountMetaList::update::<TestInstruction>(&mut buffer, &metas[..1]).unwrap_err(),
+            AccountResolutionError::ConstraintsNotUpdated.into()
+        );
+        assert_eq!(
+            ExtraAccountMetaList::update_with_account_metas::<TestInstruction>(
+                &mut buffer,
+                &[AccountMeta::new(Pubkey::new_unique(), false)],
+            )
+            .unwrap_err(),
+            AccountResolutionError::ConstraintsNotUpdated.into()
+        );
+        assert_eq!(
+            ExtraAccountMetaList::constraints::<TestInstruction>(&buffer).unwrap(),
+            [ExtraAccountMetaConstraints::default(), optional]
+        );
+
+        ExtraAccountMetaList::remove::<TestInstruction>(&mut buffer).unwrap();
+        assert!(buffer.iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn update_account_info_size_and_rent() {
+        let metas = [
+            AccountMeta::new(Pubkey::new_unique(), false).into(),
+            AccountMeta::new_readonly(Pubkey::new_unique(), false).into(),
+        ];
+        let meta_size = std::mem::size_of::<ExtraAccountMeta>() as isize;
+
+        let mut buffer = vec![0; ExtraAccountMetaList::size_of(metas.len()).unwrap()];
+        ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &metas).unwrap();
+
+        assert_eq!(
+            ExtraAccountMetaList::size_diff_for_update::<TestInstruction>(&buffer, 2).unwrap(),
+            0
+        );
+        assert_eq!(
+            ExtraAccountMetaList::size_diff_for_update::<TestInstruction>(&buffer, 5).unwrap(),
+            3 * meta_size
+        );
+        assert_eq!(
+            ExtraAccountMetaList::size_diff_for_update::<TestInstruction>(&buffer, 0).unwrap(),
+            -2 * meta_size
+        );
+        assert_eq!(
+            ExtraAccountMetaList::size_diff_for_update::<TestOtherInstruction>(&buffer, 1)
+                .unwrap_err(),
+            ProgramError::InvalidAccountData,
+        );
+
+        let rent = Rent::default();
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = rent.minimum_balance(buffer.len());
+        let account_info =
+            AccountInfo::new(&key, false, true, &mut lamports, &mut buffer, &owner, false);
+
+        // Growing requires more lamports, shrinking doesn't
+        let grown_len = ExtraAccountMetaList::size_of(5).unwrap();
+        assert_eq!(
+            ExtraAccountMetaList::lamports_needed_for_update::<TestInstruction>(
+                &account_info,
+                &rent,
+                5
+            )
+            .unwrap(),
+            rent.minimum_balance(grown_len) - account_info.lamports()
+        );
+        assert_eq!(
+            ExtraAccountMetaList::lamports_needed_for_update::<TestInstruction>(
+                &account_info,
+                &rent,
+                1
+            )
+            .unwrap(),
+            0
+        );
+
+        // An update that would leave the account below rent exemption fails
+        // before touching the data
+        let grown_metas =
+            [ExtraAccountMeta::from(AccountMeta::new(Pubkey::new_unique(), false)); 5];
+        assert_eq!(
+            ExtraAccountMetaList::update_account_info::<TestInstruction>(
+                &account_info,
+                &rent,
+                &grown_metas
+            )
+            .unwrap_err(),
+            ProgramError::AccountNotRentExempt
+        );
+
+        // An update of the same size doesn't resize the account
+        let new_metas = [
+            AccountMeta::new(Pubkey::new_unique(), true).into(),
+            AccountMeta::new(Pubkey::new_unique(), false).into(),
+        ];
+        ExtraAccountMetaList::update_account_info::<TestInstruction>(
+            &account_info,
+            &rent,
+            &new_metas,
+        )
+        .unwrap();
+        let data = account_info.try_borrow_data().unwrap();
+        let state = TlvStateBorrowed::unpack(&data).unwrap();
+        let unpacked =
+            ExtraAccountMetaList::unpack_with_tlv_state::<TestInstruction>(&state).unwrap();
+        assert_eq!(&*unpacked, &new_metas);
+    }
+
+    #[tokio::test]
+    async fn optional_accounts() {
+        let program_id = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let required_pubkey = Pubkey::new_unique();
+
+        let optional = ExtraAccountMetaConstraints::default().with_optional(true);
+        let metas = [
+            // Only resolvable if the instruction account holds data
+            ExtraAccountMeta::new_with_seeds(
+                &[Seed::AccountData {
+                    account_index: 0,
+                    data_index: 0,
+                    length: 8,
+                }],
+                false,
+                true,
+            )
+            .unwrap(),
+            // Only resolvable if the previous account was resolved
+            ExtraAccountMeta::new_with_seeds(&[Seed::AccountKey { index: 1 }], false, false)
+                .unwrap(),
+            ExtraAccountMeta::new_with_pubkey(&required_pubkey, false, false).unwrap(),
+        ];
+        let extra_meta_constraints = [optional, optional, ExtraAccountMetaConstraints::default()];
+        let account_size = ExtraAccountMetaList::size_of_with_constraints(metas.len()).unwrap();
+        let mut buffer = vec![0; account_size];
+        assert_eq!(
+            ExtraAccountMetaList::init_with_constraints::<TestInstruction>(
+                &mut buffer,
+                &metas,
+                &extra_meta_constraints[..2],
+            )
+            .unwrap_err(),
+            ProgramError::InvalidArgument
+        );
+        ExtraAccountMetaList::init_with_constraints::<TestInstruction>(
+            &mut buffer,
+            &metas,
+            &extra_meta_constraints,
+        )
+        .unwrap();
+
+        // The metas are stored unchanged, with strict boolean privileges, so
+        // readers unaware of the constraints resolve every account as required
+        let state = TlvStateBorrowed::unpack(&buffer).unwrap();
+        let unpacked =
+            ExtraAccountMetaList::unpack_with_tlv_state::<TestInstruction>(&state).unwrap();
+        assert_eq!(&*unpacked, &metas);
+        assert!(unpacked
+            .iter()
+            .all(|meta| meta.is_signer.0 <= 1 && meta.is_writable.0 <= 1));
+        assert_eq!(
+            ExtraAccountMetaList::constraints::<TestInstruction>(&buffer).unwrap(),
+            extra_meta_constraints
+        );
+
+        let ix_pubkey = Pubkey::new_unique();
+        let mut ix_lamports = 0;
+        let mut ix_data = [];
+        let mut program_lamports = 0;
+        let mut program_data = [];
+        let mut required_lamports = 0;
+        let mut required_data = [];
+        let ix_account_info = AccountInfo::new(
+            &ix_pubkey,
+            false,
+            false,
+            &mut ix_lamports,
+            &mut ix_data,
+            &owner,
+            false,
+        );
+        let program_account_info = AccountInfo::new(
+            &program_id,
+            false,
+            false,
+            &mut program_lamports,
+            &mut program_data,
+            &owner,
+            true,
+        );
+        let required_account_info = AccountInfo::new(
+            &required_pubkey,
+            false,
+            false,
+            &mut required_lamports,
+            &mut required_data,
+            &owner,
+            false,
+        );
+
+        // Off-chain, unresolved optional accounts are omitted
+        let account_infos = [ix_account_info.clone()];
+        let mock_rpc = MockRpc::setup(&account_infos);
+        let mut instruction = Instruction::new_with_bytes(
+            program_id,
+            &[],
+            vec![AccountMeta::new_readonly(ix_pubkey, false)],
+        );
+        let omitted_indices =
+            ExtraAccountMetaList::add_to_instruction_with_fetcher::<TestInstruction, _>(
+                &mut instruction,
+                &|pubkey| mock_rpc.get_account_data(pubkey),
+                &buffer,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(omitted_indices, [1, 2]);
+        assert_eq!(
+            instruction.accounts,
+            [
+                AccountMeta::new_readonly(ix_pubkey, false),
+                AccountMeta::new_readonly(program_id, false),
+                AccountMeta::new_readonly(program_id, false),
+                AccountMeta::new_readonly(required_pubkey, false),
+            ]
+        );
+
+        // On-chain, the program id must be provided in their place
+        let account_infos = [
+            ix_account_info.clone(),
+            program_account_info.clone(),
+            program_account_info.clone(),
+            required_account_info.clone(),
+        ];
+        assert_eq!(
+            ExtraAccountMetaList::check_account_infos::<TestInstruction>(
+                &account_infos,
+                &[],
+                &program_id,
+                &buffer,
+            ),
+            Ok(()),
+        );
+        let wrong_account_infos = [
+            ix_account_info.clone(),
+            program_account_info.clone(),
+            required_account_info.clone(),
+            required_account_info.clone(),
+        ];
+        assert_eq!(
+            ExtraAccountMetaList::check_account_infos::<TestInstruction>(
+                &wrong_account_infos,
+                &[],
+                &program_id,
+                &buffer,
+            )
+            .unwrap_err(),
+            AccountResolutionError::IncorrectAccount.into(),
+        );
+
+        let mut cpi_instruction = Instruction::new_with_bytes(
+            program_id,
+            &[],
+            vec![AccountMeta::new_readonly(ix_pubkey, false)],
+        );
+        let mut cpi_account_infos = vec![ix_account_info.clone()];
+        ExtraAccountMetaList::add_to_cpi_instruction::<TestInstruction, _>(
+            &mut cpi_instruction,
+            &mut cpi_account_infos,
+            &buffer,
+            &account_infos,
+        )
+        .unwrap();
+        assert_eq!(cpi_instruction.accounts, instruction.accounts);
+        assert_eq!(
+            cpi_account_infos
+                .iter()
+                .map(|info| *info.key)
+                .collect::<Vec<_>>(),
+            [ix_pubkey, program_id, program_id, required_pubkey]
+        );
+
+        // Resolved optional accounts are required, like any other account
+        let mut ix_lamports_with_data = 0;
+        let mut ix_data_with_data = [1; 8];
+        let ix_account_info = AccountInfo::new(
+            &ix_pubkey,
+            false,
+            false,
+            &mut ix_lamports_with_data,
+            &mut ix_data_with_data,
+            &owner,
+            false,
+        );
+        let pda1 = Pubkey::find_program_address(&[&[1; 8]], &program_id).0;
+        let pda2 = Pubkey::find_program_address(&[pda1.as_ref()], &program_id).0;
+
+        let account_infos = [ix_account_info.clone()];
+        let mock_rpc = MockRpc::setup(&account_infos);
+        let mut instruction = Instruction::new_with_bytes(
+            program_id,
+            &[],
+            vec![AccountMeta::new_readonly(ix_pubkey, false)],
+        );
+        let omitted_indices =
+            ExtraAccountMetaList::add_to_instruction_with_fetcher::<TestInstruction, _>(
+                &mut instruction,
+                &|pubkey| mock_rpc.get_account_data(pubkey),
+                &buffer,
+            )
+            .await
+            .unwrap();
+
+        assert!(omitted_indices.is_empty());
+        assert_eq!(
+            instruction.accounts,
+            [
+                AccountMeta::new_readonly(ix_pubkey, false),
+                AccountMeta::new(pda1, false),
+                AccountMeta::new_readonly(pda2, false),
+                AccountMeta::new_readonly(required_pubkey, false),
+            ]
+        );
+
+        let account_infos = [
+            ix_account_info,
+            program_account_info.clone(),
+            program_account_info,
+            required_account_info,
+        ];
+        assert_eq!(
+            ExtraAccountMetaList::check_account_infos::<TestInstruction>(
+                &account_infos,
+                &[],
+                &program_id,
+                &buffer,
+            )
+            .unwrap_err(),
+            AccountResolutionError::IncorrectAccount.into(),
+        );
+    }
+
+    #[test]
+    fn iter_extra_account_metas() {
+        let pubkey = Pubkey::new_unique();
+        let metas = [
+            ExtraAccountMeta::new_with_pubkey(&pubkey, false, true).unwrap(),
+            ExtraAccountMeta::new_with_seeds(
+                &[
+                    Seed::Literal {
+                        bytes: b"config".to_vec(),
+                    },
+                    Seed::AccountKey { index: 0 },
+                ],
+                false,
+                false,
+            )
+            .unwrap(),
+            ExtraAccountMeta::new_external_pda_with_seeds(
+                1,
+                &[Seed::InstructionData {
+                    index: 1,
+                    length: 8,
+                }],
+                false,
+                true,
+            )
+            .unwrap(),
+            ExtraAccountMeta::new_with_pubkey_data(
+                &PubkeyData::AccountData {
+                    account_index: 2,
+                    data_index: 32,
+                },
+                false,
+                false,
+            )
+            .unwrap(),
+        ];
+        let account_size = ExtraAccountMetaList::size_of(metas.len()).unwrap();
+        let mut buffer = vec![0; account_size];
+        ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &metas).unwrap();
+
+        let entries = ExtraAccountMetaList::iter::<TestInstruction>(&buffer)
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries.iter().map(|(meta, _)| *meta).collect::<Vec<_>>(),
+            metas
+        );
+        assert_eq!(
+            entries
+                .iter()
+                .map(|(_, config)| config.to_string())
+                .collect::<Vec<_>>(),
+            [
+                pubkey.to_string(),
+                "PDA of the executing program with seeds [literal \"config\", key of account 0]"
+                    .to_string(),
+                "PDA of the program at account 1 with seeds [instruction data [1..9]]".to_string(),
+                "pubkey in data of account 2 [32..64]".to_string(),
+            ]
+        );
+        assert_eq!(
+            entries[1].1,
+            ResolvedSeedConfig::Pda {
+                seeds: vec![
+                    Seed::Literal {
+                        bytes: b"config".to_vec(),
+                    },
+                    Seed::AccountKey { index: 0 },
+                ],
+            }
+        );
+
+        // Fails for an instruction without extra account metas
+        assert!(ExtraAccountMetaList::iter::<TestOtherInstruction>(&buffer).is_err());
+    }
+
+    #[cfg(feature = "serde-traits")]
+    #[test]
+    fn json_round_trip() {
+        let pubkey = Pubkey::new_unique();
+        let metas = [
+            ExtraAccountMeta::new_with_pubkey(&pubkey, false, true).unwrap(),
+            ExtraAccountMeta::new_with_seeds(
+                &[
+                    Seed::Literal {
+                        bytes: b"config".to_vec(),
+                    },
+                    Seed::AccountData {
+                        account_index: 0,
+                        data_index: 32,
+                        length: 8,
+                    },
+                    Seed::ProgramId,
+                ],
+                false,
+                false,
+            )
+            .unwrap(),
+            ExtraAccountMeta::new_external_pda_with_seeds(
+                0,
+                &[Seed::AccountKey { index: 1 }],
+                false,
+                true,
+            )
+            .unwrap(),
+            ExtraAccountMeta::new_with_pubkey_data(
+                &PubkeyData::InstructionData { index: 8 },
+                true,
+                false,
+            )
+            .unwrap(),
+        ];
+        let mut extra_meta_constraints = [ExtraAccountMetaConstraints::default(); 4];
+        extra_meta_constraints[1] = extra_meta_constraints[1].with_optional(true);
+        extra_meta_constraints[3] = extra_meta_constraints[3]
+            .with_executable(true)
+            .with_owner_index(Some(2));
+        let account_size = ExtraAccountMetaList::size_of_with_constraints(metas.len()).unwrap();
+        let mut buffer = vec![0; account_size];
+        ExtraAccountMetaList::init_with_constraints::<TestInstruction>(
+            &mut buffer,
+            &metas,
+            &extra_meta_constraints,
+        )
+        .unwrap();
+
+        let json = ExtraAccountMetaList::to_json::<TestInstruction>(&buffer).unwrap();
+        let value = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+        assert_eq!(
+            value[0],
+            serde_json::json!({
+                "address": { "pubkey": pubkey.to_string() },
+                "isSigner": false,
+                "isWritable": true,
+            })
+        );
+        assert_eq!(
+            value[1],
+            serde_json::json!({
+                "address": {
+                    "pda": {
+                        "seeds": [
+                            { "literal": { "bytes": b"config" } },
+                            { "accountData": { "accountIndex": 0, "dataIndex": 32, "length": 8 } },
+                            "programId",
+                        ]
+                    }
+                },
+                "isSigner": false,
+                "isWritable": false,
+                "isOptional": true,
+            })
+        );
+        assert_eq!(
+            value[2]["address"],
+            serde_json::json!({
+                "externalPda": { "programIndex": 0, "seeds": [{ "accountKey": { "index": 1 } }] }
+            })
+        );
+        assert_eq!(value[3]["isExecutable"], true);
+        assert_eq!(value[3]["ownerIndex"], 2);
+
+        assert_eq!(
+            ExtraAccountMetaList::from_json(&json).unwrap(),
+            (metas.to_vec(), extra_meta_constraints.to_vec())
+        );
+
+        let mut imported = vec![0; account_size];
+        ExtraAccountMetaList::init_from_json::<TestInstruction>(&mut imported, &json).unwrap();
+        assert_eq!(imported, buffer);
+
+        // Lists without constraints are imported without a constraints entry
+        let mut buffer = vec![0; ExtraAccountMetaList::size_of(1).unwrap()];
+        ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &metas[..1]).unwrap();
+        let json = ExtraAccountMetaList::to_json::<TestInstruction>(&buffer).unwrap();
+        let mut imported = vec![0; buffer.len()];
+        ExtraAccountMetaList::init_from_json::<TestInstruction>(&mut imported, &json).unwrap();
+        assert_eq!(imported, buffer);
+
+        // Seeds that don't fit in the address config are rejected
+        let json = serde_json::json!([{
+            "address": { "pda": { "seeds": [{ "literal": { "bytes": vec![1u8; 32] } }] } },
+            "isSigner": false,
+            "isWritable": false,
+        }]);
+        assert_eq!(
+            ExtraAccountMetaList::from_json(&json.to_string()).unwrap_err(),
+            ProgramError::InvalidArgument,
+        );
+    }
+
+    #[test]
+    fn init_with_validation() {
+        let metas = [
+            ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), false, true).unwrap(),
+            ExtraAccountMeta::new_with_seeds(
+                &[
+                    Seed::Literal {
+                        bytes: b"seed".to_vec(),
+                    },
+                    Seed::InstructionData {
+                        index: 8,
+                        length: 8,
+                    },
+                    // The first extra account
+                    Seed::AccountKey { index: 2 },
+                ],
+                false,
+                false,
+            )
+            .unwrap(),
+            ExtraAccountMeta::new_external_pda_with_seeds(
+                3,
+                &[Seed::AccountData {
+                    account_index: 0,
+                    data_index: 0,
+                    length: 32,
+                }],
+                false,
+                true,
+            )
+            .unwrap(),
+            ExtraAccountMeta::new_with_pubkey_data(
+                &PubkeyData::InstructionData { index: 16 },
+                false,
+                false,
+            )
+            .unwrap(),
+        ];
+        let account_size = ExtraAccountMetaList::size_of(metas.len()).unwrap();
+        let mut buffer = vec![0; account_size];
+
+        assert_eq!(validate_seed_configs(&metas, 48, 2), Ok(()));
+
+        // Instruction data too small for the seed
+        assert_eq!(
+            validate_seed_configs(&metas, 15, 2).unwrap_err(),
+            InvalidSeedConfig {
+                meta_index: 1,
+                seed_index: Some(1),
+                error: AccountResolutionError::InstructionDataTooSmall.into(),
+            }
+        );
+        // Instruction data too small for the pubkey data
+        assert_eq!(
+            validate_seed_configs(&metas, 47, 2).unwrap_err(),
+            InvalidSeedConfig {
+                meta_index: 3,
+                seed_index: None,
+                error: AccountResolutionError::InstructionDataTooSmall.into(),
+            }
+        );
+        // Referenced accounts are not resolved yet
+        assert_eq!(
+            validate_seed_configs(&metas, 48, 1).unwrap_err(),
+            InvalidSeedConfig {
+                meta_index: 1,
+                seed_index: Some(2),
+                error: AccountResolutionError::AccountNotFound.into(),
+            }
+        );
+        assert_eq!(
+            validate_seed_configs(&metas[..3], 48, 0).unwrap_err(),
+            InvalidSeedConfig {
+                meta_index: 1,
+                seed_index: Some(2),
+                error: AccountResolutionError::AccountNotFound.into(),
+            }
+        );
+        let mut external_metas = metas;
+        external_metas[1] =
+            ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), false, false).unwrap();
+        assert_eq!(
+            validate_seed_configs(&external_metas, 48, 0).unwrap_err(),
+            InvalidSeedConfig {
+                meta_index: 2,
+                seed_index: None,
+                error: AccountResolutionError::AccountNotFound.into(),
+            }
+        );
+
+        assert_eq!(
+            ExtraAccountMetaList::init_with_validation::<TestInstruction>(
+                &mut buffer,
+                &metas,
+                15,
+                2
+            )
+            .unwrap_err(),
+            AccountResolutionError::InstructionDataTooSmall.into(),
+        );
+        assert_eq!(buffer, vec![0; account_size]);
+
+        ExtraAccountMetaList::init_with_validation::<TestInstruction>(&mut buffer, &metas, 48, 2)
+            .unwrap();
+        assert_eq!(
+            ExtraAccountMetaList::iter::<TestInstruction>(&buffer)
+                .unwrap()
+                .map(|(meta, _)| meta)
+                .collect::<Vec<_>>(),
+            metas
+        );
+    }
+
+    #[test]
+    fn cpi_instruction_account_info_lookup() {
+        let program_id = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+
+        let pubkeys = (0..32).map(|_| Pubkey::new_unique()).collect::<Vec<_>>();
+        let metas = pubkeys
+            .iter()
+            .map(|pubkey| ExtraAccountMeta::new_with_pubkey(pubkey, false, true).unwrap())
+            .collect::<Vec<_>>();
+        let account_size = ExtraAccountMetaList::size_of(metas.len()).unwrap();
+        let mut buffer = vec![0; account_size];
+        ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &metas).unwrap();
+
+        // Provide the account infos in reverse order, mixed with unrelated
+        // accounts
+        let unrelated_pubkeys = (0..32).map(|_| Pubkey::new_unique()).collect::<Vec<_>>();
+        let mut lamports = [0; 64];
+        let mut datas = [[0u8; 0]; 64];
+        let account_infos = pubkeys
+            .iter()
+            .rev()
+            .zip(unrelated_pubkeys.iter())
+            .flat_map(|(pubkey, unrelated)| [unrelated, pubkey])
+            .zip(lamports.iter_mut().zip(datas.iter_mut()))
+            .map(|(pubkey, (lamports, data))| {
+                AccountInfo::new(pubkey, false, true, lamports, data, &owner, false)
+            })
+            .collect::<Vec<_>>();
+
+        let mut cpi_instruction = Instruction::new_with_bytes(program_id, &[], vec![]);
+        let mut cpi_account_infos = vec![];
+        ExtraAccountMetaList::add_to_cpi_instruction::<TestInstruction, _>(
+            &mut cpi_instruction,
+            &mut cpi_account_infos,
+            &buffer,
+            &account_infos,
+        )
+        .unwrap();
+
+        assert_eq!(
+            cpi_account_infos
+                .iter()
+                .map(|info| *info.key)
+                .collect::<Vec<_>>(),
+            pubkeys
+        );
+        assert_eq!(
+            cpi_instruction.accounts,
+            pubkeys
+                .iter()
+                .map(|pubkey| AccountMeta::new(*pubkey, false))
+                .collect::<Vec<_>>()
+        );
+
+        // Fails if an account info is missing
+        let mut cpi_instruction = Instruction::new_with_bytes(program_id, &[], vec![]);
+        let mut cpi_account_infos = vec![];
+        assert_eq!(
+            ExtraAccountMetaList::add_to_cpi_instruction::<TestInstruction, _>(
+                &mut cpi_instruction,
+                &mut cpi_account_infos,
+                &buffer,
+                &account_infos[..account_infos.len() - 1],
+            )
+            .unwrap_err(),
+            AccountResolutionError::IncorrectAccount.into(),
+        );
+    }
+
+    #[test]
+    fn executable_and_owner_requirements() {
+        let program_id = Pubkey::new_unique();
+        let token_program_id = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let hook_program_id = Pubkey::new_unique();
+
+        // The mint must be owned by the token program at index 0, and the
+        // hook program must be executable
+        let metas = [
+            ExtraAccountMeta::new_with_pubkey(&mint, false, false).unwrap(),
+            ExtraAccountMeta::new_with_pubkey(&hook_program_id, false, false).unwrap(),
+        ];
+        let extra_meta_constraints = [
+            ExtraAccountMetaConstraints::default().with_owner_index(Some(0)),
+            ExtraAccountMetaConstraints::default().with_executable(true),
+        ];
+        let mut buffer =
+            vec![0; ExtraAccountMetaList::size_of_with_constraints(metas.len()).unwrap()];
+        ExtraAccountMetaList::init_with_constraints::<TestInstruction>(
+            &mut buffer,
+            &metas,
+            &extra_meta_constraints,
+        )
+        .unwrap();
+
+        // The requirements don't affect the privileges, so readers unaware of
+        // the constraints see read-only accounts
+        let state = TlvStateBorrowed::unpack(&buffer).unwrap();
+        let unpacked =
+            ExtraAccountMetaList::unpack_with_tlv_state::<TestInstruction>(&state).unwrap();
+        assert!(unpacked
+            .iter()
+            .all(|meta| meta.is_signer.0 == 0 && meta.is_writable.0 == 0));
+
+        let system_program_id = Pubkey::default();
+        let loader_id = Pubkey::new_unique();
+        let check = |mint_owner: &Pubkey, hook_executable: bool| {
+            let mut lamports = [0; 3];
+            let mut datas = [[0u8; 0]; 3];
+            let [token_lamports, mint_lamports, hook_lamports] = &mut lamports;
+            let [token_data, mint_data, hook_data] = &mut datas;
+            let account_infos = [
+                AccountInfo::new(
+                    &token_program_id,
+                    false,
+                    false,
+                    token_lamports,
+                    token_data,
+                    &loader_id,
+                    true,
+                ),
+                AccountInfo::new(
+                    &mint,
+                    false,
+                    false,
+                    mint_lamports,
+                    mint_data,
+                    mint_owner,
+                    false,
+                ),
+                AccountInfo::new(
+                    &hook_program_id,
+                    false,
+                    false,
+                    hook_lamports,
+                    hook_data,
+                    &loader_id,
+                    hook_executable,
+                ),
+            ];
+            ExtraAccountMetaList::check_account_infos::<TestInstruction>(
+                &account_infos,
+                &[],
+                &program_id,
+                &buffer,
+            )?;
+
+            let mut cpi_instruction = Instruction::new_with_bytes(
+                program_id,
+                &[],
+                vec![AccountMeta::new_readonly(token_program_id, false)],
+            );
+            let mut cpi_account_infos = vec![account_infos[0].clone()];
+            ExtraAccountMetaList::add_to_cpi_instruction::<TestInstruction, _>(
+                &mut cpi_instruction,
+                &mut cpi_account_infos,
+                &buffer,
+                &account_infos,
+            )
+        };
+
+        check(&token_program_id, true).unwrap();
+        assert_eq!(
+            check(&system_program_id, true).unwrap_err(),
+            AccountResolutionError::IncorrectAccountOwner.into()
+        );
+        assert_eq!(
+            check(&token_program_id, false).unwrap_err(),
+            AccountResolutionError::AccountNotExecutable.into()
+        );
+
+        // The owner must be provided before the account
+        assert_eq!(
+            validate_constraints(&[extra_meta_constraints[0].with_owner_index(Some(1))], 1)
+                .unwrap_err()
+                .error,
+            AccountResolutionError::AccountNotFound.into()
+        );
+    }
+
+    #[test]
+    fn config_space_remaining() {
+        let meta = ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), false, false).unwrap();
+        assert_eq!(meta.config_space_remaining().unwrap(), 0);
+
+        // 1 + 1 + 6 bytes for the literal, 2 bytes for the account key
+        let seeds = [
+            Seed::Literal {
+                bytes: b"prefix".to_vec(),
+            },
+            Seed::AccountKey { index: 0 },
+        ];
+        let meta = ExtraAccountMeta::new_with_seeds(&seeds, false, false).unwrap();
+        assert_eq!(meta.config_space_remaining().unwrap(), 22);
+        let meta = ExtraAccountMeta::new_external_pda_with_seeds(1, &seeds, false, false).unwrap();
+        assert_eq!(meta.config_space_remaining().unwrap(), 22);
+
+        // Filling the remaining space exactly
+        let mut seeds = seeds.to_vec();
+        seeds.push(Seed::Literal { bytes: vec![1; 20] });
+        let meta = ExtraAccountMeta::new_with_seeds(&seeds, false, false).unwrap();
+        assert_eq!(meta.config_space_remaining().unwrap(), 0);
+
+        let meta = ExtraAccountMeta::new_with_pubkey_data(
+            &PubkeyData::InstructionData { index: 0 },
+            false,
+            false,
+        )
+        .unwrap();
+        assert_eq!(meta.config_space_remaining().unwrap(), 30);
+    }
+
+    #[test]
+    fn pda_cache_and_known_bumps() {
+        let program_id = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+
+        // Two metas resolving to the same PDA, and a different one
+        let seeds = [Seed::Literal {
+            bytes: b"shared".to_vec(),
+        }];
+        let other_seeds = [Seed::Literal {
+            bytes: b"other".to_vec(),
+        }];
+        let metas = [
+            ExtraAccountMeta::new_with_seeds(&seeds, false, true).unwrap(),
+            ExtraAccountMeta::new_with_seeds(&seeds, false, false).unwrap(),
+            ExtraAccountMeta::new_with_seeds(&other_seeds, false, false).unwrap(),
+        ];
+        let (pda, bump) = Pubkey::find_program_address(&[b"shared"], &program_id);
+        let (other_pda, other_bump) = Pubkey::find_program_address(&[b"other"], &program_id);
+
+        let mut cache = PdaCache::default();
+        let resolved = metas
+            .iter()
+            .map(|meta| {
+                meta.resolve_with_cache(&[], &program_id, &mut cache, None, |_| None)
+                    .unwrap()
+                    .pubkey
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(resolved, [pda, pda, other_pda]);
+        assert_eq!(cache.len(), 2);
+
+        // Addresses derived from a known bump are not cached
+        let mut cache = PdaCache::default();
+        let meta = metas[0]
+            .resolve_with_cache(&[], &program_id, &mut cache, Some(bump), |_| None)
+            .unwrap();
+        assert_eq!(meta.pubkey, pda);
+        assert!(cache.is_empty());
+
+        let account_size = ExtraAccountMetaList::size_of(metas.len()).unwrap();
+        let mut buffer = vec![0; account_size];
+        ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &metas).unwrap();
+
+        let mut lamports = [0; 3];
+        let mut datas = [[0u8; 0]; 3];
+        let mut lamports_iter = lamports.iter_mut();
+        let mut datas_iter = datas.iter_mut();
+        let mut account_info = |key, is_writable| {
+            AccountInfo::new(
+                key,
+                false,
+                is_writable,
+                lamports_iter.next().unwrap(),
+                datas_iter.next().unwrap(),
+                &owner,
+                false,
+            )
+        };
+        let account_infos = [
+            account_info(&pda, true),
+            account_info(&pda, true),
+            account_info(&other_pda, false),
+        ];
+
+        // The bumps may be provided for only some of the metas
+        for known_bumps in [
+            &[][..],
+            &[Some(bump)],
+            &[None, Some(bump), Some(other_bump)],
+        ] {
+            ExtraAccountMetaList::check_account_infos_with_bumps::<TestInstruction>(
+                &account_infos,
+                &[],
+                &program_id,
+                &buffer,
+                known_bumps,
+            )
+            .unwrap();
+
+            let mut cpi_instruction = Instruction::new_with_bytes(program_id, &[], vec![]);
+            let mut cpi_account_infos = vec![];
+            ExtraAccountMetaList::add_to_cpi_instruction_with_bumps::<TestInstruction, _>(
+                &mut cpi_instruction,
+                &mut cpi_account_infos,
+                &buffer,
+                &account_infos,
+                known_bumps,
+            )
+            .unwrap();
+            assert_eq!(
+                cpi_instruction.accounts,
+                [
+                    AccountMeta::new(pda, false),
+                    AccountMeta::new(pda, false),
+                    AccountMeta::new_readonly(other_pda, false),
+                ]
+            );
+        }
+
+        // A wrong bump derives another address, or none at all
+        assert!(
+            ExtraAccountMetaList::check_account_infos_with_bumps::<TestInstruction>(
+                &account_infos,
+                &[],
+                &program_id,
+                &buffer,
+                &[Some(bump.wrapping_sub(1))],
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn diagnose_extra_account_metas() {
+        let program_id = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let available = Pubkey::new_unique();
+        let not_provided = Pubkey::new_unique();
+
+        let metas = [
+            ExtraAccountMeta::new_with_pubkey(&available, false, true).unwrap(),
+            ExtraAccountMeta::new_with_pubkey(&not_provided, false, false).unwrap(),
+            // Needs more instruction data than provided
+            ExtraAccountMeta::new_with_seeds(
+                &[
+                    Seed::AccountKey { index: 0 },
+                    Seed::InstructionData {
+                        index: 0,
+                        length: 8,
+                    },
+                ],
+                false,
+                false,
+            )
+            .unwrap(),
+            // Depends on the account that failed
+            ExtraAccountMeta::new_with_seeds(&[Seed::AccountKey { index: 3 }], false, false)
+                .unwrap(),
+            // Optional and depends on account data
+            ExtraAccountMeta::new_with_seeds(
+                &[Seed::AccountData {
+                    account_index: 0,
+                    data_index: 0,
+                    length: 8,
+                }],
+                false,
+                false,
+            )
+            .unwrap(),
+        ];
+        let mut extra_meta_constraints = [ExtraAccountMetaConstraints::default(); 5];
+        extra_meta_constraints[4] = extra_meta_constraints[4].with_optional(true);
+        let mut buffer =
+            vec![0; ExtraAccountMetaList::size_of_with_constraints(metas.len()).unwrap()];
+        ExtraAccountMetaList::init_with_constraints::<TestInstruction>(
+            &mut buffer,
+            &metas,
+            &extra_meta_constraints,
+        )
+        .unwrap();
+
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &[1, 2, 3, 4],
+            vec![AccountMeta::new_readonly(authority, true)],
+        );
+        let report = ExtraAccountMetaList::diagnose::<TestInstruction>(
+            &instruction,
+            &buffer,
+            &[authority, available],
+        )
+        .unwrap();
+
+        assert_eq!(
+            report.metas,
+            [
+                ExtraAccountMetaDiagnosis::Resolved(AccountMeta::new(available, false)),
+                ExtraAccountMetaDiagnosis::Missing(AccountMeta::new_readonly(not_provided, false)),
+                ExtraAccountMetaDiagnosis::Failed {
+                    seed_index: Some(1),
+                    error: AccountResolutionError::InstructionDataTooSmall.into(),
+                },
+                ExtraAccountMetaDiagnosis::Failed {
+                    seed_index: Some(0),
+                    error: AccountResolutionError::AccountNotFound.into(),
+                },
+                ExtraAccountMetaDiagnosis::Omitted {
+                    seed_index: Some(0),
+                    error: AccountResolutionError::AccountDataNotFound.into(),
+                },
+            ]
+        );
+        assert!(!report.is_ok());
+        assert_eq!(
+            report.missing_accounts().collect::<Vec<_>>(),
+            [(1, &AccountMeta::new_readonly(not_provided, false))]
+        );
+        assert_eq!(
+            report
+                .failures()
+                .map(|(i, seed_index, _)| (i, seed_index))
+                .collect::<Vec<_>>(),
+            [(2, Some(1)), (3, Some(0))]
+        );
+        assert_eq!(
+            report.to_string().lines().nth(1).unwrap(),
+            format!("extra account 1: missing {}", not_provided)
+        );
+
+        // Fails on truncated validation data
+        let report = ExtraAccountMetaList::diagnose::<TestInstruction>(
+            &instruction,
+            &buffer[..ExtraAccountMetaList::size_of(1).unwrap()],
+            &[available],
+        );
+        assert!(report.is_err());
+    }
+
+    #[tokio::test]
+    async fn resolve_verbose_locates_failures() {
+        let program_id = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let extra = Pubkey::new_unique();
+
+        let metas = [
+            ExtraAccountMeta::new_with_pubkey(&extra, false, true).unwrap(),
+            ExtraAccountMeta::new_with_seeds(
+                &[
+                    Seed::AccountKey { index: 1 },
+                    Seed::AccountData {
+                        account_index: 0,
+                        data_index: 0,
+                        length: 4,
+                    },
+                ],
+                false,
+                false,
+            )
+            .unwrap(),
+        ];
+        let mut buffer = vec![0; ExtraAccountMetaList::size_of(metas.len()).unwrap()];
+        ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &metas).unwrap();
+
+        let instruction =
+            Instruction::new_with_bytes(program_id, &[], vec![AccountMeta::new(owner, false)]);
+
+        // The owner account holds too little data for the second seed
+        let fetcher = BatchFetcher {
+            accounts: HashMap::from([(owner, vec![0; 2])]),
+            requests: RefCell::new(vec![]),
+        };
+        let failure = ExtraAccountMetaList::resolve_verbose::<TestInstruction, _>(
+            &instruction,
+            &fetcher,
+            &buffer,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            failure,
+            ResolutionFailure {
+                meta_index: Some(1),
+                seed_index: Some(1),
+                error: AccountResolutionError::AccountDataTooSmall.into(),
+            }
+        );
+        assert!(failure
+            .to_string()
+            .starts_with("extra account meta 1, seed 1: "));
+
+        // The same failure through the regular entry point only has the error
+        let mut failing_instruction = instruction.clone();
+        assert_eq!(
+            ExtraAccountMetaList::add_to_instruction_with_fetcher::<TestInstruction, _>(
+                &mut failing_instruction,
+                &fetcher,
+                &buffer,
+            )
+            .await
+            .unwrap_err(),
+            AccountResolutionError::AccountDataTooSmall.into(),
+        );
+
+        // Failing to read the validation data isn't tied to a meta
+        let failure = ExtraAccountMetaList::resolve_verbose::<TestOtherInstruction, _>(
+            &instruction,
+            &fetcher,
+            &buffer,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(failure.meta_index, None);
+
+        // On success, the extra metas are returned and the instruction is
+        // left untouched
+        let fetcher = BatchFetcher {
+            accounts: HashMap::from([(owner, vec![7; 4])]),
+            requests: RefCell::new(vec![]),
+        };
+        let resolved = ExtraAccountMetaList::resolve_verbose::<TestInstruction, _>(
+            &instruction,
+            &fetcher,
             &buffer,
-            &messed_account_infos,
         )
+        .await
         .unwrap();
-
-        // Our CPI instruction should match the check instruction.
-        assert_eq!(cpi_instruction, instruction);
-
-        // CPI account infos should have the instruction account infos
-        // and the extra required account infos from the validation account,
-        // and they should be in the correct order.
-        // Note: The two additional arbitrary account infos for the currently
-        // executing program won't be present in the CPI instruction's account
-        // infos, so we will omit them (hence the `..9`).
-        let check_account_infos = &all_account_infos[..12];
-        assert_eq!(cpi_account_infos.len(), check_account_infos.len());
-        for (a, b) in std::iter::zip(cpi_account_infos, check_account_infos) {
-            assert_eq!(a.key, b.key);
-            assert_eq!(a.is_signer, b.is_signer);
-            assert_eq!(a.is_writable, b.is_writable);
-        }
+        let pda = Pubkey::find_program_address(&[extra.as_ref(), &[7; 4]], &program_id).0;
+        assert_eq!(
+            resolved,
+            [
+                AccountMeta::new(extra, false),
+                AccountMeta::new_readonly(pda, false),
+            ]
+        );
+        assert_eq!(instruction.accounts.len(), 1);
     }
 
-    async fn update_and_assert_metas(
-        program_id: Pubkey,
-        buffer: &mut Vec<u8>,
-        updated_metas: &[ExtraAccountMeta],
-        check_metas: &[AccountMeta],
-    ) {
-        // resize buffer if necessary
-        let account_size = ExtraAccountMetaList::size_of(updated_metas.len()).unwrap();
-        if account_size > buffer.len() {
-            buffer.resize(account_size, 0);
-        }
+    #[tokio::test]
+    async fn versioned_extra_account_metas() {
+        let program_id = Pubkey::new_unique();
+        let v1_metas = [AccountMeta::new(Pubkey::new_unique(), false).into()];
+        let v2_metas = [
+            AccountMeta::new(Pubkey::new_unique(), false).into(),
+            AccountMeta::new_readonly(Pubkey::new_unique(), false).into(),
+        ];
 
-        // update
-        ExtraAccountMetaList::update::<TestInstruction>(buffer, updated_metas).unwrap();
+        // Version 1 is the instruction's own entry
+        assert_eq!(
+            Versioned::<TestInstruction, 1>::SPL_DISCRIMINATOR,
+            TestInstruction::SPL_DISCRIMINATOR
+        );
+        assert_ne!(
+            Versioned::<TestInstruction, 2>::SPL_DISCRIMINATOR,
+            TestInstruction::SPL_DISCRIMINATOR
+        );
 
-        // retrieve metas and assert
-        let state = TlvStateBorrowed::unpack(buffer).unwrap();
-        let unpacked_metas_pod =
-            ExtraAccountMetaList::unpack_with_tlv_state::<TestInstruction>(&state).unwrap();
+        // Versions of instructions whose discriminators only differ in their
+        // last byte don't collide
+        let next_discriminator = ArrayDiscriminator::new([1, 1, 1, 1, 1, 1, 1, 2]);
+        let discriminators = (1..=MAX_VERSION)
+            .flat_map(|version| {
+                [
+                    versioned_discriminator(TestInstruction::SPL_DISCRIMINATOR, version),
+                    versioned_discriminator(next_discriminator, version),
+                ]
+            })
+            .map(|discriminator| discriminator.as_slice().to_vec())
+            .collect::<HashSet<_>>();
+        assert_eq!(discriminators.len(), 2 * usize::from(MAX_VERSION));
+
+        let account_size = ExtraAccountMetaList::size_of(v1_metas.len()).unwrap()
+            + ExtraAccountMetaList::size_of(v2_metas.len()).unwrap();
+        let mut buffer = vec![0; account_size];
+        ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &v1_metas).unwrap();
         assert_eq!(
-            &*unpacked_metas_pod, updated_metas,
-            "The ExtraAccountMetas in the buffer should match the expected ones."
+            ExtraAccountMetaList::latest_version::<TestInstruction>(&buffer).unwrap(),
+            Some(1)
         );
 
-        let mock_rpc = MockRpc::setup(&[]);
+        let mut instruction = Instruction::new_with_bytes(program_id, &[], vec![]);
+        let version = ExtraAccountMetaList::add_to_instruction_versioned::<TestInstruction, _>(
+            &mut instruction,
+            &|_| async { Ok(None) },
+            &buffer,
+        )
+        .await
+        .unwrap();
+        assert_eq!(version, 1);
+        assert_eq!(instruction.accounts.len(), 1);
 
+        // Adding a later version makes clients pick it, while the program can
+        // still check the earlier one
+        ExtraAccountMetaList::init::<Versioned<TestInstruction, 2>>(&mut buffer, &v2_metas)
+            .unwrap();
         let mut instruction = Instruction::new_with_bytes(program_id, &[], vec![]);
-        ExtraAccountMetaList::add_to_instruction::<TestInstruction, _, _>(
+        let version = ExtraAccountMetaList::add_to_instruction_versioned::<TestInstruction, _>(
             &mut instruction,
-            |pubkey| mock_rpc.get_account_data(pubkey),
-            buffer,
+            &|_| async { Ok(None) },
+            &buffer,
         )
         .await
         .unwrap();
+        assert_eq!(version, 2);
+        let state = TlvStateBorrowed::unpack(&buffer).unwrap();
+        assert_eq!(
+            &*ExtraAccountMetaList::unpack_with_tlv_state::<Versioned<TestInstruction, 2>>(&state)
+                .unwrap(),
+            &v2_metas
+        );
+        assert_eq!(
+            instruction.accounts,
+            v2_metas
+                .iter()
+                .map(|meta| AccountMeta::try_from(meta).unwrap())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            &*ExtraAccountMetaList::unpack_with_tlv_state::<TestInstruction>(&state).unwrap(),
+            &v1_metas
+        );
 
-        assert_eq!(instruction.accounts, check_metas,);
+        // Other instructions have no version
+        assert_eq!(
+            ExtraAccountMetaList::latest_version::<TestOtherInstruction>(&buffer).unwrap(),
+            None
+        );
+        let mut instruction = Instruction::new_with_bytes(program_id, &[], vec![]);
+        assert_eq!(
+            ExtraAccountMetaList::add_to_instruction_versioned::<TestOtherInstruction, _>(
+                &mut instruction,
+                &|_| async { Ok(None) },
+                &buffer,
+            )
+            .await
+            .unwrap_err(),
+            TlvError::TypeNotFound.into()
+        );
     }
 
     #[tokio::test]
-    async fn update_extra_account_meta_list() {
+    async fn duplicate_accounts() {
         let program_id = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
 
-        // Create list of initial metas
-        let initial_metas = [
-            ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), false, true).unwrap(),
-            ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), true, false).unwrap(),
+        let authority = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let extra = Pubkey::new_unique();
+
+        let ix_accounts = vec![
+            AccountMeta::new(authority, true),
+            AccountMeta::new_readonly(mint, false),
+        ];
+        let metas = [
+            ExtraAccountMeta::new_with_pubkey(&authority, false, false).unwrap(),
+            ExtraAccountMeta::new_with_pubkey(&mint, false, false).unwrap(),
+            ExtraAccountMeta::new_with_pubkey(&extra, false, true).unwrap(),
+            ExtraAccountMeta::new_with_pubkey(&extra, false, false).unwrap(),
         ];
+        let account_size = ExtraAccountMetaList::size_of(metas.len()).unwrap();
+        let mut buffer = vec![0; account_size];
+        ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &metas).unwrap();
 
-        // initialize
-        let initial_account_size = ExtraAccountMetaList::size_of(initial_metas.len()).unwrap();
-        let mut buffer = vec![0; initial_account_size];
-        ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &initial_metas).unwrap();
+        // Duplicate entries also get the privileges of the existing ones
+        let mock_rpc = MockRpc::setup(&[]);
+        let mut instruction = Instruction::new_with_bytes(program_id, &[], ix_accounts.clone());
+        ExtraAccountMetaList::add_to_instruction::<TestInstruction, _, _>(
+            &mut instruction,
+            |pubkey| mock_rpc.get_account_data(pubkey),
+            &buffer,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            instruction.accounts,
+            [
+                AccountMeta::new(authority, true),
+                AccountMeta::new_readonly(mint, false),
+                AccountMeta::new(authority, true),
+                AccountMeta::new_readonly(mint, false),
+                AccountMeta::new(extra, false),
+                AccountMeta::new(extra, false),
+            ]
+        );
 
-        // Create updated metas list of the same size
-        let updated_metas_1 = [
-            ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), true, true).unwrap(),
-            ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), false, false).unwrap(),
+        // The runtime provides duplicate accounts with merged privileges
+        let mut authority_lamports = 0;
+        let mut authority_data = [];
+        let mut mint_lamports = 0;
+        let mut mint_data = [];
+        let mut extra_lamports = 0;
+        let mut extra_data = [];
+        let authority_info = AccountInfo::new(
+            &authority,
+            true,
+            true,
+            &mut authority_lamports,
+            &mut authority_data,
+            &owner,
+            false,
+        );
+        let mint_info = AccountInfo::new(
+            &mint,
+            false,
+            false,
+            &mut mint_lamports,
+            &mut mint_data,
+            &owner,
+            false,
+        );
+        let extra_info = AccountInfo::new(
+            &extra,
+            false,
+            true,
+            &mut extra_lamports,
+            &mut extra_data,
+            &owner,
+            false,
+        );
+        let account_infos = [
+            authority_info.clone(),
+            mint_info.clone(),
+            authority_info.clone(),
+            mint_info.clone(),
+            extra_info.clone(),
+            extra_info.clone(),
         ];
-        let check_metas_1 = updated_metas_1
-            .iter()
-            .map(|e| de_escalate_signer(AccountMeta::try_from(e).unwrap()))
-            .collect::<Vec<_>>();
-        update_and_assert_metas(program_id, &mut buffer, &updated_metas_1, &check_metas_1).await;
+        assert_eq!(
+            ExtraAccountMetaList::check_account_infos::<TestInstruction>(
+                &account_infos,
+                &[],
+                &program_id,
+                &buffer,
+            ),
+            Ok(()),
+        );
 
-        // Create updated and larger list of metas
-        let updated_metas_2 = [
-            ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), true, true).unwrap(),
-            ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), false, false).unwrap(),
-            ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), false, true).unwrap(),
-        ];
-        let check_metas_2 = updated_metas_2
-            .iter()
-            .map(|e| de_escalate_signer(AccountMeta::try_from(e).unwrap()))
-            .collect::<Vec<_>>();
-        update_and_assert_metas(program_id, &mut buffer, &updated_metas_2, &check_metas_2).await;
+        // Even a duplicate can't have fewer privileges than expected
+        let mut wrong_privilege_account_infos = account_infos.to_vec();
+        wrong_privilege_account_infos[4].is_writable = false;
+        assert_eq!(
+            ExtraAccountMetaList::check_account_infos::<TestInstruction>(
+                &wrong_privilege_account_infos,
+                &[],
+                &program_id,
+                &buffer,
+            )
+            .unwrap_err(),
+            AccountResolutionError::IncorrectAccountWritable.into(),
+        );
 
-        // Create updated and smaller list of metas
-        let updated_metas_3 =
-            [ExtraAccountMeta::new_with_pubkey(&Pubkey::new_unique(), true, true).unwrap()];
-        let check_metas_3 = updated_metas_3
-            .iter()
-            .map(|e| de_escalate_signer(AccountMeta::try_from(e).unwrap()))
-            .collect::<Vec<_>>();
-        update_and_assert_metas(program_id, &mut buffer, &updated_metas_3, &check_metas_3).await;
+        let mut cpi_instruction = Instruction::new_with_bytes(program_id, &[], ix_accounts);
+        let mut cpi_account_infos = vec![authority_info, mint_info];
+        ExtraAccountMetaList::add_to_cpi_instruction::<TestInstruction, _>(
+            &mut cpi_instruction,
+            &mut cpi_account_infos,
+            &buffer,
+            &account_infos,
+        )
+        .unwrap();
+        assert_eq!(cpi_instruction.accounts, instruction.accounts);
+        assert_eq!(cpi_account_infos.len(), 6);
 
-        // Create updated list of metas with a simple PDA
-        let seed_pubkey = Pubkey::new_unique();
-        let updated_metas_4 = [
-            ExtraAccountMeta::new_with_pubkey(&seed_pubkey, true, true).unwrap(),
-            ExtraAccountMeta::new_with_seeds(
-                &[
-                    Seed::Literal {
-                        bytes: b"seed-prefix".to_vec(),
-                    },
-                    Seed::AccountKey { index: 0 },
-                ],
-                false,
-                true,
-            )
-            .unwrap(),
-        ];
-        let simple_pda = Pubkey::find_program_address(
-            &[
-                b"seed-prefix",       // Literal prefix
-                seed_pubkey.as_ref(), // Account at index 0
-            ],
-            &program_id,
+        // Duplicates keep their own writable privilege, without changing the
+        // existing entries
+        let metas = [ExtraAccountMeta::new_with_pubkey(&mint, true, true).unwrap()];
+        let mut buffer = vec![0; ExtraAccountMetaList::size_of(metas.len()).unwrap()];
+        ExtraAccountMetaList::init::<TestInstruction>(&mut buffer, &metas).unwrap();
+        let mut instruction = Instruction::new_with_bytes(
+            program_id,
+            &[],
+            vec![AccountMeta::new_readonly(mint, false)],
+        );
+        ExtraAccountMetaList::add_to_instruction::<TestInstruction, _, _>(
+            &mut instruction,
+            |pubkey| mock_rpc.get_account_data(pubkey),
+            &buffer,
         )
-        .0;
-        let check_metas_4 = [
-            AccountMeta::new(seed_pubkey, false),
-            AccountMeta::new(simple_pda, false),
-        ];
+        .await
+        .unwrap();
+        assert_eq!(
+            instruction.accounts,
+            [
+                AccountMeta::new_readonly(mint, false),
+                AccountMeta::new(mint, false),
+            ]
+        );
 
-        update_and_assert_metas(program_id, &mut buffer, &updated_metas_4, &check_metas_4).await;
+        // Or each account is only listed once, with the merged privileges
+        let mut instruction = Instruction::new_with_bytes(
+            program_id,
+            &[],
+            vec![AccountMeta::new_readonly(mint, false)],
+        );
+        ExtraAccountMetaList::add_to_instruction_dedup::<TestInstruction, _, _>(
+            &mut instruction,
+            |pubkey| mock_rpc.get_account_data(pubkey),
+            &buffer,
+        )
+        .await
+        .unwrap();
+        assert_eq!(instruction.accounts, [AccountMeta::new(mint, false)]);
+    }
+
+    #[test]
+    fn account_info_index() {
+        let owner = Pubkey::new_unique();
+        let keys = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let missing_key = Pubkey::new_unique();
+
+        for num_account_infos in [3, MAX_INDEXED_ACCOUNT_INFOS + 1] {
+            let mut lamports = vec![0; num_account_infos];
+            let mut datas = vec![[0u8; 0]; num_account_infos];
+            let account_infos = lamports
+                .iter_mut()
+                .zip(datas.iter_mut())
+                .enumerate()
+                .map(|(i, (lamports, data))| {
+                    // Only the first account info of a key is writable
+                    AccountInfo::new(&keys[i % 2], false, i < 2, lamports, data, &owner, false)
+                })
+                .collect::<Vec<_>>();
+
+            let index = AccountInfoIndex::new(&account_infos);
+            for key in &keys {
+                let account_info = index.find(key).unwrap();
+                assert_eq!(account_info.key, key);
+                assert!(account_info.is_writable);
+            }
+            assert!(index.find(&missing_key).is_none());
+        }
     }
 
     #[test]
@@ -1686,5 +4841,57 @@ mod tests {
             ),
             Ok(()),
         );
+
+        // Account info check should fail if a privilege doesn't match
+        let mut wrong_privilege_account_infos = account_infos.clone().to_vec();
+        wrong_privilege_account_infos[3].is_writable = true;
+        assert_eq!(
+            ExtraAccountMetaList::check_account_infos::<TestInstruction>(
+                &wrong_privilege_account_infos,
+                &instruction_data,
+                &program_id,
+                &buffer,
+            )
+            .unwrap_err(),
+            AccountResolutionError::IncorrectAccountWritable.into(),
+        );
+        wrong_privilege_account_infos[3].is_writable = false;
+        wrong_privilege_account_infos[4].is_signer = true;
+        assert_eq!(
+            ExtraAccountMetaList::check_account_infos::<TestInstruction>(
+                &wrong_privilege_account_infos,
+                &instruction_data,
+                &program_id,
+                &buffer,
+            )
+            .unwrap_err(),
+            AccountResolutionError::IncorrectAccountSigner.into(),
+        );
+
+        // Account info check should fail if the instruction accounts are
+        // missing, since the PDA seeds can't be resolved
+        assert_eq!(
+            ExtraAccountMetaList::check_account_infos::<TestInstruction>(
+                &account_infos[1..],
+                &instruction_data,
+                &program_id,
+                &buffer,
+            )
+            .unwrap_err(),
+            AccountResolutionError::IncorrectAccount.into(),
+        );
+
+        // Account info check should fail if there are fewer accounts than
+        // extra metas
+        assert_eq!(
+            ExtraAccountMetaList::check_account_infos::<TestInstruction>(
+                &account_infos[3..],
+                &instruction_data,
+                &program_id,
+                &buffer,
+            )
+            .unwrap_err(),
+            AccountResolutionError::NotEnoughAccounts.into(),
+        );
     }
 }
@@ -0,0 +1,435 @@
+//! Helpers for the `Execute` instruction of transfer-hook programs
+//!
+//! Token-2022 invokes the `Execute` instruction of a mint's transfer hook on
+//! every transfer, with the following accounts:
+//!
+//!   0. `[]` Source account
+//!   1. `[]` Token mint
+//!   2. `[]` Destination account
+//!   3. `[]` Source account's owner or delegate
+//!   4. `[]` Validation account, storing the extra account metas
+//!   5. ..`5+M` `[]` `M` extra accounts, resolved from the validation account
+//!
+//! The validation account is a PDA of the hook program, derived from the
+//! mint. This module provides the discriminator of `Execute`, helpers to
+//! initialize and check the validation account from the hook program, and
+//! helpers to build a complete `Execute` instruction, either off-chain or for
+//! a CPI.
+
+use {
+    crate::{
+        account::ExtraAccountMeta,
+        account_info::AccountInfoView,
+        error::AccountResolutionError,
+        state::{AccountFetcher, ExtraAccountMetaList},
+    },
+    solana_account_info::AccountInfo,
+    solana_instruction::{AccountMeta, Instruction},
+    solana_program_error::ProgramError,
+    solana_pubkey::Pubkey,
+    spl_discriminator::{ArrayDiscriminator, SplDiscriminate},
+};
+
+/// Seed prefix of the validation account address
+pub const EXTRA_ACCOUNT_METAS_SEED: &[u8] = b"extra-account-metas";
+
+/// Number of accounts of `Execute` before the extra accounts
+const NUM_EXECUTE_ACCOUNTS: usize = 5;
+/// Index of the mint in the accounts of `Execute`
+const MINT_INDEX: usize = 1;
+/// Index of the validation account in the accounts of `Execute`
+const VALIDATION_INDEX: usize = 4;
+
+/// The transfer-hook `Execute` instruction, whose discriminator is the first
+/// 8 bytes of the hash of `"spl-transfer-hook-interface:execute"`
+///
+/// Extra account metas for `Execute` are stored under this discriminator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExecuteInstruction;
+impl SplDiscriminate for ExecuteInstruction {
+    const SPL_DISCRIMINATOR: ArrayDiscriminator =
+        ArrayDiscriminator::new([105, 37, 101, 197, 75, 251, 102, 26]);
+}
+
+/// The accounts required by every `Execute` instruction, before the
+/// validation account
+///
+/// Holds pubkeys when building the instruction off-chain, and account infos
+/// when building a CPI.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExecuteAccounts<T> {
+    /// The source token account
+    pub source: T,
+    /// The token mint
+    pub mint: T,
+    /// The destination token account
+    pub destination: T,
+    /// The owner or delegate of the source account
+    pub authority: T,
+}
+
+/// Get the address of the validation account of a mint, and its bump seed
+pub fn get_extra_account_metas_address_and_bump_seed(
+    mint: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[EXTRA_ACCOUNT_METAS_SEED, mint.as_ref()], program_id)
+}
+
+/// Get the address of the validation account of a mint
+pub fn get_extra_account_metas_address(mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+    get_extra_account_metas_address_and_bump_seed(mint, program_id).0
+}
+
+/// Get the signer seeds of the validation account of a mint, to create it
+/// with `invoke_signed`
+pub fn collect_extra_account_metas_signer_seeds<'a>(
+    mint: &'a Pubkey,
+    bump_seed: &'a [u8],
+) -> [&'a [u8]; 3] {
+    [EXTRA_ACCOUNT_METAS_SEED, mint.as_ref(), bump_seed]
+}
+
+/// Pack the data of an `Execute` instruction transferring `amount` tokens
+pub fn execute_instruction_data(amount: u64) -> Vec<u8> {
+    let mut data = Vec::with_capacity(ArrayDiscriminator::LENGTH + 8);
+    data.extend_from_slice(ExecuteInstruction::SPL_DISCRIMINATOR_SLICE);
+    data.extend_from_slice(&amount.to_le_bytes());
+    data
+}
+
+/// Create an `Execute` instruction, without its extra accounts
+pub fn execute_instruction(
+    program_id: &Pubkey,
+    accounts: &ExecuteAccounts<Pubkey>,
+    amount: u64,
+) -> Instruction {
+    let validation_pubkey = get_extra_account_metas_address(&accounts.mint, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &execute_instruction_data(amount),
+        vec![
+            AccountMeta::new_readonly(accounts.source, false),
+            AccountMeta::new_readonly(accounts.mint, false),
+            AccountMeta::new_readonly(accounts.destination, false),
+            AccountMeta::new_readonly(accounts.authority, false),
+            AccountMeta::new_readonly(validation_pubkey, false),
+        ],
+    )
+}
+
+/// Create an `Execute` instruction with all of its extra accounts, fetching
+/// the validation account and any account data required for resolution
+///
+/// If the validation account doesn't exist, the hook requires no extra
+/// accounts.
+pub async fn execute_instruction_with_extra_metas<F: AccountFetcher>(
+    program_id: &Pubkey,
+    accounts: &ExecuteAccounts<Pubkey>,
+    amount: u64,
+    fetcher: &F,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = execute_instruction(program_id, accounts, amount);
+    let validation_pubkey = instruction.accounts[VALIDATION_INDEX].pubkey;
+    let validation_data = fetcher
+        .get_multiple_account_data(&[validation_pubkey])
+        .await
+        .map_err(|_| AccountResolutionError::AccountFetchFailed)?
+        .pop()
+        .flatten();
+    if let Some(validation_data) = validation_data {
+        ExtraAccountMetaList::add_to_instruction_with_fetcher::<ExecuteInstruction, _>(
+            &mut instruction,
+            fetcher,
+            &validation_data,
+        )
+        .await?;
+    }
+    Ok(instruction)
+}
+
+/// Create an `Execute` instruction and its account infos for a CPI, with all
+/// of its extra accounts
+///
+/// The validation account and the extra accounts are looked up in
+/// `additional_account_infos`. The result can be passed to `invoke`.
+///
+/// The account infos may be of any type implementing `AccountInfoView`, such
+/// as pinocchio's `AccountView`.
+pub fn execute_cpi_instruction<A: AccountInfoView + Clone>(
+    program_id: &Pubkey,
+    accounts: &ExecuteAccounts<A>,
+    amount: u64,
+    additional_account_infos: &[A],
+) -> Result<(Instruction, Vec<A>), ProgramError> {
+    let mut cpi_instruction = execute_instruction(
+        program_id,
+        &ExecuteAccounts {
+            source: accounts.source.key(),
+            mint: accounts.mint.key(),
+            destination: accounts.destination.key(),
+            authority: accounts.authority.key(),
+        },
+        amount,
+    );
+    let validation_pubkey = cpi_instruction.accounts[VALIDATION_INDEX].pubkey;
+    let validation_info = additional_account_infos
+        .iter()
+        .find(|info| info.key() == validation_pubkey)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    let mut cpi_account_infos = vec![
+        accounts.source.clone(),
+        accounts.mint.clone(),
+        accounts.destination.clone(),
+        accounts.authority.clone(),
+        validation_info.clone(),
+    ];
+    ExtraAccountMetaList::add_to_cpi_instruction::<ExecuteInstruction, _>(
+        &mut cpi_instruction,
+        &mut cpi_account_infos,
+        &validation_info.try_borrow_data()?,
+        additional_account_infos,
+    )?;
+    Ok((cpi_instruction, cpi_account_infos))
+}
+
+/// Validation account of a transfer hook, storing the extra account metas
+/// required by `Execute` for one mint
+pub struct ExecuteExtraAccountMetaList;
+impl ExecuteExtraAccountMetaList {
+    /// Get the size of a validation account holding `num_extra_accounts`
+    /// extra account metas
+    pub fn size_of(num_extra_accounts: usize) -> Result<usize, ProgramError> {
+        ExtraAccountMetaList::size_of(num_extra_accounts)
+    }
+
+    /// Initialize the validation account of `mint` with the given extra
+    /// account metas
+    ///
+    /// The account must already be allocated at the address of the
+    /// validation account, see `get_extra_account_metas_address`.
+    pub fn init(
+        validation_info: &AccountInfo,
+        mint: &Pubkey,
+        program_id: &Pubkey,
+        extra_account_metas: &[ExtraAccountMeta],
+    ) -> Result<(), ProgramError> {
+        if *validation_info.key != get_extra_account_metas_address(mint, program_id) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        ExtraAccountMetaList::init::<ExecuteInstruction>(
+            &mut validation_info.try_borrow_mut_data()?,
+            extra_account_metas,
+        )
+    }
+
+    /// Check the account infos provided to `Execute` against the extra
+    /// account metas stored in the validation account, itself checked to be
+    /// the validation account of the mint
+    pub fn check_account_infos(
+        account_infos: &[impl AccountInfoView],
+        instruction_data: &[u8],
+        program_id: &Pubkey,
+    ) -> Result<(), ProgramError> {
+        if account_infos.len() < NUM_EXECUTE_ACCOUNTS {
+            return Err(AccountResolutionError::NotEnoughAccounts.into());
+        }
+        let validation_info = &account_infos[VALIDATION_INDEX];
+        let mint = account_infos[MINT_INDEX].key();
+        if validation_info.key() != get_extra_account_metas_address(&mint, program_id) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        ExtraAccountMetaList::check_account_infos::<ExecuteInstruction>(
+            account_infos,
+            instruction_data,
+            program_id,
+            &validation_info.try_borrow_data()?,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::seeds::Seed, std::collections::HashMap};
+
+    #[test]
+    fn execute_discriminator() {
+        assert_eq!(
+            ExecuteInstruction::SPL_DISCRIMINATOR,
+            ArrayDiscriminator::new_with_hash_input("spl-transfer-hook-interface:execute")
+        );
+        assert_eq!(
+            execute_instruction_data(1),
+            [105, 37, 101, 197, 75, 251, 102, 26, 1, 0, 0, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[tokio::test]
+    async fn execute_with_extra_metas() {
+        let program_id = Pubkey::new_unique();
+        let accounts = ExecuteAccounts {
+            source: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            destination: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+        };
+        let amount = 100;
+        let (validation_pubkey, bump_seed) =
+            get_extra_account_metas_address_and_bump_seed(&accounts.mint, &program_id);
+        assert_eq!(
+            Pubkey::create_program_address(
+                &collect_extra_account_metas_signer_seeds(&accounts.mint, &[bump_seed]),
+                &program_id
+            )
+            .unwrap(),
+            validation_pubkey
+        );
+
+        // Without a validation account, no extra accounts are required
+        let instruction =
+            execute_instruction_with_extra_metas(&program_id, &accounts, amount, &|_| async {
+                Ok(None)
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            instruction,
+            execute_instruction(&program_id, &accounts, amount)
+        );
+
+        // A PDA derived from the source owner, and a fixed account
+        let fixed_pubkey = Pubkey::new_unique();
+        let metas = [
+            ExtraAccountMeta::new_with_seeds(&[Seed::AccountKey { index: 3 }], false, true)
+                .unwrap(),
+            ExtraAccountMeta::new_with_pubkey(&fixed_pubkey, false, false).unwrap(),
+        ];
+        let mut validation_data =
+            vec![0; ExecuteExtraAccountMetaList::size_of(metas.len()).unwrap()];
+        let mut validation_lamports = 0;
+        let validation_info = AccountInfo::new(
+            &validation_pubkey,
+            false,
+            false,
+            &mut validation_lamports,
+            &mut validation_data,
+            &program_id,
+            false,
+        );
+        ExecuteExtraAccountMetaList::init(&validation_info, &accounts.mint, &program_id, &metas)
+            .unwrap();
+        assert_eq!(
+            ExecuteExtraAccountMetaList::init(
+                &validation_info,
+                &Pubkey::new_unique(),
+                &program_id,
+                &metas
+            )
+            .unwrap_err(),
+            ProgramError::InvalidSeeds
+        );
+
+        let fetched = HashMap::from([(validation_pubkey, validation_info.data.borrow().to_vec())]);
+        let instruction =
+            execute_instruction_with_extra_metas(&program_id, &accounts, amount, &|pubkey| {
+                let data = fetched.get(&pubkey).cloned();
+                async move { Ok(data) }
+            })
+            .await
+            .unwrap();
+        let pda = Pubkey::find_program_address(&[accounts.authority.as_ref()], &program_id).0;
+        assert_eq!(instruction.data, execute_instruction_data(amount));
+        assert_eq!(
+            instruction.accounts,
+            [
+                AccountMeta::new_readonly(accounts.source, false),
+                AccountMeta::new_readonly(accounts.mint, false),
+                AccountMeta::new_readonly(accounts.destination, false),
+                AccountMeta::new_readonly(accounts.authority, false),
+                AccountMeta::new_readonly(validation_pubkey, false),
+                AccountMeta::new(pda, false),
+                AccountMeta::new_readonly(fixed_pubkey, false),
+            ]
+        );
+
+        // Build the same instruction for a CPI, from account infos provided
+        // in any order
+        let keys = [
+            accounts.source,
+            accounts.mint,
+            accounts.destination,
+            accounts.authority,
+            pda,
+            fixed_pubkey,
+        ];
+        let mut lamports = [0; 6];
+        let mut datas = [[0u8; 0]; 6];
+        let infos = keys
+            .iter()
+            .zip(lamports.iter_mut().zip(datas.iter_mut()))
+            .map(|(key, (lamports, data))| {
+                AccountInfo::new(key, false, key == &pda, lamports, data, &program_id, false)
+            })
+            .collect::<Vec<_>>();
+        let cpi_accounts = ExecuteAccounts {
+            source: infos[0].clone(),
+            mint: infos[1].clone(),
+            destination: infos[2].clone(),
+            authority: infos[3].clone(),
+        };
+        let additional_account_infos =
+            [infos[5].clone(), validation_info.clone(), infos[4].clone()];
+        let (cpi_instruction, cpi_account_infos) = execute_cpi_instruction(
+            &program_id,
+            &cpi_accounts,
+            amount,
+            &additional_account_infos,
+        )
+        .unwrap();
+        assert_eq!(cpi_instruction, instruction);
+        assert_eq!(
+            cpi_account_infos
+                .iter()
+                .map(|info| *info.key)
+                .collect::<Vec<_>>(),
+            instruction
+                .accounts
+                .iter()
+                .map(|meta| meta.pubkey)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            execute_cpi_instruction(&program_id, &cpi_accounts, amount, &infos[4..]).unwrap_err(),
+            ProgramError::NotEnoughAccountKeys
+        );
+
+        // The hook program checks the accounts it receives
+        ExecuteExtraAccountMetaList::check_account_infos(
+            &cpi_account_infos,
+            &cpi_instruction.data,
+            &program_id,
+        )
+        .unwrap();
+        let mut wrong_mint_infos = cpi_account_infos.clone();
+        wrong_mint_infos[MINT_INDEX] = infos[2].clone();
+        assert_eq!(
+            ExecuteExtraAccountMetaList::check_account_infos(
+                &wrong_mint_infos,
+                &cpi_instruction.data,
+                &program_id,
+            )
+            .unwrap_err(),
+            ProgramError::InvalidSeeds
+        );
+        assert_eq!(
+            ExecuteExtraAccountMetaList::check_account_infos(
+                &cpi_account_infos[..6],
+                &cpi_instruction.data,
+                &program_id,
+            )
+            .unwrap_err(),
+            AccountResolutionError::IncorrectAccount.into()
+        );
+    }
+}
@@ -0,0 +1,60 @@
+//! Versioned extra account meta configurations
+//!
+//! A validation account may hold several versions of the extra account metas
+//! for the same instruction, each in its own TLV entry. Version 1 uses the
+//! instruction's own discriminator, so existing validation accounts hold
+//! version 1, and each later version uses a discriminator derived from it by
+//! the library.
+//!
+//! A program evolving its account requirements writes the new version next to
+//! the old one and checks the version it implements, while clients pick the
+//! highest version present with
+//! `ExtraAccountMetaList::add_to_instruction_versioned`.
+
+use {
+    crate::constraints::derive_discriminator,
+    spl_discriminator::{ArrayDiscriminator, SplDiscriminate},
+    std::marker::PhantomData,
+};
+
+/// Highest version of the extra account metas for an instruction
+pub const MAX_VERSION: u8 = 16;
+
+/// Get the discriminator of a version of the extra account metas for the
+/// instruction with the given discriminator
+///
+/// Versions start at 1, which is the instruction's discriminator itself, and
+/// go up to `MAX_VERSION`. Later versions hash the instruction's
+/// discriminator and the version, so they can't collide with each other or
+/// with the versions of another instruction.
+pub const fn versioned_discriminator(
+    discriminator: ArrayDiscriminator,
+    version: u8,
+) -> ArrayDiscriminator {
+    assert!(version > 0, "versions start at 1");
+    assert!(version <= MAX_VERSION, "version is above MAX_VERSION");
+    if version == 1 {
+        return discriminator;
+    }
+    let source = discriminator.as_slice();
+    let mut bytes = [0; ArrayDiscriminator::LENGTH + 1];
+    let mut i = 0;
+    while i < ArrayDiscriminator::LENGTH {
+        bytes[i] = source[i];
+        i += 1;
+    }
+    bytes[ArrayDiscriminator::LENGTH] = version;
+    derive_discriminator("version", &bytes)
+}
+
+/// Discriminator type for version `VERSION` of the extra account metas for
+/// the instruction `T`
+///
+/// Use it in place of `T` with the `ExtraAccountMetaList` functions, e.g.
+/// `ExtraAccountMetaList::init::<Versioned<T, 2>>`.
+pub struct Versioned<T, const VERSION: u8>(PhantomData<T>);
+
+impl<T: SplDiscriminate, const VERSION: u8> SplDiscriminate for Versioned<T, VERSION> {
+    const SPL_DISCRIMINATOR: ArrayDiscriminator =
+        versioned_discriminator(T::SPL_DISCRIMINATOR, VERSION);
+}
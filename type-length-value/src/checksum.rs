@@ -0,0 +1,87 @@
+//! Checksums guarding the values of TLV entries
+//!
+//! Checksummed entries store a 4-byte checksum of their value before the
+//! value itself, so that a corrupted entry is caught when it's read rather
+//! than wherever the bad data ends up being used. See
+//! `TlvState::get_checksummed_bytes_with_repetition` and
+//! `TlvStateMut::update_checksummed_with_repetition`.
+
+use {crate::error::TlvError, solana_program_error::ProgramError};
+
+/// Number of bytes taken by the checksum at the start of an entry
+pub const CHECKSUM_LEN: usize = 4;
+
+/// Compute the checksum of a value, with 32-bit FNV-1a
+pub const fn checksum(value: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let mut hash: u32 = 0x811c_9dc5;
+    let mut i = 0;
+    while i < value.len() {
+        hash ^= value[i] as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+        i += 1;
+    }
+    hash.to_le_bytes()
+}
+
+fn split(entry: &[u8]) -> Result<(&[u8], &[u8]), ProgramError> {
+    if entry.len() < CHECKSUM_LEN {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(entry.split_at(CHECKSUM_LEN))
+}
+
+/// Get the value of a checksummed entry, after verifying its checksum
+pub fn verify(entry: &[u8]) -> Result<&[u8], ProgramError> {
+    let (stored, value) = split(entry)?;
+    if stored != checksum(value) {
+        return Err(TlvError::ChecksumMismatch.into());
+    }
+    Ok(value)
+}
+
+/// Get the mutable value of a checksummed entry, after verifying its checksum
+///
+/// Call `update` once done writing to the value.
+pub fn verify_mut(entry: &mut [u8]) -> Result<&mut [u8], ProgramError> {
+    verify(entry)?;
+    Ok(&mut entry[CHECKSUM_LEN..])
+}
+
+/// Store the checksum of the value of a checksummed entry
+pub fn update(entry: &mut [u8]) -> Result<(), ProgramError> {
+    let sum = checksum(split(entry)?.1);
+    entry[..CHECKSUM_LEN].copy_from_slice(&sum);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn verify_entries() {
+        assert_eq!(checksum(&[]), 0x811c_9dc5u32.to_le_bytes());
+        assert_eq!(checksum(b"a"), 0xe40c_292cu32.to_le_bytes());
+
+        let mut entry = [0; CHECKSUM_LEN + 3];
+        assert_eq!(
+            verify(&entry).unwrap_err(),
+            TlvError::ChecksumMismatch.into()
+        );
+        update(&mut entry).unwrap();
+        assert_eq!(verify(&entry).unwrap(), [0; 3]);
+
+        verify_mut(&mut entry).unwrap()[1] = 1;
+        assert_eq!(
+            verify(&entry).unwrap_err(),
+            TlvError::ChecksumMismatch.into()
+        );
+        update(&mut entry).unwrap();
+        assert_eq!(verify(&entry).unwrap(), [0, 1, 0]);
+
+        assert_eq!(
+            verify(&entry[..3]).unwrap_err(),
+            ProgramError::InvalidAccountData
+        );
+    }
+}
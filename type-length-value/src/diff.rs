@@ -0,0 +1,214 @@
+//! Comparison of TLV data, to describe how entries changed
+
+use {
+    crate::state::{get_base_len, get_entry_bounds},
+    alloc::vec::Vec,
+    core::ops::Range,
+    solana_program_error::ProgramError,
+    spl_discriminator::ArrayDiscriminator,
+};
+
+/// Change to a TLV entry between two versions of TLV data, see `tlv_diff`
+///
+/// Entries are identified by their type and repetition number, and ranges
+/// cover the value bytes of the entries within their TLV data.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TlvChange {
+    /// Entry only present in the new data
+    Added {
+        /// Type of the entry
+        discriminator: ArrayDiscriminator,
+        /// Repetition number of the entry
+        repetition_number: usize,
+        /// Value range in the new data
+        range: Range<usize>,
+    },
+    /// Entry only present in the old data
+    Removed {
+        /// Type of the entry
+        discriminator: ArrayDiscriminator,
+        /// Repetition number of the entry
+        repetition_number: usize,
+        /// Value range in the old data
+        range: Range<usize>,
+    },
+    /// Entry present in both, with a different value
+    Modified {
+        /// Type of the entry
+        discriminator: ArrayDiscriminator,
+        /// Repetition number of the entry
+        repetition_number: usize,
+        /// Value range in the old data
+        before: Range<usize>,
+        /// Value range in the new data
+        after: Range<usize>,
+    },
+}
+
+/// TLV entry with its repetition number and value range
+struct Entry {
+    discriminator: ArrayDiscriminator,
+    repetition_number: usize,
+    range: Range<usize>,
+}
+
+fn get_entries(tlv_data: &[u8]) -> Result<Vec<Entry>, ProgramError> {
+    let bounds = get_entry_bounds(tlv_data)?;
+    let mut entries: Vec<Entry> = Vec::with_capacity(bounds.len());
+    for (discriminator, type_start, value_end) in bounds {
+        let repetition_number = entries
+            .iter()
+            .filter(|entry| entry.discriminator == discriminator)
+            .count();
+        entries.push(Entry {
+            discriminator,
+            repetition_number,
+            range: type_start.saturating_add(get_base_len())..value_end,
+        });
+    }
+    Ok(entries)
+}
+
+/// Describe the changes to the entries between two versions of TLV data
+///
+/// Changes to entries of the old data come first, in the order of the old
+/// data, followed by the added entries, in the order of the new data. Entries
+/// that were only moved, such as when an earlier entry is removed, aren't
+/// reported.
+pub fn tlv_diff(before: &[u8], after: &[u8]) -> Result<Vec<TlvChange>, ProgramError> {
+    let before_entries = get_entries(before)?;
+    let after_entries = get_entries(after)?;
+    let find_after = |entry: &Entry| {
+        after_entries.iter().find(|after_entry| {
+            after_entry.discriminator == entry.discriminator
+                && after_entry.repetition_number == entry.repetition_number
+        })
+    };
+
+    let mut changes = Vec::new();
+    for entry in &before_entries {
+        match find_after(entry) {
+            Some(after_entry) => {
+                if before[entry.range.clone()] != after[after_entry.range.clone()] {
+                    changes.push(TlvChange::Modified {
+                        discriminator: entry.discriminator,
+                        repetition_number: entry.repetition_number,
+                        before: entry.range.clone(),
+                        after: after_entry.range.clone(),
+                    });
+                }
+            }
+            None => changes.push(TlvChange::Removed {
+                discriminator: entry.discriminator,
+                repetition_number: entry.repetition_number,
+                range: entry.range.clone(),
+            }),
+        }
+    }
+    for after_entry in &after_entries {
+        let existed = before_entries.iter().any(|entry| {
+            entry.discriminator == after_entry.discriminator
+                && entry.repetition_number == after_entry.repetition_number
+        });
+        if !existed {
+            changes.push(TlvChange::Added {
+                discriminator: after_entry.discriminator,
+                repetition_number: after_entry.repetition_number,
+                range: after_entry.range.clone(),
+            });
+        }
+    }
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::state::{TlvState, TlvStateOwned},
+        spl_discriminator::SplDiscriminate,
+    };
+
+    struct First;
+    impl SplDiscriminate for First {
+        const SPL_DISCRIMINATOR: ArrayDiscriminator =
+            ArrayDiscriminator::new([1; ArrayDiscriminator::LENGTH]);
+    }
+
+    struct Second;
+    impl SplDiscriminate for Second {
+        const SPL_DISCRIMINATOR: ArrayDiscriminator =
+            ArrayDiscriminator::new([2; ArrayDiscriminator::LENGTH]);
+    }
+
+    #[test]
+    fn diff_entries() {
+        let mut state = TlvStateOwned::new();
+        state.alloc::<First>(4, true).unwrap().0.fill(1);
+        state.alloc::<Second>(2, false).unwrap().0.fill(2);
+        state.alloc::<First>(3, true).unwrap().0.fill(3);
+        let before = state.get_data().to_vec();
+        assert_eq!(tlv_diff(&before, &before).unwrap(), []);
+
+        // removing the first entry moves the others and shifts the repetition
+        // numbers of its type, so both of its repetitions are modified
+        let mut state = TlvStateOwned::new();
+        state.alloc::<Second>(2, false).unwrap().0.fill(2);
+        state.alloc::<First>(3, true).unwrap().0.fill(4);
+        state.alloc::<First>(1, true).unwrap().0.fill(5);
+        let after = state.get_data().to_vec();
+
+        let base_len = get_base_len();
+        assert_eq!(
+            tlv_diff(&before, &after).unwrap(),
+            [
+                TlvChange::Modified {
+                    discriminator: First::SPL_DISCRIMINATOR,
+                    repetition_number: 0,
+                    before: base_len..base_len + 4,
+                    after: base_len * 2 + 2..base_len * 2 + 5,
+                },
+                TlvChange::Modified {
+                    discriminator: First::SPL_DISCRIMINATOR,
+                    repetition_number: 1,
+                    before: base_len * 3 + 6..base_len * 3 + 9,
+                    after: base_len * 3 + 5..base_len * 3 + 6,
+                },
+            ]
+        );
+        assert_eq!(
+            tlv_diff(&after, &[]).unwrap(),
+            [
+                TlvChange::Removed {
+                    discriminator: Second::SPL_DISCRIMINATOR,
+                    repetition_number: 0,
+                    range: base_len..base_len + 2,
+                },
+                TlvChange::Removed {
+                    discriminator: First::SPL_DISCRIMINATOR,
+                    repetition_number: 0,
+                    range: base_len * 2 + 2..base_len * 2 + 5,
+                },
+                TlvChange::Removed {
+                    discriminator: First::SPL_DISCRIMINATOR,
+                    repetition_number: 1,
+                    range: base_len * 3 + 5..base_len * 3 + 6,
+                },
+            ]
+        );
+        assert_eq!(
+            tlv_diff(&before[..base_len * 2 + 6], &before).unwrap(),
+            [TlvChange::Added {
+                discriminator: First::SPL_DISCRIMINATOR,
+                repetition_number: 1,
+                range: base_len * 3 + 6..base_len * 3 + 9,
+            }]
+        );
+
+        // malformed data is rejected
+        assert_eq!(
+            tlv_diff(&[1; 12], &before).unwrap_err(),
+            ProgramError::InvalidAccountData
+        );
+    }
+}
@@ -19,6 +19,21 @@ pub enum TlvError {
     /// Type already exists in TLV data
     #[error("Type already exists in TLV data")]
     TypeAlreadyExists,
+    /// Discriminator already used by a different type
+    #[error("Discriminator already used by a different type")]
+    DiscriminatorCollision,
+    /// TLV header belongs to a different account type
+    #[error("TLV header belongs to a different account type")]
+    InvalidAccountType,
+    /// TLV header version is not supported
+    #[error("TLV header version is not supported")]
+    UnsupportedVersion,
+    /// Checksum doesn't match the value of the TLV entry
+    #[error("Checksum doesn't match the value of the TLV entry")]
+    ChecksumMismatch,
+    /// Chunked TLV entry has not been fully written
+    #[error("Chunked TLV entry has not been fully written")]
+    IncompleteEntry,
 }
 
 impl From<TlvError> for ProgramError {
@@ -32,6 +47,11 @@ impl ToStr for TlvError {
         match self {
             TlvError::TypeNotFound => "Type not found in TLV data",
             TlvError::TypeAlreadyExists => "Type already exists in TLV data",
+            TlvError::DiscriminatorCollision => "Discriminator already used by a different type",
+            TlvError::InvalidAccountType => "TLV header belongs to a different account type",
+            TlvError::UnsupportedVersion => "TLV header version is not supported",
+            TlvError::ChecksumMismatch => "Checksum doesn't match the value of the TLV entry",
+            TlvError::IncompleteEntry => "Chunked TLV entry has not been fully written",
         }
     }
 }
@@ -0,0 +1,174 @@
+//! Header identifying the account type and layout version of TLV data
+
+use {
+    crate::error::TlvError,
+    bytemuck::{Pod, Zeroable},
+    core::mem::size_of,
+    solana_program_error::ProgramError,
+    spl_discriminator::{ArrayDiscriminator, SplDiscriminate},
+};
+
+/// Header stored before the TLV entries of an account
+///
+/// The account type tells accounts of different programs, or different
+/// accounts of the same program, apart, so that foreign data isn't mistaken
+/// for valid TLV state. The version lets the layout evolve, see
+/// `TlvHeader::check`.
+///
+/// ```
+/// use {
+///     spl_discriminator::{ArrayDiscriminator, SplDiscriminate},
+///     spl_type_length_value::{
+///         header::TlvHeader,
+///         state::{TlvState, TlvStateBorrowed, TlvStateMut},
+///     },
+/// };
+/// struct MyAccount;
+/// impl SplDiscriminate for MyAccount {
+///     const SPL_DISCRIMINATOR: ArrayDiscriminator = ArrayDiscriminator::new([1; ArrayDiscriminator::LENGTH]);
+/// }
+/// let header = TlvHeader::of::<MyAccount>(1);
+/// let mut buffer = vec![0; TlvHeader::LEN + 16];
+/// let tlv_data = header.init(&mut buffer).unwrap();
+/// TlvStateMut::unpack(tlv_data).unwrap();
+///
+/// let state = TlvStateBorrowed::unpack_checked(&buffer, &header).unwrap();
+/// assert_eq!(state.get_discriminators().unwrap(), []);
+/// ```
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct TlvHeader {
+    /// Type of the account
+    pub account_type: ArrayDiscriminator,
+    /// Version of the account layout
+    pub version: u8,
+}
+
+impl TlvHeader {
+    /// Size of the header in bytes
+    pub const LEN: usize = size_of::<Self>();
+
+    /// Create a new header
+    pub const fn new(account_type: ArrayDiscriminator, version: u8) -> Self {
+        Self {
+            account_type,
+            version,
+        }
+    }
+
+    /// Create a new header for the account type of `A`
+    pub const fn of<A: SplDiscriminate>(version: u8) -> Self {
+        Self::new(A::SPL_DISCRIMINATOR, version)
+    }
+
+    /// Check that this header, read from an account, is supported by the
+    /// expected header
+    ///
+    /// The account types must match, and the version must not be newer than
+    /// the expected one, since a program can read the layouts it knows about.
+    pub fn check(&self, expected: &Self) -> Result<(), ProgramError> {
+        if self.account_type == ArrayDiscriminator::UNINITIALIZED {
+            Err(ProgramError::UninitializedAccount)
+        } else if self.account_type != expected.account_type {
+            Err(TlvError::InvalidAccountType.into())
+        } else if self.version > expected.version {
+            Err(TlvError::UnsupportedVersion.into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Split the header from the TLV data that follows it, without checking
+    /// the header
+    pub fn unpack(data: &[u8]) -> Result<(&Self, &[u8]), ProgramError> {
+        if data.len() < Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let (header, tlv_data) = data.split_at(Self::LEN);
+        Ok((bytemuck::from_bytes(header), tlv_data))
+    }
+
+    /// Split the header from the TLV data that follows it as mutable, without
+    /// checking the header
+    pub fn unpack_mut(data: &mut [u8]) -> Result<(&mut Self, &mut [u8]), ProgramError> {
+        if data.len() < Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let (header, tlv_data) = data.split_at_mut(Self::LEN);
+        Ok((bytemuck::from_bytes_mut(header), tlv_data))
+    }
+
+    /// Write this header at the start of uninitialized data, returning the
+    /// rest of the data for the TLV entries
+    pub fn init(self, data: &mut [u8]) -> Result<&mut [u8], ProgramError> {
+        let (header, tlv_data) = Self::unpack_mut(data)?;
+        if header.account_type != ArrayDiscriminator::UNINITIALIZED {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        *header = self;
+        Ok(tlv_data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::*,
+        crate::state::{TlvState, TlvStateBorrowed, TlvStateMut},
+        alloc::vec,
+    };
+
+    struct TestAccount;
+    impl SplDiscriminate for TestAccount {
+        const SPL_DISCRIMINATOR: ArrayDiscriminator =
+            ArrayDiscriminator::new([7; ArrayDiscriminator::LENGTH]);
+    }
+
+    struct TestValue;
+    impl SplDiscriminate for TestValue {
+        const SPL_DISCRIMINATOR: ArrayDiscriminator =
+            ArrayDiscriminator::new([1; ArrayDiscriminator::LENGTH]);
+    }
+
+    #[test]
+    fn header_checks() {
+        let header = TlvHeader::of::<TestAccount>(2);
+        assert_eq!(TlvHeader::LEN, 9);
+        let mut buffer = vec![0; TlvHeader::LEN + 16];
+        assert_eq!(
+            TlvStateBorrowed::unpack_checked(&buffer, &header).unwrap_err(),
+            ProgramError::UninitializedAccount
+        );
+
+        let tlv_data = header.init(&mut buffer).unwrap();
+        let mut state = TlvStateMut::unpack(tlv_data).unwrap();
+        state.alloc::<TestValue>(4, false).unwrap().0.fill(1);
+        assert_eq!(
+            header.init(&mut buffer).unwrap_err(),
+            ProgramError::AccountAlreadyInitialized
+        );
+        assert_eq!(buffer[..TlvHeader::LEN], [7, 7, 7, 7, 7, 7, 7, 7, 2]);
+
+        // older and current versions are supported
+        let state = TlvStateBorrowed::unpack_checked(&buffer, &header).unwrap();
+        assert_eq!(state.get_first_bytes::<TestValue>().unwrap(), [1; 4]);
+        let mut state =
+            TlvStateMut::unpack_checked(&mut buffer, &TlvHeader::of::<TestAccount>(3)).unwrap();
+        state.get_first_bytes_mut::<TestValue>().unwrap().fill(2);
+
+        // foreign accounts and newer versions are rejected
+        assert_eq!(
+            TlvStateBorrowed::unpack_checked(&buffer, &TlvHeader::of::<TestValue>(2)).unwrap_err(),
+            TlvError::InvalidAccountType.into()
+        );
+        assert_eq!(
+            TlvStateBorrowed::unpack_checked(&buffer, &TlvHeader::of::<TestAccount>(1))
+                .unwrap_err(),
+            TlvError::UnsupportedVersion.into()
+        );
+        assert_eq!(
+            TlvStateBorrowed::unpack_checked(&buffer[..4], &header).unwrap_err(),
+            ProgramError::InvalidAccountData
+        );
+    }
+}
@@ -1,5 +1,10 @@
 //! Crate defining an interface for managing type-length-value entries in a slab
 //! of bytes, to be used with Solana accounts.
+//!
+//! The TLV state works on plain byte slices with `core` and `alloc` only, so
+//! it can be used with any account type, such as pinocchio's. The helpers
+//! resizing a `solana_account_info::AccountInfo` are enabled by the default
+//! `account-info` feature.
 
 #![allow(clippy::arithmetic_side_effects)]
 #![deny(missing_docs)]
@@ -8,14 +13,21 @@
 
 extern crate alloc;
 
+pub mod checksum;
+pub mod diff;
 pub mod error;
+pub mod header;
 pub mod length;
+#[cfg(feature = "serde-traits")]
+pub mod registry;
 pub mod state;
 pub mod variable_len_pack;
 
 // Export current sdk types for downstream users building with a different sdk
 // version
 // Expose derive macro on feature flag
+#[cfg(feature = "account-info")]
+pub use solana_account_info;
+pub use solana_program_error;
 #[cfg(feature = "derive")]
 pub use spl_type_length_value_derive::SplBorshVariableLenPack;
-pub use {solana_account_info, solana_program_error};
@@ -0,0 +1,278 @@
+//! Registry of TLV types, to render TLV data as JSON
+//!
+//! Programs register the types they store in TLV data, each with a name and a
+//! function decoding its value. `TlvRegistry::dump` then renders the entries
+//! of any TLV data, falling back to hex for unregistered types.
+
+use {
+    crate::{length::Length, state::TlvIter, variable_len_pack::VariableLenPack},
+    alloc::{format, string::String, vec::Vec},
+    core::{fmt::Write, mem::size_of},
+    serde::Serialize,
+    serde_json::{json, Value},
+    solana_program_error::ProgramError,
+    spl_discriminator::{ArrayDiscriminator, SplDiscriminate},
+};
+
+/// Function decoding the value bytes of a TLV entry into JSON
+pub type DecodeFn = fn(&[u8]) -> Result<Value, ProgramError>;
+
+/// Type registered in a `TlvRegistry`
+#[derive(Clone, Debug)]
+struct RegisteredType {
+    discriminator: ArrayDiscriminator,
+    name: &'static str,
+    decode: DecodeFn,
+}
+
+/// Registry of the types stored in TLV data, by discriminator
+#[derive(Clone, Debug, Default)]
+pub struct TlvRegistry {
+    types: Vec<RegisteredType>,
+}
+
+impl TlvRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a type by discriminator, replacing any type previously
+    /// registered with the same discriminator
+    pub fn register(
+        &mut self,
+        discriminator: ArrayDiscriminator,
+        name: &'static str,
+        decode: DecodeFn,
+    ) -> &mut Self {
+        self.types
+            .retain(|registered| registered.discriminator != discriminator);
+        self.types.push(RegisteredType {
+            discriminator,
+            name,
+            decode,
+        });
+        self
+    }
+
+    /// Register a `SplDiscriminate` type with the given decoding function
+    pub fn register_type<V: SplDiscriminate>(
+        &mut self,
+        name: &'static str,
+        decode: DecodeFn,
+    ) -> &mut Self {
+        self.register(V::SPL_DISCRIMINATOR, name, decode)
+    }
+
+    /// Register a variable-length type, decoded with `VariableLenPack` and
+    /// rendered with its `Serialize` implementation
+    pub fn register_variable_len<V: SplDiscriminate + VariableLenPack + Serialize>(
+        &mut self,
+        name: &'static str,
+    ) -> &mut Self {
+        self.register_type::<V>(name, decode_variable_len::<V>)
+    }
+
+    /// Get the name of the type registered for a discriminator
+    pub fn name(&self, discriminator: &ArrayDiscriminator) -> Option<&'static str> {
+        self.get(discriminator).map(|registered| registered.name)
+    }
+
+    fn get(&self, discriminator: &ArrayDiscriminator) -> Option<&RegisteredType> {
+        self.types
+            .iter()
+            .find(|registered| registered.discriminator == *discriminator)
+    }
+
+    /// Render the entries of TLV data as a JSON array
+    ///
+    /// Every entry includes its discriminator in hex. Registered entries
+    /// include the name of their type and their decoded value, while other
+    /// entries, or entries that fail to decode, include their raw data in hex.
+    ///
+    /// Malformed data doesn't fail the dump, so that corrupt data can be
+    /// inspected: the well-formed entries are followed by a last entry with
+    /// an error giving the offset of the malformed data, and the remaining
+    /// bytes in hex.
+    pub fn dump(&self, data: &[u8]) -> Value {
+        let mut offset = 0;
+        let mut entries: Vec<Value> = TlvIter::with_free_regions(data)
+            .map(|(discriminator, value)| {
+                offset += ENTRY_HEADER_LEN + value.len();
+                let mut entry = json!({ "discriminator": to_hex(discriminator.as_ref()) });
+                if let Some(registered) = self.get(&discriminator) {
+                    entry["type"] = registered.name.into();
+                    match (registered.decode)(value) {
+                        Ok(decoded) => entry["value"] = decoded,
+                        Err(err) => {
+                            entry["data"] = to_hex(value).into();
+                            entry["error"] = format!("{err}").into();
+                        }
+                    }
+                } else {
+                    entry["data"] = to_hex(value).into();
+                }
+                entry
+            })
+            .collect();
+        let remainder = &data[offset..];
+        if !is_free_space(remainder) {
+            entries.push(json!({
+                "error": format!("malformed data at offset {offset}"),
+                "data": to_hex(remainder),
+            }));
+        }
+        Value::Array(entries)
+    }
+}
+
+/// Length of the type and length of a TLV entry
+const ENTRY_HEADER_LEN: usize = size_of::<ArrayDiscriminator>() + size_of::<Length>();
+
+/// Whether the data following the last entry is free space, starting with an
+/// uninitialized type, or too short to hold a type and zeroed
+fn is_free_space(remainder: &[u8]) -> bool {
+    let type_len = size_of::<ArrayDiscriminator>();
+    match remainder.get(..type_len) {
+        Some(discriminator) => discriminator == ArrayDiscriminator::UNINITIALIZED.as_slice(),
+        None => remainder.iter().all(|&byte| byte == 0),
+    }
+}
+
+fn decode_variable_len<V: VariableLenPack + Serialize>(data: &[u8]) -> Result<Value, ProgramError> {
+    let value = V::unpack_from_slice(data)?;
+    serde_json::to_value(&value).map_err(|_| ProgramError::InvalidAccountData)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, crate::state::TlvStateOwned, alloc::string::ToString};
+
+    #[derive(Clone, Debug, PartialEq, Serialize)]
+    struct Name {
+        name: String,
+    }
+    impl SplDiscriminate for Name {
+        const SPL_DISCRIMINATOR: ArrayDiscriminator =
+            ArrayDiscriminator::new([1; ArrayDiscriminator::LENGTH]);
+    }
+    impl VariableLenPack for Name {
+        fn pack_into_slice(&self, dst: &mut [u8]) -> Result<(), ProgramError> {
+            dst.copy_from_slice(self.name.as_bytes());
+            Ok(())
+        }
+        fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+            let name = core::str::from_utf8(src).map_err(|_| ProgramError::InvalidAccountData)?;
+            Ok(Self {
+                name: name.to_string(),
+            })
+        }
+        fn get_packed_len(&self) -> Result<usize, ProgramError> {
+            Ok(self.name.len())
+        }
+    }
+
+    struct Counter;
+    impl SplDiscriminate for Counter {
+        const SPL_DISCRIMINATOR: ArrayDiscriminator =
+            ArrayDiscriminator::new([2; ArrayDiscriminator::LENGTH]);
+    }
+
+    fn decode_counter(data: &[u8]) -> Result<Value, ProgramError> {
+        let count = u64::from_le_bytes(
+            data.try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+        Ok(json!({ "count": count }))
+    }
+
+    #[test]
+    fn dump_tlv_data() {
+        let mut registry = TlvRegistry::new();
+        registry
+            .register_variable_len::<Name>("name")
+            .register_type::<Counter>("counter", decode_counter);
+        assert_eq!(registry.name(&Counter::SPL_DISCRIMINATOR), Some("counter"));
+
+        let mut state = TlvStateOwned::new();
+        state
+            .alloc_and_pack_variable_len_entry(
+                &Name {
+                    name: "alice".to_string(),
+                },
+                false,
+            )
+            .unwrap();
+        state
+            .alloc::<Counter>(8, true)
+            .unwrap()
+            .0
+            .copy_from_slice(&7u64.to_le_bytes());
+        // fails to decode
+        state.alloc::<Counter>(1, true).unwrap().0[0] = 0xab;
+        let unknown = ArrayDiscriminator::new([3; ArrayDiscriminator::LENGTH]);
+        let mut data = state.into_bytes();
+        data.extend_from_slice(unknown.as_ref());
+        data.extend_from_slice(&[2, 0, 0, 0, 0xff, 0x01]);
+
+        assert_eq!(
+            registry.dump(&data),
+            json!([
+                {
+                    "discriminator": "0101010101010101",
+                    "type": "name",
+                    "value": { "name": "alice" },
+                },
+                {
+                    "discriminator": "0202020202020202",
+                    "type": "counter",
+                    "value": { "count": 7 },
+                },
+                {
+                    "discriminator": "0202020202020202",
+                    "type": "counter",
+                    "data": "ab",
+                    "error": "An account's data contents was invalid",
+                },
+                {
+                    "discriminator": "0303030303030303",
+                    "data": "ff01",
+                },
+            ])
+        );
+
+        // registering again replaces the type
+        registry.register(unknown, "unknown", |_| Ok(Value::Null));
+        registry.register(unknown, "flag", |data| Ok(json!(data[0] != 0)));
+        assert_eq!(registry.dump(&data)[3]["type"], "flag");
+
+        // free space after the entries is left out
+        let mut free = data.clone();
+        free.extend_from_slice(&[0; 20]);
+        assert_eq!(registry.dump(&free), registry.dump(&data));
+
+        // malformed data is reported after the well-formed entries
+        let mut malformed = data.clone();
+        malformed.extend_from_slice(&[1; 12]);
+        let dump = registry.dump(&malformed);
+        assert_eq!(dump.as_array().unwrap().len(), 5);
+        assert_eq!(
+            dump[4],
+            json!({
+                "error": format!("malformed data at offset {}", data.len()),
+                "data": "010101010101010101010101",
+            })
+        );
+        assert_eq!(
+            registry.dump(&[1; 3]),
+            json!([{ "error": "malformed data at offset 0", "data": "010101" }])
+        );
+    }
+}
@@ -1,13 +1,22 @@
 //! Type-length-value structure definition and manipulation
 
+#[cfg(feature = "account-info")]
+use solana_account_info::AccountInfo;
 use {
-    crate::{error::TlvError, length::Length, variable_len_pack::VariableLenPack},
+    crate::{
+        checksum, error::TlvError, header::TlvHeader, length::Length,
+        variable_len_pack::VariableLenPack,
+    },
     alloc::{vec, vec::Vec},
     bytemuck::{try_from_bytes, try_from_bytes_mut, Pod},
-    core::{cmp::Ordering, mem::size_of},
-    solana_account_info::AccountInfo,
+    core::{
+        any::{type_name, TypeId},
+        cmp::Ordering,
+        mem::size_of,
+    },
     solana_program_error::ProgramError,
     spl_discriminator::{ArrayDiscriminator, SplDiscriminate},
+    spl_list_view::{ListView, ListViewMut, ListViewReadOnly},
 };
 
 fn pod_from_bytes<T: Pod>(bytes: &[u8]) -> Result<&T, ProgramError> {
@@ -130,8 +139,9 @@ fn get_discriminators_and_end_index(
     Ok((discriminators, start_index))
 }
 
-fn get_bytes<V: SplDiscriminate>(
+fn get_bytes(
     tlv_data: &[u8],
+    discriminator: ArrayDiscriminator,
     repetition_number: usize,
 ) -> Result<&[u8], ProgramError> {
     let TlvIndices {
@@ -139,12 +149,7 @@ fn get_bytes<V: SplDiscriminate>(
         length_start,
         value_start,
         value_repetition_number: _,
-    } = get_indices(
-        tlv_data,
-        V::SPL_DISCRIMINATOR,
-        false,
-        Some(repetition_number),
-    )?;
+    } = get_indices(tlv_data, discriminator, false, Some(repetition_number))?;
     // get_indices has checked that tlv_data is long enough to include these
     // indices
     let length = pod_from_bytes::<Length>(&tlv_data[length_start..value_start])?;
@@ -155,6 +160,47 @@ fn get_bytes<V: SplDiscriminate>(
     Ok(&tlv_data[value_start..value_end])
 }
 
+/// Discriminator reserved for regions freed with `TlvStateMut::free`
+///
+/// Freed regions stay in the buffer as regular entries with this type and
+/// a zeroed value, forming an in-band free list that
+/// `TlvStateMut::alloc_best_fit` draws from before appending new entries.
+/// They are skipped by `TlvState::iter` and `TlvState::get_discriminators`.
+pub const FREE_REGION_DISCRIMINATOR: ArrayDiscriminator =
+    ArrayDiscriminator::new([u8::MAX; ArrayDiscriminator::LENGTH]);
+
+/// Get the type, start index, and value end index of every entry in the TLV
+/// data, in order
+pub(crate) fn get_entry_bounds(
+    tlv_data: &[u8],
+) -> Result<Vec<(ArrayDiscriminator, usize, usize)>, ProgramError> {
+    let (_, end_index) = get_discriminators_and_end_index(tlv_data)?;
+    let mut entries = vec![];
+    let mut type_start = 0;
+    while type_start < end_index {
+        let (discriminator, _, value_end) =
+            split_entry(&tlv_data[type_start..]).ok_or(ProgramError::InvalidAccountData)?;
+        let value_end = type_start.saturating_add(value_end);
+        entries.push((discriminator, type_start, value_end));
+        type_start = value_end;
+    }
+    Ok(entries)
+}
+
+/// Split a chunked entry into the number of bytes written so far and its
+/// data, see `TlvStateMut::alloc_chunked`
+fn split_chunked(entry: &[u8]) -> Result<(usize, &[u8]), ProgramError> {
+    if entry.len() < size_of::<Length>() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let (written, data) = entry.split_at(size_of::<Length>());
+    let written = usize::try_from(*pod_from_bytes::<Length>(written)?)?;
+    if written > data.len() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok((written, data))
+}
+
 /// Trait for all TLV state
 ///
 /// Stores data as any number of type-length-value structures underneath, where:
@@ -221,7 +267,7 @@ pub trait TlvState {
         &self,
         repetition_number: usize,
     ) -> Result<&V, ProgramError> {
-        let data = get_bytes::<V>(self.get_data(), repetition_number)?;
+        let data = get_bytes(self.get_data(), V::SPL_DISCRIMINATOR, repetition_number)?;
         pod_from_bytes::<V>(data)
     }
 
@@ -237,7 +283,7 @@ pub trait TlvState {
         &self,
         repetition_number: usize,
     ) -> Result<V, ProgramError> {
-        let data = get_bytes::<V>(self.get_data(), repetition_number)?;
+        let data = get_bytes(self.get_data(), V::SPL_DISCRIMINATOR, repetition_number)?;
         V::unpack_from_slice(data)
     }
 
@@ -249,12 +295,104 @@ pub trait TlvState {
         self.get_variable_len_value_with_repetition::<V>(0)
     }
 
+    /// Unpack a portion of the TLV data as bytes for the checksummed entry
+    /// number specified, failing if the bytes don't match their checksum
+    ///
+    /// See `TlvStateMut::alloc_checksummed`.
+    fn get_checksummed_bytes_with_repetition<V: SplDiscriminate>(
+        &self,
+        repetition_number: usize,
+    ) -> Result<&[u8], ProgramError> {
+        let entry = get_bytes(self.get_data(), V::SPL_DISCRIMINATOR, repetition_number)?;
+        checksum::verify(entry)
+    }
+
+    /// Unpack a portion of the TLV data as bytes for the first checksummed
+    /// entry found
+    fn get_first_checksummed_bytes<V: SplDiscriminate>(&self) -> Result<&[u8], ProgramError> {
+        self.get_checksummed_bytes_with_repetition::<V>(0)
+    }
+
+    /// Unpack a portion of the TLV data as the desired Pod type for the
+    /// checksummed entry number specified
+    fn get_checksummed_value_with_repetition<V: SplDiscriminate + Pod>(
+        &self,
+        repetition_number: usize,
+    ) -> Result<&V, ProgramError> {
+        let data = self.get_checksummed_bytes_with_repetition::<V>(repetition_number)?;
+        pod_from_bytes::<V>(data)
+    }
+
+    /// Unpack a portion of the TLV data as the desired Pod type for the first
+    /// checksummed entry found
+    fn get_first_checksummed_value<V: SplDiscriminate + Pod>(&self) -> Result<&V, ProgramError> {
+        self.get_checksummed_value_with_repetition::<V>(0)
+    }
+
+    /// Check whether all the data of the chunked entry number specified has
+    /// been written, see `TlvStateMut::alloc_chunked`
+    fn is_complete_with_repetition<V: SplDiscriminate>(
+        &self,
+        repetition_number: usize,
+    ) -> Result<bool, ProgramError> {
+        let entry = get_bytes(self.get_data(), V::SPL_DISCRIMINATOR, repetition_number)?;
+        let (written, data) = split_chunked(entry)?;
+        Ok(written == data.len())
+    }
+
+    /// Check whether all the data of the first chunked entry found has been
+    /// written
+    fn is_complete<V: SplDiscriminate>(&self) -> Result<bool, ProgramError> {
+        self.is_complete_with_repetition::<V>(0)
+    }
+
+    /// Unpack the data of the chunked entry number specified, failing if it
+    /// hasn't been fully written
+    fn get_chunked_bytes_with_repetition<V: SplDiscriminate>(
+        &self,
+        repetition_number: usize,
+    ) -> Result<&[u8], ProgramError> {
+        let entry = get_bytes(self.get_data(), V::SPL_DISCRIMINATOR, repetition_number)?;
+        let (written, data) = split_chunked(entry)?;
+        if written != data.len() {
+            return Err(TlvError::IncompleteEntry.into());
+        }
+        Ok(data)
+    }
+
+    /// Unpack the data of the first chunked entry found, failing if it hasn't
+    /// been fully written
+    fn get_first_chunked_bytes<V: SplDiscriminate>(&self) -> Result<&[u8], ProgramError> {
+        self.get_chunked_bytes_with_repetition::<V>(0)
+    }
+
+    /// Unpack a portion of the TLV data as a list of Pod elements for the
+    /// entry number specified, see `TlvStateMut::alloc_list`
+    ///
+    /// Values don't start at aligned addresses, so the elements should have
+    /// an alignment of 1, like the unaligned Pod types.
+    fn get_list_with_repetition<V: SplDiscriminate, T: Pod>(
+        &self,
+        repetition_number: usize,
+    ) -> Result<ListViewReadOnly<'_, T>, ProgramError> {
+        let data = get_bytes(self.get_data(), V::SPL_DISCRIMINATOR, repetition_number)?;
+        ListView::<T>::unpack(data)
+    }
+
+    /// Unpack a portion of the TLV data as a list of Pod elements for the
+    /// first entry found
+    fn get_first_list<V: SplDiscriminate, T: Pod>(
+        &self,
+    ) -> Result<ListViewReadOnly<'_, T>, ProgramError> {
+        self.get_list_with_repetition::<V, T>(0)
+    }
+
     /// Unpack a portion of the TLV data as bytes for the entry number specified
     fn get_bytes_with_repetition<V: SplDiscriminate>(
         &self,
         repetition_number: usize,
     ) -> Result<&[u8], ProgramError> {
-        get_bytes::<V>(self.get_data(), repetition_number)
+        get_bytes(self.get_data(), V::SPL_DISCRIMINATOR, repetition_number)
     }
 
     /// Unpack a portion of the TLV data as bytes for the first entry found
@@ -262,9 +400,33 @@ pub trait TlvState {
         self.get_bytes_with_repetition::<V>(0)
     }
 
+    /// Unpack a portion of the TLV data as bytes for the first entry found
+    /// with the given discriminator, for types only known at runtime
+    fn get_first_bytes_with_discriminator(
+        &self,
+        discriminator: ArrayDiscriminator,
+    ) -> Result<&[u8], ProgramError> {
+        get_bytes(self.get_data(), discriminator, 0)
+    }
+
+    /// Iterate through the TLV entries, returning the type and the value
+    /// bytes of each initialized entry, in order
+    ///
+    /// Regions freed with `TlvStateMut::free` are skipped.
+    fn iter(&self) -> TlvIter<'_> {
+        TlvIter::new(self.get_data())
+    }
+
     /// Iterates through the TLV entries, returning only the types
+    ///
+    /// Regions freed with `TlvStateMut::free` are skipped.
     fn get_discriminators(&self) -> Result<Vec<ArrayDiscriminator>, ProgramError> {
-        get_discriminators_and_end_index(self.get_data()).map(|v| v.0)
+        get_discriminators_and_end_index(self.get_data()).map(|(discriminators, _)| {
+            discriminators
+                .into_iter()
+                .filter(|discriminator| *discriminator != FREE_REGION_DISCRIMINATOR)
+                .collect()
+        })
     }
 
     /// Get the base size required for TLV data
@@ -273,13 +435,155 @@ pub trait TlvState {
     }
 }
 
+/// Split the entry at the start of the given TLV data, returning its type and
+/// the end index of its value
+///
+/// Returns `None` if there's no initialized entry, or if it's malformed.
+fn split_entry(tlv_data: &[u8]) -> Option<(ArrayDiscriminator, usize, usize)> {
+    let tlv_indices = get_indices_unchecked(0, 0);
+    let discriminator =
+        ArrayDiscriminator::try_from(tlv_data.get(..tlv_indices.length_start)?).ok()?;
+    if discriminator == ArrayDiscriminator::UNINITIALIZED {
+        return None;
+    }
+    let length =
+        pod_from_bytes::<Length>(tlv_data.get(tlv_indices.length_start..tlv_indices.value_start)?)
+            .ok()?;
+    let value_end = tlv_indices
+        .value_start
+        .checked_add(usize::try_from(*length).ok()?)?;
+    (value_end <= tlv_data.len()).then_some((discriminator, tlv_indices.value_start, value_end))
+}
+
+/// Iterator over the entries of TLV data, see `TlvState::iter`
+///
+/// Stops at the first uninitialized or malformed entry.
+#[derive(Clone, Debug)]
+pub struct TlvIter<'data> {
+    /// Remaining TLV data, starting at the next entry
+    data: &'data [u8],
+    /// Whether regions freed with `TlvStateMut::free` are returned
+    free_regions: bool,
+}
+impl<'data> TlvIter<'data> {
+    /// Iterate over the live entries of TLV data that may be malformed
+    pub(crate) fn new(data: &'data [u8]) -> Self {
+        Self {
+            data,
+            free_regions: false,
+        }
+    }
+
+    /// Iterate over all the entries of TLV data that may be malformed,
+    /// including free regions
+    #[cfg(feature = "serde-traits")]
+    pub(crate) fn with_free_regions(data: &'data [u8]) -> Self {
+        Self {
+            data,
+            free_regions: true,
+        }
+    }
+}
+impl<'data> Iterator for TlvIter<'data> {
+    type Item = (ArrayDiscriminator, &'data [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let Some((discriminator, value_start, value_end)) = split_entry(self.data) else {
+                self.data = &[];
+                return None;
+            };
+            let (entry, rest) = self.data.split_at(value_end);
+            self.data = rest;
+            if self.free_regions || discriminator != FREE_REGION_DISCRIMINATOR {
+                return Some((discriminator, &entry[value_start..]));
+            }
+        }
+    }
+}
+
+/// Mutable iterator over the entries of TLV data, see `TlvStateMut::iter_mut`
+///
+/// Stops at the first uninitialized or malformed entry.
+#[derive(Debug)]
+pub struct TlvIterMut<'data> {
+    /// Remaining TLV data, starting at the next entry
+    data: &'data mut [u8],
+}
+impl<'data> Iterator for TlvIterMut<'data> {
+    type Item = (ArrayDiscriminator, &'data mut [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let data = core::mem::take(&mut self.data);
+            let (discriminator, value_start, value_end) = split_entry(data)?;
+            let (entry, rest) = data.split_at_mut(value_end);
+            self.data = rest;
+            if discriminator != FREE_REGION_DISCRIMINATOR {
+                return Some((discriminator, &mut entry[value_start..]));
+            }
+        }
+    }
+}
+
+/// Record of the type behind each discriminator, to detect distinct types
+/// sharing a discriminator, see `TlvStateMut::alloc_checked`
+#[derive(Clone, Debug, Default)]
+pub struct DiscriminatorTypes {
+    /// Discriminators seen so far, with the id and name of their type
+    types: Vec<(ArrayDiscriminator, TypeId, &'static str)>,
+}
+impl DiscriminatorTypes {
+    /// Create an empty record
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the type behind the discriminator of `V`, failing if the
+    /// discriminator was already recorded for a different type
+    pub fn check<V: SplDiscriminate + 'static>(&mut self) -> Result<(), ProgramError> {
+        let type_id = TypeId::of::<V>();
+        match self
+            .types
+            .iter()
+            .find(|(discriminator, ..)| *discriminator == V::SPL_DISCRIMINATOR)
+        {
+            Some((_, recorded_type_id, _)) if *recorded_type_id != type_id => {
+                Err(TlvError::DiscriminatorCollision.into())
+            }
+            Some(_) => Ok(()),
+            None => {
+                self.types
+                    .push((V::SPL_DISCRIMINATOR, type_id, type_name::<V>()));
+                Ok(())
+            }
+        }
+    }
+
+    /// Get the name of the type recorded for a discriminator
+    pub fn type_name(&self, discriminator: &ArrayDiscriminator) -> Option<&'static str> {
+        self.types
+            .iter()
+            .find(|(recorded, ..)| recorded == discriminator)
+            .map(|(_, _, name)| *name)
+    }
+}
+
 /// Encapsulates owned TLV data
-#[derive(Debug, PartialEq)]
+///
+/// The data grows as entries are allocated, so expected account data can be
+/// built without sizing the buffer up front.
+#[derive(Debug, Default, PartialEq)]
 pub struct TlvStateOwned {
     /// Raw TLV data, deserialized on demand
     data: Vec<u8>,
 }
 impl TlvStateOwned {
+    /// Creates empty TLV state
+    pub fn new() -> Self {
+        Self::default()
+    }
+
     /// Unpacks TLV state data
     ///
     /// Fails if no state is initialized or if data is too small
@@ -287,6 +591,82 @@ impl TlvStateOwned {
         check_data(&data)?;
         Ok(Self { data })
     }
+
+    /// Allocate the given number of bytes for the given `SplDiscriminate`,
+    /// growing the data if there isn't enough free space, see
+    /// `TlvStateMut::alloc`
+    pub fn alloc<V: SplDiscriminate>(
+        &mut self,
+        length: usize,
+        allow_repetition: bool,
+    ) -> Result<(&mut [u8], usize), ProgramError> {
+        let (_, end_index) = get_discriminators_and_end_index(&self.data)?;
+        let value_start = end_index.saturating_add(get_base_len());
+        let value_end = value_start
+            .checked_add(length)
+            .ok_or(ProgramError::InvalidArgument)?;
+        let previous_len = self.data.len();
+        if previous_len < value_end {
+            self.data.resize(value_end, 0);
+        }
+        match self.state_mut().alloc::<V>(length, allow_repetition) {
+            Ok((_, repetition_number)) => {
+                Ok((&mut self.data[value_start..value_end], repetition_number))
+            }
+            Err(err) => {
+                self.data.truncate(previous_len);
+                Err(err)
+            }
+        }
+    }
+
+    /// Allocate and initialize a Pod value for the given type, growing the
+    /// data if there isn't enough free space, see `TlvStateMut::init_value`
+    pub fn init_value<V: SplDiscriminate + Pod + Default>(
+        &mut self,
+        allow_repetition: bool,
+    ) -> Result<(&mut V, usize), ProgramError> {
+        self.alloc_and_init(V::default(), allow_repetition)
+    }
+
+    /// Allocate an entry for the given Pod type and write the given value
+    /// into it, growing the data if there isn't enough free space, see
+    /// `TlvStateMut::alloc_and_init`
+    pub fn alloc_and_init<V: SplDiscriminate + Pod>(
+        &mut self,
+        value: V,
+        allow_repetition: bool,
+    ) -> Result<(&mut V, usize), ProgramError> {
+        let (buffer, repetition_number) = self.alloc::<V>(size_of::<V>(), allow_repetition)?;
+        let extension_ref = pod_from_bytes_mut::<V>(buffer)?;
+        *extension_ref = value;
+        Ok((extension_ref, repetition_number))
+    }
+
+    /// Allocates and serializes a new TLV entry from a `VariableLenPack`
+    /// type, growing the data if there isn't enough free space
+    pub fn alloc_and_pack_variable_len_entry<V: SplDiscriminate + VariableLenPack>(
+        &mut self,
+        value: &V,
+        allow_repetition: bool,
+    ) -> Result<usize, ProgramError> {
+        let length = value.get_packed_len()?;
+        let (data, repetition_number) = self.alloc::<V>(length, allow_repetition)?;
+        value.pack_into_slice(data)?;
+        Ok(repetition_number)
+    }
+
+    /// Get mutable access to the entries, which can be modified but not grown
+    pub fn state_mut(&mut self) -> TlvStateMut<'_> {
+        TlvStateMut {
+            data: &mut self.data,
+        }
+    }
+
+    /// Get the raw TLV data
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
 }
 impl TlvState for TlvStateOwned {
     fn get_data(&self) -> &[u8] {
@@ -309,6 +689,14 @@ impl<'data> TlvStateBorrowed<'data> {
         check_data(data)?;
         Ok(Self { data })
     }
+
+    /// Unpacks TLV state data following a `TlvHeader`, checking that the
+    /// header is supported by the expected one, see `TlvHeader::check`
+    pub fn unpack_checked(data: &'data [u8], expected: &TlvHeader) -> Result<Self, ProgramError> {
+        let (header, tlv_data) = TlvHeader::unpack(data)?;
+        header.check(expected)?;
+        Self::unpack(tlv_data)
+    }
 }
 impl TlvState for TlvStateBorrowed<'_> {
     fn get_data(&self) -> &[u8] {
@@ -332,6 +720,17 @@ impl<'data> TlvStateMut<'data> {
         Ok(Self { data })
     }
 
+    /// Unpacks TLV state data following a `TlvHeader`, checking that the
+    /// header is supported by the expected one, see `TlvHeader::check`
+    pub fn unpack_checked(
+        data: &'data mut [u8],
+        expected: &TlvHeader,
+    ) -> Result<Self, ProgramError> {
+        let (header, tlv_data) = TlvHeader::unpack_mut(data)?;
+        header.check(expected)?;
+        Self::unpack(tlv_data)
+    }
+
     /// Unpack a portion of the TLV data as the desired type that allows
     /// modifying the type for the entry number specified
     pub fn get_value_with_repetition_mut<V: SplDiscriminate + Pod>(
@@ -382,6 +781,170 @@ impl<'data> TlvStateMut<'data> {
         self.get_bytes_with_repetition_mut::<V>(0)
     }
 
+    /// Write bytes into the value of the entry number specified, starting at
+    /// the given offset, failing if they don't fit in the value
+    pub fn write_at_with_repetition<V: SplDiscriminate>(
+        &mut self,
+        repetition_number: usize,
+        offset: usize,
+        bytes: &[u8],
+    ) -> Result<(), ProgramError> {
+        let value = self.get_bytes_with_repetition_mut::<V>(repetition_number)?;
+        let end = offset
+            .checked_add(bytes.len())
+            .filter(|end| *end <= value.len())
+            .ok_or(ProgramError::InvalidArgument)?;
+        value[offset..end].copy_from_slice(bytes);
+        Ok(())
+    }
+
+    /// Write bytes into the value of the first entry found, starting at the
+    /// given offset
+    pub fn write_at<V: SplDiscriminate>(
+        &mut self,
+        offset: usize,
+        bytes: &[u8],
+    ) -> Result<(), ProgramError> {
+        self.write_at_with_repetition::<V>(0, offset, bytes)
+    }
+
+    /// Allocate a chunked entry for the given `SplDiscriminate`, with room
+    /// for `length` bytes of data to be written over several instructions
+    ///
+    /// The value starts with the number of bytes written so far, followed by
+    /// the data. Chunks must be written in order with `write_chunk`, so that
+    /// the entry is complete once that number reaches `length`, see
+    /// `TlvState::is_complete`.
+    pub fn alloc_chunked<V: SplDiscriminate>(
+        &mut self,
+        length: usize,
+        allow_repetition: bool,
+    ) -> Result<usize, ProgramError> {
+        let (_, repetition_number) =
+            self.alloc::<V>(length.saturating_add(size_of::<Length>()), allow_repetition)?;
+        Ok(repetition_number)
+    }
+
+    /// Write a chunk of data into the chunked entry number specified,
+    /// returning whether the entry is now complete
+    ///
+    /// The chunk must start at or before the end of the data written so far,
+    /// so that resending a chunk is harmless but no gap is left behind.
+    pub fn write_chunk_with_repetition<V: SplDiscriminate>(
+        &mut self,
+        repetition_number: usize,
+        offset: usize,
+        chunk: &[u8],
+    ) -> Result<bool, ProgramError> {
+        let entry = self.get_bytes_with_repetition_mut::<V>(repetition_number)?;
+        let (written, data) = split_chunked(entry)?;
+        let end = offset
+            .checked_add(chunk.len())
+            .filter(|end| offset <= written && *end <= data.len())
+            .ok_or(ProgramError::InvalidArgument)?;
+        let complete = end.max(written) == data.len();
+        let (written_ref, data) = entry.split_at_mut(size_of::<Length>());
+        data[offset..end].copy_from_slice(chunk);
+        *pod_from_bytes_mut::<Length>(written_ref)? = Length::try_from(end.max(written))?;
+        Ok(complete)
+    }
+
+    /// Write a chunk of data into the first chunked entry found, see
+    /// `write_chunk_with_repetition`
+    pub fn write_chunk<V: SplDiscriminate>(
+        &mut self,
+        offset: usize,
+        chunk: &[u8],
+    ) -> Result<bool, ProgramError> {
+        self.write_chunk_with_repetition::<V>(0, offset, chunk)
+    }
+
+    /// Allocate an entry for the given `SplDiscriminate` holding a copy of
+    /// the given bytes, preceded by their checksum
+    ///
+    /// Checksummed entries must be read with the `get_checksummed_*`
+    /// functions and written with `update_checksummed_with_repetition`, so
+    /// that writes made any other way are detected.
+    pub fn alloc_checksummed<V: SplDiscriminate>(
+        &mut self,
+        value: &[u8],
+        allow_repetition: bool,
+    ) -> Result<usize, ProgramError> {
+        let length = value.len().saturating_add(checksum::CHECKSUM_LEN);
+        let (entry, repetition_number) = self.alloc::<V>(length, allow_repetition)?;
+        entry[checksum::CHECKSUM_LEN..].copy_from_slice(value);
+        checksum::update(entry)?;
+        Ok(repetition_number)
+    }
+
+    /// Allocate a checksummed entry holding the given Pod value, see
+    /// `alloc_checksummed`
+    pub fn init_checksummed_value<V: SplDiscriminate + Pod>(
+        &mut self,
+        value: &V,
+        allow_repetition: bool,
+    ) -> Result<usize, ProgramError> {
+        self.alloc_checksummed::<V>(bytemuck::bytes_of(value), allow_repetition)
+    }
+
+    /// Update the value of the checksummed entry number specified, after
+    /// verifying its checksum, and store the checksum of the new value
+    ///
+    /// If `update` fails, the checksum is left as is, so that anything it
+    /// wrote shows up as a mismatch on the next read.
+    pub fn update_checksummed_with_repetition<V: SplDiscriminate, T>(
+        &mut self,
+        repetition_number: usize,
+        update: impl FnOnce(&mut [u8]) -> Result<T, ProgramError>,
+    ) -> Result<T, ProgramError> {
+        let entry = self.get_bytes_with_repetition_mut::<V>(repetition_number)?;
+        let result = update(checksum::verify_mut(entry)?)?;
+        checksum::update(entry)?;
+        Ok(result)
+    }
+
+    /// Update the value of the first checksummed entry found, see
+    /// `update_checksummed_with_repetition`
+    pub fn update_first_checksummed<V: SplDiscriminate, T>(
+        &mut self,
+        update: impl FnOnce(&mut [u8]) -> Result<T, ProgramError>,
+    ) -> Result<T, ProgramError> {
+        self.update_checksummed_with_repetition::<V, T>(0, update)
+    }
+
+    /// Unpack a portion of the TLV data as a mutable list of Pod elements for
+    /// the entry number specified
+    ///
+    /// Elements can be pushed until the list fills the entry, see
+    /// `TlvState::get_list_with_repetition`.
+    pub fn get_list_with_repetition_mut<V: SplDiscriminate, T: Pod>(
+        &mut self,
+        repetition_number: usize,
+    ) -> Result<ListViewMut<'_, T>, ProgramError> {
+        let data = self.get_bytes_with_repetition_mut::<V>(repetition_number)?;
+        ListView::<T>::unpack_mut(data)
+    }
+
+    /// Unpack a portion of the TLV data as a mutable list of Pod elements for
+    /// the first entry found
+    pub fn get_first_list_mut<V: SplDiscriminate, T: Pod>(
+        &mut self,
+    ) -> Result<ListViewMut<'_, T>, ProgramError> {
+        self.get_list_with_repetition_mut::<V, T>(0)
+    }
+
+    /// Allocate an entry holding an empty list with room for `capacity` Pod
+    /// elements, returning the list along with its repetition number
+    pub fn alloc_list<V: SplDiscriminate, T: Pod>(
+        &mut self,
+        capacity: usize,
+        allow_repetition: bool,
+    ) -> Result<(ListViewMut<'_, T>, usize), ProgramError> {
+        let length = ListView::<T>::size_of(capacity)?;
+        let (data, repetition_number) = self.alloc::<V>(length, allow_repetition)?;
+        Ok((ListView::<T>::init(data)?, repetition_number))
+    }
+
     /// Packs the default TLV data into the first open slot in the data buffer.
     /// Handles repetition based on the Boolean arg provided:
     /// * `true`:   If extension is already found in the buffer, it returns an
@@ -391,13 +954,31 @@ impl<'data> TlvStateMut<'data> {
         &mut self,
         allow_repetition: bool,
     ) -> Result<(&mut V, usize), ProgramError> {
-        let length = size_of::<V>();
-        let (buffer, repetition_number) = self.alloc::<V>(length, allow_repetition)?;
+        self.alloc_default::<V>(allow_repetition)
+    }
+
+    /// Allocate an entry for the given Pod type and write the given value
+    /// into it, returning the value in place along with its repetition number
+    pub fn alloc_and_init<V: SplDiscriminate + Pod>(
+        &mut self,
+        value: V,
+        allow_repetition: bool,
+    ) -> Result<(&mut V, usize), ProgramError> {
+        let (buffer, repetition_number) = self.alloc::<V>(size_of::<V>(), allow_repetition)?;
         let extension_ref = pod_from_bytes_mut::<V>(buffer)?;
-        *extension_ref = V::default();
+        *extension_ref = value;
         Ok((extension_ref, repetition_number))
     }
 
+    /// Allocate an entry for the given Pod type and write its default value
+    /// into it, see `alloc_and_init`
+    pub fn alloc_default<V: SplDiscriminate + Pod + Default>(
+        &mut self,
+        allow_repetition: bool,
+    ) -> Result<(&mut V, usize), ProgramError> {
+        self.alloc_and_init(V::default(), allow_repetition)
+    }
+
     /// Packs a variable-length value into its appropriate data segment, where
     /// repeating discriminators _are_ allowed
     pub fn pack_variable_len_value_with_repetition<V: SplDiscriminate + VariableLenPack>(
@@ -420,6 +1001,42 @@ impl<'data> TlvStateMut<'data> {
         self.pack_variable_len_value_with_repetition::<V>(value, 0)
     }
 
+    /// Packs a variable-length value into its entry for the entry number
+    /// specified, growing the entry in place if the value doesn't fit
+    ///
+    /// Growing the entry moves the following entries, so the buffer must have
+    /// enough free space at its end. Entries are never shrunk, and any bytes
+    /// left after the value are zeroed out.
+    pub fn realloc_value_with_repetition<V: SplDiscriminate + VariableLenPack>(
+        &mut self,
+        value: &V,
+        repetition_number: usize,
+    ) -> Result<(), ProgramError> {
+        let packed_len = value.get_packed_len()?;
+        let current_len = self
+            .get_bytes_with_repetition::<V>(repetition_number)?
+            .len();
+        let data = if current_len < packed_len {
+            self.realloc_with_repetition::<V>(packed_len, repetition_number)?
+        } else {
+            self.get_bytes_with_repetition_mut::<V>(repetition_number)?
+        };
+        let (value_data, slack) = data.split_at_mut(packed_len);
+        value.pack_into_slice(value_data)?;
+        slack.fill(0);
+        Ok(())
+    }
+
+    /// Packs a variable-length value into its entry, growing the entry in
+    /// place if the value doesn't fit, where no repeating discriminators are
+    /// allowed
+    pub fn realloc_value<V: SplDiscriminate + VariableLenPack>(
+        &mut self,
+        value: &V,
+    ) -> Result<(), ProgramError> {
+        self.realloc_value_with_repetition::<V>(value, 0)
+    }
+
     /// Allocate the given number of bytes for the given `SplDiscriminate`
     pub fn alloc<V: SplDiscriminate>(
         &mut self,
@@ -461,6 +1078,22 @@ impl<'data> TlvStateMut<'data> {
         }
     }
 
+    /// Allocate the given number of bytes for the given `SplDiscriminate`,
+    /// after checking that no other type allocated through `types` shares its
+    /// discriminator
+    ///
+    /// Meant for tests and debug builds, where reusing the same `types` for
+    /// every allocation catches hash-derived discriminators that collide.
+    pub fn alloc_checked<V: SplDiscriminate + 'static>(
+        &mut self,
+        types: &mut DiscriminatorTypes,
+        length: usize,
+        allow_repetition: bool,
+    ) -> Result<(&mut [u8], usize), ProgramError> {
+        types.check::<V>()?;
+        self.alloc::<V>(length, allow_repetition)
+    }
+
     /// Allocates and serializes a new TLV entry from a `VariableLenPack` type
     pub fn alloc_and_pack_variable_len_entry<V: SplDiscriminate + VariableLenPack>(
         &mut self,
@@ -473,6 +1106,25 @@ impl<'data> TlvStateMut<'data> {
         Ok(repetition_number)
     }
 
+    /// Allocates a new TLV entry of at least `capacity` bytes for a
+    /// `VariableLenPack` type, and serializes the value at its start
+    ///
+    /// The zeroed bytes after the value let it grow later without moving the
+    /// following entries, see `realloc_value`. Only use this for types that
+    /// can be unpacked from a slice with trailing bytes, such as types
+    /// deriving `SplBorshVariableLenPack`.
+    pub fn alloc_with_capacity<V: SplDiscriminate + VariableLenPack>(
+        &mut self,
+        value: &V,
+        capacity: usize,
+        allow_repetition: bool,
+    ) -> Result<usize, ProgramError> {
+        let length = value.get_packed_len()?.max(capacity);
+        let (data, repetition_number) = self.alloc::<V>(length, allow_repetition)?;
+        value.pack_into_slice(data)?;
+        Ok(repetition_number)
+    }
+
     /// Reallocate the given number of bytes for the given `SplDiscriminate`. If
     /// the new length is smaller, it will compact the rest of the buffer
     /// and zero out the difference at the end. If it's larger, it will move
@@ -539,16 +1191,245 @@ impl<'data> TlvStateMut<'data> {
     ) -> Result<&mut [u8], ProgramError> {
         self.realloc_with_repetition::<V>(length, 0)
     }
-}
 
-impl TlvState for TlvStateMut<'_> {
-    fn get_data(&self) -> &[u8] {
-        self.data
+    /// Iterate through the TLV entries, returning the type and the mutable
+    /// value bytes of each initialized entry, in order
+    ///
+    /// Regions freed with `free` are skipped.
+    pub fn iter_mut(&mut self) -> TlvIterMut<'_> {
+        TlvIterMut { data: self.data }
     }
-}
+
+    /// Deallocate the entry for the given `SplDiscriminate`, including its type
+    /// and length. The rest of the buffer is compacted, and the freed bytes at
+    /// the end are zeroed out.
+    pub fn dealloc_with_repetition<V: SplDiscriminate>(
+        &mut self,
+        repetition_number: usize,
+    ) -> Result<(), ProgramError> {
+        let tlv_indices = get_indices(
+            self.data,
+            V::SPL_DISCRIMINATOR,
+            false,
+            Some(repetition_number),
+        )?;
+        self.dealloc_entry(tlv_indices)
+    }
+
+    /// Deallocate the entry for the given `SplDiscriminate`, where no
+    /// repeating discriminators are allowed
+    pub fn dealloc<V: SplDiscriminate>(&mut self) -> Result<(), ProgramError> {
+        self.dealloc_with_repetition::<V>(0)
+    }
+
+    /// Deallocate the entry at the given position in the buffer, whatever its
+    /// type, following the order of `get_discriminators`. The rest of the
+    /// buffer is compacted, and the freed bytes at the end are zeroed out.
+    ///
+    /// Useful to retire entries whose type is no longer known.
+    pub fn dealloc_with_index(&mut self, index: usize) -> Result<(), ProgramError> {
+        let (_, type_start, _) = get_entry_bounds(self.data)?
+            .into_iter()
+            .filter(|(discriminator, ..)| *discriminator != FREE_REGION_DISCRIMINATOR)
+            .nth(index)
+            .ok_or(TlvError::TypeNotFound)?;
+        self.dealloc_entry(get_indices_unchecked(type_start, 0))
+    }
+
+    /// Remove the entry at the given indices, moving back the rest of the
+    /// buffer
+    fn dealloc_entry(&mut self, tlv_indices: TlvIndices) -> Result<(), ProgramError> {
+        let TlvIndices {
+            type_start,
+            length_start,
+            value_start,
+            value_repetition_number: _,
+        } = tlv_indices;
+        let (_, end_index) = get_discriminators_and_end_index(self.data)?;
+
+        let length = pod_from_bytes::<Length>(&self.data[length_start..value_start])?;
+        let value_end = value_start.saturating_add(usize::try_from(*length)?);
+
+        self.data.copy_within(value_end..end_index, type_start);
+        let new_end_index = end_index.saturating_sub(value_end.saturating_sub(type_start));
+        self.data[new_end_index..end_index].fill(0);
+
+        Ok(())
+    }
+
+    /// Zero out any bytes left after the last entry, such as the remains of
+    /// an entry removed by hand, and return the number of bytes used by the
+    /// entries
+    ///
+    /// Entries are always stored back to back, so the buffer can be truncated
+    /// to the returned length, for example before downsizing the account.
+    pub fn compact(&mut self) -> Result<usize, ProgramError> {
+        let (_, end_index) = get_discriminators_and_end_index(self.data)?;
+        self.data[end_index..].fill(0);
+        Ok(end_index)
+    }
+
+    /// Run several updates as one, restoring the buffer to its previous
+    /// contents if any of them fails
+    ///
+    /// The whole buffer is copied before calling `update`, so that no
+    /// half-written entries are left behind on error.
+    pub fn transaction<T, F>(&mut self, update: F) -> Result<T, ProgramError>
+    where
+        F: FnOnce(&mut Self) -> Result<T, ProgramError>,
+    {
+        let snapshot = self.data.to_vec();
+        update(self).inspect_err(|_| self.data.copy_from_slice(&snapshot))
+    }
+
+    /// Free the entry for the given `SplDiscriminate` without moving the rest
+    /// of the buffer
+    ///
+    /// The entry becomes a free region, merged with any adjacent free region,
+    /// which `alloc_best_fit` can reuse later. A free region at the end of the
+    /// entries is zeroed out instead, returning its bytes to the unallocated
+    /// space.
+    pub fn free_with_repetition<V: SplDiscriminate>(
+        &mut self,
+        repetition_number: usize,
+    ) -> Result<(), ProgramError> {
+        let TlvIndices {
+            type_start,
+            length_start,
+            value_start,
+            value_repetition_number: _,
+        } = get_indices(
+            self.data,
+            V::SPL_DISCRIMINATOR,
+            false,
+            Some(repetition_number),
+        )?;
+        let length = pod_from_bytes::<Length>(&self.data[length_start..value_start])?;
+        let value_end = value_start.saturating_add(usize::try_from(*length)?);
+        self.data[type_start..length_start].copy_from_slice(FREE_REGION_DISCRIMINATOR.as_ref());
+        self.data[value_start..value_end].fill(0);
+        self.merge_free_regions()
+    }
+
+    /// Free the entry for the given `SplDiscriminate`, where no repeating
+    /// discriminators are allowed
+    pub fn free<V: SplDiscriminate>(&mut self) -> Result<(), ProgramError> {
+        self.free_with_repetition::<V>(0)
+    }
+
+    /// Merge runs of adjacent free regions into one, and zero out the free
+    /// region at the end of the entries, if any
+    fn merge_free_regions(&mut self) -> Result<(), ProgramError> {
+        let mut run_start = None;
+        let mut run_end = 0;
+        for (discriminator, type_start, value_end) in get_entry_bounds(self.data)? {
+            if discriminator != FREE_REGION_DISCRIMINATOR {
+                run_start = None;
+                continue;
+            }
+            run_end = value_end;
+            let Some(run_start) = run_start else {
+                run_start = Some(type_start);
+                continue;
+            };
+            // only the header of the absorbed region needs to be zeroed
+            self.data[type_start..value_end].fill(0);
+            let TlvIndices {
+                length_start,
+                value_start,
+                ..
+            } = get_indices_unchecked(run_start, 0);
+            let length_ref =
+                pod_from_bytes_mut::<Length>(&mut self.data[length_start..value_start])?;
+            *length_ref = Length::try_from(value_end.saturating_sub(value_start))?;
+        }
+        if let Some(run_start) = run_start {
+            self.data[run_start..run_end].fill(0);
+        }
+        Ok(())
+    }
+
+    /// Allocate the given number of bytes for the given `SplDiscriminate`,
+    /// reusing the smallest free region that fits before appending a new
+    /// entry, see `free`
+    ///
+    /// A free region fits if it has exactly the requested length, or if it
+    /// has room left for the header of a smaller free region holding the
+    /// rest. Since the new entry may be placed before existing entries of the
+    /// same type, their repetition numbers may shift.
+    pub fn alloc_best_fit<V: SplDiscriminate>(
+        &mut self,
+        length: usize,
+        allow_repetition: bool,
+    ) -> Result<(&mut [u8], usize), ProgramError> {
+        let entries = get_entry_bounds(self.data)?;
+        if !allow_repetition
+            && entries
+                .iter()
+                .any(|(discriminator, ..)| *discriminator == V::SPL_DISCRIMINATOR)
+        {
+            return Err(TlvError::TypeAlreadyExists.into());
+        }
+        let best_fit = entries
+            .iter()
+            .filter(|(discriminator, ..)| *discriminator == FREE_REGION_DISCRIMINATOR)
+            .map(|&(_, type_start, value_end)| {
+                (
+                    type_start,
+                    value_end.saturating_sub(type_start.saturating_add(get_base_len())),
+                )
+            })
+            .filter(|&(_, free_length)| {
+                free_length == length || free_length >= length.saturating_add(get_base_len())
+            })
+            .min_by_key(|&(_, free_length)| free_length);
+        let Some((type_start, free_length)) = best_fit else {
+            return self.alloc::<V>(length, allow_repetition);
+        };
+        let repetition_number = entries
+            .iter()
+            .take_while(|(_, entry_start, _)| *entry_start < type_start)
+            .filter(|(discriminator, ..)| *discriminator == V::SPL_DISCRIMINATOR)
+            .count();
+
+        let TlvIndices {
+            type_start,
+            length_start,
+            value_start,
+            value_repetition_number,
+        } = get_indices_unchecked(type_start, repetition_number);
+        self.data[type_start..length_start].copy_from_slice(V::SPL_DISCRIMINATOR.as_ref());
+        let length_ref = pod_from_bytes_mut::<Length>(&mut self.data[length_start..value_start])?;
+        *length_ref = Length::try_from(length)?;
+
+        // split off the rest of the region, its value is already zeroed
+        let value_end = value_start.saturating_add(length);
+        if free_length > length {
+            let rest = get_indices_unchecked(value_end, 0);
+            self.data[rest.type_start..rest.length_start]
+                .copy_from_slice(FREE_REGION_DISCRIMINATOR.as_ref());
+            let length_ref =
+                pod_from_bytes_mut::<Length>(&mut self.data[rest.length_start..rest.value_start])?;
+            *length_ref = Length::try_from(
+                free_length.saturating_sub(length.saturating_add(get_base_len())),
+            )?;
+        }
+        Ok((
+            &mut self.data[value_start..value_end],
+            value_repetition_number,
+        ))
+    }
+}
+
+impl TlvState for TlvStateMut<'_> {
+    fn get_data(&self) -> &[u8] {
+        self.data
+    }
+}
 
 /// Packs a variable-length value into an existing TLV space, reallocating
 /// the account and TLV as needed to accommodate for any change in space
+#[cfg(feature = "account-info")]
 pub fn realloc_and_pack_variable_len_with_repetition<V: SplDiscriminate + VariableLenPack>(
     account_info: &AccountInfo,
     value: &V,
@@ -597,6 +1478,7 @@ pub fn realloc_and_pack_variable_len_with_repetition<V: SplDiscriminate + Variab
 
 /// Packs a variable-length value into an existing TLV space, where no repeating
 /// discriminators are allowed
+#[cfg(feature = "account-info")]
 pub fn realloc_and_pack_first_variable_len<V: SplDiscriminate + VariableLenPack>(
     account_info: &AccountInfo,
     value: &V,
@@ -604,11 +1486,109 @@ pub fn realloc_and_pack_first_variable_len<V: SplDiscriminate + VariableLenPack>
     realloc_and_pack_variable_len_with_repetition::<V>(account_info, value, 0)
 }
 
+/// Get the length the TLV data must have to allocate the given number of
+/// bytes for the given `SplDiscriminate`, for resizing the account before
+/// calling `TlvStateMut::alloc`
+///
+/// The result may be smaller than the current length of the data, if there's
+/// already enough free space.
+pub fn get_alloc_required_len<V: SplDiscriminate>(
+    tlv_data: &[u8],
+    length: usize,
+    allow_repetition: bool,
+) -> Result<usize, ProgramError> {
+    if !allow_repetition && get_bytes(tlv_data, V::SPL_DISCRIMINATOR, 0).is_ok() {
+        return Err(TlvError::TypeAlreadyExists.into());
+    }
+    let (_, end_index) = get_discriminators_and_end_index(tlv_data)?;
+    end_index
+        .checked_add(get_base_len())
+        .and_then(|size| size.checked_add(length))
+        .ok_or(ProgramError::InvalidArgument)
+}
+
+/// Allocate the given number of bytes for the given `SplDiscriminate` in the
+/// TLV data of an account, growing the account first if there isn't enough
+/// free space
+///
+/// `resize` is called with the new length of the account data, and must
+/// resize the account to that length, typically with `AccountInfo::resize`
+/// after funding the account for rent. The account is grown by exactly the
+/// missing number of bytes, and the new value is zeroed out.
+///
+/// Returns the repetition number of the new entry.
+#[cfg(feature = "account-info")]
+pub fn alloc_with_realloc<V: SplDiscriminate, F>(
+    account_info: &AccountInfo,
+    length: usize,
+    allow_repetition: bool,
+    resize: F,
+) -> Result<usize, ProgramError>
+where
+    F: FnOnce(usize) -> Result<(), ProgramError>,
+{
+    let (previous_account_size, required_account_size) = {
+        let data = account_info.try_borrow_data()?;
+        let required_account_size = get_alloc_required_len::<V>(&data, length, allow_repetition)?;
+        (data.len(), required_account_size)
+    };
+    if previous_account_size < required_account_size {
+        resize(required_account_size)?;
+        let mut data = account_info.try_borrow_mut_data()?;
+        if data.len() < required_account_size {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        data[previous_account_size..].fill(0);
+    }
+    let mut buffer = account_info.try_borrow_mut_data()?;
+    let mut state = TlvStateMut::unpack(&mut buffer)?;
+    let (_, repetition_number) = state.alloc::<V>(length, allow_repetition)?;
+    Ok(repetition_number)
+}
+
 /// Get the base size required for TLV data
-const fn get_base_len() -> usize {
+pub(crate) const fn get_base_len() -> usize {
     get_indices_unchecked(0, 0).value_start
 }
 
+/// Get the number of bytes taken by a TLV entry whose value has the given
+/// length, including its type and length
+pub const fn get_entry_len(value_len: usize) -> usize {
+    get_base_len() + value_len
+}
+
+/// Compute the number of bytes needed to store one TLV entry for each of the
+/// given fixed-size types, usable in const contexts
+///
+/// ```
+/// use {
+///     bytemuck::{Pod, Zeroable},
+///     spl_discriminator::{ArrayDiscriminator, SplDiscriminate},
+///     spl_type_length_value::{state::TlvStateMut, tlv_size_of},
+/// };
+/// #[repr(C)]
+/// #[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+/// struct MyPodValue {
+///     data: [u8; 8],
+/// }
+/// impl SplDiscriminate for MyPodValue {
+///     const SPL_DISCRIMINATOR: ArrayDiscriminator = ArrayDiscriminator::new([1; ArrayDiscriminator::LENGTH]);
+/// }
+/// const ACCOUNT_LEN: usize = tlv_size_of!(MyPodValue, MyPodValue);
+/// assert_eq!(ACCOUNT_LEN, 2 * (8 + 4 + 8));
+///
+/// let mut buffer = [0; ACCOUNT_LEN];
+/// let mut state = TlvStateMut::unpack(&mut buffer).unwrap();
+/// state.init_value::<MyPodValue>(true).unwrap();
+/// state.init_value::<MyPodValue>(true).unwrap();
+/// ```
+#[macro_export]
+macro_rules! tlv_size_of {
+    ($($ty:ty),* $(,)?) => {
+        0usize $(+ $crate::state::get_entry_len(::core::mem::size_of::<$ty>()))*
+    };
+}
+
 fn check_data(tlv_data: &[u8]) -> Result<(), ProgramError> {
     // should be able to iterate through all entries in the TLV structure
     let _ = get_discriminators_and_end_index(tlv_data)?;
@@ -925,6 +1905,27 @@ mod test {
             state.get_first_value::<TestSmallValue>().unwrap(),
             other_state.get_first_value::<TestSmallValue>().unwrap()
         );
+
+        // values can also be found by their runtime discriminator
+        for state in [&state, &other_state] {
+            for discriminator in state.get_discriminators().unwrap() {
+                assert!(state
+                    .get_first_bytes_with_discriminator(discriminator)
+                    .is_ok());
+            }
+            assert_eq!(
+                state
+                    .get_first_bytes_with_discriminator(TestSmallValue::SPL_DISCRIMINATOR)
+                    .unwrap(),
+                &small_data
+            );
+        }
+        assert_eq!(
+            state
+                .get_first_bytes_with_discriminator(ArrayDiscriminator::from(7))
+                .unwrap_err(),
+            ProgramError::InvalidAccountData
+        );
     }
 
     #[test]
@@ -936,6 +1937,258 @@ mod test {
         assert_eq!(value.data, TEST_NON_ZERO_DEFAULT_DATA);
     }
 
+    #[test]
+    fn alloc_and_init_values() {
+        let mut buffer = vec![0; get_base_len() * 3 + 32 + size_of::<TestNonZeroDefault>() * 2];
+        let mut state = TlvStateMut::unpack(&mut buffer).unwrap();
+        let (value, repetition_number) = state
+            .alloc_and_init(TestValue { data: [7; 32] }, false)
+            .unwrap();
+        assert_eq!(value.data, [7; 32]);
+        assert_eq!(repetition_number, 0);
+        assert_eq!(
+            state
+                .alloc_and_init(TestValue::default(), false)
+                .unwrap_err(),
+            TlvError::TypeAlreadyExists.into()
+        );
+
+        let (value, _) = state.alloc_default::<TestNonZeroDefault>(true).unwrap();
+        assert_eq!(value.data, TEST_NON_ZERO_DEFAULT_DATA);
+        value.data = [5; 5];
+        let (value, repetition_number) = state.alloc_default::<TestNonZeroDefault>(true).unwrap();
+        assert_eq!(value.data, TEST_NON_ZERO_DEFAULT_DATA);
+        assert_eq!(repetition_number, 1);
+        assert_eq!(
+            state
+                .get_value_with_repetition::<TestNonZeroDefault>(0)
+                .unwrap()
+                .data,
+            [5; 5]
+        );
+        assert_eq!(state.get_first_value::<TestValue>().unwrap().data, [7; 32]);
+
+        // owned state grows to fit
+        let mut state = TlvStateOwned::new();
+        let (value, _) = state
+            .alloc_and_init(TestValue { data: [8; 32] }, false)
+            .unwrap();
+        assert_eq!(value.data, [8; 32]);
+        assert_eq!(state.get_data().len(), get_base_len() + 32);
+    }
+
+    #[test]
+    fn size_of_layout() {
+        const ACCOUNT_LEN: usize = tlv_size_of!(TestValue, TestSmallValue, TestEmptyValue,);
+        assert_eq!(
+            ACCOUNT_LEN,
+            get_base_len() * 3 + size_of::<TestValue>() + size_of::<TestSmallValue>()
+        );
+        assert_eq!(tlv_size_of!(), 0);
+
+        let mut buffer = [0; ACCOUNT_LEN];
+        let mut state = TlvStateMut::unpack(&mut buffer).unwrap();
+        state.init_value::<TestValue>(false).unwrap();
+        state.init_value::<TestSmallValue>(false).unwrap();
+        state.init_value::<TestEmptyValue>(false).unwrap();
+        assert_eq!(state.compact().unwrap(), ACCOUNT_LEN);
+    }
+
+    #[test]
+    fn alloc_checked_detects_collisions() {
+        struct OtherTestValue;
+        impl SplDiscriminate for OtherTestValue {
+            const SPL_DISCRIMINATOR: ArrayDiscriminator = TestValue::SPL_DISCRIMINATOR;
+        }
+
+        let mut buffer = vec![0; get_base_len() * 3 + 32 + 3];
+        let mut state = TlvStateMut::unpack(&mut buffer).unwrap();
+        let mut types = DiscriminatorTypes::new();
+        state
+            .alloc_checked::<TestValue>(&mut types, 32, true)
+            .unwrap();
+        state
+            .alloc_checked::<TestSmallValue>(&mut types, 3, true)
+            .unwrap();
+        assert_eq!(
+            state
+                .alloc_checked::<OtherTestValue>(&mut types, 0, true)
+                .unwrap_err(),
+            TlvError::DiscriminatorCollision.into()
+        );
+        // the same type can be allocated again
+        state
+            .alloc_checked::<TestValue>(&mut types, 0, true)
+            .unwrap();
+        assert!(types
+            .type_name(&TestValue::SPL_DISCRIMINATOR)
+            .unwrap()
+            .ends_with("TestValue"));
+        assert_eq!(
+            state.get_discriminators().unwrap(),
+            [
+                TestValue::SPL_DISCRIMINATOR,
+                TestSmallValue::SPL_DISCRIMINATOR,
+                TestValue::SPL_DISCRIMINATOR,
+            ]
+        );
+    }
+
+    #[test]
+    fn list_entries() {
+        let mut buffer =
+            vec![0; get_base_len() * 2 + ListView::<[u8; 2]>::size_of(3).unwrap() + 32];
+        let mut state = TlvStateMut::unpack(&mut buffer).unwrap();
+        let (mut list, repetition_number) = state
+            .alloc_list::<TestSmallValue, [u8; 2]>(3, false)
+            .unwrap();
+        assert_eq!(repetition_number, 0);
+        assert!(list.is_empty());
+        list.push([1, 1]).unwrap();
+        list.push([2, 2]).unwrap();
+        state.alloc::<TestValue>(32, false).unwrap();
+
+        // the list is bounded by the entry length
+        let mut list = state
+            .get_first_list_mut::<TestSmallValue, [u8; 2]>()
+            .unwrap();
+        list.push([3, 3]).unwrap();
+        assert!(list.push([4, 4]).is_err());
+        assert_eq!(list.remove(0).unwrap(), [1, 1]);
+        assert_eq!(
+            *state.get_first_list::<TestSmallValue, [u8; 2]>().unwrap(),
+            [[2, 2], [3, 3]]
+        );
+        let state = TlvStateBorrowed::unpack(&buffer).unwrap();
+        assert_eq!(
+            state
+                .get_first_list::<TestSmallValue, [u8; 2]>()
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn checksummed_entries() {
+        let mut buffer = vec![0; get_base_len() * 2 + checksum::CHECKSUM_LEN * 2 + 32 + 3];
+        let mut state = TlvStateMut::unpack(&mut buffer).unwrap();
+        state
+            .init_checksummed_value(&TestValue { data: [1; 32] }, false)
+            .unwrap();
+        let repetition_number = state
+            .alloc_checksummed::<TestSmallValue>(&[2; 3], true)
+            .unwrap();
+        assert_eq!(repetition_number, 0);
+        assert_eq!(
+            state
+                .get_first_checksummed_value::<TestValue>()
+                .unwrap()
+                .data,
+            [1; 32]
+        );
+
+        // checked updates keep the checksum in sync
+        let previous = state
+            .update_first_checksummed::<TestSmallValue, _>(|value| {
+                let previous = value[0];
+                value.fill(3);
+                Ok(previous)
+            })
+            .unwrap();
+        assert_eq!(previous, 2);
+        assert_eq!(
+            state
+                .get_first_checksummed_bytes::<TestSmallValue>()
+                .unwrap(),
+            [3; 3]
+        );
+
+        // other writes are caught on the next read
+        state.get_first_bytes_mut::<TestValue>().unwrap()[checksum::CHECKSUM_LEN] = 9;
+        assert_eq!(
+            state
+                .get_first_checksummed_value::<TestValue>()
+                .unwrap_err(),
+            TlvError::ChecksumMismatch.into()
+        );
+        assert_eq!(
+            state
+                .update_first_checksummed::<TestValue, _>(|_| Ok(()))
+                .unwrap_err(),
+            TlvError::ChecksumMismatch.into()
+        );
+        assert_eq!(
+            state
+                .update_first_checksummed::<TestSmallValue, ()>(|value| {
+                    value[0] = 4;
+                    Err(ProgramError::InvalidArgument)
+                })
+                .unwrap_err(),
+            ProgramError::InvalidArgument
+        );
+        assert_eq!(
+            state
+                .get_first_checksummed_bytes::<TestSmallValue>()
+                .unwrap_err(),
+            TlvError::ChecksumMismatch.into()
+        );
+    }
+
+    #[test]
+    fn chunked_writes() {
+        let mut buffer = vec![0; get_base_len() * 2 + size_of::<Length>() + 10 + 32];
+        let mut state = TlvStateMut::unpack(&mut buffer).unwrap();
+        state.alloc::<TestValue>(32, false).unwrap();
+        state.write_at::<TestValue>(30, &[1, 2]).unwrap();
+        assert_eq!(
+            state.get_first_bytes::<TestValue>().unwrap()[29..],
+            [0, 1, 2]
+        );
+        assert_eq!(
+            state.write_at::<TestValue>(31, &[1, 2]).unwrap_err(),
+            ProgramError::InvalidArgument
+        );
+        assert_eq!(
+            state.write_at::<TestValue>(usize::MAX, &[1]).unwrap_err(),
+            ProgramError::InvalidArgument
+        );
+
+        state.alloc_chunked::<TestSmallValue>(10, false).unwrap();
+        assert!(!state.is_complete::<TestSmallValue>().unwrap());
+        assert!(!state.write_chunk::<TestSmallValue>(0, &[1; 4]).unwrap());
+        // gaps are rejected, but chunks can be sent again
+        assert_eq!(
+            state.write_chunk::<TestSmallValue>(5, &[2; 5]).unwrap_err(),
+            ProgramError::InvalidArgument
+        );
+        assert!(!state.write_chunk::<TestSmallValue>(0, &[1; 4]).unwrap());
+        assert!(!state.write_chunk::<TestSmallValue>(4, &[2; 3]).unwrap());
+        assert_eq!(
+            state
+                .get_first_chunked_bytes::<TestSmallValue>()
+                .unwrap_err(),
+            TlvError::IncompleteEntry.into()
+        );
+        assert_eq!(
+            state.write_chunk::<TestSmallValue>(7, &[3; 4]).unwrap_err(),
+            ProgramError::InvalidArgument
+        );
+        assert!(state.write_chunk::<TestSmallValue>(7, &[3; 3]).unwrap());
+        assert!(state.is_complete::<TestSmallValue>().unwrap());
+        assert_eq!(
+            state.get_first_chunked_bytes::<TestSmallValue>().unwrap(),
+            [1, 1, 1, 1, 2, 2, 2, 3, 3, 3]
+        );
+
+        // rewriting a complete entry keeps it complete
+        assert!(state.write_chunk::<TestSmallValue>(0, &[4]).unwrap());
+        assert_eq!(
+            state.get_first_chunked_bytes::<TestSmallValue>().unwrap()[0],
+            4
+        );
+    }
+
     #[test]
     fn init_buffer_too_small() {
         let account_size = get_base_len() + size_of::<TestValue>();
@@ -1066,6 +2319,456 @@ mod test {
         );
     }
 
+    #[test]
+    fn dealloc() {
+        const TLV_SIZE: usize = 10;
+        const ACCOUNT_SIZE: usize = get_base_len()
+            + TLV_SIZE
+            + get_base_len()
+            + size_of::<TestNonZeroDefault>()
+            + get_base_len()
+            + TLV_SIZE;
+        let mut buffer = vec![0; ACCOUNT_SIZE];
+        let mut state = TlvStateMut::unpack(&mut buffer).unwrap();
+
+        // alloc a repeating type around another one
+        let (data, _) = state.alloc::<TestValue>(TLV_SIZE, true).unwrap();
+        data.fill(1);
+        let _ = state.init_value::<TestNonZeroDefault>(false).unwrap();
+        let (data, _) = state.alloc::<TestValue>(TLV_SIZE, true).unwrap();
+        data.fill(2);
+
+        // dealloc the first entry, the others are moved back
+        state.dealloc::<TestValue>().unwrap();
+        assert_eq!(state.get_first_bytes::<TestValue>().unwrap(), [2; TLV_SIZE]);
+        assert_eq!(
+            state.get_bytes_with_repetition::<TestValue>(1).unwrap_err(),
+            TlvError::TypeNotFound.into(),
+        );
+        let value = state.get_first_value::<TestNonZeroDefault>().unwrap();
+        assert_eq!(*value, TestNonZeroDefault::default());
+        assert_eq!(
+            state.get_discriminators().unwrap(),
+            [
+                TestNonZeroDefault::SPL_DISCRIMINATOR,
+                TestValue::SPL_DISCRIMINATOR
+            ]
+        );
+
+        // dealloc the last entries, the buffer is zeroed out
+        state.dealloc::<TestValue>().unwrap();
+        state.dealloc::<TestNonZeroDefault>().unwrap();
+        assert_eq!(state.get_discriminators().unwrap(), []);
+        assert_eq!(
+            state.dealloc::<TestValue>().unwrap_err(),
+            TlvError::TypeNotFound.into(),
+        );
+        assert_eq!(buffer, [0; ACCOUNT_SIZE]);
+
+        // the freed space can be allocated again
+        let mut state = TlvStateMut::unpack(&mut buffer).unwrap();
+        let _ = state
+            .alloc::<TestValue>(ACCOUNT_SIZE - get_base_len(), false)
+            .unwrap();
+    }
+
+    #[test]
+    fn dealloc_with_index_and_compact() {
+        const TLV_SIZE: usize = 10;
+        const ACCOUNT_SIZE: usize =
+            get_base_len() + TLV_SIZE + get_base_len() + size_of::<TestSmallValue>() + 20;
+        let mut buffer = vec![0; ACCOUNT_SIZE];
+        let mut state = TlvStateMut::unpack(&mut buffer).unwrap();
+        let (data, _) = state.alloc::<TestValue>(TLV_SIZE, false).unwrap();
+        data.fill(1);
+        let (value, _) = state.init_value::<TestSmallValue>(false).unwrap();
+        value.data = [2; 3];
+        let used_len = get_base_len() + TLV_SIZE + get_base_len() + size_of::<TestSmallValue>();
+        assert_eq!(state.compact().unwrap(), used_len);
+
+        // entries are removed by position, whatever their type
+        assert_eq!(
+            state.dealloc_with_index(2).unwrap_err(),
+            TlvError::TypeNotFound.into(),
+        );
+        state.dealloc_with_index(1).unwrap();
+        assert_eq!(
+            state.get_discriminators().unwrap(),
+            [TestValue::SPL_DISCRIMINATOR]
+        );
+        assert_eq!(state.get_first_bytes::<TestValue>().unwrap(), [1; TLV_SIZE]);
+        let (value, _) = state.init_value::<TestSmallValue>(false).unwrap();
+        value.data = [2; 3];
+        state.dealloc_with_index(0).unwrap();
+        assert_eq!(
+            state.get_discriminators().unwrap(),
+            [TestSmallValue::SPL_DISCRIMINATOR]
+        );
+        assert_eq!(
+            state.get_first_value::<TestSmallValue>().unwrap().data,
+            [2; 3]
+        );
+        let used_len = get_base_len() + size_of::<TestSmallValue>();
+        assert_eq!(state.compact().unwrap(), used_len);
+
+        // leftovers after the last entry are zeroed out
+        buffer[used_len + size_of::<ArrayDiscriminator>()..].fill(9);
+        let mut state = TlvStateMut::unpack(&mut buffer).unwrap();
+        assert_eq!(state.compact().unwrap(), used_len);
+        assert!(buffer[used_len..].iter().all(|&byte| byte == 0));
+
+        // the buffer can be truncated to the used length
+        let mut buffer = buffer[..used_len].to_vec();
+        let state = TlvStateMut::unpack(&mut buffer).unwrap();
+        assert_eq!(
+            state.get_first_value::<TestSmallValue>().unwrap().data,
+            [2; 3]
+        );
+    }
+
+    #[test]
+    fn free_and_alloc_best_fit() {
+        const ACCOUNT_SIZE: usize = get_base_len() * 4 + 20 + 10 + 32 + 3;
+        let mut buffer = vec![0; ACCOUNT_SIZE];
+        let mut state = TlvStateMut::unpack(&mut buffer).unwrap();
+        state.alloc::<TestValue>(20, true).unwrap().0.fill(1);
+        state.alloc::<TestValue>(10, true).unwrap().0.fill(2);
+        state.init_value::<TestSmallValue>(false).unwrap().0.data = [3; 3];
+        state.alloc::<TestValue>(32, true).unwrap().0.fill(4);
+
+        // freed entries stay in place, and adjacent free regions are merged
+        let entry_discriminators = |state: &TlvStateMut| {
+            get_entry_bounds(state.get_data())
+                .unwrap()
+                .into_iter()
+                .map(|(discriminator, ..)| discriminator)
+                .collect::<Vec<_>>()
+        };
+        state.free_with_repetition::<TestValue>(1).unwrap();
+        assert_eq!(
+            entry_discriminators(&state),
+            [
+                TestValue::SPL_DISCRIMINATOR,
+                FREE_REGION_DISCRIMINATOR,
+                TestSmallValue::SPL_DISCRIMINATOR,
+                TestValue::SPL_DISCRIMINATOR,
+            ]
+        );
+        state.free::<TestValue>().unwrap();
+        assert_eq!(
+            entry_discriminators(&state),
+            [
+                FREE_REGION_DISCRIMINATOR,
+                TestSmallValue::SPL_DISCRIMINATOR,
+                TestValue::SPL_DISCRIMINATOR,
+            ]
+        );
+        let merged_length = 20 + get_base_len() + 10;
+        assert_eq!(
+            state.get_first_bytes_with_discriminator(FREE_REGION_DISCRIMINATOR),
+            Ok(&[0; 20 + get_base_len() + 10][..])
+        );
+        assert_eq!(state.get_first_bytes::<TestValue>().unwrap(), [4; 32]);
+
+        // a region too small to split isn't reused
+        assert_eq!(
+            state.alloc_best_fit::<TestValue>(merged_length - 1, true),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert_eq!(
+            state.alloc_best_fit::<TestSmallValue>(3, false),
+            Err(TlvError::TypeAlreadyExists.into())
+        );
+
+        // the region is split, and the new entry comes first in the order
+        let (data, repetition_number) = state.alloc_best_fit::<TestValue>(5, true).unwrap();
+        assert_eq!(data, [0; 5]);
+        data.fill(5);
+        assert_eq!(repetition_number, 0);
+        assert_eq!(state.get_first_bytes::<TestValue>().unwrap(), [5; 5]);
+        assert_eq!(
+            state.get_bytes_with_repetition::<TestValue>(1).unwrap(),
+            [4; 32]
+        );
+        let rest_length = merged_length - 5 - get_base_len();
+        assert_eq!(
+            state
+                .get_first_bytes_with_discriminator(FREE_REGION_DISCRIMINATOR)
+                .unwrap()
+                .len(),
+            rest_length
+        );
+
+        // an exact fit takes over the whole region
+        let (data, repetition_number) = state
+            .alloc_best_fit::<TestValue>(rest_length, true)
+            .unwrap();
+        assert_eq!(data.len(), rest_length);
+        assert_eq!(repetition_number, 1);
+        assert_eq!(
+            state.get_discriminators().unwrap(),
+            [
+                TestValue::SPL_DISCRIMINATOR,
+                TestValue::SPL_DISCRIMINATOR,
+                TestSmallValue::SPL_DISCRIMINATOR,
+                TestValue::SPL_DISCRIMINATOR,
+            ]
+        );
+
+        // freeing the last entries returns their space to the end of the buffer
+        state.free_with_repetition::<TestValue>(2).unwrap();
+        state.free::<TestSmallValue>().unwrap();
+        let used_len = get_base_len() * 2 + merged_length - get_base_len();
+        assert_eq!(state.compact().unwrap(), used_len);
+        assert!(buffer[used_len..].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn free_regions_are_skipped() {
+        let mut buffer = vec![0; get_base_len() * 3 + 20 + 10 + 3];
+        let mut state = TlvStateMut::unpack(&mut buffer).unwrap();
+        state.alloc::<TestValue>(20, true).unwrap().0.fill(1);
+        state.alloc::<TestValue>(10, true).unwrap().0.fill(2);
+        state.init_value::<TestSmallValue>(false).unwrap().0.data = [3; 3];
+        state.free::<TestValue>().unwrap();
+
+        // only live entries are returned
+        assert_eq!(
+            state.iter().collect::<Vec<_>>(),
+            [
+                (TestValue::SPL_DISCRIMINATOR, &[2; 10][..]),
+                (TestSmallValue::SPL_DISCRIMINATOR, &[3; 3][..]),
+            ]
+        );
+        assert_eq!(
+            state.get_discriminators().unwrap(),
+            [
+                TestValue::SPL_DISCRIMINATOR,
+                TestSmallValue::SPL_DISCRIMINATOR,
+            ]
+        );
+        assert_eq!(state.iter_mut().count(), 2);
+
+        // and indices follow the same order
+        state.dealloc_with_index(1).unwrap();
+        assert_eq!(
+            state.iter().collect::<Vec<_>>(),
+            [(TestValue::SPL_DISCRIMINATOR, &[2; 10][..])]
+        );
+        assert_eq!(
+            state.dealloc_with_index(1),
+            Err(TlvError::TypeNotFound.into())
+        );
+    }
+
+    #[test]
+    fn transaction_rolls_back() {
+        let mut buffer = TEST_BIG_BUFFER.to_vec();
+        buffer.extend_from_slice(&[0; 3]);
+        let mut state = TlvStateMut::unpack(&mut buffer).unwrap();
+
+        // a failed update leaves no trace of the earlier ones
+        assert_eq!(
+            state.transaction(|state| {
+                state.get_first_value_mut::<TestValue>()?.data = [2; 32];
+                state.init_value::<TestSmallValue>(false)?.0.data = [3; 3];
+                state.init_value::<TestNonZeroDefault>(false)?;
+                Ok(())
+            }),
+            Err(ProgramError::InvalidAccountData)
+        );
+        let mut expected = TEST_BIG_BUFFER.to_vec();
+        expected.extend_from_slice(&[0; 3]);
+        assert_eq!(buffer, expected);
+
+        // a successful update is kept
+        let mut state = TlvStateMut::unpack(&mut buffer).unwrap();
+        let repetition_number = state
+            .transaction(|state| {
+                state.get_first_value_mut::<TestValue>()?.data = [2; 32];
+                let (value, repetition_number) = state.init_value::<TestSmallValue>(false)?;
+                value.data = [3; 3];
+                Ok(repetition_number)
+            })
+            .unwrap();
+        assert_eq!(repetition_number, 0);
+        assert_eq!(state.get_first_value::<TestValue>().unwrap().data, [2; 32]);
+        assert_eq!(
+            state.get_first_value::<TestSmallValue>().unwrap().data,
+            [3; 3]
+        );
+    }
+
+    #[test]
+    fn iter_entries() {
+        let mut buffer = TEST_BIG_BUFFER.to_vec();
+        buffer.extend_from_slice(&[0; 3]);
+        let state = TlvStateBorrowed::unpack(&buffer).unwrap();
+        let entries = state.iter().collect::<Vec<_>>();
+        assert_eq!(entries, [(TestValue::SPL_DISCRIMINATOR, &[1; 32][..])]);
+
+        let mut state = TlvStateMut::unpack(&mut buffer).unwrap();
+        let (data, _) = state.alloc::<TestSmallValue>(3, false).unwrap();
+        data.copy_from_slice(&[2; 3]);
+        for (discriminator, value) in state.iter_mut() {
+            if discriminator == TestSmallValue::SPL_DISCRIMINATOR {
+                value.fill(4);
+            }
+        }
+        assert_eq!(
+            state.iter().collect::<Vec<_>>(),
+            [
+                (TestValue::SPL_DISCRIMINATOR, &[1; 32][..]),
+                (TestSmallValue::SPL_DISCRIMINATOR, &[4; 3][..]),
+            ]
+        );
+        assert_eq!(
+            state
+                .iter()
+                .map(|(discriminator, _)| discriminator)
+                .collect::<Vec<_>>(),
+            state.get_discriminators().unwrap()
+        );
+
+        // stops on malformed data
+        let state = TlvStateBorrowed {
+            data: &buffer[..50],
+        };
+        assert_eq!(
+            state.iter().collect::<Vec<_>>(),
+            [(TestValue::SPL_DISCRIMINATOR, &[1; 32][..])]
+        );
+
+        // nothing in an empty buffer
+        let empty = [0; 16];
+        let state = TlvStateBorrowed::unpack(&empty).unwrap();
+        assert_eq!(state.iter().count(), 0);
+    }
+
+    #[cfg(feature = "account-info")]
+    #[test]
+    fn alloc_with_realloc_grows_account() {
+        let key = Default::default();
+        let mut lamports = 0;
+        let mut data = TEST_BUFFER.to_vec();
+        let account_info =
+            AccountInfo::new(&key, false, true, &mut lamports, &mut data, &key, false);
+
+        // stand-in for `AccountInfo::resize`, which requires the runtime's
+        // serialized account layout
+        let resize = |new_len: usize| {
+            let mut data = account_info.try_borrow_mut_data()?;
+            let mut resized = data.to_vec();
+            resized.resize(new_len, 9);
+            *data = alloc::boxed::Box::leak(resized.into_boxed_slice());
+            Ok(())
+        };
+
+        // only the missing bytes are added, and the new value is zeroed
+        let repetition_number =
+            alloc_with_realloc::<TestSmallValue, _>(&account_info, 3, false, resize).unwrap();
+        assert_eq!(repetition_number, 0);
+        let expected_len = TEST_BUFFER.len() - 2 + get_base_len() + 3;
+        assert_eq!(account_info.data_len(), expected_len);
+        {
+            let data = account_info.try_borrow_data().unwrap();
+            let state = TlvStateBorrowed::unpack(&data).unwrap();
+            assert_eq!(state.get_first_bytes::<TestSmallValue>().unwrap(), [0; 3]);
+            assert_eq!(state.get_first_bytes::<TestValue>().unwrap(), [1; 32]);
+        }
+
+        // no resize if the type already exists
+        assert_eq!(
+            alloc_with_realloc::<TestSmallValue, _>(&account_info, 3, false, |_| panic!())
+                .unwrap_err(),
+            TlvError::TypeAlreadyExists.into()
+        );
+
+        // repeated entries are appended
+        let repetition_number =
+            alloc_with_realloc::<TestSmallValue, _>(&account_info, 3, true, resize).unwrap();
+        assert_eq!(repetition_number, 1);
+        assert_eq!(account_info.data_len(), expected_len + get_base_len() + 3);
+
+        // no resize if there's enough space
+        let mut lamports = 0;
+        let mut data = TEST_BIG_BUFFER.to_vec();
+        let account_info =
+            AccountInfo::new(&key, false, true, &mut lamports, &mut data, &key, false);
+        alloc_with_realloc::<TestEmptyValue, _>(&account_info, 0, false, |_| panic!()).unwrap();
+        assert_eq!(account_info.data_len(), TEST_BIG_BUFFER.len());
+    }
+
+    #[test]
+    fn alloc_required_len() {
+        let data = TEST_BIG_BUFFER;
+        let end_index = get_base_len() + 32;
+        assert_eq!(
+            get_alloc_required_len::<TestSmallValue>(data, 3, false).unwrap(),
+            end_index + get_base_len() + 3
+        );
+        assert_eq!(
+            get_alloc_required_len::<TestEmptyValue>(data, 0, false).unwrap(),
+            data.len()
+        );
+        assert_eq!(
+            get_alloc_required_len::<TestValue>(data, 32, false).unwrap_err(),
+            TlvError::TypeAlreadyExists.into()
+        );
+        assert_eq!(
+            get_alloc_required_len::<TestValue>(data, 32, true).unwrap(),
+            end_index + get_base_len() + 32
+        );
+    }
+
+    #[test]
+    fn owned_state_grows() {
+        let mut state = TlvStateOwned::new();
+        assert_eq!(state.get_discriminators().unwrap(), []);
+        let (value, repetition_number) = state.init_value::<TestValue>(false).unwrap();
+        value.data = [1; 32];
+        assert_eq!(repetition_number, 0);
+        let (data, repetition_number) = state.alloc::<TestSmallValue>(3, true).unwrap();
+        data.fill(2);
+        assert_eq!(repetition_number, 0);
+        let (_, repetition_number) = state.alloc::<TestSmallValue>(3, true).unwrap();
+        assert_eq!(repetition_number, 1);
+
+        // a failed allocation leaves the data untouched
+        let len = state.get_data().len();
+        assert_eq!(
+            state.init_value::<TestValue>(false).unwrap_err(),
+            TlvError::TypeAlreadyExists.into()
+        );
+        assert_eq!(state.get_data().len(), len);
+
+        // same data as a buffer sized by hand
+        let mut buffer = vec![0; get_base_len() * 3 + 32 + 3 + 3];
+        let mut expected = TlvStateMut::unpack(&mut buffer).unwrap();
+        expected.init_value::<TestValue>(false).unwrap().0.data = [1; 32];
+        expected.alloc::<TestSmallValue>(3, true).unwrap().0.fill(2);
+        expected.alloc::<TestSmallValue>(3, true).unwrap();
+        assert_eq!(state.into_bytes(), buffer);
+
+        // existing data with free space is filled first
+        let mut state = TlvStateOwned::unpack(TEST_BIG_BUFFER.to_vec()).unwrap();
+        state.init_value::<TestEmptyValue>(false).unwrap();
+        assert_eq!(state.get_data().len(), TEST_BIG_BUFFER.len());
+        state.init_value::<TestSmallValue>(false).unwrap();
+        assert_eq!(
+            state.get_data().len(),
+            TEST_BIG_BUFFER.len() + get_base_len() + 3
+        );
+        state.state_mut().dealloc::<TestValue>().unwrap();
+        assert_eq!(
+            state.get_discriminators().unwrap(),
+            [
+                TestEmptyValue::SPL_DISCRIMINATOR,
+                TestSmallValue::SPL_DISCRIMINATOR
+            ]
+        );
+    }
+
     #[test]
     fn realloc_with_repeating_entries() {
         const TLV_SIZE: usize = 10;
@@ -1195,6 +2898,91 @@ mod test {
         );
     }
 
+    #[test]
+    fn variable_len_value_with_capacity() {
+        const CAPACITY: usize = 32;
+        let short = TestVariableLen {
+            data: "short".to_string(),
+        };
+        let account_size = get_base_len() + 64 + get_base_len() + size_of::<TestSmallValue>();
+        let mut buffer = vec![0; account_size];
+        let mut state = TlvStateMut::unpack(&mut buffer).unwrap();
+        state
+            .alloc_with_capacity::<TestVariableLen>(&short, CAPACITY, false)
+            .unwrap();
+        let (value, _) = state.init_value::<TestSmallValue>(false).unwrap();
+        value.data = [3; 3];
+        assert_eq!(
+            state.get_first_bytes::<TestVariableLen>().unwrap().len(),
+            CAPACITY
+        );
+        assert_eq!(
+            state
+                .get_first_variable_len_value::<TestVariableLen>()
+                .unwrap(),
+            short
+        );
+
+        // grows within the capacity, without moving the next entry
+        let longer = TestVariableLen {
+            data: "a bit longer".to_string(),
+        };
+        state.realloc_value(&longer).unwrap();
+        assert_eq!(
+            state.get_first_bytes::<TestVariableLen>().unwrap().len(),
+            CAPACITY
+        );
+        assert_eq!(
+            state
+                .get_first_variable_len_value::<TestVariableLen>()
+                .unwrap(),
+            longer
+        );
+
+        // grows past the capacity, moving the next entry
+        let longest = TestVariableLen {
+            data: "longer than the capacity of the entry".to_string(),
+        };
+        state.realloc_value(&longest).unwrap();
+        assert_eq!(
+            state.get_first_bytes::<TestVariableLen>().unwrap().len(),
+            longest.get_packed_len().unwrap()
+        );
+        assert_eq!(
+            state
+                .get_first_variable_len_value::<TestVariableLen>()
+                .unwrap(),
+            longest
+        );
+        assert_eq!(
+            state.get_first_value::<TestSmallValue>().unwrap().data,
+            [3; 3]
+        );
+
+        // shrinking keeps the entry length, zeroing out the rest
+        state.realloc_value(&short).unwrap();
+        let data = state.get_first_bytes::<TestVariableLen>().unwrap();
+        assert_eq!(data.len(), longest.get_packed_len().unwrap());
+        assert!(data[short.get_packed_len().unwrap()..]
+            .iter()
+            .all(|&byte| byte == 0));
+
+        // fails without enough space to grow
+        let too_long = TestVariableLen {
+            data: "x".repeat(64),
+        };
+        assert_eq!(
+            state.realloc_value(&too_long).unwrap_err(),
+            ProgramError::InvalidAccountData
+        );
+        assert_eq!(
+            state
+                .get_first_variable_len_value::<TestVariableLen>()
+                .unwrap(),
+            short
+        );
+    }
+
     #[test]
     fn variable_len_value_with_repetition() {
         let variable_len_1 = TestVariableLen {
@@ -0,0 +1,182 @@
+//! Benchmarks of the zero-copy list containers: the deprecated `PodSlice`,
+//! the `ListView` of `spl-pod` and of `spl-list-view`, and a `Vec`
+//! deserialized from and serialized back to the same bytes
+
+#![allow(deprecated)]
+
+use {
+    criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion},
+    spl_pod::{
+        list::ListView as PodListView,
+        slice::{PodSlice, PodSliceMut},
+    },
+    std::hint::black_box,
+};
+
+const SIZES: [usize; 3] = [16, 256, 4096];
+
+type Item = u64;
+
+/// Serialize the items like a `PodSlice`: a `u32` length followed by the items
+fn vec_to_bytes(items: &[Item], buf: &mut [u8]) {
+    let len = u32::try_from(items.len()).unwrap();
+    buf[..4].copy_from_slice(&len.to_le_bytes());
+    for (item, bytes) in items
+        .iter()
+        .zip(buf[4..].chunks_exact_mut(size_of::<Item>()))
+    {
+        bytes.copy_from_slice(&item.to_le_bytes());
+    }
+}
+
+fn vec_from_bytes(buf: &[u8]) -> Vec<Item> {
+    let len = u32::from_le_bytes(buf[..4].try_into().unwrap()) as usize;
+    buf[4..4 + len * size_of::<Item>()]
+        .chunks_exact(size_of::<Item>())
+        .map(|bytes| Item::from_le_bytes(bytes.try_into().unwrap()))
+        .collect()
+}
+
+/// A full buffer of `num_items` items, for each container
+fn full_buffers(num_items: usize) -> [Vec<u8>; 3] {
+    let mut pod_buf = vec![0; PodListView::<Item>::size_of(num_items).unwrap()];
+    let mut list_view_buf = vec![0; spl_list_view::ListView::<Item>::size_of(num_items).unwrap()];
+    let mut vec_buf = vec![0; 4 + num_items * size_of::<Item>()];
+    let items: Vec<Item> = (0..num_items as Item).collect();
+    {
+        let mut pod_list = PodListView::<Item>::init(&mut pod_buf).unwrap();
+        let mut list_view = spl_list_view::ListView::<Item>::init(&mut list_view_buf).unwrap();
+        for item in &items {
+            pod_list.push(*item).unwrap();
+            list_view.push(*item).unwrap();
+        }
+    }
+    vec_to_bytes(&items, &mut vec_buf);
+    [pod_buf, list_view_buf, vec_buf]
+}
+
+fn bench_push(c: &mut Criterion) {
+    let mut group = c.benchmark_group("push");
+    for num_items in SIZES {
+        let [mut pod_buf, mut list_view_buf, mut vec_buf] = full_buffers(num_items);
+        group.bench_function(BenchmarkId::new("PodSlice", num_items), |b| {
+            b.iter(|| {
+                let mut slice = PodSliceMut::<Item>::init(&mut pod_buf).unwrap();
+                for item in 0..num_items as Item {
+                    slice.push(black_box(item)).unwrap();
+                }
+            })
+        });
+        group.bench_function(BenchmarkId::new("spl_pod::ListView", num_items), |b| {
+            b.iter(|| {
+                let mut list = PodListView::<Item>::init(&mut pod_buf).unwrap();
+                for item in 0..num_items as Item {
+                    list.push(black_box(item)).unwrap();
+                }
+            })
+        });
+        group.bench_function(
+            BenchmarkId::new("spl_list_view::ListView", num_items),
+            |b| {
+                b.iter(|| {
+                    let mut list =
+                        spl_list_view::ListView::<Item>::init(&mut list_view_buf).unwrap();
+                    for item in 0..num_items as Item {
+                        list.push(black_box(item)).unwrap();
+                    }
+                })
+            },
+        );
+        group.bench_function(BenchmarkId::new("Vec", num_items), |b| {
+            b.iter(|| {
+                let mut items = vec_from_bytes(&vec_buf);
+                items.clear();
+                for item in 0..num_items as Item {
+                    items.push(black_box(item));
+                }
+                vec_to_bytes(&items, &mut vec_buf);
+            })
+        });
+    }
+    group.finish();
+}
+
+// `PodSlice` can't remove items, so only the lists are compared
+fn bench_remove(c: &mut Criterion) {
+    let mut group = c.benchmark_group("remove_first");
+    for num_items in SIZES {
+        let [pod_buf, list_view_buf, vec_buf] = full_buffers(num_items);
+        group.bench_function(BenchmarkId::new("spl_pod::ListView", num_items), |b| {
+            b.iter_batched_ref(
+                || pod_buf.clone(),
+                |buf| {
+                    let mut list = PodListView::<Item>::unpack_mut(buf).unwrap();
+                    black_box(list.remove(0).unwrap())
+                },
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function(
+            BenchmarkId::new("spl_list_view::ListView", num_items),
+            |b| {
+                b.iter_batched_ref(
+                    || list_view_buf.clone(),
+                    |buf| {
+                        let mut list = spl_list_view::ListView::<Item>::unpack_mut(buf).unwrap();
+                        black_box(list.remove(0).unwrap())
+                    },
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+        group.bench_function(BenchmarkId::new("Vec", num_items), |b| {
+            b.iter_batched_ref(
+                || vec_buf.clone(),
+                |buf| {
+                    let mut items = vec_from_bytes(buf);
+                    let item = items.remove(0);
+                    vec_to_bytes(&items, buf);
+                    black_box(item)
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn bench_iterate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("iterate");
+    for num_items in SIZES {
+        let [pod_buf, list_view_buf, vec_buf] = full_buffers(num_items);
+        group.bench_function(BenchmarkId::new("PodSlice", num_items), |b| {
+            b.iter(|| {
+                let slice = PodSlice::<Item>::unpack(black_box(&pod_buf)).unwrap();
+                slice.data().iter().sum::<Item>()
+            })
+        });
+        group.bench_function(BenchmarkId::new("spl_pod::ListView", num_items), |b| {
+            b.iter(|| {
+                let list = PodListView::<Item>::unpack(black_box(&pod_buf)).unwrap();
+                list.iter().sum::<Item>()
+            })
+        });
+        group.bench_function(
+            BenchmarkId::new("spl_list_view::ListView", num_items),
+            |b| {
+                b.iter(|| {
+                    let list =
+                        spl_list_view::ListView::<Item>::unpack(black_box(&list_view_buf)).unwrap();
+                    list.iter().sum::<Item>()
+                })
+            },
+        );
+        group.bench_function(BenchmarkId::new("Vec", num_items), |b| {
+            b.iter(|| vec_from_bytes(black_box(&vec_buf)).iter().sum::<Item>())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_push, bench_remove, bench_iterate);
+criterion_main!(benches);